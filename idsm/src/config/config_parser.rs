@@ -2,6 +2,107 @@
 
 use std::fs;
 
+use crate::events::event_encoder::event_encoder_type;
+use crate::events::event_mgr::{event_mgr, event_overflow_policy};
+
+// @brief - report a key that is not in the config object
+//
+// @param [in] key - name of the key
+// @param [in] required - missing key is an error
+//
+// @return 0 if the key is optional -1 otherwise
+fn get_missing(key : &str, required : bool) -> i32 {
+    if required {
+        log::error!("config: {} is missing", key);
+        return -1;
+    }
+    return 0;
+}
+
+// @brief - get a boolean of a config object
+//
+// @param [in] obj - config object
+// @param [in] key - name of the key
+// @param [in] required - missing key is an error, otherwise val is kept
+// @param [out] val - value of the key
+//
+// @return 0 on success -1 if the key is missing or not a boolean
+fn get_bool(obj : &serde_json::Value, key : &str, required : bool, val : &mut bool) -> i32 {
+    let v = match obj.get(key) {
+        Some(v) => v,
+        None => return get_missing(key, required),
+    };
+
+    match v.as_bool() {
+        Some(b) => *val = b,
+        None => {
+            log::error!("config: {} is not a boolean", key);
+            return -1;
+        }
+    }
+
+    return 0;
+}
+
+// @brief - get a string of a config object
+//
+// @param [in] obj - config object
+// @param [in] key - name of the key
+// @param [in] required - missing key is an error, otherwise val is kept
+// @param [out] val - value of the key
+//
+// @return 0 on success -1 if the key is missing or not a string
+fn get_str(obj : &serde_json::Value, key : &str, required : bool, val : &mut String) -> i32 {
+    let v = match obj.get(key) {
+        Some(v) => v,
+        None => return get_missing(key, required),
+    };
+
+    match v.as_str() {
+        Some(s) => *val = s.to_string(),
+        None => {
+            log::error!("config: {} is not a string", key);
+            return -1;
+        }
+    }
+
+    return 0;
+}
+
+// @brief - get an unsigned integer of a config object
+//
+// @param [in] obj - config object
+// @param [in] key - name of the key
+// @param [in] required - missing key is an error, otherwise val is kept
+// @param [out] val - value of the key
+//
+// @return 0 on success -1 if the key is missing, not an unsigned integer
+//         or does not fit in val
+fn get_uint<T : TryFrom<u64>>(obj : &serde_json::Value, key : &str, required : bool, val : &mut T) -> i32 {
+    let v = match obj.get(key) {
+        Some(v) => v,
+        None => return get_missing(key, required),
+    };
+
+    let n = match v.as_u64() {
+        Some(n) => n,
+        None => {
+            log::error!("config: {} is not an unsigned integer", key);
+            return -1;
+        }
+    };
+
+    match T::try_from(n) {
+        Ok(n) => *val = n,
+        Err(_) => {
+            log::error!("config: {} {} is out of range", key, n);
+            return -1;
+        }
+    }
+
+    return 0;
+}
+
 pub struct idsm_pcap_config {
     pub enable : bool,
     pub file_prefix : String
@@ -17,11 +118,19 @@ impl idsm_pcap_config {
     }
 
     pub fn parse(&mut self, config_data : &serde_json::Value) -> i32 {
-        let pcap_obj = config_data.get("pcap").unwrap();
+        let pcap_obj = match config_data.get("pcap") {
+            Some(obj) => obj,
+            None => {
+                log::error!("config: pcap is missing");
+                return -1;
+            }
+        };
 
         if pcap_obj.is_object() {
-            self.enable = pcap_obj.get("enable").unwrap().as_bool().unwrap();
-            self.file_prefix = pcap_obj.get("file_prefix").unwrap().as_str().unwrap().to_string();
+            if (get_bool(pcap_obj, "enable", true, &mut self.enable) < 0) ||
+               (get_str(pcap_obj, "file_prefix", true, &mut self.file_prefix) < 0) {
+                return -1;
+            }
         } else {
             return -1;
         }
@@ -53,12 +162,20 @@ impl idsm_event_transport_udp {
     }
 
     pub fn parse(&mut self, config_data :  &serde_json::Value) -> i32 {
-        let udp_obj = config_data.get("udp").unwrap();
+        let udp_obj = match config_data.get("udp") {
+            Some(obj) => obj,
+            None => {
+                log::error!("config: udp is missing");
+                return -1;
+            }
+        };
 
         if udp_obj.is_object() {
-            self.ipaddr = udp_obj.get("ipaddr").unwrap().as_str().unwrap().to_string();
-            self.port = (udp_obj.get("port").unwrap().as_u64().unwrap()) as u16;
-            self.interval_sec = (udp_obj.get("interval_sec").unwrap().as_u64().unwrap()) as u32;
+            if (get_str(udp_obj, "ipaddr", true, &mut self.ipaddr) < 0) ||
+               (get_uint(udp_obj, "port", true, &mut self.port) < 0) ||
+               (get_uint(udp_obj, "interval_sec", true, &mut self.interval_sec) < 0) {
+                return -1;
+            }
         } else {
             return -1;
         }
@@ -74,17 +191,325 @@ impl idsm_event_transport_udp {
     }
 }
 
+pub struct idsm_event_aggr_config {
+    pub enable : bool,
+    pub window_sec : u32
+}
+
+impl idsm_event_aggr_config {
+    pub fn new() -> idsm_event_aggr_config {
+        let aggr_config = idsm_event_aggr_config {
+            enable : false,
+            window_sec : 0
+        };
+        aggr_config
+    }
+
+    pub fn parse(&mut self, config_data : &serde_json::Value) -> i32 {
+        // aggregation is optional
+        let aggr_obj = match config_data.get("aggregation") {
+            Some(obj) => obj,
+            None => return 0,
+        };
+
+        if aggr_obj.is_object() {
+            if (get_bool(aggr_obj, "enable", true, &mut self.enable) < 0) ||
+               (get_uint(aggr_obj, "window_sec", true, &mut self.window_sec) < 0) {
+                return -1;
+            }
+        } else {
+            return -1;
+        }
+
+        // disabled aggregation is a zero window
+        if !self.enable {
+            self.window_sec = 0;
+        }
+
+        return 0;
+    }
+
+    pub fn print(&self) {
+        log::info!("aggregation_config: ");
+        log::info!("\t enable: {}", self.enable);
+        log::info!("\t window_sec: {}", self.window_sec);
+    }
+}
+
+pub struct idsm_event_file_log_config {
+    pub enable : bool,
+    pub file_prefix : String,
+    pub max_size_bytes : u64,
+    pub max_age_sec : u32,
+    pub max_files : u32
+}
+
+impl idsm_event_file_log_config {
+    pub fn new() -> idsm_event_file_log_config {
+        let file_log_config = idsm_event_file_log_config {
+            enable : false,
+            file_prefix : "".to_string(),
+            max_size_bytes : 0,
+            max_age_sec : 0,
+            max_files : 0
+        };
+        file_log_config
+    }
+
+    pub fn parse(&mut self, config_data : &serde_json::Value) -> i32 {
+        // file log is optional
+        let file_log_obj = match config_data.get("file_log") {
+            Some(obj) => obj,
+            None => return 0,
+        };
+
+        if file_log_obj.is_object() {
+            if (get_bool(file_log_obj, "enable", true, &mut self.enable) < 0) ||
+               (get_str(file_log_obj, "file_prefix", true, &mut self.file_prefix) < 0) ||
+               (get_uint(file_log_obj, "max_size_bytes", true, &mut self.max_size_bytes) < 0) ||
+               (get_uint(file_log_obj, "max_age_sec", true, &mut self.max_age_sec) < 0) ||
+               (get_uint(file_log_obj, "max_files", true, &mut self.max_files) < 0) {
+                return -1;
+            }
+        } else {
+            return -1;
+        }
+
+        return 0;
+    }
+
+    pub fn print(&self) {
+        log::info!("file_log_config: ");
+        log::info!("\t enable: {}", self.enable);
+        log::info!("\t file_prefix: {}", self.file_prefix);
+        log::info!("\t max_size_bytes: {}", self.max_size_bytes);
+        log::info!("\t max_age_sec: {}", self.max_age_sec);
+        log::info!("\t max_files: {}", self.max_files);
+    }
+}
+
+#[non_exhaustive]
+pub struct idsm_syslog_proto;
+
+impl idsm_syslog_proto {
+    pub const UDP : u32 = 1;
+    pub const TCP : u32 = 2;
+    pub const UNIX : u32 = 3;
+}
+
+pub struct idsm_event_transport_syslog {
+    pub proto : u32,
+    pub ipaddr : String,
+    pub port : u16,
+    pub path : String,
+    pub facility : u8,
+    pub app_name : String,
+    pub interval_sec : u32
+}
+
+impl idsm_event_transport_syslog {
+    const FACILITY_NAMES : [(&'static str, u8); 20] = [
+        ("kern", 0), ("user", 1), ("mail", 2), ("daemon", 3),
+        ("auth", 4), ("syslog", 5), ("lpr", 6), ("news", 7),
+        ("uucp", 8), ("cron", 9), ("authpriv", 10), ("ftp", 11),
+        ("local0", 16), ("local1", 17), ("local2", 18), ("local3", 19),
+        ("local4", 20), ("local5", 21), ("local6", 22), ("local7", 23),
+    ];
+
+    pub fn new() -> idsm_event_transport_syslog {
+        let syslog_config = idsm_event_transport_syslog {
+            proto : idsm_syslog_proto::UDP,
+            ipaddr : "127.0.0.1".to_string(),
+            port : 514,
+            path : "/dev/log".to_string(),
+            facility : 4,
+            app_name : "idsd".to_string(),
+            interval_sec : 0
+        };
+        syslog_config
+    }
+
+    // @brief - parse syslog facility by name or by number
+    //
+    // @param [in] self - syslog config
+    // @param [in] facility - facility value in the config
+    //
+    // @return 0 on success -1 on failure
+    fn parse_facility(&mut self, facility : &serde_json::Value) -> i32 {
+        if let Some(val) = facility.as_u64() {
+            if val > 23 {
+                return -1;
+            }
+
+            self.facility = val as u8;
+            return 0;
+        }
+
+        let facility_str = match facility.as_str() {
+            Some(facility_str) => facility_str,
+            None => return -1,
+        };
+        for (name, val) in idsm_event_transport_syslog::FACILITY_NAMES {
+            if name == facility_str {
+                self.facility = val;
+                return 0;
+            }
+        }
+
+        return -1;
+    }
+
+    pub fn parse(&mut self, config_data :  &serde_json::Value) -> i32 {
+        let syslog_obj = match config_data.get("syslog") {
+            Some(obj) => obj,
+            None => {
+                log::error!("config: syslog is missing");
+                return -1;
+            }
+        };
+
+        if syslog_obj.is_object() {
+            let mut proto_str = String::new();
+            if get_str(syslog_obj, "proto", true, &mut proto_str) < 0 {
+                return -1;
+            }
+
+            match proto_str.as_str() {
+                "udp" => self.proto = idsm_syslog_proto::UDP,
+                "tcp" => self.proto = idsm_syslog_proto::TCP,
+                "unix" => self.proto = idsm_syslog_proto::UNIX,
+                _ => {
+                    log::error!("invalid syslog proto {}", proto_str);
+                    return -1;
+                }
+            }
+
+            if (get_str(syslog_obj, "ipaddr", false, &mut self.ipaddr) < 0) ||
+               (get_uint(syslog_obj, "port", false, &mut self.port) < 0) ||
+               (get_str(syslog_obj, "path", false, &mut self.path) < 0) {
+                return -1;
+            }
+            if let Some(facility) = syslog_obj.get("facility") {
+                if self.parse_facility(facility) < 0 {
+                    log::error!("invalid syslog facility {}", facility);
+                    return -1;
+                }
+            }
+            if (get_str(syslog_obj, "app_name", false, &mut self.app_name) < 0) ||
+               (get_uint(syslog_obj, "interval_sec", true, &mut self.interval_sec) < 0) {
+                return -1;
+            }
+        } else {
+            return -1;
+        }
+
+        return 0;
+    }
+
+    pub fn print(&self) {
+        log::info!("syslog_config: ");
+        log::info!("\t proto: {}", self.proto);
+        log::info!("\t ipaddr: {}", self.ipaddr);
+        log::info!("\t port: {}", self.port);
+        log::info!("\t path: {}", self.path);
+        log::info!("\t facility: {}", self.facility);
+        log::info!("\t app_name: {}", self.app_name);
+        log::info!("\t interval_sec: {}", self.interval_sec);
+    }
+}
+
+pub struct idsm_event_transport_tcp {
+    pub ipaddr : String,
+    pub port : u16,
+    pub interval_sec : u32,
+    pub spool_dir : String,
+    pub max_spool_bytes : u64,
+    pub reconnect_min_sec : u32,
+    pub reconnect_max_sec : u32
+}
+
+impl idsm_event_transport_tcp {
+    pub fn new() -> idsm_event_transport_tcp {
+        let tcp_config = idsm_event_transport_tcp {
+            ipaddr : "".to_string(),
+            port : 0,
+            interval_sec : 0,
+            spool_dir : "".to_string(),
+            max_spool_bytes : 16 * 1024 * 1024,
+            reconnect_min_sec : 1,
+            reconnect_max_sec : 60
+        };
+        tcp_config
+    }
+
+    pub fn parse(&mut self, config_data :  &serde_json::Value) -> i32 {
+        let tcp_obj = match config_data.get("tcp") {
+            Some(obj) => obj,
+            None => {
+                log::error!("config: tcp is missing");
+                return -1;
+            }
+        };
+
+        if tcp_obj.is_object() {
+            if (get_str(tcp_obj, "ipaddr", true, &mut self.ipaddr) < 0) ||
+               (get_uint(tcp_obj, "port", true, &mut self.port) < 0) ||
+               (get_uint(tcp_obj, "interval_sec", true, &mut self.interval_sec) < 0) ||
+               (get_str(tcp_obj, "spool_dir", true, &mut self.spool_dir) < 0) {
+                return -1;
+            }
+
+            if (get_uint(tcp_obj, "max_spool_bytes", false, &mut self.max_spool_bytes) < 0) ||
+               (get_uint(tcp_obj, "reconnect_min_sec", false, &mut self.reconnect_min_sec) < 0) ||
+               (get_uint(tcp_obj, "reconnect_max_sec", false, &mut self.reconnect_max_sec) < 0) {
+                return -1;
+            }
+
+            if (self.reconnect_min_sec == 0) || (self.reconnect_max_sec < self.reconnect_min_sec) {
+                log::error!("invalid tcp reconnect interval {}..{}",
+                                        self.reconnect_min_sec, self.reconnect_max_sec);
+                return -1;
+            }
+        } else {
+            return -1;
+        }
+
+        return 0;
+    }
+
+    pub fn print(&self) {
+        log::info!("tcp_config: ");
+        log::info!("\t ipaddr: {}", self.ipaddr);
+        log::info!("\t port: {}", self.port);
+        log::info!("\t interval_sec: {}", self.interval_sec);
+        log::info!("\t spool_dir: {}", self.spool_dir);
+        log::info!("\t max_spool_bytes: {}", self.max_spool_bytes);
+        log::info!("\t reconnect_min_sec: {}", self.reconnect_min_sec);
+        log::info!("\t reconnect_max_sec: {}", self.reconnect_max_sec);
+    }
+}
+
 #[non_exhaustive]
-struct idsm_event_transport_type;
+pub struct idsm_event_transport_type;
 
 impl idsm_event_transport_type {
     pub const UDP : u32 = 1;
+    pub const SYSLOG : u32 = 2;
+    pub const TCP : u32 = 3;
 }
 
 pub struct idsm_events_config {
     pub enable : bool,
     pub evt_transport_type : u32,
-    pub udp_config : idsm_event_transport_udp
+    pub encoder_type : u32,
+    pub capacity : usize,
+    pub overflow_policy : u32,
+    pub pkt_snapshot_len : usize,
+    pub aggr_config : idsm_event_aggr_config,
+    pub file_log_config : idsm_event_file_log_config,
+    pub udp_config : idsm_event_transport_udp,
+    pub syslog_config : idsm_event_transport_syslog,
+    pub tcp_config : idsm_event_transport_tcp
 }
 
 impl idsm_events_config {
@@ -92,23 +517,113 @@ impl idsm_events_config {
         let evt_config = idsm_events_config {
             enable : false,
             evt_transport_type : idsm_event_transport_type::UDP,
-            udp_config : idsm_event_transport_udp::new()
+            encoder_type : event_encoder_type::BINARY,
+            capacity : event_mgr::EVT_LIST_DEFAULT_CAPACITY,
+            overflow_policy : event_overflow_policy::DROP_NEWEST,
+            pkt_snapshot_len : 0,
+            aggr_config : idsm_event_aggr_config::new(),
+            file_log_config : idsm_event_file_log_config::new(),
+            udp_config : idsm_event_transport_udp::new(),
+            syslog_config : idsm_event_transport_syslog::new(),
+            tcp_config : idsm_event_transport_tcp::new()
         };
         evt_config
     }
 
     pub fn parse(&mut self, config_data : &serde_json::Value) -> i32 {
-        let evt_obj = config_data.get("events").unwrap();
+        let evt_obj = match config_data.get("events") {
+            Some(obj) => obj,
+            None => {
+                log::error!("config: events is missing");
+                return -1;
+            }
+        };
 
         if evt_obj.is_object() {
-            self.enable = evt_obj.get("enable").unwrap().as_bool().unwrap();
-            let evt_transport_type_str = evt_obj.get("transport").unwrap().as_str().unwrap().to_string();
+            if get_bool(evt_obj, "enable", true, &mut self.enable) < 0 {
+                return -1;
+            }
+            let mut evt_transport_type_str = String::new();
+            if get_str(evt_obj, "transport", true, &mut evt_transport_type_str) < 0 {
+                return -1;
+            }
+
+            match evt_transport_type_str.as_str() {
+                "udp" => self.evt_transport_type = idsm_event_transport_type::UDP,
+                "syslog" => self.evt_transport_type = idsm_event_transport_type::SYSLOG,
+                "tcp" => self.evt_transport_type = idsm_event_transport_type::TCP,
+                _ => {
+                    log::error!("invalid events transport {}", evt_transport_type_str);
+                    return -1;
+                }
+            }
+
+            // encoder is optional, binary event messages by default
+            if evt_obj.get("encoder").is_some() {
+                let mut encoder_str = String::new();
+                if get_str(evt_obj, "encoder", true, &mut encoder_str) < 0 {
+                    return -1;
+                }
+
+                match encoder_str.as_str() {
+                    "binary" => self.encoder_type = event_encoder_type::BINARY,
+                    "json" => self.encoder_type = event_encoder_type::JSON,
+                    "cef" => self.encoder_type = event_encoder_type::CEF,
+                    "leef" => self.encoder_type = event_encoder_type::LEEF,
+                    _ => {
+                        log::error!("invalid events encoder {}", encoder_str);
+                        return -1;
+                    }
+                }
+            }
+
+            // capacity and overflow policy are optional
+            if get_uint(evt_obj, "capacity", false, &mut self.capacity) < 0 {
+                return -1;
+            }
 
-            if evt_transport_type_str == "udp" {
-                self.evt_transport_type = idsm_event_transport_type::UDP;
+            if evt_obj.get("overflow_policy").is_some() {
+                let mut policy_str = String::new();
+                if get_str(evt_obj, "overflow_policy", true, &mut policy_str) < 0 {
+                    return -1;
+                }
+
+                match policy_str.as_str() {
+                    "drop_newest" => self.overflow_policy = event_overflow_policy::DROP_NEWEST,
+                    "drop_oldest" => self.overflow_policy = event_overflow_policy::DROP_OLDEST,
+                    "coalesce" => self.overflow_policy = event_overflow_policy::COALESCE,
+                    _ => {
+                        log::error!("invalid events overflow_policy {}", policy_str);
+                        return -1;
+                    }
+                }
+            }
+
+            // number of frame bytes to attach to each event, optional
+            if get_uint(evt_obj, "pkt_snapshot_len", false, &mut self.pkt_snapshot_len) < 0 {
+                return -1;
+            }
+
+            let mut ret = self.aggr_config.parse(&evt_obj);
+            if ret < 0 {
+                return -1;
+            }
+
+            ret = self.file_log_config.parse(&evt_obj);
+            if ret < 0 {
+                return -1;
             }
 
-            self.udp_config.parse(&evt_obj);
+            // parse the config of the selected transport
+            match self.evt_transport_type {
+                idsm_event_transport_type::UDP => ret = self.udp_config.parse(&evt_obj),
+                idsm_event_transport_type::SYSLOG => ret = self.syslog_config.parse(&evt_obj),
+                idsm_event_transport_type::TCP => ret = self.tcp_config.parse(&evt_obj),
+                _ => ret = -1,
+            }
+            if ret < 0 {
+                return -1;
+            }
         } else {
             return -1;
         }
@@ -120,53 +635,1030 @@ impl idsm_events_config {
         log::info!("event_config: ");
         log::info!("\t enable: {}", self.enable);
         log::info!("\t evt_transport_type: {}", self.evt_transport_type);
-        self.udp_config.print();
+        log::info!("\t encoder_type: {}", self.encoder_type);
+        log::info!("\t capacity: {}", self.capacity);
+        log::info!("\t overflow_policy: {}", self.overflow_policy);
+        log::info!("\t pkt_snapshot_len: {}", self.pkt_snapshot_len);
+        self.aggr_config.print();
+        self.file_log_config.print();
+        match self.evt_transport_type {
+            idsm_event_transport_type::UDP => self.udp_config.print(),
+            idsm_event_transport_type::SYSLOG => self.syslog_config.print(),
+            idsm_event_transport_type::TCP => self.tcp_config.print(),
+            _ => (),
+        }
+    }
+
+    // @brief - get the upload interval of the selected transport
+    //
+    // @param [in] self - events config
+    //
+    // @return upload interval in seconds
+    pub fn interval_sec(&self) -> u32 {
+        match self.evt_transport_type {
+            idsm_event_transport_type::UDP => return self.udp_config.interval_sec,
+            idsm_event_transport_type::SYSLOG => return self.syslog_config.interval_sec,
+            idsm_event_transport_type::TCP => return self.tcp_config.interval_sec,
+            _ => return 0,
+        }
     }
 }
 
-pub struct idsm_config {
-    pub ifname : String,
-    pub pcap_config : idsm_pcap_config,
-    pub evt_config : idsm_events_config
+pub struct idsm_control_config {
+    pub enable : bool,
+    pub path : String
 }
 
-impl idsm_config {
-    pub fn new() -> idsm_config {
-        let config = idsm_config {
-            ifname : "".to_string(),
-            pcap_config : idsm_pcap_config::new(),
-            evt_config : idsm_events_config::new()
+impl idsm_control_config {
+    pub const DEFAULT_PATH : &'static str = "/var/run/idsd.sock";
+
+    pub fn new() -> idsm_control_config {
+        let control_config = idsm_control_config {
+            enable : false,
+            path : idsm_control_config::DEFAULT_PATH.to_string()
         };
-        config
+        control_config
     }
 
-    pub fn parse(&mut self, config_file : &String, debug : bool) -> i32 {
-        let file = fs::File::open(config_file)
-                                            .expect("file should be read-only");
-        let json : serde_json::Value = serde_json::from_reader(file)
-                                            .expect("file must be a valid json");
-        let ifname = json.get("ifname")
-                                            .expect("ifname must contain a valid string").as_str().unwrap();
-        self.ifname = ifname.to_string();
-        let mut ret = self.pcap_config.parse(&json);
-        if ret < 0 {
+    pub fn parse(&mut self, config_data : &serde_json::Value) -> i32 {
+        // control socket is optional
+        let control_obj = match config_data.get("control") {
+            Some(obj) => obj,
+            None => return 0,
+        };
+
+        if control_obj.is_object() {
+            if (get_bool(control_obj, "enable", true, &mut self.enable) < 0) ||
+               (get_str(control_obj, "path", false, &mut self.path) < 0) {
+                return -1;
+            }
+        } else {
             return -1;
         }
 
-        ret = self.evt_config.parse(&json);
-        if ret < 0 {
+        return 0;
+    }
+
+    pub fn print(&self) {
+        log::info!("control_config: ");
+        log::info!("\t enable: {}", self.enable);
+        log::info!("\t path: {}", self.path);
+    }
+}
+
+pub struct idsm_metrics_config {
+    pub enable : bool,
+    pub ipaddr : String,
+    pub port : u16
+}
+
+impl idsm_metrics_config {
+    pub const DEFAULT_IPADDR : &'static str = "127.0.0.1";
+    pub const DEFAULT_PORT : u16 = 9180;
+
+    pub fn new() -> idsm_metrics_config {
+        let metrics_config = idsm_metrics_config {
+            enable : false,
+            ipaddr : idsm_metrics_config::DEFAULT_IPADDR.to_string(),
+            port : idsm_metrics_config::DEFAULT_PORT
+        };
+        metrics_config
+    }
+
+    pub fn parse(&mut self, config_data : &serde_json::Value) -> i32 {
+        // metrics endpoint is optional
+        let metrics_obj = match config_data.get("metrics") {
+            Some(obj) => obj,
+            None => return 0,
+        };
+
+        if metrics_obj.is_object() {
+            if (get_bool(metrics_obj, "enable", true, &mut self.enable) < 0) ||
+               (get_str(metrics_obj, "ipaddr", false, &mut self.ipaddr) < 0) ||
+               (get_uint(metrics_obj, "port", false, &mut self.port) < 0) {
+                return -1;
+            }
+        } else {
             return -1;
         }
 
-        if debug { self.print(); }
-
         return 0;
     }
 
     pub fn print(&self) {
-        log::info!("idsm config data: ");
-        log::info!("ifname: {}", self.ifname);
-        self.pcap_config.print();
-        self.evt_config.print();
+        log::info!("metrics_config: ");
+        log::info!("\t enable: {}", self.enable);
+        log::info!("\t ipaddr: {}", self.ipaddr);
+        log::info!("\t port: {}", self.port);
+    }
+}
+
+pub struct idsm_reasm_config {
+    pub enable : bool,
+    pub timeout_sec : u32,
+    pub max_datagrams : u32,
+    pub max_mem_bytes : u64
+}
+
+impl idsm_reasm_config {
+    pub const DEFAULT_TIMEOUT_SEC : u32 = 30;
+    pub const DEFAULT_MAX_DATAGRAMS : u32 = 1024;
+    pub const DEFAULT_MAX_MEM_BYTES : u64 = 4194304;
+
+    pub fn new() -> idsm_reasm_config {
+        let reasm_config = idsm_reasm_config {
+            enable : true,
+            timeout_sec : idsm_reasm_config::DEFAULT_TIMEOUT_SEC,
+            max_datagrams : idsm_reasm_config::DEFAULT_MAX_DATAGRAMS,
+            max_mem_bytes : idsm_reasm_config::DEFAULT_MAX_MEM_BYTES
+        };
+        reasm_config
+    }
+
+    pub fn parse(&mut self, config_data : &serde_json::Value) -> i32 {
+        // reassembly is on with the default limits unless configured
+        let reasm_obj = match config_data.get("reassembly") {
+            Some(obj) => obj,
+            None => return 0,
+        };
+
+        if reasm_obj.is_object() {
+            if (get_bool(reasm_obj, "enable", true, &mut self.enable) < 0) ||
+               (get_uint(reasm_obj, "timeout_sec", false, &mut self.timeout_sec) < 0) ||
+               (get_uint(reasm_obj, "max_datagrams", false, &mut self.max_datagrams) < 0) ||
+               (get_uint(reasm_obj, "max_mem_bytes", false, &mut self.max_mem_bytes) < 0) {
+                return -1;
+            }
+        } else {
+            return -1;
+        }
+
+        return 0;
+    }
+
+    pub fn print(&self) {
+        log::info!("\t\t reassembly: ");
+        log::info!("\t\t\t enable: {}", self.enable);
+        log::info!("\t\t\t timeout_sec: {}", self.timeout_sec);
+        log::info!("\t\t\t max_datagrams: {}", self.max_datagrams);
+        log::info!("\t\t\t max_mem_bytes: {}", self.max_mem_bytes);
+    }
+}
+
+pub struct idsm_ipv4_config {
+    pub check_checksum : bool,
+    pub check_total_len : bool,
+    pub check_ttl : bool,
+    pub min_ttl : u8,
+    pub check_src_addr : bool,
+    pub check_land : bool,
+    pub reasm_config : idsm_reasm_config
+}
+
+impl idsm_ipv4_config {
+    pub const DEFAULT_MIN_TTL : u8 = 1;
+
+    pub fn new() -> idsm_ipv4_config {
+        let ipv4_config = idsm_ipv4_config {
+            check_checksum : true,
+            check_total_len : true,
+            check_ttl : true,
+            min_ttl : idsm_ipv4_config::DEFAULT_MIN_TTL,
+            check_src_addr : true,
+            check_land : true,
+            reasm_config : idsm_reasm_config::new()
+        };
+        ipv4_config
+    }
+
+    pub fn parse(&mut self, config_data : &serde_json::Value) -> i32 {
+        // every check is enabled unless turned off
+        let ipv4_obj = match config_data.get("ipv4") {
+            Some(obj) => obj,
+            None => return 0,
+        };
+
+        if ipv4_obj.is_object() {
+            if (get_bool(ipv4_obj, "check_checksum", false, &mut self.check_checksum) < 0) ||
+               (get_bool(ipv4_obj, "check_total_len", false, &mut self.check_total_len) < 0) ||
+               (get_bool(ipv4_obj, "check_ttl", false, &mut self.check_ttl) < 0) ||
+               (get_uint(ipv4_obj, "min_ttl", false, &mut self.min_ttl) < 0) ||
+               (get_bool(ipv4_obj, "check_src_addr", false, &mut self.check_src_addr) < 0) ||
+               (get_bool(ipv4_obj, "check_land", false, &mut self.check_land) < 0) {
+                return -1;
+            }
+            if self.reasm_config.parse(ipv4_obj) < 0 {
+                return -1;
+            }
+        } else {
+            return -1;
+        }
+
+        return 0;
+    }
+
+    pub fn print(&self) {
+        log::info!("\t ipv4_config: ");
+        log::info!("\t\t check_checksum: {}", self.check_checksum);
+        log::info!("\t\t check_total_len: {}", self.check_total_len);
+        log::info!("\t\t check_ttl: {}", self.check_ttl);
+        log::info!("\t\t min_ttl: {}", self.min_ttl);
+        log::info!("\t\t check_src_addr: {}", self.check_src_addr);
+        log::info!("\t\t check_land: {}", self.check_land);
+        self.reasm_config.print();
+    }
+}
+
+pub struct idsm_ipv6_config {
+    pub max_ext_hdrs : u32,
+    pub reasm_config : idsm_reasm_config
+}
+
+impl idsm_ipv6_config {
+    pub const DEFAULT_MAX_EXT_HDRS : u32 = 8;
+
+    pub fn new() -> idsm_ipv6_config {
+        let ipv6_config = idsm_ipv6_config {
+            max_ext_hdrs : idsm_ipv6_config::DEFAULT_MAX_EXT_HDRS,
+            reasm_config : idsm_reasm_config::new()
+        };
+        ipv6_config
+    }
+
+    pub fn parse(&mut self, config_data : &serde_json::Value) -> i32 {
+        let ipv6_obj = match config_data.get("ipv6") {
+            Some(obj) => obj,
+            None => return 0,
+        };
+
+        if ipv6_obj.is_object() {
+            if get_uint(ipv6_obj, "max_ext_hdrs", false, &mut self.max_ext_hdrs) < 0 {
+                return -1;
+            }
+            if self.reasm_config.parse(ipv6_obj) < 0 {
+                return -1;
+            }
+        } else {
+            return -1;
+        }
+
+        return 0;
+    }
+
+    pub fn print(&self) {
+        log::info!("\t ipv6_config: ");
+        log::info!("\t\t max_ext_hdrs: {}", self.max_ext_hdrs);
+        self.reasm_config.print();
+    }
+}
+
+pub struct idsm_conn_track_config {
+    pub enable : bool,
+    pub midstream : bool,
+    pub max_conns : u32,
+    pub syn_timeout_sec : u32,
+    pub established_timeout_sec : u32,
+    pub fin_timeout_sec : u32,
+    pub close_timeout_sec : u32
+}
+
+impl idsm_conn_track_config {
+    pub const DEFAULT_MAX_CONNS : u32 = 65536;
+    pub const DEFAULT_SYN_TIMEOUT_SEC : u32 = 30;
+    pub const DEFAULT_ESTABLISHED_TIMEOUT_SEC : u32 = 3600;
+    pub const DEFAULT_FIN_TIMEOUT_SEC : u32 = 120;
+    pub const DEFAULT_CLOSE_TIMEOUT_SEC : u32 = 10;
+
+    pub fn new() -> idsm_conn_track_config {
+        let conn_track_config = idsm_conn_track_config {
+            enable : true,
+            midstream : false,
+            max_conns : idsm_conn_track_config::DEFAULT_MAX_CONNS,
+            syn_timeout_sec : idsm_conn_track_config::DEFAULT_SYN_TIMEOUT_SEC,
+            established_timeout_sec : idsm_conn_track_config::DEFAULT_ESTABLISHED_TIMEOUT_SEC,
+            fin_timeout_sec : idsm_conn_track_config::DEFAULT_FIN_TIMEOUT_SEC,
+            close_timeout_sec : idsm_conn_track_config::DEFAULT_CLOSE_TIMEOUT_SEC
+        };
+        conn_track_config
+    }
+
+    pub fn parse(&mut self, config_data : &serde_json::Value) -> i32 {
+        let conn_track_obj = match config_data.get("conn_track") {
+            Some(obj) => obj,
+            None => return 0,
+        };
+
+        if conn_track_obj.is_object() {
+            if (get_bool(conn_track_obj, "enable", true, &mut self.enable) < 0) ||
+               (get_bool(conn_track_obj, "midstream", false, &mut self.midstream) < 0) ||
+               (get_uint(conn_track_obj, "max_conns", false, &mut self.max_conns) < 0) ||
+               (get_uint(conn_track_obj, "syn_timeout_sec", false, &mut self.syn_timeout_sec) < 0) ||
+               (get_uint(conn_track_obj, "established_timeout_sec", false, &mut self.established_timeout_sec) < 0) ||
+               (get_uint(conn_track_obj, "fin_timeout_sec", false, &mut self.fin_timeout_sec) < 0) ||
+               (get_uint(conn_track_obj, "close_timeout_sec", false, &mut self.close_timeout_sec) < 0) {
+                return -1;
+            }
+        } else {
+            return -1;
+        }
+
+        return 0;
+    }
+
+    pub fn print(&self) {
+        log::info!("\t\t conn_track: ");
+        log::info!("\t\t\t enable: {}", self.enable);
+        log::info!("\t\t\t midstream: {}", self.midstream);
+        log::info!("\t\t\t max_conns: {}", self.max_conns);
+        log::info!("\t\t\t syn_timeout_sec: {}", self.syn_timeout_sec);
+        log::info!("\t\t\t established_timeout_sec: {}", self.established_timeout_sec);
+        log::info!("\t\t\t fin_timeout_sec: {}", self.fin_timeout_sec);
+        log::info!("\t\t\t close_timeout_sec: {}", self.close_timeout_sec);
+    }
+}
+
+pub struct idsm_syn_flood_config {
+    pub enable : bool,
+    pub window_sec : u32,
+    pub dst_threshold : u32,
+    pub src_threshold : u32,
+    pub top_srcs : u32,
+    pub max_hosts : u32
+}
+
+impl idsm_syn_flood_config {
+    pub const DEFAULT_WINDOW_SEC : u32 = 10;
+    pub const DEFAULT_DST_THRESHOLD : u32 = 500;
+    pub const DEFAULT_SRC_THRESHOLD : u32 = 100;
+    pub const DEFAULT_TOP_SRCS : u32 = 5;
+    pub const DEFAULT_MAX_HOSTS : u32 = 16384;
+
+    pub fn new() -> idsm_syn_flood_config {
+        let syn_flood_config = idsm_syn_flood_config {
+            enable : true,
+            window_sec : idsm_syn_flood_config::DEFAULT_WINDOW_SEC,
+            dst_threshold : idsm_syn_flood_config::DEFAULT_DST_THRESHOLD,
+            src_threshold : idsm_syn_flood_config::DEFAULT_SRC_THRESHOLD,
+            top_srcs : idsm_syn_flood_config::DEFAULT_TOP_SRCS,
+            max_hosts : idsm_syn_flood_config::DEFAULT_MAX_HOSTS
+        };
+        syn_flood_config
+    }
+
+    pub fn parse(&mut self, config_data : &serde_json::Value) -> i32 {
+        let syn_flood_obj = match config_data.get("syn_flood") {
+            Some(obj) => obj,
+            None => return 0,
+        };
+
+        if syn_flood_obj.is_object() {
+            if (get_bool(syn_flood_obj, "enable", true, &mut self.enable) < 0) ||
+               (get_uint(syn_flood_obj, "window_sec", false, &mut self.window_sec) < 0) ||
+               (get_uint(syn_flood_obj, "dst_threshold", false, &mut self.dst_threshold) < 0) ||
+               (get_uint(syn_flood_obj, "src_threshold", false, &mut self.src_threshold) < 0) ||
+               (get_uint(syn_flood_obj, "top_srcs", false, &mut self.top_srcs) < 0) ||
+               (get_uint(syn_flood_obj, "max_hosts", false, &mut self.max_hosts) < 0) {
+                return -1;
+            }
+        } else {
+            return -1;
+        }
+
+        if self.window_sec == 0 {
+            log::error!("config: syn_flood window_sec must not be 0");
+            return -1;
+        }
+
+        return 0;
+    }
+
+    pub fn print(&self) {
+        log::info!("\t\t syn_flood: ");
+        log::info!("\t\t\t enable: {}", self.enable);
+        log::info!("\t\t\t window_sec: {}", self.window_sec);
+        log::info!("\t\t\t dst_threshold: {}", self.dst_threshold);
+        log::info!("\t\t\t src_threshold: {}", self.src_threshold);
+        log::info!("\t\t\t top_srcs: {}", self.top_srcs);
+        log::info!("\t\t\t max_hosts: {}", self.max_hosts);
+    }
+}
+
+pub struct idsm_tcp_config {
+    pub conn_track_config : idsm_conn_track_config,
+    pub syn_flood_config : idsm_syn_flood_config
+}
+
+impl idsm_tcp_config {
+    pub fn new() -> idsm_tcp_config {
+        let tcp_config = idsm_tcp_config {
+            conn_track_config : idsm_conn_track_config::new(),
+            syn_flood_config : idsm_syn_flood_config::new()
+        };
+        tcp_config
+    }
+
+    pub fn parse(&mut self, config_data : &serde_json::Value) -> i32 {
+        let tcp_obj = match config_data.get("tcp") {
+            Some(obj) => obj,
+            None => return 0,
+        };
+
+        if !tcp_obj.is_object() {
+            return -1;
+        }
+
+        if self.conn_track_config.parse(tcp_obj) < 0 {
+            return -1;
+        }
+
+        return self.syn_flood_config.parse(tcp_obj);
+    }
+
+    pub fn print(&self) {
+        log::info!("\t tcp_config: ");
+        self.conn_track_config.print();
+        self.syn_flood_config.print();
+    }
+}
+
+pub struct idsm_port_scan_config {
+    pub enable : bool,
+    pub window_sec : u32,
+    pub port_threshold : u32,
+    pub host_threshold : u32,
+    pub max_srcs : u32,
+    pub max_probes : u32
+}
+
+impl idsm_port_scan_config {
+    pub const DEFAULT_WINDOW_SEC : u32 = 60;
+    pub const DEFAULT_PORT_THRESHOLD : u32 = 25;
+    pub const DEFAULT_HOST_THRESHOLD : u32 = 25;
+    pub const DEFAULT_MAX_SRCS : u32 = 4096;
+    pub const DEFAULT_MAX_PROBES : u32 = 1024;
+
+    pub fn new() -> idsm_port_scan_config {
+        let port_scan_config = idsm_port_scan_config {
+            enable : true,
+            window_sec : idsm_port_scan_config::DEFAULT_WINDOW_SEC,
+            port_threshold : idsm_port_scan_config::DEFAULT_PORT_THRESHOLD,
+            host_threshold : idsm_port_scan_config::DEFAULT_HOST_THRESHOLD,
+            max_srcs : idsm_port_scan_config::DEFAULT_MAX_SRCS,
+            max_probes : idsm_port_scan_config::DEFAULT_MAX_PROBES
+        };
+        port_scan_config
+    }
+
+    pub fn parse(&mut self, config_data : &serde_json::Value) -> i32 {
+        let port_scan_obj = match config_data.get("port_scan") {
+            Some(obj) => obj,
+            None => return 0,
+        };
+
+        if port_scan_obj.is_object() {
+            if (get_bool(port_scan_obj, "enable", true, &mut self.enable) < 0) ||
+               (get_uint(port_scan_obj, "window_sec", false, &mut self.window_sec) < 0) ||
+               (get_uint(port_scan_obj, "port_threshold", false, &mut self.port_threshold) < 0) ||
+               (get_uint(port_scan_obj, "host_threshold", false, &mut self.host_threshold) < 0) ||
+               (get_uint(port_scan_obj, "max_srcs", false, &mut self.max_srcs) < 0) ||
+               (get_uint(port_scan_obj, "max_probes", false, &mut self.max_probes) < 0) {
+                return -1;
+            }
+        } else {
+            return -1;
+        }
+
+        return 0;
+    }
+
+    pub fn print(&self) {
+        log::info!("\t port_scan: ");
+        log::info!("\t\t enable: {}", self.enable);
+        log::info!("\t\t window_sec: {}", self.window_sec);
+        log::info!("\t\t port_threshold: {}", self.port_threshold);
+        log::info!("\t\t host_threshold: {}", self.host_threshold);
+        log::info!("\t\t max_srcs: {}", self.max_srcs);
+        log::info!("\t\t max_probes: {}", self.max_probes);
+    }
+}
+
+// @brief - parse a mac address written as aa:bb:cc:dd:ee:ff
+//
+// @param [in] mac_str - mac address as string
+// @param [out] mac - mac address
+//
+// @return 0 on success -1 on failure
+pub fn parse_mac(mac_str : &str, mac : &mut [u8; 6]) -> i32 {
+    let octets : Vec<&str> = mac_str.split(':').collect();
+
+    if octets.len() != mac.len() {
+        return -1;
+    }
+
+    for i in 0..mac.len() {
+        if octets[i].len() != 2 {
+            return -1;
+        }
+        match u8::from_str_radix(octets[i], 16) {
+            Ok(octet) => mac[i] = octet,
+            Err(_) => return -1,
+        }
+    }
+
+    return 0;
+}
+
+pub struct idsm_arp_guard_config {
+    pub enable : bool,
+    pub max_bindings : u32,
+    pub binding_timeout_sec : u32,
+    pub request_timeout_sec : u32,
+    pub garp_window_sec : u32,
+    pub garp_threshold : u32,
+    // ipv4 address and mac address, host byte order
+    pub static_bindings : Vec<(u32, [u8; 6])>
+}
+
+impl idsm_arp_guard_config {
+    pub const DEFAULT_MAX_BINDINGS : u32 = 4096;
+    pub const DEFAULT_BINDING_TIMEOUT_SEC : u32 = 14400;
+    pub const DEFAULT_REQUEST_TIMEOUT_SEC : u32 = 5;
+    pub const DEFAULT_GARP_WINDOW_SEC : u32 = 10;
+    pub const DEFAULT_GARP_THRESHOLD : u32 = 10;
+
+    pub fn new() -> idsm_arp_guard_config {
+        let arp_guard_config = idsm_arp_guard_config {
+            enable : true,
+            max_bindings : idsm_arp_guard_config::DEFAULT_MAX_BINDINGS,
+            binding_timeout_sec : idsm_arp_guard_config::DEFAULT_BINDING_TIMEOUT_SEC,
+            request_timeout_sec : idsm_arp_guard_config::DEFAULT_REQUEST_TIMEOUT_SEC,
+            garp_window_sec : idsm_arp_guard_config::DEFAULT_GARP_WINDOW_SEC,
+            garp_threshold : idsm_arp_guard_config::DEFAULT_GARP_THRESHOLD,
+            static_bindings : Vec::new()
+        };
+        arp_guard_config
+    }
+
+    fn parse_static_bindings(&mut self, bindings : &serde_json::Value) -> i32 {
+        let bindings_list = match bindings.as_array() {
+            Some(list) => list,
+            None => return -1,
+        };
+
+        for binding in bindings_list {
+            let mut ipaddr_str = String::new();
+            let mut mac_str = String::new();
+            let mut mac : [u8; 6] = [0; 6];
+
+            if (get_str(binding, "ipaddr", true, &mut ipaddr_str) < 0) ||
+               (get_str(binding, "mac", true, &mut mac_str) < 0) {
+                return -1;
+            }
+
+            let ipaddr = match ipaddr_str.parse::<std::net::Ipv4Addr>() {
+                Ok(ipaddr) => ipaddr,
+                Err(_) => {
+                    log::error!("config: arp_guard invalid ipaddr {}", ipaddr_str);
+                    return -1;
+                }
+            };
+            if parse_mac(&mac_str, &mut mac) < 0 {
+                log::error!("config: arp_guard invalid mac {}", mac_str);
+                return -1;
+            }
+
+            self.static_bindings.push((u32::from(ipaddr), mac));
+        }
+
+        return 0;
+    }
+
+    pub fn parse(&mut self, config_data : &serde_json::Value) -> i32 {
+        let arp_guard_obj = match config_data.get("arp_guard") {
+            Some(obj) => obj,
+            None => return 0,
+        };
+
+        if arp_guard_obj.is_object() {
+            if (get_bool(arp_guard_obj, "enable", true, &mut self.enable) < 0) ||
+               (get_uint(arp_guard_obj, "max_bindings", false, &mut self.max_bindings) < 0) ||
+               (get_uint(arp_guard_obj, "binding_timeout_sec", false, &mut self.binding_timeout_sec) < 0) ||
+               (get_uint(arp_guard_obj, "request_timeout_sec", false, &mut self.request_timeout_sec) < 0) ||
+               (get_uint(arp_guard_obj, "garp_window_sec", false, &mut self.garp_window_sec) < 0) ||
+               (get_uint(arp_guard_obj, "garp_threshold", false, &mut self.garp_threshold) < 0) {
+                return -1;
+            }
+            if let Some(static_bindings) = arp_guard_obj.get("static_bindings") {
+                if self.parse_static_bindings(static_bindings) < 0 {
+                    return -1;
+                }
+            }
+        } else {
+            return -1;
+        }
+
+        if self.garp_window_sec == 0 {
+            log::error!("config: arp_guard garp_window_sec must not be 0");
+            return -1;
+        }
+
+        return 0;
+    }
+
+    pub fn print(&self) {
+        log::info!("\t arp_guard: ");
+        log::info!("\t\t enable: {}", self.enable);
+        log::info!("\t\t max_bindings: {}", self.max_bindings);
+        log::info!("\t\t binding_timeout_sec: {}", self.binding_timeout_sec);
+        log::info!("\t\t request_timeout_sec: {}", self.request_timeout_sec);
+        log::info!("\t\t garp_window_sec: {}", self.garp_window_sec);
+        log::info!("\t\t garp_threshold: {}", self.garp_threshold);
+        for (ipaddr, mac) in &self.static_bindings {
+            log::info!("\t\t static_binding: {} {:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}",
+                       std::net::Ipv4Addr::from(*ipaddr),
+                       mac[0], mac[1], mac[2], mac[3], mac[4], mac[5]);
+        }
+    }
+}
+
+pub struct idsm_ra_guard_config {
+    pub enable : bool,
+    pub allowed_macs : Vec<[u8; 6]>,
+    pub allowed_link_locals : Vec<[u8; 16]>
+}
+
+impl idsm_ra_guard_config {
+    pub fn new() -> idsm_ra_guard_config {
+        let ra_guard_config = idsm_ra_guard_config {
+            enable : false,
+            allowed_macs : Vec::new(),
+            allowed_link_locals : Vec::new()
+        };
+        ra_guard_config
+    }
+
+    fn parse_allowed_macs(&mut self, macs : &serde_json::Value) -> i32 {
+        let macs_list = match macs.as_array() {
+            Some(list) => list,
+            None => return -1,
+        };
+
+        for mac_val in macs_list {
+            let mac_str = match mac_val.as_str() {
+                Some(mac_str) => mac_str,
+                None => {
+                    log::error!("config: ra_guard invalid mac {}", mac_val);
+                    return -1;
+                }
+            };
+            let mut mac : [u8; 6] = [0; 6];
+
+            if parse_mac(mac_str, &mut mac) < 0 {
+                log::error!("config: ra_guard invalid mac {}", mac_str);
+                return -1;
+            }
+
+            self.allowed_macs.push(mac);
+        }
+
+        return 0;
+    }
+
+    fn parse_allowed_link_locals(&mut self, addrs : &serde_json::Value) -> i32 {
+        let addrs_list = match addrs.as_array() {
+            Some(list) => list,
+            None => return -1,
+        };
+
+        for addr_val in addrs_list {
+            let addr_str = match addr_val.as_str() {
+                Some(addr_str) => addr_str,
+                None => {
+                    log::error!("config: ra_guard invalid link-local {}", addr_val);
+                    return -1;
+                }
+            };
+
+            // routers advertise from their link-local address only
+            match addr_str.parse::<std::net::Ipv6Addr>() {
+                Ok(addr) if addr.is_unicast_link_local() => self.allowed_link_locals.push(addr.octets()),
+                _ => {
+                    log::error!("config: ra_guard invalid link-local {}", addr_str);
+                    return -1;
+                }
+            }
+        }
+
+        return 0;
+    }
+
+    pub fn parse(&mut self, config_data : &serde_json::Value) -> i32 {
+        let ra_guard_obj = match config_data.get("ra_guard") {
+            Some(obj) => obj,
+            None => return 0,
+        };
+
+        if ra_guard_obj.is_object() {
+            if get_bool(ra_guard_obj, "enable", true, &mut self.enable) < 0 {
+                return -1;
+            }
+            if let Some(allowed_macs) = ra_guard_obj.get("allowed_macs") {
+                if self.parse_allowed_macs(allowed_macs) < 0 {
+                    return -1;
+                }
+            }
+            if let Some(allowed_link_locals) = ra_guard_obj.get("allowed_link_locals") {
+                if self.parse_allowed_link_locals(allowed_link_locals) < 0 {
+                    return -1;
+                }
+            }
+        } else {
+            return -1;
+        }
+
+        return 0;
+    }
+
+    pub fn print(&self) {
+        log::info!("\t\t ra_guard: ");
+        log::info!("\t\t\t enable: {}", self.enable);
+        for mac in &self.allowed_macs {
+            log::info!("\t\t\t allowed_mac: {:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}",
+                       mac[0], mac[1], mac[2], mac[3], mac[4], mac[5]);
+        }
+        for addr in &self.allowed_link_locals {
+            log::info!("\t\t\t allowed_link_local: {}", std::net::Ipv6Addr::from(*addr));
+        }
+    }
+}
+
+pub struct idsm_nd_guard_config {
+    pub enable : bool,
+    pub max_bindings : u32,
+    pub binding_timeout_sec : u32,
+    pub dad_timeout_sec : u32,
+    pub ra_guard_config : idsm_ra_guard_config
+}
+
+impl idsm_nd_guard_config {
+    pub const DEFAULT_MAX_BINDINGS : u32 = 4096;
+    pub const DEFAULT_BINDING_TIMEOUT_SEC : u32 = 14400;
+    pub const DEFAULT_DAD_TIMEOUT_SEC : u32 = 3;
+
+    pub fn new() -> idsm_nd_guard_config {
+        let nd_guard_config = idsm_nd_guard_config {
+            enable : true,
+            max_bindings : idsm_nd_guard_config::DEFAULT_MAX_BINDINGS,
+            binding_timeout_sec : idsm_nd_guard_config::DEFAULT_BINDING_TIMEOUT_SEC,
+            dad_timeout_sec : idsm_nd_guard_config::DEFAULT_DAD_TIMEOUT_SEC,
+            ra_guard_config : idsm_ra_guard_config::new()
+        };
+        nd_guard_config
+    }
+
+    pub fn parse(&mut self, config_data : &serde_json::Value) -> i32 {
+        let nd_guard_obj = match config_data.get("nd_guard") {
+            Some(obj) => obj,
+            None => return 0,
+        };
+
+        if nd_guard_obj.is_object() {
+            if (get_bool(nd_guard_obj, "enable", true, &mut self.enable) < 0) ||
+               (get_uint(nd_guard_obj, "max_bindings", false, &mut self.max_bindings) < 0) ||
+               (get_uint(nd_guard_obj, "binding_timeout_sec", false, &mut self.binding_timeout_sec) < 0) ||
+               (get_uint(nd_guard_obj, "dad_timeout_sec", false, &mut self.dad_timeout_sec) < 0) {
+                return -1;
+            }
+            if self.ra_guard_config.parse(nd_guard_obj) < 0 {
+                return -1;
+            }
+        } else {
+            return -1;
+        }
+
+        return 0;
+    }
+
+    pub fn print(&self) {
+        log::info!("\t nd_guard: ");
+        log::info!("\t\t enable: {}", self.enable);
+        log::info!("\t\t max_bindings: {}", self.max_bindings);
+        log::info!("\t\t binding_timeout_sec: {}", self.binding_timeout_sec);
+        log::info!("\t\t dad_timeout_sec: {}", self.dad_timeout_sec);
+        self.ra_guard_config.print();
+    }
+}
+
+pub struct idsm_protocols_config {
+    // NICs with checksum offload hand over frames we send with the
+    // TCP and UDP checksum not yet filled in
+    pub verify_l4_checksum : bool,
+    pub ipv4_config : idsm_ipv4_config,
+    pub ipv6_config : idsm_ipv6_config,
+    pub tcp_config : idsm_tcp_config,
+    pub port_scan_config : idsm_port_scan_config,
+    pub arp_guard_config : idsm_arp_guard_config,
+    pub nd_guard_config : idsm_nd_guard_config
+}
+
+impl idsm_protocols_config {
+    pub fn new() -> idsm_protocols_config {
+        let protocols_config = idsm_protocols_config {
+            verify_l4_checksum : true,
+            ipv4_config : idsm_ipv4_config::new(),
+            ipv6_config : idsm_ipv6_config::new(),
+            tcp_config : idsm_tcp_config::new(),
+            port_scan_config : idsm_port_scan_config::new(),
+            arp_guard_config : idsm_arp_guard_config::new(),
+            nd_guard_config : idsm_nd_guard_config::new()
+        };
+        protocols_config
+    }
+
+    pub fn parse(&mut self, config_data : &serde_json::Value) -> i32 {
+        // protocol checks are optional, defaults apply when missing
+        let protocols_obj = match config_data.get("protocols") {
+            Some(obj) => obj,
+            None => return 0,
+        };
+
+        if !protocols_obj.is_object() {
+            return -1;
+        }
+
+        if get_bool(protocols_obj, "verify_l4_checksum", false, &mut self.verify_l4_checksum) < 0 {
+            return -1;
+        }
+
+        if self.ipv4_config.parse(protocols_obj) < 0 {
+            return -1;
+        }
+
+        if self.ipv6_config.parse(protocols_obj) < 0 {
+            return -1;
+        }
+
+        if self.tcp_config.parse(protocols_obj) < 0 {
+            return -1;
+        }
+
+        if self.port_scan_config.parse(protocols_obj) < 0 {
+            return -1;
+        }
+
+        if self.arp_guard_config.parse(protocols_obj) < 0 {
+            return -1;
+        }
+
+        return self.nd_guard_config.parse(protocols_obj);
+    }
+
+    pub fn print(&self) {
+        log::info!("protocols_config: ");
+        log::info!("\t verify_l4_checksum: {}", self.verify_l4_checksum);
+        self.ipv4_config.print();
+        self.ipv6_config.print();
+        self.tcp_config.print();
+        self.port_scan_config.print();
+        self.arp_guard_config.print();
+        self.nd_guard_config.print();
+    }
+}
+
+pub struct idsm_config {
+    pub ifname : String,
+    pub pcap_config : idsm_pcap_config,
+    pub evt_config : idsm_events_config,
+    pub control_config : idsm_control_config,
+    pub metrics_config : idsm_metrics_config,
+    pub protocols_config : idsm_protocols_config
+}
+
+impl idsm_config {
+    pub fn new() -> idsm_config {
+        let config = idsm_config {
+            ifname : "".to_string(),
+            pcap_config : idsm_pcap_config::new(),
+            evt_config : idsm_events_config::new(),
+            control_config : idsm_control_config::new(),
+            metrics_config : idsm_metrics_config::new(),
+            protocols_config : idsm_protocols_config::new()
+        };
+        config
+    }
+
+    pub fn parse(&mut self, config_file : &String, debug : bool) -> i32 {
+        // the config is also parsed again at runtime on reload-config,
+        // so a bad file must not bring down the daemon
+        let file = match fs::File::open(config_file) {
+            Ok(f) => f,
+            Err(e) => {
+                log::error!("config: cannot open {}: {}", config_file, e);
+                return -1;
+            }
+        };
+        let json : serde_json::Value = match serde_json::from_reader(file) {
+            Ok(j) => j,
+            Err(e) => {
+                log::error!("config: {} is not a valid json: {}", config_file, e);
+                return -1;
+            }
+        };
+        let ifname = match json.get("ifname").and_then(|v| v.as_str()) {
+            Some(i) => i,
+            None => {
+                log::error!("config: ifname must contain a valid string");
+                return -1;
+            }
+        };
+        self.ifname = ifname.to_string();
+        let mut ret = self.pcap_config.parse(&json);
+        if ret < 0 {
+            return -1;
+        }
+
+        ret = self.evt_config.parse(&json);
+        if ret < 0 {
+            return -1;
+        }
+
+        ret = self.control_config.parse(&json);
+        if ret < 0 {
+            return -1;
+        }
+
+        ret = self.metrics_config.parse(&json);
+        if ret < 0 {
+            return -1;
+        }
+
+        ret = self.protocols_config.parse(&json);
+        if ret < 0 {
+            return -1;
+        }
+
+        if debug { self.print(); }
+
+        return 0;
+    }
+
+    pub fn print(&self) {
+        log::info!("idsm config data: ");
+        log::info!("ifname: {}", self.ifname);
+        self.pcap_config.print();
+        self.evt_config.print();
+        self.control_config.print();
+        self.metrics_config.print();
+        self.protocols_config.print();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn udp_transport() {
+        let mut udp_config = idsm_event_transport_udp::new();
+        let config = json!({ "udp": { "ipaddr": "127.0.0.1", "port": 9000, "interval_sec": 1 } });
+
+        assert_eq!(udp_config.parse(&config), 0);
+        assert_eq!((udp_config.ipaddr.as_str(), udp_config.port, udp_config.interval_sec), ("127.0.0.1", 9000, 1));
+
+        // required key missing, mistyped and out of range
+        let mut udp_config = idsm_event_transport_udp::new();
+        assert_eq!(udp_config.parse(&json!({ "udp": { "port": 9000, "interval_sec": 1 } })), -1);
+        assert_eq!(udp_config.parse(&json!({ "udp": { "ipaddr": "127.0.0.1", "port": "9000", "interval_sec": 1 } })), -1);
+        assert_eq!(udp_config.parse(&json!({ "udp": { "ipaddr": "127.0.0.1", "port": 70000, "interval_sec": 1 } })), -1);
+        assert_eq!(udp_config.parse(&json!({})), -1);
+    }
+
+    #[test]
+    fn optional_keys() {
+        let mut syn_flood_config = idsm_syn_flood_config::new();
+
+        assert_eq!(syn_flood_config.parse(&json!({ "syn_flood": { "enable": false, "dst_threshold": 7 } })), 0);
+        assert!(!syn_flood_config.enable);
+        assert_eq!(syn_flood_config.dst_threshold, 7);
+        assert_eq!(syn_flood_config.window_sec, idsm_syn_flood_config::DEFAULT_WINDOW_SEC);
+
+        // an optional key of the wrong type is an error
+        assert_eq!(syn_flood_config.parse(&json!({ "syn_flood": { "enable": true, "window_sec": "10" } })), -1);
+        assert_eq!(syn_flood_config.parse(&json!({ "syn_flood": { "window_sec": 10 } })), -1);
+    }
+
+    #[test]
+    fn static_bindings() {
+        let mut arp_guard_config = idsm_arp_guard_config::new();
+        let config = json!({ "arp_guard": { "enable": true, "static_bindings": [
+            { "ipaddr": "10.0.0.1", "mac": "00:11:22:33:44:55" }
+        ] } });
+
+        assert_eq!(arp_guard_config.parse(&config), 0);
+        assert_eq!(arp_guard_config.static_bindings, vec![(0x0A000001, [0x00, 0x11, 0x22, 0x33, 0x44, 0x55])]);
+
+        let config = json!({ "arp_guard": { "enable": true, "static_bindings": [ { "ipaddr": "10.0.0.1" } ] } });
+        assert_eq!(idsm_arp_guard_config::new().parse(&config), -1);
     }
 }
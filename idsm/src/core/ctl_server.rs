@@ -0,0 +1,228 @@
+// @brief - implements the control socket of idsd
+// @copyright - Devendra Naga 2024-present All rights reserved.
+#![allow(dead_code)]
+#![allow(non_camel_case_types)]
+
+use crate::lib::socket_linux::{stream_conn::stream_conn, unix_server::unix_server};
+
+// @brief - defines a control request
+//
+// @details - a request is a single line of text, the command followed
+//            by its arguments separated by spaces
+//
+//            stats
+//            events [since]
+//            clear-stats
+//            reload-config
+//            pcap rotate
+//
+//            every request is answered with a single JSON object followed
+//            by a newline and the connection is closed.
+pub struct ctl_request {
+    pub cmd     : String,
+    pub args    : Vec<String>
+}
+
+impl ctl_request {
+    pub fn new() -> ctl_request {
+        let req = ctl_request {
+            cmd     : "".to_string(),
+            args    : Vec::new()
+        };
+        req
+    }
+}
+
+// @brief - defines the control server
+pub struct ctl_server {
+    server      : unix_server,
+    conn        : stream_conn
+}
+
+impl ctl_server {
+    const REQUEST_MAX_LEN   : usize = 512;
+    const CONN_TIMEOUT_SEC  : u32 = 1;
+    // the request is read in the packet loop, a slow client is cut off
+    const REQUEST_TIMEOUT_MS : u32 = 200;
+    const REQUEST_MAX_RECVS  : u32 = 8;
+
+    pub fn new() -> ctl_server {
+        let ctl = ctl_server {
+            server      : unix_server::new(),
+            conn        : stream_conn::new(-1)
+        };
+        ctl
+    }
+
+    // @brief - create the control socket
+    //
+    // @param [in] self - control server
+    // @param [in] path - path of the control socket
+    //
+    // @return 0 on success -1 on failure
+    pub fn init(&mut self, path : &String) -> i32 {
+        let ret : i32;
+
+        ret = self.server.create(path);
+        if ret < 0 {
+            log::error!("ctl_server: cannot create control socket {}", path);
+            return -1;
+        }
+
+        log::info!("ctl_server: listening on {}", path);
+
+        return 0;
+    }
+
+    // @brief - returns the socket to register with the select
+    pub fn get(&self) -> i32 { return self.server.get(); }
+
+    // @brief - accept a connection and read the request
+    //
+    // @param [in] self - control server
+    // @param [out] req - request
+    //
+    // @return 0 on success -1 on failure
+    //
+    // @details - the connection is kept until respond() is called. a slow
+    //            client is cut off by the request deadline so that it
+    //            cannot stall the packet processing.
+    pub fn recv_request(&mut self, req : &mut ctl_request) -> i32 {
+        let mut buf : [u8; ctl_server::REQUEST_MAX_LEN] = [0; ctl_server::REQUEST_MAX_LEN];
+        let mut ret : i32;
+
+        ret = self.server.accept(&mut self.conn);
+        if ret < 0 {
+            return -1;
+        }
+
+        self.conn.set_timeout(ctl_server::CONN_TIMEOUT_SEC);
+
+        let len = self.conn.recv_request(&mut buf, ctl_server::REQUEST_TIMEOUT_MS,
+                                         ctl_server::REQUEST_MAX_RECVS,
+                                         |req| req.contains(&b'\n'));
+
+        let line = String::from_utf8_lossy(&buf[..len]).to_string();
+        let line = line.lines().next().unwrap_or("");
+        let mut words = line.split_whitespace();
+
+        req.cmd = match words.next() {
+            Some(cmd) => cmd.to_string(),
+            None => {
+                ret = -1;
+                "".to_string()
+            }
+        };
+        req.args = words.map(|w| w.to_string()).collect();
+
+        if ret < 0 {
+            self.respond_error("empty request");
+            return -1;
+        }
+
+        return 0;
+    }
+
+    // @brief - send the response and close the connection
+    //
+    // @param [in] self - control server
+    // @param [in] resp - response
+    pub fn respond(&mut self, resp : &serde_json::Value) {
+        let mut msg = resp.to_string();
+
+        msg.push('\n');
+        if self.conn.send(msg.as_bytes(), msg.len()) < 0 {
+            log::error!("ctl_server: failed to send response");
+        }
+
+        self.conn.close();
+    }
+
+    // @brief - send an error response and close the connection
+    //
+    // @param [in] self - control server
+    // @param [in] reason - error description
+    pub fn respond_error(&mut self, reason : &str) {
+        let resp = serde_json::json!({
+            "status" : "error",
+            "error" : reason
+        });
+
+        self.respond(&resp);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::os::unix::net::UnixStream;
+
+    fn server(name : &str) -> (ctl_server, String) {
+        let mut ctl = ctl_server::new();
+        let path = std::env::temp_dir().join(format!("idsm_ctl_{}_{}.sock", name, std::process::id()))
+                                       .to_string_lossy().to_string();
+
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(ctl.init(&path), 0);
+        (ctl, path)
+    }
+
+    fn read_resp(client : &mut UnixStream) -> serde_json::Value {
+        let mut resp = String::new();
+
+        client.read_to_string(&mut resp).unwrap();
+        assert!(resp.ends_with('\n'));
+        return serde_json::from_str(&resp).unwrap();
+    }
+
+    #[test]
+    fn request_and_response() {
+        let (mut ctl, path) = server("req");
+        let mut req = ctl_request::new();
+        let mut client = UnixStream::connect(&path).unwrap();
+
+        client.write_all(b"events  5\nignored\n").unwrap();
+        assert_eq!(ctl.recv_request(&mut req), 0);
+        assert_eq!(req.cmd, "events");
+        assert_eq!(req.args, vec!["5".to_string()]);
+
+        ctl.respond(&serde_json::json!({ "status" : "ok" }));
+        assert_eq!(read_resp(&mut client)["status"], "ok");
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn empty_request() {
+        let (mut ctl, path) = server("empty");
+        let mut req = ctl_request::new();
+        let mut client = UnixStream::connect(&path).unwrap();
+
+        client.write_all(b"  \n").unwrap();
+        assert_eq!(ctl.recv_request(&mut req), -1);
+
+        let resp = read_resp(&mut client);
+        assert_eq!(resp["status"], "error");
+        assert_eq!(resp["error"], "empty request");
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn slow_client_cut_off() {
+        let (mut ctl, path) = server("slow");
+        let mut req = ctl_request::new();
+        let mut client = UnixStream::connect(&path).unwrap();
+        let start = std::time::Instant::now();
+
+        // the request never ends with a newline
+        client.write_all(b"stats").unwrap();
+        assert_eq!(ctl.recv_request(&mut req), 0);
+        assert_eq!(req.cmd, "stats");
+        assert!(start.elapsed() < std::time::Duration::from_millis(1000));
+
+        ctl.respond_error("timeout");
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -3,7 +3,7 @@
 #![allow(non_camel_case_types)]
 
 use crate::core::debug::{is_debug_level_config_data, is_debug_level_protocol};
-use crate::events::event_mgr;
+use crate::events::{event_encoder::event_encoder, event_mgr};
 use crate::lib::pcap::{self};
 use crate::lib::protocols::packet::packet::packet;
 use crate::lib::select_linux::select::select_time_val;
@@ -11,8 +11,12 @@ use crate::lib::time_linux::gmtime::gmtime_filename;
 use crate::parser::pkt_parser;
 use crate::{config, lib};
 use crate::stats::stats_mgr;
+use crate::config::config_parser::idsm_event_transport_type;
+use crate::transport::{file_log, syslog_transport, tcp_transport, udp_transport};
 
 use super::cmd_args::idsm_cmd_args;
+use super::ctl_server::{ctl_request, ctl_server};
+use super::metrics_server::metrics_server;
 
 // @brief - defines idsm context
 pub struct idsm_context {
@@ -20,7 +24,15 @@ pub struct idsm_context {
     config_data             : config::config_parser::idsm_config,
     evt_mgr                 : event_mgr::event_mgr,
     stats_mgr               : stats_mgr::idsm_stats_mgr,
-    pcap_write              : pcap::pcap_write::pcap_writer
+    pcap_write              : pcap::pcap_write::pcap_writer,
+    pcap_filename           : String,
+    udp_transport           : udp_transport::udp_transport,
+    syslog_transport        : syslog_transport::syslog_transport,
+    tcp_transport           : tcp_transport::tcp_transport,
+    file_log                : file_log::file_log,
+    parser                  : pkt_parser::pkt_parser,
+    ctl_server              : ctl_server,
+    metrics_server          : metrics_server
 }
 
 impl idsm_context {
@@ -35,6 +47,14 @@ impl idsm_context {
             evt_mgr                 : event_mgr::event_mgr::new(),
             stats_mgr               : stats_mgr::idsm_stats_mgr::new(),
             pcap_write              : pcap::pcap_write::pcap_writer::new(),
+            pcap_filename           : "".to_string(),
+            udp_transport           : udp_transport::udp_transport::new(),
+            syslog_transport        : syslog_transport::syslog_transport::new(),
+            tcp_transport           : tcp_transport::tcp_transport::new(),
+            file_log                : file_log::file_log::new(),
+            parser                  : pkt_parser::pkt_parser::new(),
+            ctl_server              : ctl_server::new(),
+            metrics_server          : metrics_server::new()
         };
         context
     }
@@ -45,32 +65,118 @@ impl idsm_context {
     //
     // @return 0 on success -1 on failure
     fn init_pcap_writing(&mut self) -> i32 {
-        let mut pcap_filename = String::new();
-        let mut ret = -1;
+        return idsm_context::create_pcap(&self.config_data.pcap_config,
+                                         &mut self.pcap_write, &mut self.pcap_filename);
+    }
+
+    // @brief - create a pcap file if enabled
+    //
+    // @param [in] pcap_config - pcap config
+    // @param [out] pcap_write - pcap writer
+    // @param [out] pcap_filename - name of the created pcap file
+    //
+    // @return 0 on success -1 on failure
+    fn create_pcap(pcap_config : &config::config_parser::idsm_pcap_config,
+                   pcap_write : &mut pcap::pcap_write::pcap_writer,
+                   pcap_filename : &mut String) -> i32 {
+        let mut filename = String::new();
+        let mut ret = 0;
 
         // create pcap file if enabled
-        if self.config_data.pcap_config.enable {
+        if pcap_config.enable {
             let file_prefix = ".pcap".to_string();
 
             // make a filename with timestamp
-            ret = gmtime_filename(&self.config_data.pcap_config.file_prefix,
-                                &file_prefix, &mut pcap_filename);
+            ret = gmtime_filename(&pcap_config.file_prefix,
+                                &file_prefix, &mut filename);
             if ret < 0 {
                 log::error!("cannot create a filename with current time");
                 return -1;
             }
 
             // create a pcap file
-            ret = self.pcap_write.create(&pcap_filename);
+            ret = pcap_write.create(&filename);
             if ret < 0 {
                 log::error!("idsm: failed to create pcap file");
                 return -1;
             }
+
+            *pcap_filename = filename.trim_end_matches('\0').to_string();
         }
 
         return ret;
     }
 
+    // @brief - apply the events config to the event manager
+    //
+    // @param [in] self - this structure
+    fn init_evt_mgr(&mut self) {
+        self.evt_mgr.set_capacity(self.config_data.evt_config.capacity,
+                                  self.config_data.evt_config.overflow_policy);
+        self.evt_mgr.set_aggr_window(self.config_data.evt_config.aggr_config.window_sec);
+        self.evt_mgr.set_ifname(&self.config_data.ifname);
+        self.evt_mgr.set_pkt_snapshot_len(self.config_data.evt_config.pkt_snapshot_len);
+    }
+
+    // @brief - initialize event upload
+    //
+    // @param [in] self - this structure
+    //
+    // @return 0 on success -1 on failure
+    fn init_evt_upload(&mut self) -> i32 {
+        return idsm_context::create_evt_upload(&self.config_data.evt_config,
+                                               &mut self.udp_transport,
+                                               &mut self.syslog_transport,
+                                               &mut self.tcp_transport,
+                                               &mut self.file_log);
+    }
+
+    // @brief - initialize the configured event transport and the event log
+    //
+    // @param [in] evt_config - events config
+    // @param [out] udp - UDP transport
+    // @param [out] syslog - syslog transport
+    // @param [out] tcp - TCP transport
+    // @param [out] file_log - event log
+    //
+    // @return 0 on success -1 on failure
+    fn create_evt_upload(evt_config : &config::config_parser::idsm_events_config,
+                         udp : &mut udp_transport::udp_transport,
+                         syslog : &mut syslog_transport::syslog_transport,
+                         tcp : &mut tcp_transport::tcp_transport,
+                         file_log : &mut file_log::file_log) -> i32 {
+        let mut ret : i32;
+
+        if !evt_config.enable {
+            return 0;
+        }
+
+        match evt_config.evt_transport_type {
+            idsm_event_transport_type::UDP => ret = udp.init(),
+            idsm_event_transport_type::SYSLOG => {
+                ret = syslog.init(&evt_config.syslog_config);
+            }
+            idsm_event_transport_type::TCP => {
+                ret = tcp.init(&evt_config.tcp_config);
+            }
+            _ => ret = -1,
+        }
+        if ret < 0 {
+            log::error!("idsm: failed to create event transport");
+            return -1;
+        }
+
+        if evt_config.file_log_config.enable {
+            ret = file_log.init(&evt_config.file_log_config);
+            if ret < 0 {
+                log::error!("idsm: failed to create event log file");
+                return -1;
+            }
+        }
+
+        return 0;
+    }
+
     // @brief - initialize the idsm context
     //
     // @param [in] self - idsm context
@@ -94,12 +200,38 @@ impl idsm_context {
             return -1;
         }
 
+        self.init_evt_mgr();
+        self.parser.set_config(&self.config_data);
+
         ret = self.init_pcap_writing();
         if ret < 0 {
             log::error!("idsm: cannot create pcap context");
             return -1;
         }
 
+        ret = self.init_evt_upload();
+        if ret < 0 {
+            log::error!("idsm: cannot create event upload context");
+            return -1;
+        }
+
+        if self.config_data.control_config.enable {
+            ret = self.ctl_server.init(&self.config_data.control_config.path);
+            if ret < 0 {
+                log::error!("idsm: cannot create control socket");
+                return -1;
+            }
+        }
+
+        if self.config_data.metrics_config.enable {
+            ret = self.metrics_server.init(&self.config_data.metrics_config.ipaddr,
+                                           self.config_data.metrics_config.port);
+            if ret < 0 {
+                log::error!("idsm: cannot create metrics endpoint");
+                return -1;
+            }
+        }
+
         log::info!("idsm: init ok");
 
         return 0;
@@ -127,24 +259,255 @@ impl idsm_context {
         }
 
         p.pkt_len = ret as usize;
-        // allocate a new parser context
-        let mut parser : pkt_parser::pkt_parser = pkt_parser::pkt_parser::new();
 
         // update stats for rx
         self.stats_mgr.inc_rx();
 
         // parse the incoming frame, store events if necessary
-        _ = parser.parse(&mut p, &mut self.evt_mgr, &mut self.stats_mgr, debug_protocols);
+        _ = self.parser.parse(&mut p, &mut self.evt_mgr, &mut self.stats_mgr, debug_protocols);
+
+        // account events lost due to the event list being full
+        self.stats_mgr.set_evt_dropped(self.evt_mgr.get_n_dropped());
+        self.stats_mgr.set_evt_coalesced(self.evt_mgr.get_n_coalesced());
+        self.stats_mgr.set_evt_aggregated(self.evt_mgr.get_n_aggregated());
 
         // write to pcap log
-        if self.config_data.pcap_config.enable {
+        if self.pcap_write.is_open() {
             self.pcap_write.write(&p.buf, p.pkt_len as u32);
         }
 
         return 0;
     }
 
+    // @brief - upload the collected events
+    //
+    // @param [inout] self - this struct
+    //
+    // @details - called periodically from the event timer. events that are
+    //            sent are removed from the event manager.
     fn process_evt_upload(&mut self) {
+        let mut ret : i32;
+
+        if !self.config_data.evt_config.enable {
+            return;
+        }
+
+        // keep a local record before the events leave for the collector
+        if self.config_data.evt_config.file_log_config.enable {
+            ret = self.file_log.write(&mut self.evt_mgr,
+                                      &self.config_data.evt_config.file_log_config);
+            if ret < 0 {
+                log::error!("idsm: failed to write events to the event log");
+            }
+        }
+
+        match self.config_data.evt_config.evt_transport_type {
+            idsm_event_transport_type::UDP => {
+                ret = self.udp_transport.upload(&mut self.evt_mgr, &self.config_data.evt_config);
+            }
+            idsm_event_transport_type::SYSLOG => {
+                ret = self.syslog_transport.upload(&mut self.evt_mgr, &self.config_data.evt_config);
+            }
+            idsm_event_transport_type::TCP => {
+                ret = self.tcp_transport.upload(&mut self.evt_mgr, &self.config_data.evt_config);
+            }
+            _ => ret = -1,
+        }
+        if ret < 0 {
+            log::error!("idsm: failed to upload events, {} events pending",
+                                    self.evt_mgr.n_evts());
+        }
+    }
+
+    // @brief - continue the pending work of the event transport
+    //
+    // @param [in] self - idsm context
+    fn process_evt_transport(&mut self) {
+        if !self.config_data.evt_config.enable {
+            return;
+        }
+
+        match self.config_data.evt_config.evt_transport_type {
+            idsm_event_transport_type::SYSLOG => {
+                self.syslog_transport.poll(&self.config_data.evt_config);
+            }
+            idsm_event_transport_type::TCP => {
+                self.tcp_transport.poll(&self.config_data.evt_config);
+            }
+            _ => { }
+        }
+    }
+
+    // @brief - parse the config file again and apply it
+    //
+    // @param [in] self - idsm context
+    //
+    // @return 0 on success -1 on failure
+    //
+    // @details - the running config is kept if the new config cannot be parsed
+    //            or its transports, event log or pcap file cannot be created.
+    //            the new ones are set up aside and swapped in only once all of
+    //            them are created. the interface, the upload interval, the
+    //            control socket and the metrics endpoint are set up once and
+    //            need a restart.
+    fn reload_config(&mut self) -> i32 {
+        let mut config_data = config::config_parser::idsm_config::new();
+        let config_dbg = is_debug_level_config_data(self.cmd_args.debug);
+        let mut ret : i32;
+
+        ret = config_data.parse(&self.cmd_args.config_file, config_dbg);
+        if ret < 0 {
+            log::error!("idsm: failed to parse config data, keeping the running config");
+            return -1;
+        }
+
+        if config_data.ifname != self.config_data.ifname ||
+           config_data.evt_config.interval_sec() != self.config_data.evt_config.interval_sec() ||
+           config_data.control_config.enable != self.config_data.control_config.enable ||
+           config_data.control_config.path != self.config_data.control_config.path ||
+           config_data.metrics_config.enable != self.config_data.metrics_config.enable ||
+           config_data.metrics_config.ipaddr != self.config_data.metrics_config.ipaddr ||
+           config_data.metrics_config.port != self.config_data.metrics_config.port {
+            log::info!("idsm: interface, upload interval, control socket and metrics \
+                        endpoint changes apply after restart");
+        }
+
+        let pcap_changed = config_data.pcap_config.enable != self.config_data.pcap_config.enable ||
+                           config_data.pcap_config.file_prefix != self.config_data.pcap_config.file_prefix;
+
+        // interface is bound to the raw socket, keep the running one
+        config_data.ifname = self.config_data.ifname.clone();
+
+        // start the transports again, the collector may have changed
+        let mut udp = udp_transport::udp_transport::new();
+        let mut syslog = syslog_transport::syslog_transport::new();
+        let mut tcp = tcp_transport::tcp_transport::new();
+        let mut evt_file_log = file_log::file_log::new();
+
+        ret = idsm_context::create_evt_upload(&config_data.evt_config, &mut udp, &mut syslog,
+                                              &mut tcp, &mut evt_file_log);
+        if ret < 0 {
+            log::error!("idsm: cannot create event upload context, keeping the running config");
+            return -1;
+        }
+
+        let mut pcap_write = pcap::pcap_write::pcap_writer::new();
+        let mut pcap_filename = String::new();
+
+        if pcap_changed {
+            ret = idsm_context::create_pcap(&config_data.pcap_config,
+                                            &mut pcap_write, &mut pcap_filename);
+            if ret < 0 {
+                log::error!("idsm: cannot create pcap context, keeping the running config");
+                return -1;
+            }
+
+            self.pcap_write.close();
+            self.pcap_write = pcap_write;
+            self.pcap_filename = pcap_filename;
+        }
+
+        self.udp_transport = udp;
+        self.syslog_transport = syslog;
+        self.tcp_transport = tcp;
+        self.file_log = evt_file_log;
+        self.config_data = config_data;
+
+        self.init_evt_mgr();
+        self.parser.set_config(&self.config_data);
+
+        log::info!("idsm: config reloaded");
+
+        return 0;
+    }
+
+    // @brief - close the current pcap file and start a new one
+    //
+    // @param [in] self - idsm context
+    //
+    // @return 0 on success -1 on failure
+    fn rotate_pcap(&mut self) -> i32 {
+        self.pcap_write.close();
+
+        return self.init_pcap_writing();
+    }
+
+    // @brief - process a request on the control socket
+    //
+    // @param [in] self - idsm context
+    fn process_ctl_recv(&mut self) {
+        let mut req = ctl_request::new();
+        let resp : serde_json::Value;
+
+        if self.ctl_server.recv_request(&mut req) < 0 {
+            return;
+        }
+
+        match (req.cmd.as_str(), req.args.len()) {
+            ("stats", 0) => {
+                resp = serde_json::json!({
+                    "status" : "ok",
+                    "stats" : self.stats_mgr.get().to_json(),
+                    "n_pending_evts" : self.evt_mgr.n_evts()
+                });
+            }
+            ("events", 0) | ("events", 1) => {
+                let since_id : u64 = match req.args.get(0) {
+                    Some(arg) => match arg.parse() {
+                        Ok(id) => id,
+                        Err(_) => {
+                            self.ctl_server.respond_error("since must be an event id");
+                            return;
+                        }
+                    },
+                    None => 0,
+                };
+                let evts = self.evt_mgr.get_evts_since(since_id);
+                let last_id = evts.last().map_or(since_id, |e| e.id);
+                let evt_list : Vec<serde_json::Value> = evts.iter()
+                                                .map(|e| event_encoder::to_json(e))
+                                                .collect();
+
+                resp = serde_json::json!({
+                    "status" : "ok",
+                    "last_id" : last_id,
+                    "events" : evt_list
+                });
+            }
+            ("clear-stats", 0) => {
+                self.stats_mgr.clear();
+                self.evt_mgr.clear_counters();
+                resp = serde_json::json!({ "status" : "ok" });
+            }
+            ("reload-config", 0) => {
+                if self.reload_config() < 0 {
+                    self.ctl_server.respond_error("failed to reload config");
+                    return;
+                }
+                resp = serde_json::json!({ "status" : "ok" });
+            }
+            ("pcap", 1) if req.args[0] == "rotate" => {
+                if !self.config_data.pcap_config.enable {
+                    self.ctl_server.respond_error("pcap is disabled");
+                    return;
+                }
+                if self.rotate_pcap() < 0 {
+                    self.ctl_server.respond_error("failed to rotate pcap file");
+                    return;
+                }
+                resp = serde_json::json!({
+                    "status" : "ok",
+                    "file" : self.pcap_filename
+                });
+            }
+            _ => {
+                log::error!("idsm: unknown control command {}", req.cmd);
+                self.ctl_server.respond_error("unknown command");
+                return;
+            }
+        }
+
+        self.ctl_server.respond(&resp);
     }
 
     // @brief - start firewall
@@ -158,13 +521,15 @@ impl idsm_context {
     //            3. registers to the select
     //              3.1. receive raw socket data -> parse it -> filter it -> consume it
     //              3.2. periodically forward events
+    //              3.3. answer requests on the control socket
+    //              3.4. answer scrapes on the metrics endpoint
     pub fn start_firewall(&mut self) {
         use crate::lib;
 
         let ret;
         let mut raw_sock = lib::raw::raw_socket::raw_socket::new();
         let mut select_loop = lib::select_linux::select::select_linux::new();
-        let evt_intvl_sec = self.config_data.evt_config.udp_config.interval_sec;
+        let evt_intvl_sec = self.config_data.evt_config.interval_sec();
 
         // create raw socket
         ret = lib::raw::raw_socket::raw_socket::create(&mut raw_sock, &self.config_data.ifname);
@@ -176,6 +541,16 @@ impl idsm_context {
         // add raw socket to the monitoring fds
         select_loop.add_fd(raw_sock.get());
 
+        // add control socket to the monitoring fds
+        if self.config_data.control_config.enable {
+            select_loop.add_fd(self.ctl_server.get());
+        }
+
+        // add metrics endpoint to the monitoring fds
+        if self.config_data.metrics_config.enable {
+            select_loop.add_fd(self.metrics_server.get());
+        }
+
         let mut evt_timeval = select_time_val::new();
         evt_timeval.id = 1;
         evt_timeval.sec = evt_intvl_sec;
@@ -184,13 +559,28 @@ impl idsm_context {
         // add timer to the monitoring
         select_loop.add_timer(&mut evt_timeval);
 
+        // a connect or a spool replay of the event transport moves on with
+        // this timer, a chunk at a time
+        let mut transport_timeval = select_time_val::new();
+        transport_timeval.id = 2;
+        transport_timeval.sec = 0;
+        transport_timeval.nsec = 100 * 1000 * 1000;
+
+        select_loop.add_timer(&mut transport_timeval);
+
         loop {
             let select_res = select_loop.select();
             if select_res.res == 0 {
                 if select_res.fd == raw_sock.get() {
                     _ = self.process_raw_sock_recv(&mut raw_sock);
+                } else if select_res.fd == self.ctl_server.get() {
+                    self.process_ctl_recv();
+                } else if select_res.fd == self.metrics_server.get() {
+                    _ = self.metrics_server.process(self.stats_mgr.get(), &self.evt_mgr);
                 } else if select_res.id == evt_timeval.id {
                     self.process_evt_upload();
+                } else if select_res.id == transport_timeval.id {
+                    self.process_evt_transport();
                 }
             }
         }
@@ -0,0 +1,292 @@
+// @brief - implements the Prometheus metrics endpoint of idsd
+// @copyright - Devendra Naga 2024-present All rights reserved.
+#![allow(dead_code)]
+#![allow(non_camel_case_types)]
+
+use std::fmt::Write;
+
+use crate::{
+    events::{event_desc::event_desc, event_mgr::event_mgr},
+    lib::socket_linux::{stream_conn::stream_conn, tcp_server::tcp_server},
+    stats::stats::idsm_stats
+};
+
+// @brief - defines the metrics server
+//
+// @details - answers HTTP GET /metrics with the counters in the Prometheus
+//            text exposition format. every connection serves a single
+//            request and is closed after the response.
+pub struct metrics_server {
+    server      : tcp_server,
+    conn        : stream_conn
+}
+
+impl metrics_server {
+    const REQUEST_MAX_LEN   : usize = 2048;
+    const CONN_TIMEOUT_SEC  : u32 = 1;
+    // the request is read in the packet loop, a slow client is cut off
+    const REQUEST_TIMEOUT_MS : u32 = 200;
+    const REQUEST_MAX_RECVS  : u32 = 8;
+    const CONTENT_TYPE      : &'static str = "text/plain; version=0.0.4; charset=utf-8";
+
+    pub fn new() -> metrics_server {
+        let metrics = metrics_server {
+            server      : tcp_server::new(),
+            conn        : stream_conn::new(-1)
+        };
+        metrics
+    }
+
+    // @brief - create the metrics endpoint
+    //
+    // @param [in] self - metrics server
+    // @param [in] ipaddr - address to listen on
+    // @param [in] port - port to listen on
+    //
+    // @return 0 on success -1 on failure
+    pub fn init(&mut self, ipaddr : &String, port : u16) -> i32 {
+        let ret : i32;
+
+        ret = self.server.create(ipaddr, port as u32);
+        if ret < 0 {
+            log::error!("metrics_server: cannot listen on {}:{}", ipaddr, port);
+            return -1;
+        }
+
+        log::info!("metrics_server: listening on {}:{}", ipaddr, port);
+
+        return 0;
+    }
+
+    // @brief - returns the socket to register with the select
+    pub fn get(&self) -> i32 { return self.server.get(); }
+
+    // @brief - add a counter with its help and type lines
+    fn add_counter(body : &mut String, name : &str, help : &str, val : u64) {
+        _ = writeln!(body, "# HELP {} {}", name, help);
+        _ = writeln!(body, "# TYPE {} counter", name);
+        _ = writeln!(body, "{} {}", name, val);
+    }
+
+    // @brief - format the metrics in the text exposition format
+    //
+    // @param [in] stats - idsm stats
+    // @param [in] evt_mgr - event manager
+    //
+    // @return metrics
+    fn format(stats : &idsm_stats, evt_mgr : &event_mgr) -> String {
+        let mut body = String::new();
+        let parse_errs : [(&str, u64); 9] = [
+            ("eth",     stats.n_eth_parse_err),
+            ("vlan",    stats.n_vlan_parse_err),
+            ("arp",     stats.n_arp_parse_err),
+            ("ipv4",    stats.n_ipv4_parse_err),
+            ("ipv6",    stats.n_ipv6_parse_err),
+            ("tcp",     stats.n_tcp_parse_err),
+            ("udp",     stats.n_udp_parse_err),
+            ("icmp",    stats.n_icmp_parse_err),
+            ("icmp6",   stats.n_icmp6_parse_err),
+        ];
+
+        metrics_server::add_counter(&mut body, "idsm_rx_total",
+                                    "Frames received on the interface.", stats.n_rx);
+        metrics_server::add_counter(&mut body, "idsm_eth_rx_total",
+                                    "Ethernet frames parsed.", stats.n_eth_rx);
+        metrics_server::add_counter(&mut body, "idsm_vlan_rx_total",
+                                    "VLAN tagged frames parsed.", stats.n_vlan_rx);
+        metrics_server::add_counter(&mut body, "idsm_arp_rx_total",
+                                    "ARP packets parsed.", stats.n_arp_rx);
+        metrics_server::add_counter(&mut body, "idsm_ipv4_rx_total",
+                                    "IPv4 packets parsed.", stats.n_ipv4_rx);
+        metrics_server::add_counter(&mut body, "idsm_ipv4_frag_rx_total",
+                                    "IPv4 fragments received.", stats.n_ipv4_frag_rx);
+        metrics_server::add_counter(&mut body, "idsm_ipv4_reasm_total",
+                                    "IPv4 datagrams reassembled from fragments.", stats.n_ipv4_reasm);
+        metrics_server::add_counter(&mut body, "idsm_ipv6_rx_total",
+                                    "IPv6 packets parsed.", stats.n_ipv6_rx);
+        metrics_server::add_counter(&mut body, "idsm_ipv6_frag_rx_total",
+                                    "IPv6 fragments received.", stats.n_ipv6_frag_rx);
+        metrics_server::add_counter(&mut body, "idsm_ipv6_reasm_total",
+                                    "IPv6 packets reassembled from fragments.", stats.n_ipv6_reasm);
+        metrics_server::add_counter(&mut body, "idsm_tcp_rx_total",
+                                    "TCP segments parsed.", stats.n_tcp_rx);
+        metrics_server::add_counter(&mut body, "idsm_udp_rx_total",
+                                    "UDP datagrams parsed.", stats.n_udp_rx);
+        metrics_server::add_counter(&mut body, "idsm_icmp_rx_total",
+                                    "ICMP messages parsed.", stats.n_icmp_rx);
+        metrics_server::add_counter(&mut body, "idsm_icmp6_rx_total",
+                                    "ICMPv6 messages parsed.", stats.n_icmp6_rx);
+        metrics_server::add_counter(&mut body, "idsm_events_dropped_total",
+                                    "Events dropped because the event list was full.",
+                                    stats.n_evt_dropped);
+        metrics_server::add_counter(&mut body, "idsm_events_coalesced_total",
+                                    "Events coalesced because the event list was full.",
+                                    stats.n_evt_coalesced);
+        metrics_server::add_counter(&mut body, "idsm_events_aggregated_total",
+                                    "Events aggregated into an earlier event of the same source.",
+                                    stats.n_evt_aggregated);
+
+        _ = writeln!(body, "# HELP idsm_parse_errors_total Headers that failed to parse, by protocol.");
+        _ = writeln!(body, "# TYPE idsm_parse_errors_total counter");
+        for (protocol, val) in parse_errs {
+            _ = writeln!(body, "idsm_parse_errors_total{{protocol=\"{}\"}} {}", protocol, val);
+        }
+
+        _ = writeln!(body, "# HELP idsm_events_total Events detected, by event description.");
+        _ = writeln!(body, "# TYPE idsm_events_total counter");
+        for (desc, val) in evt_mgr.get_evt_desc_counts() {
+            _ = writeln!(body, "idsm_events_total{{event=\"{}\",desc=\"0x{:x}\"}} {}",
+                         event_desc::name(*desc), desc, val);
+        }
+
+        _ = writeln!(body, "# HELP idsm_events_pending Events waiting for upload.");
+        _ = writeln!(body, "# TYPE idsm_events_pending gauge");
+        _ = writeln!(body, "idsm_events_pending {}", evt_mgr.n_evts());
+
+        return body;
+    }
+
+    // @brief - check if the request holds all the headers
+    fn is_request_complete(req : &[u8]) -> bool {
+        return req.windows(4).any(|w| w == b"\r\n\r\n") ||
+               req.windows(2).any(|w| w == b"\n\n");
+    }
+
+    // @brief - send an HTTP response and close the connection
+    fn respond(&mut self, status : &str, content_type : &str, body : &str) {
+        let msg = format!("HTTP/1.1 {}\r\n\
+                           Content-Type: {}\r\n\
+                           Content-Length: {}\r\n\
+                           Connection: close\r\n\
+                           \r\n\
+                           {}", status, content_type, body.len(), body);
+
+        if self.conn.send(msg.as_bytes(), msg.len()) < 0 {
+            log::error!("metrics_server: failed to send response");
+        }
+
+        self.conn.close();
+    }
+
+    // @brief - accept a connection and answer the request
+    //
+    // @param [in] self - metrics server
+    // @param [in] stats - idsm stats
+    // @param [in] evt_mgr - event manager
+    //
+    // @return 0 on success -1 on failure
+    pub fn process(&mut self, stats : &idsm_stats, evt_mgr : &event_mgr) -> i32 {
+        let mut buf : [u8; metrics_server::REQUEST_MAX_LEN] = [0; metrics_server::REQUEST_MAX_LEN];
+        let ret : i32;
+
+        ret = self.server.accept(&mut self.conn);
+        if ret < 0 {
+            return -1;
+        }
+
+        self.conn.set_timeout(metrics_server::CONN_TIMEOUT_SEC);
+
+        // only the request line is needed, but read the headers so that
+        // the client does not see a reset
+        let len = self.conn.recv_request(&mut buf, metrics_server::REQUEST_TIMEOUT_MS,
+                                         metrics_server::REQUEST_MAX_RECVS,
+                                         metrics_server::is_request_complete);
+
+        let req = String::from_utf8_lossy(&buf[..len]).to_string();
+        let mut words = req.lines().next().unwrap_or("").split_whitespace();
+        let method = words.next().unwrap_or("");
+        let path = words.next().unwrap_or("").split('?').next().unwrap_or("");
+
+        if method != "GET" {
+            self.respond("405 Method Not Allowed", "text/plain", "method not allowed\n");
+            return -1;
+        }
+
+        if path != "/metrics" {
+            self.respond("404 Not Found", "text/plain", "not found\n");
+            return -1;
+        }
+
+        let body = metrics_server::format(stats, evt_mgr);
+        self.respond("200 OK", metrics_server::CONTENT_TYPE, &body);
+
+        return 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use crate::events::event_type::event_type;
+
+    #[test]
+    fn exposition_format() {
+        let mut stats = idsm_stats::new();
+        let mut evt_mgr = event_mgr::new();
+
+        stats.n_rx = 10;
+        stats.n_udp_parse_err = 2;
+        evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY, event_desc::IPV4_FRAG_TINY);
+        evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY, event_desc::IPV4_FRAG_TINY);
+
+        let body = metrics_server::format(&stats, &evt_mgr);
+        assert!(body.contains("# HELP idsm_rx_total Frames received on the interface.\n\
+                               # TYPE idsm_rx_total counter\n\
+                               idsm_rx_total 10\n"));
+        assert!(body.contains("\nidsm_parse_errors_total{protocol=\"udp\"} 2\n"));
+        assert!(body.contains("\nidsm_events_total{event=\"IPV4_FRAG_TINY\",desc=\"0x2013\"} 2\n"));
+        assert!(body.contains("# TYPE idsm_events_pending gauge\nidsm_events_pending 2\n"));
+
+        // every sample has its type declared once
+        for line in body.lines().filter(|l| l.starts_with("# TYPE")) {
+            let name = line.split_whitespace().nth(2).unwrap();
+            assert_eq!(body.matches(&format!("# TYPE {} ", name)).count(), 1);
+        }
+    }
+
+    #[test]
+    fn request_complete() {
+        assert!(metrics_server::is_request_complete(b"GET /metrics HTTP/1.1\r\nHost: a\r\n\r\n"));
+        assert!(metrics_server::is_request_complete(b"GET /metrics HTTP/1.0\n\n"));
+        assert!(!metrics_server::is_request_complete(b"GET /metrics HTTP/1.1\r\nHost: a\r\n"));
+    }
+
+    fn get(port : u16, req : &[u8]) -> std::thread::JoinHandle<String> {
+        let req = req.to_vec();
+
+        return std::thread::spawn(move || {
+            let mut client = std::net::TcpStream::connect(("127.0.0.1", port)).unwrap();
+            let mut resp = String::new();
+
+            std::io::Write::write_all(&mut client, &req).unwrap();
+            client.read_to_string(&mut resp).unwrap();
+            resp
+        });
+    }
+
+    #[test]
+    fn scrape() {
+        let mut server = metrics_server::new();
+        let stats = idsm_stats::new();
+        let evt_mgr = event_mgr::new();
+        let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+
+        assert_eq!(server.init(&"127.0.0.1".to_string(), port), 0);
+
+        let client = get(port, b"GET /metrics?x=1 HTTP/1.1\r\n\r\n");
+        assert_eq!(server.process(&stats, &evt_mgr), 0);
+        let resp = client.join().unwrap();
+        assert!(resp.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(resp.contains(&format!("Content-Type: {}\r\n", metrics_server::CONTENT_TYPE)));
+        assert!(resp.contains("\r\n\r\n# HELP idsm_rx_total"));
+
+        let client = get(port, b"GET / HTTP/1.1\r\n\r\n");
+        assert_eq!(server.process(&stats, &evt_mgr), -1);
+        assert!(client.join().unwrap().starts_with("HTTP/1.1 404 Not Found\r\n"));
+
+        let client = get(port, b"POST /metrics HTTP/1.1\r\n\r\n");
+        assert_eq!(server.process(&stats, &evt_mgr), -1);
+        assert!(client.join().unwrap().starts_with("HTTP/1.1 405 Method Not Allowed\r\n"));
+    }
+}
@@ -1,3 +1,5 @@
 pub(crate) mod idsm_context;
 pub(crate) mod cmd_args;
 pub(crate) mod debug;
+pub(crate) mod ctl_server;
+pub(crate) mod metrics_server;
@@ -0,0 +1,79 @@
+// @brief - parse command line arguments
+// @copyright - Devendra Naga 2024-present All rights reserved.
+use std::env;
+
+use getopts::Options;
+
+// defines idsm_ctl command line arguments
+pub struct ctl_cmd_args {
+    // path of the idsd control socket
+    // the -s of command line argument sets this
+    pub socket_path : String,
+    // command and its arguments sent to idsd
+    pub request : String,
+}
+
+impl ctl_cmd_args {
+    pub const DEFAULT_SOCKET_PATH : &'static str = "/var/run/idsd.sock";
+
+    // zero initialize the structure
+    // returns cmd_args of type ctl_cmd_args
+    pub fn new() -> ctl_cmd_args {
+        let cmd_args = ctl_cmd_args {
+            socket_path : ctl_cmd_args::DEFAULT_SOCKET_PATH.to_string(),
+            request : "".to_string()
+        };
+        cmd_args
+    }
+
+    // displays the usage
+    // @param [in] self - struct ctl_cmd_args
+    // @param [in] progname - program name
+    fn usage(&mut self, progname : &String) {
+        eprintln!("{} [options] <command>\n\
+                 \t -s / --socket <idsd control socket, default {}>\n \
+                 \t -h / -help <show this help>\n\n\
+                 commands:\n \
+                 \t stats\n \
+                 \t events [since]\n \
+                 \t clear-stats\n \
+                 \t reload-config\n \
+                 \t pcap rotate", progname, ctl_cmd_args::DEFAULT_SOCKET_PATH);
+    }
+
+    // parse command line arguments
+    // @param [in] self - struct ctl_cmd_args
+    // @returns 0 on success -1 on failure
+    pub fn parse(&mut self) -> i32 {
+        let args : Vec <String> = env::args().collect();
+        let progname = args[0].clone();
+        let mut options = Options::new();
+
+        options.optopt("s", "socket", "control socket path", "");
+        options.optflag("h", "help", "shows the help");
+
+        let matches = match options.parse(&args[1..]) {
+            Ok(m) => {m}
+            Err(f) => {
+                eprintln!("{}", f.to_string());
+                self.usage(&progname);
+                return -1;
+            }
+        };
+
+        // if -h is set or no command is given, show usage
+        if matches.opt_present("h") || matches.free.is_empty() {
+            self.usage(&progname);
+            return -1;
+        }
+
+        // if -s is set, copy socket path
+        if matches.opt_present("s") {
+            self.socket_path = matches.opt_str("s").unwrap();
+        }
+
+        self.request = matches.free.join(" ");
+
+        return 0;
+    }
+}
@@ -18,6 +18,25 @@ impl event_desc {
     pub const IPV4_INVAL_VERSION                    : u32 = 0x2001;
     pub const IPV4_IHL_INVAL                        : u32 = 0x2002;
     pub const IPV4_RESERVED_SET                     : u32 = 0x2003;
+    pub const IPV4_OPT_INVAL_LEN                    : u32 = 0x2004;
+    pub const IPV4_OPT_INVAL_PTR                    : u32 = 0x2005;
+    pub const IPV4_OPT_LOOSE_SRC_ROUTE              : u32 = 0x2006;
+    pub const IPV4_OPT_STRICT_SRC_ROUTE             : u32 = 0x2007;
+    pub const IPV4_INVAL_CHECKSUM                   : u32 = 0x2008;
+    pub const IPV4_TOTAL_LEN_TOO_SHORT              : u32 = 0x2009;
+    pub const IPV4_TOTAL_LEN_EXCEEDS_PKT            : u32 = 0x200A;
+    pub const IPV4_LOW_TTL                          : u32 = 0x200B;
+    pub const IPV4_SRC_ADDR_ZERO                    : u32 = 0x200C;
+    pub const IPV4_SRC_ADDR_BCAST                   : u32 = 0x200D;
+    pub const IPV4_SRC_ADDR_LOOPBACK                : u32 = 0x200E;
+    pub const IPV4_SRC_ADDR_MCAST                   : u32 = 0x200F;
+    pub const IPV4_SRC_ADDR_RESERVED                : u32 = 0x2010;
+    pub const IPV4_LAND_ATTACK                      : u32 = 0x2011;
+    pub const IPV4_FRAG_OVERLAP                     : u32 = 0x2012;
+    pub const IPV4_FRAG_TINY                        : u32 = 0x2013;
+    pub const IPV4_FRAG_OVERSIZE                    : u32 = 0x2014;
+    pub const IPV4_FRAG_FLOOD                       : u32 = 0x2015;
+    pub const IPV4_FRAG_INVAL_LEN                   : u32 = 0x2016;
 
     // list of events related to TCP
     pub const TCP_SHORT_HDR_LEN                     : u32 = 0x3000;
@@ -28,6 +47,17 @@ impl event_desc {
     pub const TCP_UNKNOWN_OPT                       : u32 = 0x3005;
     pub const TCP_FLAGS_ALL_SET                     : u32 = 0x3006;
     pub const TCP_TIMESTAMP_OPT_LEN_INVAL           : u32 = 0x3007;
+    pub const TCP_INVAL_CHECKSUM                    : u32 = 0x3008;
+    pub const TCP_INVAL_HDR_LEN                     : u32 = 0x3009;
+    pub const TCP_OPT_INVAL_LEN                     : u32 = 0x300A;
+    pub const TCP_OPT_DUPLICATE                     : u32 = 0x300B;
+    pub const TCP_OPT_WRONG_SEGMENT                 : u32 = 0x300C;
+    pub const TCP_OPT_WSCALE_TOO_LARGE              : u32 = 0x300D;
+    pub const TCP_CONN_NO_SESSION                   : u32 = 0x300E;
+    pub const TCP_CONN_INVAL_STATE                  : u32 = 0x300F;
+    pub const TCP_CONN_SEQ_OUT_OF_WINDOW            : u32 = 0x3010;
+    pub const TCP_CONN_TABLE_FULL                   : u32 = 0x3011;
+    pub const TCP_SYN_FLOOD                         : u32 = 0x3012;
 
     // list of events related to VLAN
     pub const VLAN_ID_RESERVED                      : u32 = 0x4000;
@@ -36,13 +66,202 @@ impl event_desc {
     pub const ARP_INVAL_HWADDR_LEN                  : u32 = 0x5000;
     pub const ARP_INVAL_PROTOCOL_LEN                : u32 = 0x5001;
     pub const ARP_OP_INVALID                        : u32 = 0x5002;
+    pub const ARP_SHORT_HDR_LEN                     : u32 = 0x5003;
+    pub const ARP_BINDING_CHANGED                   : u32 = 0x5004;
+    pub const ARP_GRATUITOUS_FLOOD                  : u32 = 0x5005;
+    pub const ARP_UNSOLICITED_REPLY                 : u32 = 0x5006;
+    pub const ARP_SENDER_MAC_MISMATCH               : u32 = 0x5007;
 
     pub const IPV6_SHORT_HDR_LEN                    : u32 = 0x6000;
     pub const IPV6_INVAL_VERSION                    : u32 = 0x6001;
     pub const IPV6_SRC_ADDR_INVALID                 : u32 = 0x6002;
     pub const IPV6_DST_ADDR_INVALID                 : u32 = 0x6003;
+    pub const IPV6_EXT_SHORT_HDR_LEN                : u32 = 0x6004;
+    pub const IPV6_EXT_TOO_MANY_HDRS                : u32 = 0x6005;
+    pub const IPV6_EXT_HBH_NOT_FIRST                : u32 = 0x6006;
+    pub const IPV6_EXT_RH0                          : u32 = 0x6007;
+    pub const IPV6_EXT_INVAL_OPT_LEN                : u32 = 0x6008;
+    pub const IPV6_EXT_UNKNOWN_OPT_DISCARD          : u32 = 0x6009;
+    pub const IPV6_FRAG_ATOMIC                      : u32 = 0x600A;
+    pub const IPV6_FRAG_UPPER_HDR_MISSING           : u32 = 0x600B;
+    pub const IPV6_FRAG_OVERLAP                     : u32 = 0x600C;
+    pub const IPV6_FRAG_OVERSIZE                    : u32 = 0x600D;
+    pub const IPV6_FRAG_FLOOD                       : u32 = 0x600E;
+    pub const IPV6_FRAG_INVAL_LEN                   : u32 = 0x600F;
 
     pub const ICMP6_SHORT_HDR_LEN                   : u32 = 0x7000;
     pub const ICMP6_SHORT_DEST_UNREACH_HDR_LEN      : u32 = 0x7001;
+    pub const ICMP6_SHORT_MSG_LEN                   : u32 = 0x7002;
+    pub const ICMP6_UNKNOWN_TYPE                    : u32 = 0x7003;
+    pub const ICMP6_INVAL_CODE                      : u32 = 0x7004;
+    pub const ICMP6_NDP_INVAL_HOP_LIMIT             : u32 = 0x7005;
+    pub const ICMP6_NDP_OPT_INVAL_LEN               : u32 = 0x7006;
+    pub const ICMP6_NDP_TARGET_MCAST                : u32 = 0x7007;
+    pub const ICMP6_NDP_PREFIX_INVAL                : u32 = 0x7008;
+    pub const ICMP6_RA_SRC_NOT_LINK_LOCAL           : u32 = 0x7009;
+    pub const ICMP6_INVAL_CHECKSUM                  : u32 = 0x700A;
+    pub const ICMP6_NDP_NA_OVERRIDE                 : u32 = 0x700B;
+    pub const ICMP6_NDP_DAD_DENIAL                  : u32 = 0x700C;
+    pub const ICMP6_RA_NOT_ALLOWED                  : u32 = 0x700D;
+
+    // list of events related to UDP
+    pub const UDP_SHORT_HDR_LEN                     : u32 = 0x8000;
+    pub const UDP_SRC_PORT_ZERO                     : u32 = 0x8001;
+    pub const UDP_DST_PORT_ZERO                     : u32 = 0x8002;
+    pub const UDP_LEN_TOO_SHORT                     : u32 = 0x8003;
+    pub const UDP_LEN_EXCEEDS_IP_PAYLOAD            : u32 = 0x8004;
+    pub const UDP_IPV6_ZERO_CHECKSUM                : u32 = 0x8005;
+    pub const UDP_INVAL_CHECKSUM                    : u32 = 0x8006;
+
+    // list of events related to ICMP
+    pub const ICMP_SHORT_HDR_LEN                    : u32 = 0x9000;
+    pub const ICMP_UNKNOWN_TYPE                     : u32 = 0x9001;
+    pub const ICMP_INVAL_CODE                       : u32 = 0x9002;
+    pub const ICMP_PING_OF_DEATH                    : u32 = 0x9003;
+    pub const ICMP_REDIRECT                         : u32 = 0x9004;
+    pub const ICMP_TIMESTAMP_REQ                    : u32 = 0x9005;
+    pub const ICMP_ADDR_MASK_REQ                    : u32 = 0x9006;
+    pub const ICMP_NESTED_IP_INVALID                : u32 = 0x9007;
+    pub const ICMP_INVAL_CHECKSUM                   : u32 = 0x9008;
+
+    // list of events related to scans
+    pub const PORT_SCAN                             : u32 = 0xA000;
     pub const NONE                                  : u32 = 0xDEADBEEF;
+
+    // @brief - event name and severity of each event description
+    //
+    // @details - severity is in the range of 0 (lowest) to 10 (highest)
+    const EVENT_DESC_INFO : &'static [(u32, &'static str, u8)] = &[
+        (event_desc::ETH_SHORT_HDR_LEN,                 "ETH_SHORT_HDR_LEN",                    3),
+        (event_desc::ETH_SRC_ZERO_MAC,                  "ETH_SRC_ZERO_MAC",                     5),
+        (event_desc::ETH_SRC_BROADCAST_MAC,             "ETH_SRC_BROADCAST_MAC",                5),
+        (event_desc::IPV4_SHORT_HDR_LEN,                "IPV4_SHORT_HDR_LEN",                   3),
+        (event_desc::IPV4_INVAL_VERSION,                "IPV4_INVAL_VERSION",                   5),
+        (event_desc::IPV4_IHL_INVAL,                    "IPV4_IHL_INVAL",                       5),
+        (event_desc::IPV4_RESERVED_SET,                 "IPV4_RESERVED_SET",                    5),
+        (event_desc::IPV4_OPT_INVAL_LEN,                "IPV4_OPT_INVAL_LEN",                   5),
+        (event_desc::IPV4_OPT_INVAL_PTR,                "IPV4_OPT_INVAL_PTR",                   5),
+        (event_desc::IPV4_OPT_LOOSE_SRC_ROUTE,          "IPV4_OPT_LOOSE_SRC_ROUTE",             7),
+        (event_desc::IPV4_OPT_STRICT_SRC_ROUTE,         "IPV4_OPT_STRICT_SRC_ROUTE",            7),
+        (event_desc::IPV4_INVAL_CHECKSUM,               "IPV4_INVAL_CHECKSUM",                  4),
+        (event_desc::IPV4_TOTAL_LEN_TOO_SHORT,          "IPV4_TOTAL_LEN_TOO_SHORT",             5),
+        (event_desc::IPV4_TOTAL_LEN_EXCEEDS_PKT,        "IPV4_TOTAL_LEN_EXCEEDS_PKT",           5),
+        (event_desc::IPV4_LOW_TTL,                      "IPV4_LOW_TTL",                         3),
+        (event_desc::IPV4_SRC_ADDR_ZERO,                "IPV4_SRC_ADDR_ZERO",                   6),
+        (event_desc::IPV4_SRC_ADDR_BCAST,               "IPV4_SRC_ADDR_BCAST",                  6),
+        (event_desc::IPV4_SRC_ADDR_LOOPBACK,            "IPV4_SRC_ADDR_LOOPBACK",               6),
+        (event_desc::IPV4_SRC_ADDR_MCAST,               "IPV4_SRC_ADDR_MCAST",                  6),
+        (event_desc::IPV4_SRC_ADDR_RESERVED,            "IPV4_SRC_ADDR_RESERVED",               6),
+        (event_desc::IPV4_LAND_ATTACK,                  "IPV4_LAND_ATTACK",                     8),
+        (event_desc::IPV4_FRAG_OVERLAP,                 "IPV4_FRAG_OVERLAP",                    8),
+        (event_desc::IPV4_FRAG_TINY,                    "IPV4_FRAG_TINY",                       7),
+        (event_desc::IPV4_FRAG_OVERSIZE,                "IPV4_FRAG_OVERSIZE",                   9),
+        (event_desc::IPV4_FRAG_FLOOD,                   "IPV4_FRAG_FLOOD",                      7),
+        (event_desc::IPV4_FRAG_INVAL_LEN,               "IPV4_FRAG_INVAL_LEN",                  5),
+        (event_desc::TCP_SHORT_HDR_LEN,                 "TCP_SHORT_HDR_LEN",                    3),
+        (event_desc::TCP_SRC_PORT_ZERO,                 "TCP_SRC_PORT_ZERO",                    6),
+        (event_desc::TCP_DST_PORT_ZERO,                 "TCP_DST_PORT_ZERO",                    6),
+        (event_desc::TCP_FLAGS_ALL_ZERO,                "TCP_FLAGS_ALL_ZERO",                   7),
+        (event_desc::TCP_FLAGS_SYN_FIN_SET,             "TCP_FLAGS_SYN_FIN_SET",                7),
+        (event_desc::TCP_UNKNOWN_OPT,                   "TCP_UNKNOWN_OPT",                      3),
+        (event_desc::TCP_FLAGS_ALL_SET,                 "TCP_FLAGS_ALL_SET",                    7),
+        (event_desc::TCP_TIMESTAMP_OPT_LEN_INVAL,       "TCP_TIMESTAMP_OPT_LEN_INVAL",          4),
+        (event_desc::TCP_INVAL_CHECKSUM,                "TCP_INVAL_CHECKSUM",                   4),
+        (event_desc::TCP_INVAL_HDR_LEN,                 "TCP_INVAL_HDR_LEN",                    5),
+        (event_desc::TCP_OPT_INVAL_LEN,                 "TCP_OPT_INVAL_LEN",                    5),
+        (event_desc::TCP_OPT_DUPLICATE,                 "TCP_OPT_DUPLICATE",                    5),
+        (event_desc::TCP_OPT_WRONG_SEGMENT,             "TCP_OPT_WRONG_SEGMENT",                4),
+        (event_desc::TCP_OPT_WSCALE_TOO_LARGE,          "TCP_OPT_WSCALE_TOO_LARGE",             3),
+        (event_desc::TCP_CONN_NO_SESSION,               "TCP_CONN_NO_SESSION",                  3),
+        (event_desc::TCP_CONN_INVAL_STATE,              "TCP_CONN_INVAL_STATE",                 4),
+        (event_desc::TCP_CONN_SEQ_OUT_OF_WINDOW,        "TCP_CONN_SEQ_OUT_OF_WINDOW",           4),
+        (event_desc::TCP_CONN_TABLE_FULL,               "TCP_CONN_TABLE_FULL",                  5),
+        (event_desc::TCP_SYN_FLOOD,                     "TCP_SYN_FLOOD",                        7),
+        (event_desc::VLAN_ID_RESERVED,                  "VLAN_ID_RESERVED",                     4),
+        (event_desc::ARP_INVAL_HWADDR_LEN,              "ARP_INVAL_HWADDR_LEN",                 5),
+        (event_desc::ARP_INVAL_PROTOCOL_LEN,            "ARP_INVAL_PROTOCOL_LEN",               5),
+        (event_desc::ARP_OP_INVALID,                    "ARP_OP_INVALID",                       5),
+        (event_desc::ARP_SHORT_HDR_LEN,                 "ARP_SHORT_HDR_LEN",                    3),
+        (event_desc::ARP_BINDING_CHANGED,               "ARP_BINDING_CHANGED",                  7),
+        (event_desc::ARP_GRATUITOUS_FLOOD,              "ARP_GRATUITOUS_FLOOD",                 6),
+        (event_desc::ARP_UNSOLICITED_REPLY,             "ARP_UNSOLICITED_REPLY",                5),
+        (event_desc::ARP_SENDER_MAC_MISMATCH,           "ARP_SENDER_MAC_MISMATCH",              6),
+        (event_desc::IPV6_SHORT_HDR_LEN,                "IPV6_SHORT_HDR_LEN",                   3),
+        (event_desc::IPV6_INVAL_VERSION,                "IPV6_INVAL_VERSION",                   5),
+        (event_desc::IPV6_SRC_ADDR_INVALID,             "IPV6_SRC_ADDR_INVALID",                6),
+        (event_desc::IPV6_DST_ADDR_INVALID,             "IPV6_DST_ADDR_INVALID",                6),
+        (event_desc::IPV6_EXT_SHORT_HDR_LEN,            "IPV6_EXT_SHORT_HDR_LEN",               3),
+        (event_desc::IPV6_EXT_TOO_MANY_HDRS,            "IPV6_EXT_TOO_MANY_HDRS",               6),
+        (event_desc::IPV6_EXT_HBH_NOT_FIRST,            "IPV6_EXT_HBH_NOT_FIRST",               5),
+        (event_desc::IPV6_EXT_RH0,                      "IPV6_EXT_RH0",                         8),
+        (event_desc::IPV6_EXT_INVAL_OPT_LEN,            "IPV6_EXT_INVAL_OPT_LEN",               5),
+        (event_desc::IPV6_EXT_UNKNOWN_OPT_DISCARD,      "IPV6_EXT_UNKNOWN_OPT_DISCARD",         5),
+        (event_desc::IPV6_FRAG_ATOMIC,                  "IPV6_FRAG_ATOMIC",                     4),
+        (event_desc::IPV6_FRAG_UPPER_HDR_MISSING,       "IPV6_FRAG_UPPER_HDR_MISSING",          7),
+        (event_desc::IPV6_FRAG_OVERLAP,                 "IPV6_FRAG_OVERLAP",                    8),
+        (event_desc::IPV6_FRAG_OVERSIZE,                "IPV6_FRAG_OVERSIZE",                   9),
+        (event_desc::IPV6_FRAG_FLOOD,                   "IPV6_FRAG_FLOOD",                      7),
+        (event_desc::IPV6_FRAG_INVAL_LEN,               "IPV6_FRAG_INVAL_LEN",                  5),
+        (event_desc::ICMP6_SHORT_HDR_LEN,               "ICMP6_SHORT_HDR_LEN",                  3),
+        (event_desc::ICMP6_SHORT_DEST_UNREACH_HDR_LEN,  "ICMP6_SHORT_DEST_UNREACH_HDR_LEN",     3),
+        (event_desc::ICMP6_SHORT_MSG_LEN,               "ICMP6_SHORT_MSG_LEN",                  3),
+        (event_desc::ICMP6_UNKNOWN_TYPE,                "ICMP6_UNKNOWN_TYPE",                   4),
+        (event_desc::ICMP6_INVAL_CODE,                  "ICMP6_INVAL_CODE",                     4),
+        (event_desc::ICMP6_NDP_INVAL_HOP_LIMIT,         "ICMP6_NDP_INVAL_HOP_LIMIT",            7),
+        (event_desc::ICMP6_NDP_OPT_INVAL_LEN,           "ICMP6_NDP_OPT_INVAL_LEN",              5),
+        (event_desc::ICMP6_NDP_TARGET_MCAST,            "ICMP6_NDP_TARGET_MCAST",               5),
+        (event_desc::ICMP6_NDP_PREFIX_INVAL,            "ICMP6_NDP_PREFIX_INVAL",               5),
+        (event_desc::ICMP6_RA_SRC_NOT_LINK_LOCAL,       "ICMP6_RA_SRC_NOT_LINK_LOCAL",          7),
+        (event_desc::ICMP6_INVAL_CHECKSUM,              "ICMP6_INVAL_CHECKSUM",                 4),
+        (event_desc::ICMP6_NDP_NA_OVERRIDE,             "ICMP6_NDP_NA_OVERRIDE",                7),
+        (event_desc::ICMP6_NDP_DAD_DENIAL,              "ICMP6_NDP_DAD_DENIAL",                 7),
+        (event_desc::ICMP6_RA_NOT_ALLOWED,              "ICMP6_RA_NOT_ALLOWED",                 7),
+        (event_desc::UDP_SHORT_HDR_LEN,                 "UDP_SHORT_HDR_LEN",                    3),
+        (event_desc::UDP_SRC_PORT_ZERO,                 "UDP_SRC_PORT_ZERO",                    6),
+        (event_desc::UDP_DST_PORT_ZERO,                 "UDP_DST_PORT_ZERO",                    6),
+        (event_desc::UDP_LEN_TOO_SHORT,                 "UDP_LEN_TOO_SHORT",                    5),
+        (event_desc::UDP_LEN_EXCEEDS_IP_PAYLOAD,        "UDP_LEN_EXCEEDS_IP_PAYLOAD",           5),
+        (event_desc::UDP_IPV6_ZERO_CHECKSUM,            "UDP_IPV6_ZERO_CHECKSUM",               4),
+        (event_desc::UDP_INVAL_CHECKSUM,                "UDP_INVAL_CHECKSUM",                   4),
+        (event_desc::ICMP_SHORT_HDR_LEN,                "ICMP_SHORT_HDR_LEN",                   3),
+        (event_desc::ICMP_UNKNOWN_TYPE,                 "ICMP_UNKNOWN_TYPE",                    4),
+        (event_desc::ICMP_INVAL_CODE,                   "ICMP_INVAL_CODE",                      4),
+        (event_desc::ICMP_PING_OF_DEATH,                "ICMP_PING_OF_DEATH",                   9),
+        (event_desc::ICMP_REDIRECT,                     "ICMP_REDIRECT",                        6),
+        (event_desc::ICMP_TIMESTAMP_REQ,                "ICMP_TIMESTAMP_REQ",                   3),
+        (event_desc::ICMP_ADDR_MASK_REQ,                "ICMP_ADDR_MASK_REQ",                   3),
+        (event_desc::ICMP_NESTED_IP_INVALID,            "ICMP_NESTED_IP_INVALID",               4),
+        (event_desc::ICMP_INVAL_CHECKSUM,               "ICMP_INVAL_CHECKSUM",                  4),
+        (event_desc::PORT_SCAN,                         "PORT_SCAN",                            6),
+    ];
+
+    // @brief - get the human readable name of the event description
+    //
+    // @param [in] desc - event description
+    //
+    // @return name of the event, "UNKNOWN" if the event is not known
+    pub fn name(desc : u32) -> &'static str {
+        for (evt_desc, name, _) in event_desc::EVENT_DESC_INFO {
+            if *evt_desc == desc {
+                return name;
+            }
+        }
+
+        return "UNKNOWN";
+    }
+
+    // @brief - get the severity of the event description
+    //
+    // @param [in] desc - event description
+    //
+    // @return severity between 0 and 10, 5 if the event is not known
+    pub fn severity(desc : u32) -> u8 {
+        for (evt_desc, _, severity) in event_desc::EVENT_DESC_INFO {
+            if *evt_desc == desc {
+                return *severity;
+            }
+        }
+
+        return 5;
+    }
 }
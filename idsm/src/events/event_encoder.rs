@@ -0,0 +1,331 @@
+// @brief - implements text encoders for the events
+// @copyright - Devendra Naga 2024-present All rights reserved.
+#![allow(dead_code)]
+#![allow(non_camel_case_types)]
+
+use super::{event_desc::event_desc, event_info::{event_info, event_pkt_info}, event_type::event_type};
+
+#[non_exhaustive]
+pub struct event_encoder_type;
+
+// @brief - defines the list of event encoders
+impl event_encoder_type {
+    // binary event messages, see event_msg
+    pub const BINARY    : u32 = 1;
+    // one JSON object per line
+    pub const JSON      : u32 = 2;
+    // ArcSight common event format
+    pub const CEF       : u32 = 3;
+    // QRadar log event extended format
+    pub const LEEF      : u32 = 4;
+}
+
+// @brief - defines event encoder
+pub struct event_encoder;
+
+impl event_encoder {
+    const VENDOR        : &'static str = "idsm";
+    const PRODUCT       : &'static str = "idsd";
+    const VERSION       : &'static str = env!("CARGO_PKG_VERSION");
+
+    // @brief - encode an event as a single line of text
+    //
+    // @param [in] encoder_type - one of event_encoder_type other than BINARY
+    // @param [in] evt_info - event to encode
+    // @param [out] line - encoded event without the line terminator
+    //
+    // @return 0 on success -1 if the encoder is not a text encoder
+    pub fn encode(encoder_type : u32, evt_info : &event_info, line : &mut String) -> i32 {
+        match encoder_type {
+            event_encoder_type::JSON => event_encoder::encode_json(evt_info, line),
+            event_encoder_type::CEF => event_encoder::encode_cef(evt_info, line),
+            event_encoder_type::LEEF => event_encoder::encode_leef(evt_info, line),
+            _ => return -1,
+        }
+
+        return 0;
+    }
+
+    // @brief - encode an event as a JSON object
+    //
+    // @param [in] evt_info - event to encode
+    // @param [out] line - encoded event
+    pub fn encode_json(evt_info : &event_info, line : &mut String) {
+        line.push_str(&event_encoder::to_json(evt_info).to_string());
+    }
+
+    // @brief - build the JSON object of an event
+    //
+    // @param [in] evt_info - event to encode
+    //
+    // @return JSON object
+    pub fn to_json(evt_info : &event_info) -> serde_json::Value {
+        let pkt_info = &evt_info.pkt_info;
+        let mut obj = serde_json::json!({
+            "id" : evt_info.id,
+            "event_type" : event_type::name(evt_info.event_type),
+            "event_desc" : evt_info.event_desc,
+            "event_name" : event_desc::name(evt_info.event_desc),
+            "severity" : event_desc::severity(evt_info.event_desc),
+            "first_seen_ms" : evt_info.first_seen_ms(),
+            "last_seen_ms" : evt_info.last_seen_ms(),
+            "count" : evt_info.count,
+            "ifname" : pkt_info.ifname,
+            "src_mac" : event_pkt_info::mac_str(&pkt_info.src_mac),
+            "dst_mac" : event_pkt_info::mac_str(&pkt_info.dst_mac),
+        });
+
+        if pkt_info.has_vlan {
+            obj["vlan_id"] = serde_json::json!(pkt_info.vlan_id);
+        }
+
+        if pkt_info.ip_version != 0 {
+            obj["ip_version"] = serde_json::json!(pkt_info.ip_version);
+            obj["src_ip"] = serde_json::json!(pkt_info.src_ip_str());
+            obj["dst_ip"] = serde_json::json!(pkt_info.dst_ip_str());
+            obj["protocol"] = serde_json::json!(pkt_info.protocol);
+        }
+
+        if pkt_info.has_ports {
+            obj["src_port"] = serde_json::json!(pkt_info.src_port);
+            obj["dst_port"] = serde_json::json!(pkt_info.dst_port);
+        }
+
+        if pkt_info.frame.len() > 0 {
+            obj["frame"] = serde_json::json!(pkt_info.frame_hex());
+        }
+
+        if evt_info.details.len() > 0 {
+            let mut details = serde_json::Map::new();
+
+            for (key, val) in &evt_info.details {
+                details.insert(key.clone(), serde_json::json!(val));
+            }
+            obj["details"] = serde_json::Value::Object(details);
+        }
+
+        return obj;
+    }
+
+    // @brief - escape a CEF header field
+    fn cef_escape_hdr(val : &str) -> String {
+        return val.replace('\\', "\\\\").replace('|', "\\|");
+    }
+
+    // @brief - escape a CEF or LEEF extension value
+    fn escape_ext(val : &str) -> String {
+        return val.replace('\\', "\\\\")
+                  .replace('=', "\\=")
+                  .replace('\n', "\\n")
+                  .replace('\r', "\\r");
+    }
+
+    // @brief - encode an event in ArcSight common event format
+    //
+    // @param [in] evt_info - event to encode
+    // @param [out] line - encoded event
+    //
+    // @details - CEF:0|vendor|product|version|signature id|name|severity|extension
+    pub fn encode_cef(evt_info : &event_info, line : &mut String) {
+        let pkt_info = &evt_info.pkt_info;
+
+        line.push_str(&format!("CEF:0|{}|{}|{}|0x{:04X}|{}|{}|",
+                               event_encoder::VENDOR,
+                               event_encoder::PRODUCT,
+                               event_encoder::VERSION,
+                               evt_info.event_desc,
+                               event_encoder::cef_escape_hdr(event_desc::name(evt_info.event_desc)),
+                               event_desc::severity(evt_info.event_desc)));
+
+        line.push_str(&format!("act={} cnt={} start={} end={} rt={}",
+                               event_type::name(evt_info.event_type),
+                               evt_info.count,
+                               evt_info.first_seen_ms(),
+                               evt_info.last_seen_ms(),
+                               evt_info.last_seen_ms()));
+        line.push_str(&format!(" deviceInboundInterface={}", event_encoder::escape_ext(&pkt_info.ifname)));
+        line.push_str(&format!(" smac={} dmac={}",
+                               event_pkt_info::mac_str(&pkt_info.src_mac),
+                               event_pkt_info::mac_str(&pkt_info.dst_mac)));
+
+        if pkt_info.has_vlan {
+            line.push_str(&format!(" cn1Label=vlanId cn1={}", pkt_info.vlan_id));
+        }
+
+        if pkt_info.ip_version == 4 {
+            line.push_str(&format!(" src={} dst={} proto={}",
+                                   pkt_info.src_ip_str(), pkt_info.dst_ip_str(), pkt_info.protocol));
+        } else if pkt_info.ip_version == 6 {
+            line.push_str(&format!(" c6a2Label=srcIPv6 c6a2={} c6a3Label=dstIPv6 c6a3={} proto={}",
+                                   pkt_info.src_ip_str(), pkt_info.dst_ip_str(), pkt_info.protocol));
+        }
+
+        if pkt_info.has_ports {
+            line.push_str(&format!(" spt={} dpt={}", pkt_info.src_port, pkt_info.dst_port));
+        }
+
+        if pkt_info.frame.len() > 0 {
+            line.push_str(&format!(" cs1Label=frame cs1={}", pkt_info.frame_hex()));
+        }
+
+        if evt_info.details.len() > 0 {
+            line.push_str(&format!(" cs2Label=details cs2={}",
+                                   event_encoder::escape_ext(&evt_info.details_str())));
+        }
+    }
+
+    // @brief - encode an event in QRadar log event extended format
+    //
+    // @param [in] evt_info - event to encode
+    // @param [out] line - encoded event
+    //
+    // @details - LEEF:1.0|vendor|product|version|event id|attributes separated by tab
+    pub fn encode_leef(evt_info : &event_info, line : &mut String) {
+        let pkt_info = &evt_info.pkt_info;
+        let severity = event_desc::severity(evt_info.event_desc);
+        // LEEF severity is in the range of 1 to 10
+        let sev = if severity == 0 { 1 } else { severity };
+
+        line.push_str(&format!("LEEF:1.0|{}|{}|{}|{}|",
+                               event_encoder::VENDOR,
+                               event_encoder::PRODUCT,
+                               event_encoder::VERSION,
+                               event_desc::name(evt_info.event_desc)));
+
+        line.push_str(&format!("cat={}\tsev={}\tdevTime={}\tcount={}\tfirstSeen={}\tlastSeen={}",
+                               event_type::name(evt_info.event_type),
+                               sev,
+                               evt_info.last_seen_ms(),
+                               evt_info.count,
+                               evt_info.first_seen_ms(),
+                               evt_info.last_seen_ms()));
+        line.push_str(&format!("\tifname={}", event_encoder::escape_ext(&pkt_info.ifname)));
+        line.push_str(&format!("\tsrcMAC={}\tdstMAC={}",
+                               event_pkt_info::mac_str(&pkt_info.src_mac),
+                               event_pkt_info::mac_str(&pkt_info.dst_mac)));
+
+        if pkt_info.has_vlan {
+            line.push_str(&format!("\tvlanId={}", pkt_info.vlan_id));
+        }
+
+        if pkt_info.ip_version != 0 {
+            line.push_str(&format!("\tsrc={}\tdst={}\tproto={}",
+                                   pkt_info.src_ip_str(), pkt_info.dst_ip_str(), pkt_info.protocol));
+        }
+
+        if pkt_info.has_ports {
+            line.push_str(&format!("\tsrcPort={}\tdstPort={}", pkt_info.src_port, pkt_info.dst_port));
+        }
+
+        if pkt_info.frame.len() > 0 {
+            line.push_str(&format!("\tframe={}", pkt_info.frame_hex()));
+        }
+
+        if evt_info.details.len() > 0 {
+            line.push_str(&format!("\tdetails={}", event_encoder::escape_ext(&evt_info.details_str())));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn evt() -> event_info {
+        let mut evt_info = event_info::new();
+
+        evt_info.event_type = event_type::EVENT_TYPE_DENY;
+        evt_info.event_desc = event_desc::IPV4_FRAG_TINY;
+        evt_info.detection_ts.sec = 1700000000;
+        evt_info.detection_ts.usec = 5000;
+        evt_info.last_seen_ts.sec = 1700000001;
+        evt_info.count = 3;
+        evt_info.id = 9;
+        evt_info.pkt_info.ifname = "eth0".to_string();
+        evt_info.pkt_info.src_mac = [0x02, 0, 0, 0, 0, 1];
+        evt_info.pkt_info.dst_mac = [0x02, 0, 0, 0, 0, 2];
+        evt_info.pkt_info.ip_version = 4;
+        evt_info.pkt_info.protocol = 6;
+        evt_info.pkt_info.src_ipaddr = 0x0A000001;
+        evt_info.pkt_info.dst_ipaddr = 0x0A000002;
+        evt_info.pkt_info.has_ports = true;
+        evt_info.pkt_info.src_port = 1234;
+        evt_info.pkt_info.dst_port = 80;
+        evt_info.details = vec![("path".to_string(), "a=b|c".to_string())];
+        evt_info
+    }
+
+    #[test]
+    fn json() {
+        let mut line = String::new();
+
+        event_encoder::encode_json(&evt(), &mut line);
+        let obj : serde_json::Value = serde_json::from_str(&line).unwrap();
+
+        assert_eq!(obj["id"], 9);
+        assert_eq!(obj["event_type"], "deny");
+        assert_eq!(obj["event_desc"], event_desc::IPV4_FRAG_TINY);
+        assert_eq!(obj["event_name"], "IPV4_FRAG_TINY");
+        assert_eq!(obj["severity"], 7);
+        assert_eq!(obj["first_seen_ms"], 1700000000005u64);
+        assert_eq!(obj["last_seen_ms"], 1700000001000u64);
+        assert_eq!(obj["count"], 3);
+        assert_eq!(obj["src_mac"], "02:00:00:00:00:01");
+        assert_eq!(obj["src_ip"], "10.0.0.1");
+        assert_eq!(obj["dst_port"], 80);
+        assert_eq!(obj["details"]["path"], "a=b|c");
+        // fields that were not decoded are left out
+        assert!(obj.get("vlan_id").is_none());
+        assert!(obj.get("frame").is_none());
+    }
+
+    #[test]
+    fn cef() {
+        let mut line = String::new();
+
+        assert_eq!(event_encoder::encode(event_encoder_type::CEF, &evt(), &mut line), 0);
+        assert_eq!(line, format!("CEF:0|idsm|idsd|{}|0x2013|IPV4_FRAG_TINY|7|\
+                                  act=deny cnt=3 start=1700000000005 end=1700000001000 rt=1700000001000 \
+                                  deviceInboundInterface=eth0 smac=02:00:00:00:00:01 dmac=02:00:00:00:00:02 \
+                                  src=10.0.0.1 dst=10.0.0.2 proto=6 spt=1234 dpt=80 \
+                                  cs2Label=details cs2=path\\=a\\=b|c",
+                                 event_encoder::VERSION));
+    }
+
+    #[test]
+    fn cef_ipv6() {
+        let mut evt_info = evt();
+        let mut line = String::new();
+
+        evt_info.pkt_info.ip_version = 6;
+        evt_info.pkt_info.src_ip6addr[0] = 0xFE;
+        evt_info.pkt_info.src_ip6addr[1] = 0x80;
+        evt_info.pkt_info.src_ip6addr[15] = 1;
+        evt_info.pkt_info.dst_ip6addr[15] = 1;
+        event_encoder::encode_cef(&evt_info, &mut line);
+        assert!(line.contains(" c6a2Label=srcIPv6 c6a2=fe80::1 c6a3Label=dstIPv6 c6a3=::1 proto=6"));
+        assert!(!line.contains(" src="));
+    }
+
+    #[test]
+    fn leef() {
+        let mut line = String::new();
+
+        assert_eq!(event_encoder::encode(event_encoder_type::LEEF, &evt(), &mut line), 0);
+        assert_eq!(line, format!("LEEF:1.0|idsm|idsd|{}|IPV4_FRAG_TINY|\
+                                  cat=deny\tsev=7\tdevTime=1700000001000\tcount=3\t\
+                                  firstSeen=1700000000005\tlastSeen=1700000001000\tifname=eth0\t\
+                                  srcMAC=02:00:00:00:00:01\tdstMAC=02:00:00:00:00:02\t\
+                                  src=10.0.0.1\tdst=10.0.0.2\tproto=6\tsrcPort=1234\tdstPort=80\t\
+                                  details=path\\=a\\=b|c",
+                                 event_encoder::VERSION));
+    }
+
+    #[test]
+    fn binary_is_not_text() {
+        let mut line = String::new();
+
+        assert_eq!(event_encoder::encode(event_encoder_type::BINARY, &evt(), &mut line), -1);
+        assert!(line.is_empty());
+    }
+}
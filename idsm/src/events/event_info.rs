@@ -3,24 +3,228 @@
 #![allow(dead_code)]
 #![allow(non_camel_case_types)]
 
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use crate::lib::protocols::packet::packet::packet;
 use crate::lib::time_linux::{self, timestamp::get_wallclock};
 
 use self::time_linux::timestamp::timestamp;
 
+// @brief - describes the packet that raised the event
+//
+// @details - fields are filled in as the packet parser decodes the headers,
+//            so an event carries only the fields decoded before it was raised.
+#[derive(Clone)]
+pub struct event_pkt_info {
+    pub ifname          : String,
+    pub src_mac         : [u8; 6],
+    pub dst_mac         : [u8; 6],
+    pub has_vlan        : bool,
+    pub vlan_id         : u16,
+    pub ip_version      : u8, // 0 if no IP header is decoded
+    pub protocol        : u8,
+    pub src_ipaddr      : u32,
+    pub dst_ipaddr      : u32,
+    pub src_ip6addr     : [u8; 16],
+    pub dst_ip6addr     : [u8; 16],
+    pub has_ports       : bool,
+    pub src_port        : u16,
+    pub dst_port        : u16,
+    pub frame           : Vec<u8> // first bytes of the frame
+}
+
+impl event_pkt_info {
+    pub const FLAG_VLAN     : u8 = 0x01;
+    pub const FLAG_PORTS    : u8 = 0x02;
+    // length of the serialized packet info without the ifname and the frame,
+    // the fields up to dst_port plus ifname_len and frame_len
+    pub const PKT_INFO_LEN  : usize = 6 + 6 + 2 + 1 + 1 + 1 + 16 + 16 + 2 + 2 + 1 + 2;
+
+    // @brief - zero initialize packet info
+    //
+    // @return packet info
+    pub fn new() -> event_pkt_info {
+        let pkt_info = event_pkt_info {
+            ifname          : "".to_string(),
+            src_mac         : [0; 6],
+            dst_mac         : [0; 6],
+            has_vlan        : false,
+            vlan_id         : 0,
+            ip_version      : 0,
+            protocol        : 0,
+            src_ipaddr      : 0,
+            dst_ipaddr      : 0,
+            src_ip6addr     : [0; 16],
+            dst_ip6addr     : [0; 16],
+            has_ports       : false,
+            src_port        : 0,
+            dst_port        : 0,
+            frame           : Vec::new()
+        };
+        pkt_info
+    }
+
+    // @brief - check if the two packets are from the same source
+    //
+    // @param [in] self - packet info
+    // @param [in] other - packet info to compare with
+    //
+    // @return true if same source false otherwise
+    //
+    // @details - the source is the IP address if an IP header is decoded,
+    //            the mac address otherwise.
+    pub fn same_src(&self, other : &event_pkt_info) -> bool {
+        if self.ip_version != other.ip_version {
+            return false;
+        }
+
+        match self.ip_version {
+            4 => return self.src_ipaddr == other.src_ipaddr,
+            6 => return self.src_ip6addr == other.src_ip6addr,
+            _ => return self.src_mac == other.src_mac,
+        }
+    }
+
+    // @brief - format a mac address as a string
+    //
+    // @param [in] mac - mac address
+    //
+    // @return mac address as aa:bb:cc:dd:ee:ff
+    pub fn mac_str(mac : &[u8; 6]) -> String {
+        return format!("{:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}",
+                       mac[0], mac[1], mac[2], mac[3], mac[4], mac[5]);
+    }
+
+    // @brief - format the source IP address as a string
+    //
+    // @return source IP address, empty if no IP header is decoded
+    pub fn src_ip_str(&self) -> String {
+        match self.ip_version {
+            4 => return Ipv4Addr::from(self.src_ipaddr).to_string(),
+            6 => return Ipv6Addr::from(self.src_ip6addr).to_string(),
+            _ => return "".to_string(),
+        }
+    }
+
+    // @brief - format the destination IP address as a string
+    //
+    // @return destination IP address, empty if no IP header is decoded
+    pub fn dst_ip_str(&self) -> String {
+        match self.ip_version {
+            4 => return Ipv4Addr::from(self.dst_ipaddr).to_string(),
+            6 => return Ipv6Addr::from(self.dst_ip6addr).to_string(),
+            _ => return "".to_string(),
+        }
+    }
+
+    // @brief - format the recorded frame bytes as a hex string
+    pub fn frame_hex(&self) -> String {
+        let mut hex = String::with_capacity(self.frame.len() * 2);
+
+        for b in &self.frame {
+            hex.push_str(&format!("{:02x}", b));
+        }
+
+        return hex;
+    }
+
+    // @brief - returns the length of the serialized packet info
+    pub fn serialized_len(&self) -> usize {
+        return event_pkt_info::PKT_INFO_LEN + self.ifname.len() + self.frame.len();
+    }
+
+    // @brief - serialize packet info
+    //
+    // @param [in] self - packet info
+    // @param [inout] p - packet to write into
+    //
+    // @details - written in network byte order as below
+    //
+    //            src_mac (6 bytes) | dst_mac (6 bytes) | vlan_id (2 bytes) |
+    //            flags (1 byte) | ip_version (1 byte) | protocol (1 byte) |
+    //            src_ip (16 bytes) | dst_ip (16 bytes) |
+    //            src_port (2 bytes) | dst_port (2 bytes) |
+    //            ifname_len (1 byte) | ifname (ifname_len bytes) |
+    //            frame_len (2 bytes) | frame (frame_len bytes)
+    //
+    //            IPv4 addresses are written in the first 4 bytes of src_ip and dst_ip.
+    pub fn serialize(&mut self, p : &mut packet) {
+        let mut flags : u8 = 0;
+        let mut ifname_len : u8 = self.ifname.len() as u8;
+        let mut frame_len : u16 = self.frame.len() as u16;
+
+        if self.has_vlan {
+            flags |= event_pkt_info::FLAG_VLAN;
+        }
+        if self.has_ports {
+            flags |= event_pkt_info::FLAG_PORTS;
+        }
+
+        p.serialize_mac(&mut self.src_mac);
+        p.serialize_mac(&mut self.dst_mac);
+        p.serialize_2_bytes(&mut self.vlan_id);
+        p.serialize_byte(&mut flags);
+        p.serialize_byte(&mut self.ip_version);
+        p.serialize_byte(&mut self.protocol);
+
+        if self.ip_version == 4 {
+            let mut src_ip : [u8; 16] = [0; 16];
+            let mut dst_ip : [u8; 16] = [0; 16];
+
+            src_ip[0..4].copy_from_slice(&self.src_ipaddr.to_be_bytes());
+            dst_ip[0..4].copy_from_slice(&self.dst_ipaddr.to_be_bytes());
+            p.serialize_ip6addr(&mut src_ip);
+            p.serialize_ip6addr(&mut dst_ip);
+        } else {
+            p.serialize_ip6addr(&mut self.src_ip6addr);
+            p.serialize_ip6addr(&mut self.dst_ip6addr);
+        }
+
+        p.serialize_2_bytes(&mut self.src_port);
+        p.serialize_2_bytes(&mut self.dst_port);
+
+        p.serialize_byte(&mut ifname_len);
+        p.buf[p.off..p.off + ifname_len as usize].copy_from_slice(self.ifname.as_bytes());
+        p.off += ifname_len as usize;
+
+        p.serialize_2_bytes(&mut frame_len);
+        p.buf[p.off..p.off + frame_len as usize].copy_from_slice(&self.frame);
+        p.off += frame_len as usize;
+    }
+}
+
 // @brief - describes event info
 pub struct event_info {
     pub event_type : u32,
     pub event_desc : u32,
-    pub detection_ts : timestamp
+    pub detection_ts : timestamp, // first seen
+    pub last_seen_ts : timestamp,
+    pub count : u32,
+    pub pkt_info : event_pkt_info,
+    // key value pairs of the detectors that summarize many packets
+    pub details : Vec<(String, String)>,
+    pub logged_count : u32, // count written to the local event log, 0 if not written
+    pub id : u64 // increasing id given by the event manager
 }
 
 impl event_info {
+    // length of the serialized event info record without the packet info
+    pub const EVENT_INFO_LEN : usize = 28;
+    // longest details string written to the binary record
+    pub const EVENT_DETAILS_MAX_LEN : usize = 256;
+
     // @brief - returns an initialized event_info
     pub fn new() -> event_info {
         let evt_info = event_info {
             event_type : 0,
             event_desc : 0,
-            detection_ts : timestamp::new()
+            detection_ts : timestamp::new(),
+            last_seen_ts : timestamp::new(),
+            count : 1,
+            pkt_info : event_pkt_info::new(),
+            details : Vec::new(),
+            logged_count : 0,
+            id : 0
         };
         evt_info
     }
@@ -31,5 +235,168 @@ impl event_info {
         self.event_desc = event_desc;
         // write detection time as now
         get_wallclock(&mut self.detection_ts);
+        self.last_seen_ts.sec = self.detection_ts.sec;
+        self.last_seen_ts.usec = self.detection_ts.usec;
+    }
+
+    // @brief - count another occurrence of this event
+    //
+    // @param [in] self - event info
+    pub fn add_occurrence(&mut self) {
+        self.count = self.count.saturating_add(1);
+        get_wallclock(&mut self.last_seen_ts);
+    }
+
+    // @brief - returns the first seen time in milliseconds since epoch
+    pub fn first_seen_ms(&self) -> u64 {
+        return (self.detection_ts.sec as u64 * 1000) + (self.detection_ts.usec as u64 / 1000);
+    }
+
+    // @brief - returns the last seen time in milliseconds since epoch
+    pub fn last_seen_ms(&self) -> u64 {
+        return (self.last_seen_ts.sec as u64 * 1000) + (self.last_seen_ts.usec as u64 / 1000);
+    }
+
+    // @brief - format the details as a string
+    //
+    // @return details as key=value pairs separated by ;
+    pub fn details_str(&self) -> String {
+        let mut details = String::new();
+
+        for (key, val) in &self.details {
+            if details.len() > 0 {
+                details.push(';');
+            }
+            details.push_str(&format!("{}={}", key, val));
+        }
+
+        return details;
+    }
+
+    // @brief - returns the details string written to the binary record
+    fn details_bytes(&self) -> Vec<u8> {
+        let mut details = self.details_str().into_bytes();
+
+        details.truncate(event_info::EVENT_DETAILS_MAX_LEN);
+
+        return details;
+    }
+
+    // @brief - returns the length of the serialized event info record
+    pub fn serialized_len(&self) -> usize {
+        return event_info::EVENT_INFO_LEN + self.pkt_info.serialized_len() +
+               2 + self.details_bytes().len();
+    }
+
+    // @brief - serialize event info record
+    //
+    // @param [in] self - event info
+    // @param [inout] p - packet to write the record into
+    //
+    // @details - record is written in network byte order as below
+    //
+    //            event_type (4 bytes) | event_desc (4 bytes) |
+    //            detection_ts.sec (4 bytes) | detection_ts.usec (4 bytes) |
+    //            last_seen_ts.sec (4 bytes) | last_seen_ts.usec (4 bytes) |
+    //            count (4 bytes) | packet info (variable) |
+    //            details_len (2 bytes) | details (details_len bytes)
+    pub fn serialize(&mut self, p : &mut packet) {
+        let details = self.details_bytes();
+        let mut details_len : u16 = details.len() as u16;
+
+        p.serialize_4_bytes(&mut self.event_type);
+        p.serialize_4_bytes(&mut self.event_desc);
+        p.serialize_4_bytes(&mut self.detection_ts.sec);
+        p.serialize_4_bytes(&mut self.detection_ts.usec);
+        p.serialize_4_bytes(&mut self.last_seen_ts.sec);
+        p.serialize_4_bytes(&mut self.last_seen_ts.usec);
+        p.serialize_4_bytes(&mut self.count);
+        self.pkt_info.serialize(p);
+
+        p.serialize_2_bytes(&mut details_len);
+        p.buf[p.off..p.off + details.len()].copy_from_slice(&details);
+        p.off += details.len();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pkt_info_v4() -> event_pkt_info {
+        let mut pkt_info = event_pkt_info::new();
+
+        pkt_info.ifname = "eth0".to_string();
+        pkt_info.src_mac = [0x02, 0, 0, 0, 0, 1];
+        pkt_info.has_vlan = true;
+        pkt_info.vlan_id = 10;
+        pkt_info.ip_version = 4;
+        pkt_info.protocol = 6;
+        pkt_info.src_ipaddr = 0x0A000001;
+        pkt_info.dst_ipaddr = 0x0A000002;
+        pkt_info.has_ports = true;
+        pkt_info.src_port = 1234;
+        pkt_info.dst_port = 80;
+        pkt_info.frame = vec![0xAA; 20];
+        pkt_info
+    }
+
+    #[test]
+    fn pkt_info_layout() {
+        let mut pkt_info = pkt_info_v4();
+        let mut p = packet::new();
+
+        pkt_info.serialize(&mut p);
+        assert_eq!(p.off, pkt_info.serialized_len());
+        assert_eq!(&p.buf[0..6], &[0x02, 0, 0, 0, 0, 1]);
+        assert_eq!(&p.buf[12..14], &[0, 10]);
+        assert_eq!(p.buf[14], event_pkt_info::FLAG_VLAN | event_pkt_info::FLAG_PORTS);
+        assert_eq!(&p.buf[15..17], &[4, 6]);
+        // IPv4 addresses in the first 4 bytes of the 16 byte fields
+        assert_eq!(&p.buf[17..21], &[10, 0, 0, 1]);
+        assert_eq!(&p.buf[21..33], &[0; 12]);
+        assert_eq!(&p.buf[33..37], &[10, 0, 0, 2]);
+        assert_eq!(&p.buf[49..53], &[0x04, 0xD2, 0, 80]);
+        assert_eq!(p.buf[53], 4);
+        assert_eq!(&p.buf[54..58], b"eth0");
+        assert_eq!(&p.buf[58..60], &[0, 20]);
+    }
+
+    #[test]
+    fn record_len() {
+        let mut evt_info = event_info::new();
+        let mut p = packet::new();
+
+        evt_info.pkt_info = pkt_info_v4();
+        evt_info.details = vec![("syn_count".to_string(), "100".to_string()),
+                                ("window_sec".to_string(), "1".to_string())];
+        evt_info.serialize(&mut p);
+        assert_eq!(p.off, evt_info.serialized_len());
+        assert_eq!(evt_info.details_str(), "syn_count=100;window_sec=1");
+
+        // details are cut to the maximum length
+        evt_info.details = vec![("k".to_string(), "v".repeat(1000))];
+        p.off = 0;
+        evt_info.serialize(&mut p);
+        assert_eq!(p.off, evt_info.serialized_len());
+        assert_eq!(p.off, event_info::EVENT_INFO_LEN + evt_info.pkt_info.serialized_len() +
+                          2 + event_info::EVENT_DETAILS_MAX_LEN);
+    }
+
+    #[test]
+    fn same_src() {
+        let a = pkt_info_v4();
+        let mut b = pkt_info_v4();
+
+        b.src_port = 4321;
+        b.dst_ipaddr = 0x0A000003;
+        assert!(a.same_src(&b));
+
+        b.src_ipaddr = 0x0A000004;
+        assert!(!a.same_src(&b));
+
+        b = event_pkt_info::new();
+        b.src_mac = a.src_mac;
+        assert!(!a.same_src(&b));
     }
 }
@@ -1,26 +1,503 @@
 #![allow(dead_code)]
 #![allow(non_camel_case_types)]
 
-use super::event_info::event_info;
+use std::collections::{BTreeMap, VecDeque};
+
+use crate::lib::protocols::packet::packet::packet;
+use crate::lib::time_linux::timestamp::{get_wallclock, timestamp};
+
+use super::event_info::{event_info, event_pkt_info};
+
+#[non_exhaustive]
+pub struct event_overflow_policy;
+
+// @brief - defines what to do with a new event when the event list is full
+impl event_overflow_policy {
+    // drop the new event
+    pub const DROP_NEWEST   : u32 = 1;
+    // drop the oldest event in the list and insert the new event
+    pub const DROP_OLDEST   : u32 = 2;
+    // count the new event against an existing event of the same type and
+    // description, drop it if there is none
+    pub const COALESCE      : u32 = 3;
+}
 
 pub struct event_mgr {
-    evt_list : Vec<event_info>
+    evt_list            : VecDeque<event_info>,
+    capacity            : usize,
+    overflow_policy     : u32,
+    n_dropped           : u64,
+    n_coalesced         : u64,
+    n_aggregated        : u64,
+    aggr_window_sec     : u32,
+    pkt_info            : event_pkt_info,
+    ifname              : String,
+    pkt_snapshot_len    : usize,
+    next_evt_id         : u64,
+    evt_history         : VecDeque<event_info>,
+    evt_desc_counts     : BTreeMap<u32, u64>
 }
 
 impl event_mgr {
+    pub const EVT_LIST_DEFAULT_CAPACITY : usize = 4096;
+    pub const EVT_PKT_SNAPSHOT_MAX_LEN  : usize = 256;
+    pub const EVT_HISTORY_LEN           : usize = 256;
+
     pub fn new() -> event_mgr {
         let evt_mgr = event_mgr {
-            evt_list : Vec::new()
+            evt_list            : VecDeque::new(),
+            capacity            : event_mgr::EVT_LIST_DEFAULT_CAPACITY,
+            overflow_policy     : event_overflow_policy::DROP_NEWEST,
+            n_dropped           : 0,
+            n_coalesced         : 0,
+            n_aggregated        : 0,
+            aggr_window_sec     : 0,
+            pkt_info            : event_pkt_info::new(),
+            ifname              : "".to_string(),
+            pkt_snapshot_len    : 0,
+            next_evt_id         : 1,
+            evt_history         : VecDeque::new(),
+            evt_desc_counts     : BTreeMap::new()
         };
         evt_mgr
     }
 
+    // @brief - set the event list capacity and overflow policy
+    //
+    // @param [in] self - event manager
+    // @param [in] capacity - maximum number of events kept in the list
+    // @param [in] overflow_policy - one of event_overflow_policy
+    pub fn set_capacity(&mut self, capacity : usize, overflow_policy : u32) {
+        self.capacity = capacity;
+        self.overflow_policy = overflow_policy;
+
+        // shrink the list if the capacity is reduced
+        while self.evt_list.len() > self.capacity {
+            self.evt_list.pop_front();
+            self.n_dropped += 1;
+        }
+    }
+
+    // @brief - set the event aggregation window
+    //
+    // @param [in] self - event manager
+    // @param [in] aggr_window_sec - window in seconds, 0 to disable aggregation
+    //
+    // @details - events with the same description and source address seen
+    //            within the window of the first occurrence are counted in
+    //            a single event. the event is held back from the upload until
+    //            its window has expired.
+    pub fn set_aggr_window(&mut self, aggr_window_sec : u32) {
+        self.aggr_window_sec = aggr_window_sec;
+    }
+
+    // @brief - set the interface name recorded in the events
+    //
+    // @param [in] self - event manager
+    // @param [in] ifname - interface name
+    pub fn set_ifname(&mut self, ifname : &str) {
+        self.ifname = ifname.to_string();
+    }
+
+    // @brief - set the number of frame bytes recorded in the events
+    //
+    // @param [in] self - event manager
+    // @param [in] pkt_snapshot_len - number of bytes, 0 to disable
+    pub fn set_pkt_snapshot_len(&mut self, pkt_snapshot_len : usize) {
+        self.pkt_snapshot_len = if pkt_snapshot_len > event_mgr::EVT_PKT_SNAPSHOT_MAX_LEN {
+            event_mgr::EVT_PKT_SNAPSHOT_MAX_LEN
+        } else {
+            pkt_snapshot_len
+        };
+    }
+
+    // @brief - start recording the packet info of a new packet
+    //
+    // @param [in] self - event manager
+    // @param [in] p - packet about to be parsed
+    //
+    // @details - the packet info is attached to every event raised until
+    //            the next call to begin_pkt.
+    pub fn begin_pkt(&mut self, p : &packet) {
+        self.pkt_info = event_pkt_info::new();
+        self.pkt_info.ifname = self.ifname.clone();
+
+        if self.pkt_snapshot_len > 0 {
+            let len = if p.pkt_len < self.pkt_snapshot_len { p.pkt_len } else { self.pkt_snapshot_len };
+
+            self.pkt_info.frame.extend_from_slice(&p.buf[0..len]);
+        }
+    }
+
+    // @brief - record the mac addresses of the current packet
+    pub fn set_pkt_macs(&mut self, src_mac : &[u8; 6], dst_mac : &[u8; 6]) {
+        self.pkt_info.src_mac = *src_mac;
+        self.pkt_info.dst_mac = *dst_mac;
+    }
+
+    // @brief - record the VLAN id of the current packet
+    pub fn set_pkt_vlan(&mut self, vlan_id : u16) {
+        self.pkt_info.has_vlan = true;
+        self.pkt_info.vlan_id = vlan_id;
+    }
+
+    // @brief - record the IPv4 addresses and protocol of the current packet
+    pub fn set_pkt_ipv4(&mut self, src_ipaddr : u32, dst_ipaddr : u32, protocol : u8) {
+        self.pkt_info.ip_version = 4;
+        self.pkt_info.src_ipaddr = src_ipaddr;
+        self.pkt_info.dst_ipaddr = dst_ipaddr;
+        self.pkt_info.protocol = protocol;
+    }
+
+    // @brief - record the IPv6 addresses and next header of the current packet
+    pub fn set_pkt_ipv6(&mut self, src_ip6addr : &[u8; 16], dst_ip6addr : &[u8; 16], next_hdr : u8) {
+        self.pkt_info.ip_version = 6;
+        self.pkt_info.src_ip6addr = *src_ip6addr;
+        self.pkt_info.dst_ip6addr = *dst_ip6addr;
+        self.pkt_info.protocol = next_hdr;
+    }
+
+    // @brief - record the layer 4 ports of the current packet
+    pub fn set_pkt_ports(&mut self, src_port : u16, dst_port : u16) {
+        self.pkt_info.has_ports = true;
+        self.pkt_info.src_port = src_port;
+        self.pkt_info.dst_port = dst_port;
+    }
+
+    // @brief - count the event against the latest event with same type and description
+    //
+    // @param [in] self - event manager
+    // @param [in] event_type - event type
+    // @param [in] event_desc - event description
+    //
+    // @return true if coalesced false if there is no such event
+    fn coalesce_evt_info(&mut self, event_type : u32, event_desc : u32) -> bool {
+        for evt_info in self.evt_list.iter_mut().rev() {
+            if (evt_info.event_type == event_type) && (evt_info.event_desc == event_desc) {
+                evt_info.add_occurrence();
+                return true;
+            }
+        }
+
+        return false;
+    }
+
+    // @brief - count the event against an event from the same source within the window
+    //
+    // @param [in] self - event manager
+    // @param [in] event_desc - event description
+    // @param [in] now - current time
+    //
+    // @return true if aggregated false if there is no such event
+    fn aggregate_evt_info(&mut self, event_desc : u32, now : &timestamp) -> bool {
+        // events are in the order of their first occurrence, so stop at
+        // the first event whose window has expired
+        for evt_info in self.evt_list.iter_mut().rev() {
+            if now.sec.wrapping_sub(evt_info.detection_ts.sec) >= self.aggr_window_sec {
+                break;
+            }
+
+            if (evt_info.event_desc == event_desc) && evt_info.pkt_info.same_src(&self.pkt_info) {
+                evt_info.add_occurrence();
+                return true;
+            }
+        }
+
+        return false;
+    }
+
     pub fn insert_evt_info(&mut self,
                            event_type : u32,
                            event_desc : u32) {
+        self.insert_evt_info_details(event_type, event_desc, Vec::new());
+    }
+
+    // @brief - insert an event with details
+    //
+    // @param [in] self - event manager
+    // @param [in] event_type - event type
+    // @param [in] event_desc - event description
+    // @param [in] details - key value pairs describing the detection
+    pub fn insert_evt_info_details(&mut self,
+                                   event_type : u32,
+                                   event_desc : u32,
+                                   details : Vec<(String, String)>) {
         let mut evt_info : event_info = event_info::new();
 
+        // count every detection, also the ones aggregated or dropped below
+        *self.evt_desc_counts.entry(event_desc).or_insert(0) += 1;
+
+        if self.aggr_window_sec > 0 {
+            let mut now = timestamp::new();
+
+            get_wallclock(&mut now);
+            if self.aggregate_evt_info(event_desc, &now) {
+                self.n_aggregated += 1;
+                return;
+            }
+        }
+
+        // list is full, apply the overflow policy
+        if self.evt_list.len() >= self.capacity {
+            match self.overflow_policy {
+                event_overflow_policy::DROP_OLDEST => {
+                    self.evt_list.pop_front();
+                    self.n_dropped += 1;
+                }
+                event_overflow_policy::COALESCE => {
+                    if self.coalesce_evt_info(event_type, event_desc) {
+                        self.n_coalesced += 1;
+                    } else {
+                        self.n_dropped += 1;
+                    }
+                    return;
+                }
+                _ => {
+                    self.n_dropped += 1;
+                    return;
+                }
+            }
+
+            // capacity of 0 keeps no events at all
+            if self.capacity == 0 {
+                return;
+            }
+        }
+
         evt_info.set(event_type, event_desc);
-        self.evt_list.push(evt_info);
+        evt_info.pkt_info = self.pkt_info.clone();
+        evt_info.details = details;
+        evt_info.id = self.next_evt_id;
+        self.next_evt_id += 1;
+        self.evt_list.push_back(evt_info);
+    }
+
+    // @brief - get the list of collected events
+    //
+    // @param [in] self - event manager
+    //
+    // @return list of events in the order of detection
+    pub fn get_evt_list(&mut self) -> &mut VecDeque<event_info> { return &mut self.evt_list; }
+
+    // @brief - returns the number of collected events
+    pub fn n_evts(&self) -> usize { return self.evt_list.len(); }
+
+    // @brief - returns the number of events that can be uploaded
+    //
+    // @param [in] self - event manager
+    //
+    // @return number of events at the start of the list whose aggregation window has expired
+    pub fn n_ready_evts(&self) -> usize {
+        let mut now = timestamp::new();
+        let mut n_ready : usize = 0;
+
+        if self.aggr_window_sec == 0 {
+            return self.evt_list.len();
+        }
+
+        get_wallclock(&mut now);
+        for evt_info in self.evt_list.iter() {
+            if now.sec.wrapping_sub(evt_info.detection_ts.sec) < self.aggr_window_sec {
+                break;
+            }
+            n_ready += 1;
+        }
+
+        return n_ready;
+    }
+
+    // @brief - returns the number of events aggregated into earlier events
+    pub fn get_n_aggregated(&self) -> u64 { return self.n_aggregated; }
+
+    // @brief - returns the number of events dropped because the list was full
+    pub fn get_n_dropped(&self) -> u64 { return self.n_dropped; }
+
+    // @brief - returns the number of events coalesced because the list was full
+    pub fn get_n_coalesced(&self) -> u64 { return self.n_coalesced; }
+
+    // @brief - remove the oldest n_evts from the list
+    //
+    // @param [in] self - event manager
+    // @param [in] n_evts - number of events to remove
+    //
+    // @details - removed events are kept in a short history so that they
+    //            can still be queried over the control socket.
+    pub fn remove_evts(&mut self, n_evts : usize) {
+        let n = if n_evts > self.evt_list.len() { self.evt_list.len() } else { n_evts };
+
+        for evt_info in self.evt_list.drain(0..n) {
+            self.evt_history.push_back(evt_info);
+        }
+
+        while self.evt_history.len() > event_mgr::EVT_HISTORY_LEN {
+            self.evt_history.pop_front();
+        }
+    }
+
+    // @brief - get the recent and the pending events newer than an id
+    //
+    // @param [in] self - event manager
+    // @param [in] since_id - id of the last event already seen, 0 for all
+    //
+    // @return events in the order of detection
+    pub fn get_evts_since(&self, since_id : u64) -> Vec<&event_info> {
+        let mut evts : Vec<&event_info> = Vec::new();
+
+        for evt_info in self.evt_history.iter().chain(self.evt_list.iter()) {
+            if evt_info.id > since_id {
+                evts.push(evt_info);
+            }
+        }
+
+        return evts;
+    }
+
+    // @brief - returns the number of detections of each event description
+    pub fn get_evt_desc_counts(&self) -> &BTreeMap<u32, u64> { return &self.evt_desc_counts; }
+
+    // @brief - reset the dropped, coalesced, aggregated and detection counters
+    pub fn clear_counters(&mut self) {
+        self.n_dropped = 0;
+        self.n_coalesced = 0;
+        self.n_aggregated = 0;
+        self.evt_desc_counts.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::{event_desc::event_desc, event_type::event_type};
+
+    const DENY : u32 = event_type::EVENT_TYPE_DENY;
+
+    fn descs(evt_mgr : &mut event_mgr) -> Vec<u32> {
+        return evt_mgr.get_evt_list().iter().map(|e| e.event_desc).collect();
+    }
+
+    #[test]
+    fn drop_newest() {
+        let mut evt_mgr = event_mgr::new();
+
+        evt_mgr.set_capacity(2, event_overflow_policy::DROP_NEWEST);
+        evt_mgr.insert_evt_info(DENY, event_desc::IPV4_FRAG_TINY);
+        evt_mgr.insert_evt_info(DENY, event_desc::IPV4_FRAG_OVERLAP);
+        evt_mgr.insert_evt_info(DENY, event_desc::IPV4_FRAG_FLOOD);
+
+        assert_eq!(descs(&mut evt_mgr), vec![event_desc::IPV4_FRAG_TINY, event_desc::IPV4_FRAG_OVERLAP]);
+        assert_eq!(evt_mgr.get_n_dropped(), 1);
+        // dropped detections are still counted
+        assert_eq!(evt_mgr.get_evt_desc_counts().get(&event_desc::IPV4_FRAG_FLOOD), Some(&1));
+    }
+
+    #[test]
+    fn drop_oldest() {
+        let mut evt_mgr = event_mgr::new();
+
+        evt_mgr.set_capacity(2, event_overflow_policy::DROP_OLDEST);
+        evt_mgr.insert_evt_info(DENY, event_desc::IPV4_FRAG_TINY);
+        evt_mgr.insert_evt_info(DENY, event_desc::IPV4_FRAG_OVERLAP);
+        evt_mgr.insert_evt_info(DENY, event_desc::IPV4_FRAG_FLOOD);
+
+        assert_eq!(descs(&mut evt_mgr), vec![event_desc::IPV4_FRAG_OVERLAP, event_desc::IPV4_FRAG_FLOOD]);
+        assert_eq!(evt_mgr.get_n_dropped(), 1);
+    }
+
+    #[test]
+    fn coalesce() {
+        let mut evt_mgr = event_mgr::new();
+
+        evt_mgr.set_capacity(2, event_overflow_policy::COALESCE);
+        evt_mgr.insert_evt_info(DENY, event_desc::IPV4_FRAG_TINY);
+        evt_mgr.insert_evt_info(DENY, event_desc::IPV4_FRAG_OVERLAP);
+        evt_mgr.insert_evt_info(DENY, event_desc::IPV4_FRAG_TINY);
+        evt_mgr.insert_evt_info(DENY, event_desc::IPV4_FRAG_TINY);
+        evt_mgr.insert_evt_info(DENY, event_desc::IPV4_FRAG_FLOOD);
+
+        assert_eq!(descs(&mut evt_mgr), vec![event_desc::IPV4_FRAG_TINY, event_desc::IPV4_FRAG_OVERLAP]);
+        assert_eq!(evt_mgr.get_evt_list()[0].count, 3);
+        assert_eq!(evt_mgr.get_n_coalesced(), 2);
+        // nothing to count a new kind of event against
+        assert_eq!(evt_mgr.get_n_dropped(), 1);
+    }
+
+    #[test]
+    fn pkt_info_attached() {
+        let mut evt_mgr = event_mgr::new();
+        let mut p = packet::with_buf_len(64);
+
+        p.pkt_len = 64;
+        p.buf[0] = 0xAB;
+        evt_mgr.set_ifname("eth1");
+        evt_mgr.set_pkt_snapshot_len(16);
+        evt_mgr.begin_pkt(&p);
+        evt_mgr.set_pkt_ipv4(0x0A000001, 0x0A000002, 17);
+        evt_mgr.set_pkt_ports(53, 5353);
+        evt_mgr.insert_evt_info(DENY, event_desc::IPV4_FRAG_TINY);
+
+        // the next packet starts with an empty packet info
+        evt_mgr.begin_pkt(&p);
+        evt_mgr.insert_evt_info(DENY, event_desc::IPV4_FRAG_OVERLAP);
+
+        let evts = evt_mgr.get_evt_list();
+        assert_eq!(evts[0].pkt_info.ifname, "eth1");
+        assert_eq!(evts[0].pkt_info.src_ip_str(), "10.0.0.1");
+        assert_eq!(evts[0].pkt_info.dst_port, 5353);
+        assert_eq!(evts[0].pkt_info.frame.len(), 16);
+        assert_eq!(evts[0].pkt_info.frame[0], 0xAB);
+        assert_eq!(evts[1].pkt_info.ip_version, 0);
+        assert!(!evts[1].pkt_info.has_ports);
+        assert!(evts[0].id < evts[1].id);
+
+        evt_mgr.set_pkt_snapshot_len(100000);
+        assert_eq!(evt_mgr.pkt_snapshot_len, event_mgr::EVT_PKT_SNAPSHOT_MAX_LEN);
+    }
+
+    #[test]
+    fn aggregate_per_src() {
+        let mut evt_mgr = event_mgr::new();
+
+        evt_mgr.set_aggr_window(60);
+        for src_ipaddr in [0x0A000001, 0x0A000001, 0x0A000002, 0x0A000001] {
+            evt_mgr.set_pkt_ipv4(src_ipaddr, 0x0A0000FF, 6);
+            evt_mgr.insert_evt_info(DENY, event_desc::IPV4_FRAG_TINY);
+        }
+        evt_mgr.insert_evt_info(DENY, event_desc::IPV4_FRAG_OVERLAP);
+
+        assert_eq!(evt_mgr.n_evts(), 3);
+        assert_eq!(evt_mgr.get_evt_list()[0].count, 3);
+        assert_eq!(evt_mgr.get_evt_list()[1].count, 1);
+        assert_eq!(evt_mgr.get_n_aggregated(), 2);
+        assert_eq!(evt_mgr.get_evt_desc_counts().get(&event_desc::IPV4_FRAG_TINY), Some(&4));
+
+        // held back until the window of the first occurrence expires
+        assert_eq!(evt_mgr.n_ready_evts(), 0);
+        for evt_info in evt_mgr.get_evt_list().iter_mut().take(2) {
+            evt_info.detection_ts.sec -= 60;
+        }
+        assert_eq!(evt_mgr.n_ready_evts(), 2);
+
+        // a new occurrence after the window starts a new event
+        evt_mgr.set_pkt_ipv4(0x0A000002, 0x0A0000FF, 6);
+        evt_mgr.insert_evt_info(DENY, event_desc::IPV4_FRAG_TINY);
+        assert_eq!(evt_mgr.n_evts(), 4);
+        assert_eq!(evt_mgr.get_n_aggregated(), 2);
+    }
+
+    #[test]
+    fn shrink_capacity() {
+        let mut evt_mgr = event_mgr::new();
+
+        evt_mgr.insert_evt_info(DENY, event_desc::IPV4_FRAG_TINY);
+        evt_mgr.insert_evt_info(DENY, event_desc::IPV4_FRAG_OVERLAP);
+        evt_mgr.insert_evt_info(DENY, event_desc::IPV4_FRAG_FLOOD);
+        evt_mgr.set_capacity(1, event_overflow_policy::DROP_NEWEST);
+
+        assert_eq!(descs(&mut evt_mgr), vec![event_desc::IPV4_FRAG_FLOOD]);
+        assert_eq!(evt_mgr.get_n_dropped(), 2);
+
+        evt_mgr.set_capacity(0, event_overflow_policy::DROP_OLDEST);
+        evt_mgr.insert_evt_info(DENY, event_desc::IPV4_FRAG_TINY);
+        assert_eq!(evt_mgr.n_evts(), 0);
     }
 }
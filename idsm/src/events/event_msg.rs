@@ -0,0 +1,148 @@
+// @brief - defines the event upload message format
+// @copyright - Devendra Naga 2024-present All rights reserved.
+#![allow(dead_code)]
+#![allow(non_camel_case_types)]
+
+use crate::lib::protocols::packet::packet::packet;
+
+use super::event_info::event_info;
+
+// @brief - defines event message header
+//
+// @details - every event message starts with the header and is followed by
+//            n_events event info records. all fields are in network byte order.
+//
+//            magic (4 bytes) | version (2 bytes) | n_events (2 bytes) | seq_no (4 bytes)
+pub struct event_msg_hdr {
+    pub magic       : u32,
+    pub version     : u16,
+    pub n_events    : u16,
+    pub seq_no      : u32
+}
+
+impl event_msg_hdr {
+    // "IDSE" in ascii
+    pub const EVENT_MSG_MAGIC       : u32 = 0x49445345;
+    pub const EVENT_MSG_VERSION     : u16 = 4;
+    pub const EVENT_MSG_HDR_LEN     : usize = 12;
+
+    // @brief - zero initialize the event message header
+    //
+    // @return event message header
+    pub fn new() -> event_msg_hdr {
+        let hdr = event_msg_hdr {
+            magic       : event_msg_hdr::EVENT_MSG_MAGIC,
+            version     : event_msg_hdr::EVENT_MSG_VERSION,
+            n_events    : 0,
+            seq_no      : 0
+        };
+        hdr
+    }
+
+    // @brief - serialize event message header
+    //
+    // @param [in] self - event message header
+    // @param [inout] p - packet
+    pub fn serialize(&mut self, p : &mut packet) {
+        p.serialize_4_bytes(&mut self.magic);
+        p.serialize_2_bytes(&mut self.version);
+        p.serialize_2_bytes(&mut self.n_events);
+        p.serialize_4_bytes(&mut self.seq_no);
+    }
+}
+
+// @brief - defines event message builder
+pub struct event_msg;
+
+impl event_msg {
+    // keep the message within the ethernet MTU to avoid IP fragmentation
+    pub const EVENT_MSG_MAX_LEN : usize = 1400;
+
+    // @brief - serialize as many events as can fit in one message
+    //
+    // @param [in] evt_list - list of events to serialize
+    // @param [in] seq_no - sequence number of the message
+    // @param [out] p - packet to write the message into, p.off holds the message length
+    //
+    // @return number of events serialized into the message
+    pub fn serialize(evt_list : &mut [event_info], seq_no : u32, p : &mut packet) -> usize {
+        let mut hdr = event_msg_hdr::new();
+        let mut n_events : usize = 0;
+
+        // leave room for the header, it is written once the event count is known
+        p.off = event_msg_hdr::EVENT_MSG_HDR_LEN;
+
+        for evt_info in evt_list.iter_mut() {
+            if (p.off + evt_info.serialized_len() > event_msg::EVENT_MSG_MAX_LEN) ||
+               (n_events == u16::MAX as usize) {
+                break;
+            }
+
+            evt_info.serialize(p);
+            n_events += 1;
+        }
+
+        let msg_len = p.off;
+
+        hdr.n_events = n_events as u16;
+        hdr.seq_no = seq_no;
+
+        p.off = 0;
+        hdr.serialize(p);
+
+        p.off = msg_len;
+        p.pkt_len = msg_len;
+
+        return n_events;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn evt_list(n_evts : usize) -> Vec<event_info> {
+        let mut evt_list : Vec<event_info> = Vec::new();
+
+        for i in 0..n_evts {
+            let mut evt_info = event_info::new();
+
+            evt_info.event_type = 2;
+            evt_info.event_desc = 0x1000 + i as u32;
+            evt_list.push(evt_info);
+        }
+
+        return evt_list;
+    }
+
+    #[test]
+    fn msg_hdr() {
+        let mut evts = evt_list(2);
+        let mut p = packet::new();
+        let rec_len = evts[0].serialized_len();
+
+        assert_eq!(event_msg::serialize(&mut evts, 7, &mut p), 2);
+        assert_eq!(p.pkt_len, event_msg_hdr::EVENT_MSG_HDR_LEN + 2 * rec_len);
+        assert_eq!(&p.buf[0..4], b"IDSE");
+        assert_eq!(&p.buf[4..6], &event_msg_hdr::EVENT_MSG_VERSION.to_be_bytes());
+        assert_eq!(&p.buf[6..8], &[0, 2]);
+        assert_eq!(&p.buf[8..12], &[0, 0, 0, 7]);
+        // the first record follows the header
+        assert_eq!(&p.buf[12..16], &[0, 0, 0, 2]);
+        assert_eq!(&p.buf[16..20], &[0, 0, 0x10, 0x00]);
+    }
+
+    #[test]
+    fn msg_bounded_by_max_len() {
+        let mut evts = evt_list(100);
+        let mut p = packet::new();
+        let rec_len = evts[0].serialized_len();
+        let n_fit = (event_msg::EVENT_MSG_MAX_LEN - event_msg_hdr::EVENT_MSG_HDR_LEN) / rec_len;
+
+        assert_eq!(event_msg::serialize(&mut evts, 1, &mut p), n_fit);
+        assert!(p.pkt_len <= event_msg::EVENT_MSG_MAX_LEN);
+
+        // the rest goes into the next message
+        assert_eq!(event_msg::serialize(&mut evts[n_fit..], 2, &mut p), n_fit);
+    }
+}
@@ -10,4 +10,17 @@ pub struct event_type;
 impl event_type {
     pub const EVENT_TYPE_ALLOW  : u32 = 1;
     pub const EVENT_TYPE_DENY   : u32 = 2;
+
+    // @brief - get the name of the event type
+    //
+    // @param [in] evt_type - event type
+    //
+    // @return name of the event type
+    pub fn name(evt_type : u32) -> &'static str {
+        match evt_type {
+            event_type::EVENT_TYPE_ALLOW => return "allow",
+            event_type::EVENT_TYPE_DENY => return "deny",
+            _ => return "unknown",
+        }
+    }
 }
@@ -2,3 +2,5 @@ pub(crate) mod event_type;
 pub(crate) mod event_desc;
 pub(crate) mod event_info;
 pub(crate) mod event_mgr;
+pub(crate) mod event_msg;
+pub(crate) mod event_encoder;
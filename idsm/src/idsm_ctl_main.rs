@@ -0,0 +1,102 @@
+#![allow(non_camel_case_types)]
+
+use ctl::ctl_cmdargs::ctl_cmd_args;
+use lib::socket_linux::unix_client::unix_client;
+
+mod lib;
+mod events;
+mod ctl;
+
+struct ctl_context {
+    cmd_args : ctl_cmd_args,
+    client : unix_client
+}
+
+impl ctl_context {
+    pub fn new() -> ctl_context {
+        let ctx = ctl_context {
+            cmd_args : ctl_cmd_args::new(),
+            client : unix_client::new()
+        };
+        ctx
+    }
+
+    pub fn init(&mut self) -> i32 {
+        let ret : i32;
+
+        ret = self.cmd_args.parse();
+        if ret < 0 {
+            return -1;
+        }
+
+        return 0;
+    }
+
+    // @brief - send the request to idsd and print the response
+    //
+    // @param [in] self - ctl context
+    //
+    // @return 0 on success -1 on failure
+    pub fn run(&mut self) -> i32 {
+        let mut buf : [u8; 4096] = [0; 4096];
+        let mut resp : Vec<u8> = Vec::new();
+        let mut req = self.cmd_args.request.clone();
+
+        if self.client.connect_stream(&self.cmd_args.socket_path) < 0 {
+            eprintln!("cannot connect to {}", self.cmd_args.socket_path);
+            return -1;
+        }
+
+        req.push('\n');
+        if self.client.send(req.as_bytes(), req.len()) != req.len() as isize {
+            eprintln!("failed to send the request");
+            return -1;
+        }
+
+        // idsd closes the connection after the response
+        loop {
+            let rx_len = self.client.recv(&mut buf, 4096);
+            if rx_len < 0 {
+                eprintln!("failed to receive the response");
+                return -1;
+            }
+            if rx_len == 0 {
+                break;
+            }
+
+            resp.extend_from_slice(&buf[..rx_len as usize]);
+        }
+
+        let resp_str = String::from_utf8_lossy(&resp);
+        let json : serde_json::Value = match serde_json::from_str(resp_str.trim_end()) {
+            Ok(j) => j,
+            Err(_) => {
+                eprintln!("invalid response: {}", resp_str);
+                return -1;
+            }
+        };
+
+        println!("{}", serde_json::to_string_pretty(&json).unwrap());
+
+        if json.get("status").and_then(|s| s.as_str()) != Some("ok") {
+            return -1;
+        }
+
+        return 0;
+    }
+}
+
+fn main() {
+    let mut ctx = ctl_context::new();
+    let mut ret : i32;
+
+    ret = ctx.init();
+    if ret < 0 {
+        std::process::exit(1);
+    }
+
+    ret = ctx.run();
+    if ret < 0 {
+        std::process::exit(1);
+    }
+}
@@ -3,6 +3,8 @@
 #![allow(dead_code)]
 #![allow(non_camel_case_types)]
 
+use std::ffi::CString;
+
 use libc;
 
 // @brief - defines fileio structure
@@ -22,6 +24,18 @@ impl fileio {
         io
     }
 
+    // @brief - convert a filename to a C string
+    //
+    // @param [in] filename - name of the file, may be terminated with '\0'
+    //
+    // @return C string of the filename, None if the name has a '\0' in between
+    fn c_filename(filename : &String) -> Option<CString> {
+        match CString::new(filename.trim_end_matches('\0')) {
+            Ok(s) => return Some(s),
+            Err(_) => return None,
+        }
+    }
+
     // @brief - create a new file
     //
     // @param [in] self - this structure
@@ -29,8 +43,13 @@ impl fileio {
     //
     // @return 0 on success -1 on failure
     pub fn new_file(&mut self, filename : &String) -> i32 {
+        let c_name = match fileio::c_filename(filename) {
+            Some(s) => s,
+            None => return -1,
+        };
+
         unsafe {
-            self.fd = libc::open(filename.as_ptr() as *const i8,
+            self.fd = libc::open(c_name.as_ptr(),
                                  libc::O_CREAT |
                                  libc::O_WRONLY |
                                  libc::O_APPEND,
@@ -42,6 +61,27 @@ impl fileio {
         return 0;
     }
 
+    // @brief - open an existing file for reading
+    //
+    // @param [in] self - this structure
+    // @param [in] filename - name of the file
+    //
+    // @return 0 on success -1 on failure
+    pub fn open_read(&mut self, filename : &String) -> i32 {
+        let c_name = match fileio::c_filename(filename) {
+            Some(s) => s,
+            None => return -1,
+        };
+
+        unsafe {
+            self.fd = libc::open(c_name.as_ptr(), libc::O_RDONLY);
+            if self.fd < 0 {
+                return -1;
+            }
+        }
+        return 0;
+    }
+
     // @brief - write to the opened file
     //
     // @param [in] self - this structure
@@ -59,6 +99,65 @@ impl fileio {
         return ret;
     }
 
+    // @brief - read from the opened file
+    //
+    // @param [in] self - this structure
+    // @param [out] buf - buffer to read into
+    // @param [in] buf_len - length of buffer
+    //
+    // @return read length on success, 0 at the end of file, -1 on failure
+    pub fn read(&mut self, buf : &mut [u8], buf_len : usize) -> isize {
+        let ret : isize;
+        unsafe {
+            ret = libc::read(self.fd,
+                             buf.as_mut_ptr() as *mut libc::c_void,
+                             buf_len);
+        }
+        return ret;
+    }
+
+    // @brief - move the read offset of the opened file
+    //
+    // @param [in] self - this structure
+    // @param [in] off - offset from the start of the file
+    //
+    // @return 0 on success -1 on failure
+    pub fn seek(&mut self, off : u64) -> i32 {
+        unsafe {
+            if libc::lseek(self.fd, off as libc::off_t, libc::SEEK_SET) < 0 {
+                return -1;
+            }
+        }
+        return 0;
+    }
+
+    // @brief - returns the size of the opened file
+    //
+    // @param [in] self - this structure
+    //
+    // @return size of the file on success -1 on failure
+    pub fn size(&self) -> i64 {
+        unsafe {
+            let mut st : libc::stat = std::mem::MaybeUninit::zeroed().assume_init();
+
+            if libc::fstat(self.fd, &mut st) < 0 {
+                return -1;
+            }
+            return st.st_size as i64;
+        }
+    }
+
+    // @brief - flush the written data to the media
+    //
+    // @param [in] self - this structure
+    pub fn sync(&mut self) {
+        unsafe {
+            if self.fd > 0 {
+                libc::fsync(self.fd);
+            }
+        }
+    }
+
     // @brief - close a opened file
     //
     // @param [in] self - this structure
@@ -68,6 +167,47 @@ impl fileio {
                 libc::close(self.fd);
             }
         }
+        self.fd = -1;
+    }
+
+    // @brief - check if a file is opened
+    pub fn is_open(&self) -> bool { return self.fd > 0; }
+
+    // @brief - remove a file
+    //
+    // @param [in] filename - name of the file
+    //
+    // @return 0 on success -1 on failure
+    pub fn unlink(filename : &String) -> i32 {
+        let c_name = match fileio::c_filename(filename) {
+            Some(s) => s,
+            None => return -1,
+        };
+
+        unsafe {
+            return libc::unlink(c_name.as_ptr());
+        }
+    }
+
+    // @brief - rename a file
+    //
+    // @param [in] old_filename - current name of the file
+    // @param [in] new_filename - new name of the file
+    //
+    // @return 0 on success -1 on failure
+    pub fn rename(old_filename : &String, new_filename : &String) -> i32 {
+        let c_old_name = match fileio::c_filename(old_filename) {
+            Some(s) => s,
+            None => return -1,
+        };
+        let c_new_name = match fileio::c_filename(new_filename) {
+            Some(s) => s,
+            None => return -1,
+        };
+
+        unsafe {
+            return libc::rename(c_old_name.as_ptr(), c_new_name.as_ptr());
+        }
     }
 }
 
@@ -79,4 +219,4 @@ impl Drop for fileio {
             }
         }
     }
-}
\ No newline at end of file
+}
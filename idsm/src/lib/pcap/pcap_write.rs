@@ -138,4 +138,14 @@ impl pcap_writer {
 
         return 0;
     }
+
+    // @brief - flush and close the pcap file
+    pub fn close(&mut self) {
+        if self.io.is_open() {
+            self.io.sync();
+            self.io.close();
+        }
+    }
+
+    pub fn is_open(&self) -> bool { return self.io.is_open(); }
 }
@@ -0,0 +1,172 @@
+// @brief - implements the internet checksum (RFC 1071)
+// @copyright - 2024-present Devendra Naga All rights reserved
+#![allow(dead_code)]
+#![allow(non_camel_case_types)]
+
+// @brief - add the buffer to a running checksum
+//
+// @param [in] sum - running checksum
+// @param [in] buf - data, an odd trailing byte is padded with zero
+//
+// @return running checksum
+pub fn inet_checksum_add(sum : u32, buf : &[u8]) -> u32 {
+    let mut s : u64 = sum as u64;
+    let mut i : usize = 0;
+
+    while i + 1 < buf.len() {
+        s += ((buf[i] as u64) << 8) | buf[i + 1] as u64;
+        i += 2;
+    }
+
+    if i < buf.len() {
+        s += (buf[i] as u64) << 8;
+    }
+
+    while (s >> 32) != 0 {
+        s = (s & 0xFFFFFFFF) + (s >> 32);
+    }
+
+    return s as u32;
+}
+
+// @brief - fold a running checksum into 16 bits
+//
+// @param [in] sum - running checksum
+//
+// @return ones complement of the folded sum
+pub fn inet_checksum_fold(sum : u32) -> u16 {
+    let mut s : u32 = sum;
+
+    while (s >> 16) != 0 {
+        s = (s & 0xFFFF) + (s >> 16);
+    }
+
+    return !(s as u16);
+}
+
+// @brief - check a buffer that carries its own checksum
+//
+// @param [in] sum - running checksum of the data, including the checksum field
+//
+// @return true if the checksum is correct
+pub fn inet_checksum_ok(sum : u32) -> bool {
+    return inet_checksum_fold(sum) == 0;
+}
+
+// @brief - start a checksum with the ipv4 pseudo header (RFC 768, RFC 793)
+//
+// @param [in] src_ipaddr - source address
+// @param [in] dst_ipaddr - destination address
+// @param [in] protocol - layer 4 protocol
+// @param [in] l4_len - length of the layer 4 header and data
+//
+// @return running checksum
+pub fn inet_checksum_pseudo_ipv4(src_ipaddr : u32, dst_ipaddr : u32, protocol : u8, l4_len : u32) -> u32 {
+    let mut hdr : [u8; 12] = [0; 12];
+
+    hdr[0..4].copy_from_slice(&src_ipaddr.to_be_bytes());
+    hdr[4..8].copy_from_slice(&dst_ipaddr.to_be_bytes());
+    hdr[9] = protocol;
+    hdr[10..12].copy_from_slice(&(l4_len as u16).to_be_bytes());
+
+    return inet_checksum_add(0, &hdr);
+}
+
+// @brief - start a checksum with the ipv6 pseudo header (RFC 8200 section 8.1)
+//
+// @param [in] src_ip6addr - source address
+// @param [in] dst_ip6addr - final destination address
+// @param [in] next_hdr - upper layer protocol
+// @param [in] l4_len - length of the upper layer header and data
+//
+// @return running checksum
+pub fn inet_checksum_pseudo_ipv6(src_ip6addr : &[u8; 16], dst_ip6addr : &[u8; 16], next_hdr : u8, l4_len : u32) -> u32 {
+    let mut hdr : [u8; 40] = [0; 40];
+
+    hdr[0..16].copy_from_slice(src_ip6addr);
+    hdr[16..32].copy_from_slice(dst_ip6addr);
+    hdr[32..36].copy_from_slice(&l4_len.to_be_bytes());
+    hdr[39] = next_hdr;
+
+    return inet_checksum_add(0, &hdr);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const IPV4_HDR : [u8; 20] = [
+        0x45, 0x00, 0x00, 0x73, 0x00, 0x00, 0x40, 0x00, 0x40, 0x11,
+        0xB8, 0x61, 0xC0, 0xA8, 0x00, 0x01, 0xC0, 0xA8, 0x00, 0xC7
+    ];
+
+    // SYN from 192.168.0.1:12345 to 192.168.0.199:80
+    const TCP_SYN : [u8; 20] = [
+        0x30, 0x39, 0x00, 0x50, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00,
+        0x00, 0x00, 0x50, 0x02, 0xFF, 0xFF, 0xFD, 0x3F, 0x00, 0x00
+    ];
+
+    // "hello" from [fe80::1]:5353 to [fe80::2]:5353
+    const UDP_HELLO : [u8; 13] = [
+        0x14, 0xE9, 0x14, 0xE9, 0x00, 0x0D, 0x95, 0x2B,
+        b'h', b'e', b'l', b'l', b'o'
+    ];
+
+    fn ip6addr(last : u8) -> [u8; 16] {
+        let mut addr : [u8; 16] = [0; 16];
+
+        addr[0] = 0xFE;
+        addr[1] = 0x80;
+        addr[15] = last;
+        return addr;
+    }
+
+    fn without_checksum(buf : &[u8], off : usize) -> Vec<u8> {
+        let mut v = buf.to_vec();
+
+        v[off] = 0;
+        v[off + 1] = 0;
+        return v;
+    }
+
+    #[test]
+    fn rfc1071_example() {
+        let sum = inet_checksum_add(0, &[0x00, 0x01, 0xF2, 0x03, 0xF4, 0xF5, 0xF6, 0xF7]);
+
+        assert_eq!(inet_checksum_fold(sum), !0xDDF2);
+    }
+
+    #[test]
+    fn ipv4_hdr() {
+        assert!(inet_checksum_ok(inet_checksum_add(0, &IPV4_HDR)));
+        assert_eq!(inet_checksum_fold(inet_checksum_add(0, &without_checksum(&IPV4_HDR, 10))), 0xB861);
+
+        let mut bad = IPV4_HDR;
+        bad[8] = 0x3F;
+        assert!(!inet_checksum_ok(inet_checksum_add(0, &bad)));
+    }
+
+    #[test]
+    fn tcp_over_ipv4() {
+        let pseudo = inet_checksum_pseudo_ipv4(0xC0A80001, 0xC0A800C7, 6, TCP_SYN.len() as u32);
+
+        assert!(inet_checksum_ok(inet_checksum_add(pseudo, &TCP_SYN)));
+        assert_eq!(inet_checksum_fold(inet_checksum_add(pseudo, &without_checksum(&TCP_SYN, 16))), 0xFD3F);
+
+        // the same segment to another host
+        let pseudo = inet_checksum_pseudo_ipv4(0xC0A80001, 0xC0A800C8, 6, TCP_SYN.len() as u32);
+        assert!(!inet_checksum_ok(inet_checksum_add(pseudo, &TCP_SYN)));
+    }
+
+    #[test]
+    fn udp_over_ipv6() {
+        let pseudo = inet_checksum_pseudo_ipv6(&ip6addr(1), &ip6addr(2), 17, UDP_HELLO.len() as u32);
+
+        // odd length, the last byte is padded
+        assert!(inet_checksum_ok(inet_checksum_add(pseudo, &UDP_HELLO)));
+        assert_eq!(inet_checksum_fold(inet_checksum_add(pseudo, &without_checksum(&UDP_HELLO, 6))), 0x952B);
+
+        let pseudo = inet_checksum_pseudo_ipv6(&ip6addr(1), &ip6addr(2), 58, UDP_HELLO.len() as u32);
+        assert!(!inet_checksum_ok(inet_checksum_add(pseudo, &UDP_HELLO)));
+    }
+}
@@ -0,0 +1,120 @@
+// @brief - implements helpers shared by the detectors
+// @copyright - 2024-present Devendra Naga All rights reserved
+#![allow(dead_code)]
+#![allow(non_camel_case_types)]
+
+use std::net::Ipv6Addr;
+
+// @brief - format an address kept as an ipv6 address, ipv4 mapped
+//          addresses are written as ipv4
+//
+// @param [in] addr - address
+//
+// @return address as string
+pub fn ip_addr_str(addr : &[u8; 16]) -> String {
+    let ip6addr = Ipv6Addr::from(*addr);
+
+    match ip6addr.to_ipv4_mapped() {
+        Some(ipaddr) => return ipaddr.to_string(),
+        None => return ip6addr.to_string(),
+    }
+}
+
+// @brief - counts over a sliding window
+//
+// @details - the count of the previous window is weighed by how much of it
+//            still overlaps the sliding window, so a fixed window does not
+//            reset the count at its boundary.
+#[derive(Clone, Copy)]
+pub struct sliding_count {
+    window_start        : u32,
+    cur                 : u32,
+    prev                : u32
+}
+
+impl sliding_count {
+    pub fn new(now_sec : u32) -> sliding_count {
+        let count = sliding_count {
+            window_start        : now_sec,
+            cur                 : 0,
+            prev                : 0
+        };
+        count
+    }
+
+    // @brief - move the window up to now
+    //
+    // @return true if a new window started
+    fn roll(&mut self, now_sec : u32, window_sec : u32) -> bool {
+        let elapsed = now_sec.wrapping_sub(self.window_start);
+
+        if elapsed < window_sec {
+            return false;
+        }
+
+        if elapsed < window_sec * 2 {
+            self.prev = self.cur;
+            self.window_start = self.window_start.wrapping_add(window_sec);
+        } else {
+            self.prev = 0;
+            self.window_start = now_sec;
+        }
+        self.cur = 0;
+
+        return true;
+    }
+
+    // @brief - add to the count
+    pub fn add(&mut self, now_sec : u32, window_sec : u32, n : u32) {
+        self.roll(now_sec, window_sec);
+        self.cur = self.cur.saturating_add(n);
+    }
+
+    // @brief - returns the count over the last window_sec seconds
+    pub fn value(&mut self, now_sec : u32, window_sec : u32) -> u32 {
+        self.roll(now_sec, window_sec);
+
+        let elapsed = now_sec.wrapping_sub(self.window_start) as u64;
+        let prev_part = self.prev as u64 * (window_sec as u64 - elapsed) / window_sec as u64;
+
+        return self.cur.saturating_add(prev_part as u32);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn addr_str() {
+        let mut addr = [0u8; 16];
+
+        addr[10] = 0xff;
+        addr[11] = 0xff;
+        addr[12..16].copy_from_slice(&[192, 168, 1, 10]);
+        assert_eq!(ip_addr_str(&addr), "192.168.1.10");
+
+        addr = [0u8; 16];
+        addr[0] = 0xfe;
+        addr[1] = 0x80;
+        addr[15] = 1;
+        assert_eq!(ip_addr_str(&addr), "fe80::1");
+    }
+
+    #[test]
+    fn sliding_window() {
+        let mut count = sliding_count::new(100);
+
+        count.add(100, 10, 10);
+        assert_eq!(count.value(109, 10), 10);
+
+        // the previous window is weighed by how much of it still overlaps
+        assert_eq!(count.value(110, 10), 10);
+        assert_eq!(count.value(115, 10), 5);
+        count.add(115, 10, 2);
+        assert_eq!(count.value(115, 10), 7);
+
+        // nothing overlaps after two windows
+        assert_eq!(count.value(130, 10), 0);
+    }
+}
@@ -0,0 +1 @@
+pub(crate) mod detect_util;
\ No newline at end of file
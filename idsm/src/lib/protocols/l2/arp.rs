@@ -13,7 +13,7 @@ use crate::{
 };
 
 #[non_exhaustive]
-struct arp_operation_types;
+pub struct arp_operation_types;
 
 // @brief - list of ARP operations
 impl arp_operation_types {
@@ -83,6 +83,12 @@ impl arp_hdr {
     // @param [in] evt_mgt - event manager
     // @param [in] debug - debug packet
      pub fn deserialize(&mut self, p : &mut packet, evt_mgr : &mut event_mgr, debug : bool) -> i32 {
+        if !p.remaining_len_in_bounds(arp_hdr::ARP_HDR_LEN) {
+            evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY,
+                                    event_desc::ARP_SHORT_HDR_LEN);
+            return -1;
+        }
+
         p.deserialize_2_bytes(&mut self.hdr_type);
         p.deserialize_2_bytes(&mut self.protocol_type);
         p.deserialize_byte(&mut self.hw_addr_len);
@@ -106,7 +112,7 @@ impl arp_hdr {
         p.deserialize_2_bytes(&mut self.op);
 
         // invalid ARP op
-        if arp_operation_types::valid_arp_op(self.op) {
+        if !arp_operation_types::valid_arp_op(self.op) {
             evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY,
                                     event_desc::ARP_OP_INVALID);
             return -1;
@@ -122,6 +128,21 @@ impl arp_hdr {
         return 0;
     }
 
+    // @brief - returns the ARP operation
+    pub fn op(&self) -> u16 { return self.op; }
+
+    // @brief - returns the sender hardware address
+    pub fn sender_hw_addr(&self) -> &[u8; 6] { return &self.sender_hw_addr; }
+
+    // @brief - returns the sender protocol address
+    pub fn sender_proto_addr(&self) -> u32 { return self.sender_proto_addr; }
+
+    // @brief - returns the target hardware address
+    pub fn target_hw_addr(&self) -> &[u8; 6] { return &self.target_hw_addr; }
+
+    // @brief - returns the target protocol address
+    pub fn target_proto_addr(&self) -> u32 { return self.target_proto_addr; }
+
     // @brief - prints ARP header
     //
     // @param [in] self - ARP header
@@ -0,0 +1,403 @@
+// @brief - implements ARP spoofing detection
+// @copyright - 2024-present Devendra Naga All rights reserved
+#![allow(dead_code)]
+#![allow(non_camel_case_types)]
+
+use std::collections::HashMap;
+use std::net::Ipv4Addr;
+
+use crate::{
+    events::{
+        event_desc::event_desc,
+        event_info::event_pkt_info,
+        event_mgr::event_mgr,
+        event_type::event_type
+    },
+    lib::protocols::{
+        l2::arp::{arp_hdr, arp_operation_types},
+        detect::detect_util::sliding_count
+    }
+};
+
+// @brief - defines the limits of the ARP guard
+pub struct arp_guard_limits {
+    pub max_bindings        : usize,
+    // learned bindings are forgotten when not seen for this long
+    pub binding_timeout_sec : u32,
+    // a reply is expected within this time of the request
+    pub request_timeout_sec : u32,
+    pub garp_window_sec     : u32,
+    // gratuitous ARPs of one sender within the window
+    pub garp_threshold      : u32
+}
+
+impl arp_guard_limits {
+    pub const DEFAULT_MAX_BINDINGS : usize = 4096;
+    pub const DEFAULT_BINDING_TIMEOUT_SEC : u32 = 14400;
+    pub const DEFAULT_REQUEST_TIMEOUT_SEC : u32 = 5;
+    pub const DEFAULT_GARP_WINDOW_SEC : u32 = 10;
+    pub const DEFAULT_GARP_THRESHOLD : u32 = 10;
+
+    pub fn new() -> arp_guard_limits {
+        let limits = arp_guard_limits {
+            max_bindings            : arp_guard_limits::DEFAULT_MAX_BINDINGS,
+            binding_timeout_sec     : arp_guard_limits::DEFAULT_BINDING_TIMEOUT_SEC,
+            request_timeout_sec     : arp_guard_limits::DEFAULT_REQUEST_TIMEOUT_SEC,
+            garp_window_sec         : arp_guard_limits::DEFAULT_GARP_WINDOW_SEC,
+            garp_threshold          : arp_guard_limits::DEFAULT_GARP_THRESHOLD
+        };
+        limits
+    }
+}
+
+// @brief - defines an IP to MAC binding
+struct arp_binding {
+    mac                 : [u8; 6],
+    // configured bindings never expire or change
+    is_static           : bool,
+    last_sec            : u32
+}
+
+// @brief - defines the gratuitous ARPs of a sender
+struct arp_garp_count {
+    count               : sliding_count,
+    alerted             : bool,
+    last_sec            : u32
+}
+
+// @brief - defines the ARP guard
+pub struct arp_guard {
+    bindings            : HashMap<u32, arp_binding>,
+    // (requested address, requester address) to the time of the request
+    requests            : HashMap<(u32, u32), u32>,
+    garps               : HashMap<[u8; 6], arp_garp_count>,
+    last_expire_sec     : u32,
+    pub limits          : arp_guard_limits
+}
+
+impl arp_guard {
+    pub fn new() -> arp_guard {
+        let guard = arp_guard {
+            bindings            : HashMap::new(),
+            requests            : HashMap::new(),
+            garps               : HashMap::new(),
+            last_expire_sec     : 0,
+            limits              : arp_guard_limits::new()
+        };
+        guard
+    }
+
+    // @brief - returns the number of bindings
+    pub fn n_bindings(&self) -> usize { return self.bindings.len(); }
+
+    // @brief - add a configured binding
+    //
+    // @param [inout] self - ARP guard
+    // @param [in] ipaddr - IPv4 address
+    // @param [in] mac - MAC address
+    pub fn add_static(&mut self, ipaddr : u32, mac : &[u8; 6]) {
+        self.bindings.insert(ipaddr, arp_binding { mac : *mac, is_static : true, last_sec : 0 });
+    }
+
+    // @brief - forget the learned state and the configured bindings
+    pub fn clear(&mut self) {
+        self.bindings.clear();
+        self.requests.clear();
+        self.garps.clear();
+    }
+
+    fn expire(&mut self, now_sec : u32) {
+        if now_sec == self.last_expire_sec {
+            return;
+        }
+        self.last_expire_sec = now_sec;
+
+        let limits = &self.limits;
+        self.bindings.retain(|_, b| b.is_static || (now_sec.wrapping_sub(b.last_sec) < limits.binding_timeout_sec));
+        self.requests.retain(|_, sec| now_sec.wrapping_sub(*sec) < limits.request_timeout_sec);
+        self.garps.retain(|_, g| now_sec.wrapping_sub(g.last_sec) < limits.garp_window_sec * 2);
+    }
+
+    fn deny(evt_mgr : &mut event_mgr, evt_desc : u32, details : Vec<(String, String)>) -> i32 {
+        evt_mgr.insert_evt_info_details(event_type::EVENT_TYPE_DENY, evt_desc, details);
+        return -1;
+    }
+
+    // @brief - count a gratuitous ARP of the sender
+    //
+    // @return 0 on success -1 if the sender floods
+    fn check_garp(&mut self, mac : &[u8; 6], now_sec : u32, evt_mgr : &mut event_mgr) -> i32 {
+        let window_sec = self.limits.garp_window_sec;
+
+        if !self.garps.contains_key(mac) {
+            if self.garps.len() >= self.limits.max_bindings {
+                return 0;
+            }
+            self.garps.insert(*mac, arp_garp_count {
+                count : sliding_count::new(now_sec),
+                alerted : false,
+                last_sec : now_sec
+            });
+        }
+
+        let garp = self.garps.get_mut(mac).unwrap();
+        garp.last_sec = now_sec;
+        garp.count.add(now_sec, window_sec, 1);
+
+        let count = garp.count.value(now_sec, window_sec);
+        if garp.alerted {
+            if count < self.limits.garp_threshold / 2 {
+                garp.alerted = false;
+            }
+            return 0;
+        }
+
+        if count < self.limits.garp_threshold {
+            return 0;
+        }
+
+        garp.alerted = true;
+
+        return arp_guard::deny(evt_mgr, event_desc::ARP_GRATUITOUS_FLOOD, vec![
+            ("sender_mac".to_string(), event_pkt_info::mac_str(mac)),
+            ("count".to_string(), count.to_string()),
+            ("window_sec".to_string(), window_sec.to_string())
+        ]);
+    }
+
+    // @brief - check the sender binding against the known one and learn it
+    //
+    // @return 0 on success -1 if the binding changed
+    fn check_binding(&mut self, ipaddr : u32, mac : &[u8; 6], now_sec : u32, evt_mgr : &mut event_mgr) -> i32 {
+        if let Some(b) = self.bindings.get_mut(&ipaddr) {
+            if b.mac == *mac {
+                b.last_sec = now_sec;
+                return 0;
+            }
+
+            let details = vec![
+                ("ipaddr".to_string(), Ipv4Addr::from(ipaddr).to_string()),
+                ("old_mac".to_string(), event_pkt_info::mac_str(&b.mac)),
+                ("new_mac".to_string(), event_pkt_info::mac_str(mac)),
+                ("static".to_string(), b.is_static.to_string())
+            ];
+
+            // a learned binding follows the new MAC so a replaced NIC alerts
+            // once, a configured binding never moves
+            if !b.is_static {
+                b.mac = *mac;
+                b.last_sec = now_sec;
+            }
+
+            return arp_guard::deny(evt_mgr, event_desc::ARP_BINDING_CHANGED, details);
+        }
+
+        if self.bindings.len() < self.limits.max_bindings {
+            self.bindings.insert(ipaddr, arp_binding { mac : *mac, is_static : false, last_sec : now_sec });
+        }
+
+        return 0;
+    }
+
+    // @brief - inspect an ARP packet
+    //
+    // @param [inout] self - ARP guard
+    // @param [in] arp_h - ARP header
+    // @param [in] src_mac - source MAC of the ethernet header
+    // @param [in] now_sec - monotonic time
+    // @param [out] evt_mgr - event mgr
+    //
+    // @return 0 on success -1 if the packet looks spoofed
+    pub fn inspect(&mut self, arp_h : &arp_hdr, src_mac : &[u8; 6], now_sec : u32, evt_mgr : &mut event_mgr) -> i32 {
+        let sender_ip = arp_h.sender_proto_addr();
+        let target_ip = arp_h.target_proto_addr();
+        let sender_mac = arp_h.sender_hw_addr();
+        let op = arp_h.op();
+        let mut ret : i32 = 0;
+
+        if (op != arp_operation_types::REQUEST) && (op != arp_operation_types::REPLY) {
+            return 0;
+        }
+
+        self.expire(now_sec);
+
+        if sender_mac != src_mac {
+            return arp_guard::deny(evt_mgr, event_desc::ARP_SENDER_MAC_MISMATCH, vec![
+                ("sender_mac".to_string(), event_pkt_info::mac_str(sender_mac)),
+                ("src_mac".to_string(), event_pkt_info::mac_str(src_mac))
+            ]);
+        }
+
+        // RFC 5227 - probes carry no sender address
+        if sender_ip == 0 {
+            return 0;
+        }
+
+        // announcements carry the sender address as the target
+        let gratuitous = sender_ip == target_ip;
+
+        if gratuitous {
+            if self.check_garp(sender_mac, now_sec, evt_mgr) < 0 {
+                ret = -1;
+            }
+        } else if op == arp_operation_types::REQUEST {
+            if (self.requests.len() < self.limits.max_bindings) ||
+               self.requests.contains_key(&(target_ip, sender_ip)) {
+                self.requests.insert((target_ip, sender_ip), now_sec);
+            }
+        } else if !self.requests.contains_key(&(sender_ip, target_ip)) {
+            // the request is kept until it times out, repeated replies
+            // to it are not unsolicited
+            ret = arp_guard::deny(evt_mgr, event_desc::ARP_UNSOLICITED_REPLY, vec![
+                ("ipaddr".to_string(), Ipv4Addr::from(sender_ip).to_string()),
+                ("sender_mac".to_string(), event_pkt_info::mac_str(sender_mac)),
+                ("target_ipaddr".to_string(), Ipv4Addr::from(target_ip).to_string())
+            ]);
+        }
+
+        if self.check_binding(sender_ip, sender_mac, now_sec, evt_mgr) < 0 {
+            ret = -1;
+        }
+
+        return ret;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lib::protocols::packet::packet::packet;
+
+    const HOST_MAC : [u8; 6] = [0x02, 0, 0, 0, 0, 1];
+    const ATTACKER_MAC : [u8; 6] = [0x02, 0, 0, 0, 0, 0x66];
+    const HOST : u32 = 0xC0A80101;
+    const PEER : u32 = 0xC0A80102;
+
+    fn arp(op : u16, sender_mac : &[u8; 6], sender_ip : u32, target_ip : u32) -> arp_hdr {
+        let mut p = packet::with_buf_len(arp_hdr::ARP_HDR_LEN as usize);
+        let mut arp_h = arp_hdr::new();
+        let mut evt_mgr = event_mgr::new();
+
+        p.buf[0..2].copy_from_slice(&1u16.to_be_bytes());
+        p.buf[2..4].copy_from_slice(&0x0800u16.to_be_bytes());
+        p.buf[4] = 6;
+        p.buf[5] = 4;
+        p.buf[6..8].copy_from_slice(&op.to_be_bytes());
+        p.buf[8..14].copy_from_slice(sender_mac);
+        p.buf[14..18].copy_from_slice(&sender_ip.to_be_bytes());
+        p.buf[24..28].copy_from_slice(&target_ip.to_be_bytes());
+        p.pkt_len = arp_hdr::ARP_HDR_LEN as usize;
+
+        assert_eq!(arp_h.deserialize(&mut p, &mut evt_mgr, false), 0);
+        return arp_h;
+    }
+
+    // @brief - inspect and return the single event raised, 0 if none
+    fn check(guard : &mut arp_guard, arp_h : &arp_hdr, src_mac : &[u8; 6], now_sec : u32) -> u32 {
+        let mut evt_mgr = event_mgr::new();
+        let ret = guard.inspect(arp_h, src_mac, now_sec, &mut evt_mgr);
+        let counts = evt_mgr.get_evt_desc_counts();
+
+        assert!(counts.len() <= 1);
+        match counts.keys().next() {
+            Some(desc) => {
+                assert_eq!(ret, -1);
+                return *desc;
+            }
+            None => {
+                assert_eq!(ret, 0);
+                return 0;
+            }
+        }
+    }
+
+    #[test]
+    fn request_and_reply() {
+        let mut guard = arp_guard::new();
+
+        assert_eq!(check(&mut guard, &arp(arp_operation_types::REQUEST, &HOST_MAC, HOST, PEER), &HOST_MAC, 100), 0);
+        assert_eq!(check(&mut guard, &arp(arp_operation_types::REPLY, &[2, 0, 0, 0, 0, 2], PEER, HOST),
+                         &[2, 0, 0, 0, 0, 2], 101), 0);
+        // a repeated reply to the same request
+        assert_eq!(check(&mut guard, &arp(arp_operation_types::REPLY, &[2, 0, 0, 0, 0, 2], PEER, HOST),
+                         &[2, 0, 0, 0, 0, 2], 102), 0);
+        assert_eq!(guard.n_bindings(), 2);
+    }
+
+    #[test]
+    fn unsolicited_reply() {
+        let mut guard = arp_guard::new();
+
+        assert_eq!(check(&mut guard, &arp(arp_operation_types::REPLY, &ATTACKER_MAC, PEER, HOST), &ATTACKER_MAC, 100),
+                   event_desc::ARP_UNSOLICITED_REPLY);
+
+        // the request timed out before the reply
+        assert_eq!(check(&mut guard, &arp(arp_operation_types::REQUEST, &HOST_MAC, HOST, PEER), &HOST_MAC, 200), 0);
+        assert_eq!(check(&mut guard, &arp(arp_operation_types::REPLY, &ATTACKER_MAC, PEER, HOST), &ATTACKER_MAC, 210),
+                   event_desc::ARP_UNSOLICITED_REPLY);
+    }
+
+    #[test]
+    fn binding_changed() {
+        let mut guard = arp_guard::new();
+
+        assert_eq!(check(&mut guard, &arp(arp_operation_types::REQUEST, &HOST_MAC, HOST, PEER), &HOST_MAC, 100), 0);
+        assert_eq!(check(&mut guard, &arp(arp_operation_types::REQUEST, &ATTACKER_MAC, HOST, PEER), &ATTACKER_MAC, 101),
+                   event_desc::ARP_BINDING_CHANGED);
+
+        // the learned binding follows the new MAC, alerts once
+        assert_eq!(check(&mut guard, &arp(arp_operation_types::REQUEST, &ATTACKER_MAC, HOST, PEER), &ATTACKER_MAC, 102), 0);
+
+        // forgotten once not seen for the binding timeout
+        guard.limits.binding_timeout_sec = 60;
+        assert_eq!(check(&mut guard, &arp(arp_operation_types::REQUEST, &HOST_MAC, HOST, PEER), &HOST_MAC, 200), 0);
+    }
+
+    #[test]
+    fn static_binding() {
+        let mut guard = arp_guard::new();
+        let mut evt_mgr = event_mgr::new();
+
+        guard.add_static(HOST, &HOST_MAC);
+        assert_eq!(check(&mut guard, &arp(arp_operation_types::REQUEST, &HOST_MAC, HOST, PEER), &HOST_MAC, 100), 0);
+
+        // a configured binding never moves, every spoofed packet alerts
+        for now_sec in 101..104 {
+            assert_eq!(check(&mut guard, &arp(arp_operation_types::REQUEST, &ATTACKER_MAC, HOST, PEER), &ATTACKER_MAC, now_sec),
+                       event_desc::ARP_BINDING_CHANGED);
+        }
+
+        guard.limits.binding_timeout_sec = 60;
+        assert_eq!(guard.inspect(&arp(arp_operation_types::REQUEST, &ATTACKER_MAC, HOST, PEER), &ATTACKER_MAC, 1000, &mut evt_mgr), -1);
+
+        let evt = &evt_mgr.get_evt_list()[0];
+        assert!(evt.details.contains(&("old_mac".to_string(), "02:00:00:00:00:01".to_string())));
+        assert!(evt.details.contains(&("new_mac".to_string(), "02:00:00:00:00:66".to_string())));
+        assert!(evt.details.contains(&("static".to_string(), "true".to_string())));
+    }
+
+    #[test]
+    fn garp_flood() {
+        let mut guard = arp_guard::new();
+
+        guard.limits.garp_threshold = 4;
+        for _ in 0..3 {
+            assert_eq!(check(&mut guard, &arp(arp_operation_types::REQUEST, &HOST_MAC, HOST, HOST), &HOST_MAC, 100), 0);
+        }
+        assert_eq!(check(&mut guard, &arp(arp_operation_types::REPLY, &HOST_MAC, HOST, HOST), &HOST_MAC, 100),
+                   event_desc::ARP_GRATUITOUS_FLOOD);
+        assert_eq!(check(&mut guard, &arp(arp_operation_types::REPLY, &HOST_MAC, HOST, HOST), &HOST_MAC, 101), 0);
+    }
+
+    #[test]
+    fn sender_mac_mismatch_and_probe() {
+        let mut guard = arp_guard::new();
+
+        assert_eq!(check(&mut guard, &arp(arp_operation_types::REPLY, &HOST_MAC, HOST, PEER), &ATTACKER_MAC, 100),
+                   event_desc::ARP_SENDER_MAC_MISMATCH);
+
+        // RFC 5227 probes are not learned
+        assert_eq!(check(&mut guard, &arp(arp_operation_types::REQUEST, &HOST_MAC, 0, HOST), &HOST_MAC, 100), 0);
+        assert_eq!(guard.n_bindings(), 0);
+    }
+}
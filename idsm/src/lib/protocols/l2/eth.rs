@@ -63,6 +63,8 @@ impl eth_hdr {
         p.deserialize_mac(&mut self.src_mac);
         p.deserialize_2_bytes(&mut self.ethertype);
 
+        evt_mgr.set_pkt_macs(&self.src_mac, &self.dst_mac);
+
         // zero source mac.. raise event
         if c_memcmp(&self.src_mac, &z_mac, 6) == true {
             evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY,
@@ -1,5 +1,6 @@
 pub(crate) mod eth;
 pub(crate) mod arp;
+pub(crate) mod arp_guard;
 pub(crate) mod ethertypes;
 pub(crate) mod vlan;
 pub(crate) mod mka;
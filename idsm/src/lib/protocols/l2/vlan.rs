@@ -48,6 +48,8 @@ impl vlan_hdr {
         self.vid        = (((p.buf[p.off] as u32) & 0x0F) << 8) as u16 | p.buf[p.off + 1] as u16;
         p.off           += 2;
 
+        evt_mgr.set_pkt_vlan(self.vid);
+
         for vlan_id in vlan_hdr::VLAN_RESERVED {
             if self.vid == vlan_id {
                 evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY,
@@ -0,0 +1,240 @@
+// @brief - implements the fragment queue shared by ipv4 and ipv6 reassembly
+// @copyright - 2024-present Devendra Naga All rights reserved
+#![allow(dead_code)]
+#![allow(non_camel_case_types)]
+
+// @brief - defines the limits of a reassembly table
+pub struct frag_limits {
+    pub timeout_sec     : u32,
+    pub max_datagrams   : usize,
+    pub max_mem_bytes   : usize
+}
+
+impl frag_limits {
+    pub const DEFAULT_TIMEOUT_SEC : u32 = 30;
+    pub const DEFAULT_MAX_DATAGRAMS : usize = 1024;
+    pub const DEFAULT_MAX_MEM_BYTES : usize = 4194304;
+
+    pub fn new() -> frag_limits {
+        let limits = frag_limits {
+            timeout_sec     : frag_limits::DEFAULT_TIMEOUT_SEC,
+            max_datagrams   : frag_limits::DEFAULT_MAX_DATAGRAMS,
+            max_mem_bytes   : frag_limits::DEFAULT_MAX_MEM_BYTES
+        };
+        limits
+    }
+}
+
+#[non_exhaustive]
+pub struct frag_insert_result;
+
+impl frag_insert_result {
+    pub const OK : i32 = 0;
+    // same offset and length as a fragment already queued
+    pub const DUPLICATE : i32 = 1;
+    pub const OVERLAP : i32 = -1;
+    // data beyond the end given by the last fragment, or a second end
+    pub const INVAL_END : i32 = -2;
+}
+
+// @brief - defines a fragment of a datagram
+struct frag {
+    off                 : u32,
+    data                : Vec<u8>
+}
+
+// @brief - defines the fragments received for a datagram
+pub struct frag_queue {
+    frags               : Vec<frag>, // sorted by offset
+    has_end             : bool,
+    end                 : u32,
+    pub n_bytes         : usize,
+    pub created_sec     : u32,
+    // next header of the first ipv6 fragment
+    pub next_hdr        : u8,
+    // header of the first fragment, used for the reassembled datagram
+    pub hdr             : Vec<u8>
+}
+
+impl frag_queue {
+    pub fn new(created_sec : u32) -> frag_queue {
+        let q = frag_queue {
+            frags               : Vec::new(),
+            has_end             : false,
+            end                 : 0,
+            n_bytes             : 0,
+            created_sec         : created_sec,
+            next_hdr            : 0,
+            hdr                 : Vec::new()
+        };
+        q
+    }
+
+    // @brief - add a fragment to the queue
+    //
+    // @param [inout] self - fragment queue
+    // @param [in] off - offset of the fragment data in the datagram
+    // @param [in] data - fragment data
+    // @param [in] more_frags - more fragments follow
+    //
+    // @return frag_insert_result
+    pub fn insert(&mut self, off : u32, data : &[u8], more_frags : bool) -> i32 {
+        let frag_end = off + data.len() as u32;
+        let mut pos : usize = self.frags.len();
+
+        if !more_frags {
+            if self.has_end && (self.end != frag_end) {
+                return frag_insert_result::INVAL_END;
+            }
+
+            // fragments queued before the last one must end within it
+            if !self.has_end &&
+               self.frags.iter().any(|f| f.off + f.data.len() as u32 > frag_end) {
+                return frag_insert_result::INVAL_END;
+            }
+        }
+
+        if self.has_end && (frag_end > self.end) {
+            return frag_insert_result::INVAL_END;
+        }
+
+        for (i, f) in self.frags.iter().enumerate() {
+            let f_end = f.off + f.data.len() as u32;
+
+            if (f.off == off) && (f_end == frag_end) {
+                return frag_insert_result::DUPLICATE;
+            }
+
+            if (off < f_end) && (f.off < frag_end) {
+                return frag_insert_result::OVERLAP;
+            }
+
+            if (pos == self.frags.len()) && (off < f.off) {
+                pos = i;
+            }
+        }
+
+        if !more_frags {
+            self.has_end = true;
+            self.end = frag_end;
+        }
+
+        self.frags.insert(pos, frag { off : off, data : data.to_vec() });
+        self.n_bytes += data.len();
+
+        return frag_insert_result::OK;
+    }
+
+    // @brief - check if every fragment of the datagram is received
+    pub fn is_complete(&self) -> bool {
+        let mut next : u32 = 0;
+
+        if !self.has_end || self.hdr.is_empty() {
+            return false;
+        }
+
+        for f in &self.frags {
+            if f.off != next {
+                return false;
+            }
+            next += f.data.len() as u32;
+        }
+
+        return next == self.end;
+    }
+
+    // @brief - returns the length of the reassembled data
+    pub fn data_len(&self) -> u32 { return self.end; }
+
+    // @brief - copy the fragments into the buffer
+    //
+    // @param [in] self - fragment queue
+    // @param [out] buf - buffer of at least data_len bytes
+    pub fn assemble(&self, buf : &mut [u8]) {
+        for f in &self.frags {
+            let off = f.off as usize;
+            buf[off..off + f.data.len()].copy_from_slice(&f.data);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assembled(q : &frag_queue) -> Vec<u8> {
+        let mut buf : Vec<u8> = vec![0; q.data_len() as usize];
+
+        q.assemble(&mut buf);
+        return buf;
+    }
+
+    #[test]
+    fn complete_out_of_order() {
+        let mut q = frag_queue::new(0);
+
+        q.hdr = vec![0x45];
+        assert_eq!(q.insert(16, &[3; 8], false), frag_insert_result::OK);
+        assert_eq!(q.insert(0, &[1; 8], true), frag_insert_result::OK);
+        assert!(!q.is_complete());
+        assert_eq!(q.insert(8, &[2; 8], true), frag_insert_result::OK);
+        assert!(q.is_complete());
+        assert_eq!(q.n_bytes, 24);
+        assert_eq!(assembled(&q), [[1; 8], [2; 8], [3; 8]].concat());
+    }
+
+    #[test]
+    fn complete_needs_first_hdr() {
+        let mut q = frag_queue::new(0);
+
+        assert_eq!(q.insert(0, &[1; 8], true), frag_insert_result::OK);
+        assert_eq!(q.insert(8, &[2; 8], false), frag_insert_result::OK);
+        assert!(!q.is_complete());
+        q.hdr = vec![0x45];
+        assert!(q.is_complete());
+    }
+
+    #[test]
+    fn duplicate() {
+        let mut q = frag_queue::new(0);
+
+        assert_eq!(q.insert(0, &[1; 8], true), frag_insert_result::OK);
+        assert_eq!(q.insert(0, &[9; 8], true), frag_insert_result::DUPLICATE);
+        assert_eq!(q.n_bytes, 8);
+    }
+
+    #[test]
+    fn overlap() {
+        let mut q = frag_queue::new(0);
+
+        assert_eq!(q.insert(8, &[1; 16], true), frag_insert_result::OK);
+        // starts inside, ends inside, covers and is covered by the queued fragment
+        assert_eq!(q.insert(16, &[2; 16], true), frag_insert_result::OVERLAP);
+        assert_eq!(q.insert(0, &[2; 16], true), frag_insert_result::OVERLAP);
+        assert_eq!(q.insert(0, &[2; 32], true), frag_insert_result::OVERLAP);
+        assert_eq!(q.insert(8, &[2; 8], true), frag_insert_result::OVERLAP);
+        // adjacent fragments do not overlap
+        assert_eq!(q.insert(0, &[2; 8], true), frag_insert_result::OK);
+        assert_eq!(q.insert(24, &[2; 8], true), frag_insert_result::OK);
+    }
+
+    #[test]
+    fn data_past_end() {
+        let mut q = frag_queue::new(0);
+
+        assert_eq!(q.insert(16, &[1; 8], false), frag_insert_result::OK);
+        assert_eq!(q.insert(24, &[1; 8], true), frag_insert_result::INVAL_END);
+        assert_eq!(q.insert(8, &[1; 8], false), frag_insert_result::INVAL_END);
+    }
+
+    #[test]
+    fn end_below_queued() {
+        let mut q = frag_queue::new(0);
+
+        assert_eq!(q.insert(16, &[1; 8], true), frag_insert_result::OK);
+        assert_eq!(q.insert(0, &[1; 8], false), frag_insert_result::INVAL_END);
+        // the rejected fragment does not set the end
+        assert_eq!(q.insert(24, &[1; 8], false), frag_insert_result::OK);
+        assert_eq!(q.data_len(), 32);
+    }
+}
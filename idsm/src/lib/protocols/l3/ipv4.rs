@@ -9,9 +9,40 @@ use crate::{
         event_mgr::event_mgr,
         event_type::event_type
     },
-    lib::protocols::packet::packet::packet
+    lib::protocols::{
+        checksum::inet_checksum::{inet_checksum_add, inet_checksum_ok},
+        l3::{ipv4_opts::ipv4_opts, protocol_types::ProtocolTypes},
+        packet::packet::packet
+    }
 };
 
+// @brief - defines the semantic checks done on the ipv4 header
+pub struct ipv4_checks {
+    pub checksum        : bool,
+    pub total_len       : bool,
+    pub ttl             : bool,
+    pub min_ttl         : u8,
+    pub src_addr        : bool,
+    pub land            : bool
+}
+
+impl ipv4_checks {
+    pub const DEFAULT_MIN_TTL : u8 = 1;
+
+    // @brief - returns checks with everything enabled
+    pub fn new() -> ipv4_checks {
+        let checks = ipv4_checks {
+            checksum        : true,
+            total_len       : true,
+            ttl             : true,
+            min_ttl         : ipv4_checks::DEFAULT_MIN_TTL,
+            src_addr        : true,
+            land            : true
+        };
+        checks
+    }
+}
+
 // @brief - implements ipv4 header
 pub struct ipv4_hdr {
     version             : u8, // 4 bits
@@ -29,12 +60,16 @@ pub struct ipv4_hdr {
     hdr_checksum        : u16, // 16 bits
     src_ipaddr          : u32, // 32 bits
     dst_ipaddr          : u32, // 32 bits
+    pub opts            : ipv4_opts
 }
 
 impl ipv4_hdr {
     pub const IPV4_MIN_HDR_LEN          : u32 = 20;
     pub const IPV4_VERSION              : u32 = 4;
     pub const IPV4_IHL_DEFAULT          : u32 = 5;
+    pub const ADDR_ANY                  : u32 = 0x00000000;
+    pub const ADDR_BCAST                : u32 = 0xFFFFFFFF;
+    pub const LOOPBACK_NET              : u8 = 127;
 
     // @brief - return an instance of ipv4_hdr
     //
@@ -56,7 +91,8 @@ impl ipv4_hdr {
             protocol            : 0,
             hdr_checksum        : 0,
             src_ipaddr          : 0,
-            dst_ipaddr          : 0
+            dst_ipaddr          : 0,
+            opts                : ipv4_opts::new()
         };
         hdr
     }
@@ -66,10 +102,15 @@ impl ipv4_hdr {
     // @param [inout] self - ipv4 header
     // @param [inout] p - packet
     // @param [inout] evt_mgr - event manager
+    // @param [in] checks - semantic checks to run
     // @param [in] debug - debug
     //
     // @return 0 on success -1 on failure.
-    pub fn deserialize(&mut self, p : &mut packet, evt_mgr : &mut event_mgr, debug : bool) -> i32 {
+    pub fn deserialize(&mut self, p : &mut packet, evt_mgr : &mut event_mgr,
+                       checks : &ipv4_checks, debug : bool) -> i32 {
+        let hdr_start = p.off;
+        let mut ret : i32;
+
         if ((p.pkt_len - p.off) as u32) < ipv4_hdr::IPV4_MIN_HDR_LEN {
             evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY,
                          event_desc::IPV4_SHORT_HDR_LEN);
@@ -84,12 +125,19 @@ impl ipv4_hdr {
         }
 
         self.ihl = p.buf[p.off] & 0x0F;
-        if (self.ihl as u32) != ipv4_hdr::IPV4_IHL_DEFAULT {
+        if (self.ihl as u32) < ipv4_hdr::IPV4_IHL_DEFAULT {
             evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY,
                          event_desc::IPV4_IHL_INVAL);
             return -1;
         }
 
+        // options follow the fixed header up to ihl words
+        if !p.remaining_len_in_bounds(self.hdr_len()) {
+            evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY,
+                         event_desc::IPV4_SHORT_HDR_LEN);
+            return -1;
+        }
+
         p.off += 1;
 
         self.dscp = (p.buf[p.off] & 0xFC) >> 2;
@@ -117,11 +165,155 @@ impl ipv4_hdr {
         p.deserialize_4_bytes(&mut self.src_ipaddr);
         p.deserialize_4_bytes(&mut self.dst_ipaddr);
 
+        evt_mgr.set_pkt_ipv4(self.src_ipaddr, self.dst_ipaddr, self.protocol);
+
+        ret = self.opts.deserialize(p, evt_mgr, hdr_start + self.hdr_len() as usize);
+        if ret < 0 {
+            return -1;
+        }
+
+        ret = self.validate(p, evt_mgr, checks, hdr_start);
+        if ret < 0 {
+            return -1;
+        }
+
         if debug { self.print(); }
 
         return 0;
     }
 
+    // @brief - raise an event and fail
+    fn deny(evt_mgr : &mut event_mgr, evt_desc : u32) -> i32 {
+        evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY, evt_desc);
+        return -1;
+    }
+
+    // @brief - check the source address class
+    //
+    // @param [in] self - ipv4 header
+    // @param [out] evt_mgr - event mgr
+    //
+    // @return 0 on success -1 on failure
+    fn validate_src_addr(&self, evt_mgr : &mut event_mgr) -> i32 {
+        let first_octet = (self.src_ipaddr >> 24) as u8;
+
+        // DHCP clients send discover and request from 0.0.0.0 to the
+        // limited broadcast before they own an address
+        if self.src_ipaddr == ipv4_hdr::ADDR_ANY {
+            if (self.dst_ipaddr == ipv4_hdr::ADDR_BCAST) &&
+               (self.protocol == ProtocolTypes::UDP) {
+                return 0;
+            }
+            return ipv4_hdr::deny(evt_mgr, event_desc::IPV4_SRC_ADDR_ZERO);
+        }
+
+        if self.src_ipaddr == ipv4_hdr::ADDR_BCAST {
+            return ipv4_hdr::deny(evt_mgr, event_desc::IPV4_SRC_ADDR_BCAST);
+        }
+
+        if first_octet == ipv4_hdr::LOOPBACK_NET {
+            return ipv4_hdr::deny(evt_mgr, event_desc::IPV4_SRC_ADDR_LOOPBACK);
+        }
+
+        // 224.0.0.0/4
+        if (first_octet & 0xF0) == 0xE0 {
+            return ipv4_hdr::deny(evt_mgr, event_desc::IPV4_SRC_ADDR_MCAST);
+        }
+
+        // 240.0.0.0/4 class E
+        if (first_octet & 0xF0) == 0xF0 {
+            return ipv4_hdr::deny(evt_mgr, event_desc::IPV4_SRC_ADDR_RESERVED);
+        }
+
+        return 0;
+    }
+
+    // @brief - run the semantic checks on the header
+    //
+    // @param [in] self - ipv4 header
+    // @param [in] p - packet
+    // @param [out] evt_mgr - event mgr
+    // @param [in] checks - checks to run
+    // @param [in] hdr_start - offset of the ipv4 header
+    //
+    // @return 0 on success -1 on failure
+    fn validate(&self, p : &packet, evt_mgr : &mut event_mgr,
+                checks : &ipv4_checks, hdr_start : usize) -> i32 {
+        let hdr_len = self.hdr_len() as usize;
+
+        if checks.checksum &&
+           !inet_checksum_ok(inet_checksum_add(0, &p.buf[hdr_start..hdr_start + hdr_len])) {
+            return ipv4_hdr::deny(evt_mgr, event_desc::IPV4_INVAL_CHECKSUM);
+        }
+
+        if checks.total_len {
+            if (self.total_len as usize) < hdr_len {
+                return ipv4_hdr::deny(evt_mgr, event_desc::IPV4_TOTAL_LEN_TOO_SHORT);
+            }
+
+            // the frame may be longer because of ethernet padding, never shorter
+            if (self.total_len as usize) > (p.pkt_len - hdr_start) {
+                return ipv4_hdr::deny(evt_mgr, event_desc::IPV4_TOTAL_LEN_EXCEEDS_PKT);
+            }
+        }
+
+        if checks.src_addr && (self.validate_src_addr(evt_mgr) < 0) {
+            return -1;
+        }
+
+        // traffic on the loopback interface is always addressed to itself
+        if checks.land && (self.src_ipaddr == self.dst_ipaddr) &&
+           ((self.src_ipaddr >> 24) as u8 != ipv4_hdr::LOOPBACK_NET) {
+            return ipv4_hdr::deny(evt_mgr, event_desc::IPV4_LAND_ATTACK);
+        }
+
+        if checks.ttl && (self.ttl < checks.min_ttl) {
+            return ipv4_hdr::deny(evt_mgr, event_desc::IPV4_LOW_TTL);
+        }
+
+        return 0;
+    }
+
+    // @brief - returns the header length including options
+    pub fn hdr_len(&self) -> u32 { return self.ihl as u32 * 4; }
+
+    // @brief - returns the source address
+    pub fn src_ipaddr(&self) -> u32 { return self.src_ipaddr; }
+
+    // @brief - returns the destination address
+    pub fn dst_ipaddr(&self) -> u32 { return self.dst_ipaddr; }
+
+    // @brief - returns the identification
+    pub fn id(&self) -> u16 { return self.id; }
+
+    // @brief - make the header describe a reassembled datagram
+    //
+    // @param [inout] self - ipv4 header
+    // @param [in] total_len - length of the reassembled datagram
+    pub fn set_reassembled(&mut self, total_len : u32) {
+        self.total_len = total_len as u16;
+        self.flags_mf = 0;
+        self.frag_off = 0;
+    }
+
+    // @brief - returns the total length of the packet
+    pub fn total_len(&self) -> u32 { return self.total_len as u32; }
+
+    // @brief - returns the fragment offset in bytes
+    pub fn frag_off_bytes(&self) -> u32 { return self.frag_off as u32 * 8; }
+
+    // @brief - check if more fragments follow
+    pub fn more_frags(&self) -> bool { return self.flags_mf != 0; }
+
+    // @brief - returns the length of the data following the ipv4 header
+    //
+    // @param [in] self - ipv4 header
+    //
+    // @return payload length given by the total length field
+    pub fn payload_len(&self) -> u32 {
+        return (self.total_len as u32).saturating_sub(self.ihl as u32 * 4);
+    }
+
     // @brief - print ipv4 header
     //
     // @param [in] self - ipv4 header
@@ -142,5 +334,166 @@ impl ipv4_hdr {
         log::info!("\t hdr_checksum: 0x{:02X}", self.hdr_checksum);
         packet::print_ipv4("src_ipaddr", self.src_ipaddr);
         packet::print_ipv4("dst_ipaddr", self.dst_ipaddr);
+        if !self.opts.is_empty() {
+            self.opts.print();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lib::protocols::{
+        checksum::inet_checksum::inet_checksum_fold,
+        l3::ipv4_opts::ipv4_opt_types
+    };
+
+    // @brief - build an ipv4 header with a valid checksum, followed by data_len bytes
+    fn ip4(src : [u8; 4], dst : [u8; 4], protocol : u8, ttl : u8, data_len : usize) -> packet {
+        let mut p = packet::with_buf_len(ipv4_hdr::IPV4_MIN_HDR_LEN as usize + data_len);
+        let total_len = p.buf.len() as u16;
+
+        p.buf[0] = 0x45;
+        p.buf[2..4].copy_from_slice(&total_len.to_be_bytes());
+        p.buf[8] = ttl;
+        p.buf[9] = protocol;
+        p.buf[12..16].copy_from_slice(&src);
+        p.buf[16..20].copy_from_slice(&dst);
+        set_checksum(&mut p);
+        p.pkt_len = p.buf.len();
+        return p;
+    }
+
+    fn set_checksum(p : &mut packet) {
+        p.buf[10..12].copy_from_slice(&[0, 0]);
+        let csum = inet_checksum_fold(inet_checksum_add(0, &p.buf[0..20]));
+        p.buf[10..12].copy_from_slice(&csum.to_be_bytes());
+    }
+
+    fn check_with(mut p : packet, checks : &ipv4_checks) -> u32 {
+        let mut evt_mgr = event_mgr::new();
+        let mut ip_h = ipv4_hdr::new();
+        let ret = ip_h.deserialize(&mut p, &mut evt_mgr, checks, false);
+        let counts = evt_mgr.get_evt_desc_counts();
+
+        assert!(counts.len() <= 1);
+        match counts.keys().next() {
+            Some(desc) => {
+                assert_eq!(ret, -1);
+                return *desc;
+            }
+            None => {
+                assert_eq!(ret, 0);
+                return 0;
+            }
+        }
+    }
+
+    fn check(p : packet) -> u32 {
+        return check_with(p, &ipv4_checks::new());
+    }
+
+    const A : [u8; 4] = [10, 0, 0, 1];
+    const B : [u8; 4] = [10, 0, 0, 2];
+
+    #[test]
+    fn valid() {
+        assert_eq!(check(ip4(A, B, 6, 64, 20)), 0);
+        // ethernet padding after the datagram
+        let mut p = ip4(A, B, 6, 64, 20);
+        p.buf.extend_from_slice(&[0; 6]);
+        p.pkt_len += 6;
+        assert_eq!(check(p), 0);
+    }
+
+    #[test]
+    fn header_fields() {
+        let mut p = ip4(A, B, 6, 64, 0);
+        p.buf[0] = 0x44;
+        assert_eq!(check(p), event_desc::IPV4_IHL_INVAL);
+
+        let mut p = ip4(A, B, 6, 64, 0);
+        p.buf[0] = 0x65;
+        assert_eq!(check(p), event_desc::IPV4_INVAL_VERSION);
+
+        let mut p = ip4(A, B, 6, 64, 0);
+        p.buf[6] = 0x80;
+        set_checksum(&mut p);
+        assert_eq!(check(p), event_desc::IPV4_RESERVED_SET);
+
+        let mut p = ip4(A, B, 6, 64, 0);
+        p.buf[0] = 0x46;
+        assert_eq!(check(p), event_desc::IPV4_SHORT_HDR_LEN);
+    }
+
+    #[test]
+    fn checksum_and_total_len() {
+        let mut p = ip4(A, B, 6, 64, 0);
+        p.buf[10] ^= 0xFF;
+        assert_eq!(check(p), event_desc::IPV4_INVAL_CHECKSUM);
+
+        let mut p = ip4(A, B, 6, 64, 0);
+        p.buf[10] ^= 0xFF;
+        let mut checks = ipv4_checks::new();
+        checks.checksum = false;
+        assert_eq!(check_with(p, &checks), 0);
+
+        let mut p = ip4(A, B, 6, 64, 0);
+        p.buf[2..4].copy_from_slice(&19u16.to_be_bytes());
+        set_checksum(&mut p);
+        assert_eq!(check(p), event_desc::IPV4_TOTAL_LEN_TOO_SHORT);
+
+        let mut p = ip4(A, B, 6, 64, 8);
+        p.pkt_len -= 1;
+        assert_eq!(check(p), event_desc::IPV4_TOTAL_LEN_EXCEEDS_PKT);
+    }
+
+    #[test]
+    fn src_addr_classes() {
+        assert_eq!(check(ip4([0, 0, 0, 0], B, 6, 64, 0)), event_desc::IPV4_SRC_ADDR_ZERO);
+        // DHCP discover
+        assert_eq!(check(ip4([0, 0, 0, 0], [255, 255, 255, 255], 17, 64, 0)), 0);
+        assert_eq!(check(ip4([255, 255, 255, 255], B, 17, 64, 0)), event_desc::IPV4_SRC_ADDR_BCAST);
+        assert_eq!(check(ip4([127, 0, 0, 1], B, 6, 64, 0)), event_desc::IPV4_SRC_ADDR_LOOPBACK);
+        assert_eq!(check(ip4([224, 0, 0, 5], B, 89, 64, 0)), event_desc::IPV4_SRC_ADDR_MCAST);
+        assert_eq!(check(ip4([240, 0, 0, 1], B, 6, 64, 0)), event_desc::IPV4_SRC_ADDR_RESERVED);
+    }
+
+    #[test]
+    fn land_and_ttl() {
+        assert_eq!(check(ip4(A, A, 6, 64, 0)), event_desc::IPV4_LAND_ATTACK);
+        // loopback traffic is excluded from the source class check as well
+        let mut checks = ipv4_checks::new();
+        checks.src_addr = false;
+        assert_eq!(check_with(ip4([127, 0, 0, 1], [127, 0, 0, 1], 6, 64, 0), &checks), 0);
+
+        assert_eq!(check(ip4(A, B, 6, 0, 0)), event_desc::IPV4_LOW_TTL);
+        checks = ipv4_checks::new();
+        checks.min_ttl = 5;
+        assert_eq!(check_with(ip4(A, B, 6, 4, 0), &checks), event_desc::IPV4_LOW_TTL);
+        assert_eq!(check_with(ip4(A, B, 6, 5, 0), &checks), 0);
+    }
+
+    #[test]
+    fn options_in_header() {
+        let mut p = packet::with_buf_len(24);
+
+        p.buf[0] = 0x46;
+        p.buf[2..4].copy_from_slice(&24u16.to_be_bytes());
+        p.buf[8] = 64;
+        p.buf[9] = 6;
+        p.buf[12..16].copy_from_slice(&A);
+        p.buf[16..20].copy_from_slice(&B);
+        p.buf[20..24].copy_from_slice(&[ipv4_opt_types::ROUTER_ALERT, 4, 0, 0]);
+        let csum = inet_checksum_fold(inet_checksum_add(0, &p.buf[0..24]));
+        p.buf[10..12].copy_from_slice(&csum.to_be_bytes());
+        p.pkt_len = 24;
+
+        let mut evt_mgr = event_mgr::new();
+        let mut ip_h = ipv4_hdr::new();
+        assert_eq!(ip_h.deserialize(&mut p, &mut evt_mgr, &ipv4_checks::new(), false), 0);
+        assert!(ip_h.opts.has_router_alert);
+        assert_eq!(p.off, 24);
+        assert_eq!(ip_h.payload_len(), 0);
     }
 }
@@ -0,0 +1,344 @@
+// @brief - implements ipv4 options deserialization
+// @copyright - 2024-present Devendra Naga All rights reserved
+#![allow(dead_code)]
+#![allow(non_camel_case_types)]
+
+use crate::{
+    events::{
+        event_desc::event_desc,
+        event_mgr::event_mgr,
+        event_type::event_type
+    },
+    lib::protocols::packet::packet::packet
+};
+
+// @brief - defines ipv4 option types (copied flag, class and number)
+#[non_exhaustive]
+pub struct ipv4_opt_types;
+
+impl ipv4_opt_types {
+    pub const END_OF_LIST : u8 = 0;
+    pub const NOP : u8 = 1;
+    pub const RECORD_ROUTE : u8 = 7;
+    pub const TIMESTAMP : u8 = 68;
+    pub const SECURITY : u8 = 130;
+    pub const LOOSE_SRC_ROUTE : u8 = 131;
+    pub const STREAM_ID : u8 = 136;
+    pub const STRICT_SRC_ROUTE : u8 = 137;
+    pub const ROUTER_ALERT : u8 = 148;
+}
+
+// @brief - defines the ipv4 options present in a header
+pub struct ipv4_opts {
+    pub has_record_route    : bool,
+    pub has_timestamp       : bool,
+    pub has_security        : bool,
+    pub has_loose_src_route : bool,
+    pub has_strict_src_route : bool,
+    pub has_stream_id       : bool,
+    pub has_router_alert    : bool,
+    pub router_alert        : u16,
+    pub stream_id           : u16,
+    // addresses of record route and source route options
+    pub route               : Vec<u32>
+}
+
+impl ipv4_opts {
+    pub const OPT_MIN_LEN : usize = 2;
+    // RFC 1108 - the length depends on the protection authority flags
+    pub const SECURITY_MIN_LEN : usize = 3;
+    pub const STREAM_ID_LEN : usize = 4;
+    pub const ROUTER_ALERT_LEN : usize = 4;
+    // route and timestamp options carry a pointer after the length
+    pub const ROUTE_MIN_LEN : usize = 3;
+    pub const TIMESTAMP_MIN_LEN : usize = 4;
+    pub const PTR_MIN : u8 = 4;
+
+    #[inline(always)]
+    pub fn new() -> ipv4_opts {
+        let opts = ipv4_opts {
+            has_record_route    : false,
+            has_timestamp       : false,
+            has_security        : false,
+            has_loose_src_route : false,
+            has_strict_src_route : false,
+            has_stream_id       : false,
+            has_router_alert    : false,
+            router_alert        : 0,
+            stream_id           : 0,
+            route               : Vec::new()
+        };
+        opts
+    }
+
+    // @brief - check if any option is present
+    pub fn is_empty(&self) -> bool {
+        return !(self.has_record_route || self.has_timestamp || self.has_security ||
+                 self.has_loose_src_route || self.has_strict_src_route ||
+                 self.has_stream_id || self.has_router_alert);
+    }
+
+    // @brief - raise malformed option event
+    fn inval_len(evt_mgr : &mut event_mgr) -> i32 {
+        evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY,
+                                event_desc::IPV4_OPT_INVAL_LEN);
+        return -1;
+    }
+
+    // @brief - deserialize route addresses of record route and source route
+    //
+    // @param [inout] self - options
+    // @param [inout] p - packet
+    // @param [out] evt_mgr - event mgr
+    // @param [in] opt_len - option length including type and length bytes
+    //
+    // @return 0 on success -1 on failure
+    fn deserialize_route(&mut self, p : &mut packet, evt_mgr : &mut event_mgr, opt_len : usize) -> i32 {
+        let ptr : u8 = p.buf[p.off + 2];
+
+        // route data is a list of 4 byte addresses after type, length and pointer
+        if (opt_len < ipv4_opts::ROUTE_MIN_LEN) || ((opt_len - ipv4_opts::ROUTE_MIN_LEN) % 4 != 0) {
+            return ipv4_opts::inval_len(evt_mgr);
+        }
+
+        if (ptr < ipv4_opts::PTR_MIN) || (ptr as usize > opt_len + 1) || (ptr % 4 != 0) {
+            evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY,
+                                    event_desc::IPV4_OPT_INVAL_PTR);
+            return -1;
+        }
+
+        let mut off = p.off + ipv4_opts::ROUTE_MIN_LEN;
+        while off + 4 <= p.off + opt_len {
+            self.route.push(u32::from_be_bytes([p.buf[off], p.buf[off + 1],
+                                                p.buf[off + 2], p.buf[off + 3]]));
+            off += 4;
+        }
+
+        return 0;
+    }
+
+    // @brief - deserialize ipv4 options
+    //
+    // @param [inout] self - options
+    // @param [inout] p - packet, offset at the first option
+    // @param [out] evt_mgr - event mgr
+    // @param [in] opts_end - offset where the ipv4 header ends
+    //
+    // @return 0 on success -1 on failure
+    //
+    // @details - p.off is left at opts_end on success. source routing lets
+    //            the sender pick the path through the network and is used to
+    //            get around filtering, so it is reported as an event.
+    pub fn deserialize(&mut self, p : &mut packet, evt_mgr : &mut event_mgr, opts_end : usize) -> i32 {
+        let mut ret : i32 = 0;
+
+        *self = ipv4_opts::new();
+
+        while p.off < opts_end {
+            let opt_type = p.buf[p.off];
+
+            if opt_type == ipv4_opt_types::END_OF_LIST {
+                break;
+            }
+
+            if opt_type == ipv4_opt_types::NOP {
+                p.off += 1;
+                continue;
+            }
+
+            if p.off + ipv4_opts::OPT_MIN_LEN > opts_end {
+                return ipv4_opts::inval_len(evt_mgr);
+            }
+
+            let opt_len = p.buf[p.off + 1] as usize;
+            if (opt_len < ipv4_opts::OPT_MIN_LEN) || (p.off + opt_len > opts_end) {
+                return ipv4_opts::inval_len(evt_mgr);
+            }
+
+            match opt_type {
+                ipv4_opt_types::RECORD_ROUTE => {
+                    ret = self.deserialize_route(p, evt_mgr, opt_len);
+                    self.has_record_route = true;
+                }
+                ipv4_opt_types::LOOSE_SRC_ROUTE |
+                ipv4_opt_types::STRICT_SRC_ROUTE => {
+                    ret = self.deserialize_route(p, evt_mgr, opt_len);
+                    if ret == 0 {
+                        if opt_type == ipv4_opt_types::LOOSE_SRC_ROUTE {
+                            self.has_loose_src_route = true;
+                            evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY,
+                                                    event_desc::IPV4_OPT_LOOSE_SRC_ROUTE);
+                        } else {
+                            self.has_strict_src_route = true;
+                            evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY,
+                                                    event_desc::IPV4_OPT_STRICT_SRC_ROUTE);
+                        }
+                        ret = -1;
+                    }
+                }
+                ipv4_opt_types::TIMESTAMP => {
+                    if opt_len < ipv4_opts::TIMESTAMP_MIN_LEN {
+                        return ipv4_opts::inval_len(evt_mgr);
+                    }
+
+                    // pointer may be one past the end when the option is full
+                    let ptr = p.buf[p.off + 2];
+                    if (ptr < ipv4_opts::PTR_MIN + 1) || (ptr as usize > opt_len + 1) {
+                        evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY,
+                                                event_desc::IPV4_OPT_INVAL_PTR);
+                        return -1;
+                    }
+                    self.has_timestamp = true;
+                }
+                ipv4_opt_types::SECURITY => {
+                    if opt_len < ipv4_opts::SECURITY_MIN_LEN {
+                        return ipv4_opts::inval_len(evt_mgr);
+                    }
+                    self.has_security = true;
+                }
+                ipv4_opt_types::STREAM_ID => {
+                    if opt_len != ipv4_opts::STREAM_ID_LEN {
+                        return ipv4_opts::inval_len(evt_mgr);
+                    }
+                    self.stream_id = ((p.buf[p.off + 2] as u16) << 8) | p.buf[p.off + 3] as u16;
+                    self.has_stream_id = true;
+                }
+                ipv4_opt_types::ROUTER_ALERT => {
+                    if opt_len != ipv4_opts::ROUTER_ALERT_LEN {
+                        return ipv4_opts::inval_len(evt_mgr);
+                    }
+                    self.router_alert = ((p.buf[p.off + 2] as u16) << 8) | p.buf[p.off + 3] as u16;
+                    self.has_router_alert = true;
+                }
+                _ => (),
+            }
+
+            if ret < 0 {
+                return ret;
+            }
+
+            p.off += opt_len;
+        }
+
+        p.off = opts_end;
+
+        return 0;
+    }
+
+    pub fn print(&self) {
+        log::info!("\t options: ");
+        log::info!("\t\t record_route: {}", self.has_record_route);
+        log::info!("\t\t timestamp: {}", self.has_timestamp);
+        log::info!("\t\t security: {}", self.has_security);
+        log::info!("\t\t loose_src_route: {}", self.has_loose_src_route);
+        log::info!("\t\t strict_src_route: {}", self.has_strict_src_route);
+        if self.has_stream_id {
+            log::info!("\t\t stream_id: {}", self.stream_id);
+        }
+        if self.has_router_alert {
+            log::info!("\t\t router_alert: {}", self.router_alert);
+        }
+        for addr in &self.route {
+            packet::print_ipv4("\t\t route", *addr);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(opts : &[u8], evt_mgr : &mut event_mgr) -> (i32, ipv4_opts) {
+        let mut p = packet::with_buf_len(opts.len());
+        let mut ip_opts = ipv4_opts::new();
+
+        p.buf.copy_from_slice(opts);
+        p.pkt_len = opts.len();
+        let ret = ip_opts.deserialize(&mut p, evt_mgr, opts.len());
+        return (ret, ip_opts);
+    }
+
+    fn denied(evt_mgr : &event_mgr, desc : u32) -> bool {
+        return evt_mgr.get_evt_desc_counts().get(&desc) == Some(&1);
+    }
+
+    #[test]
+    fn security_lengths() {
+        let mut evt_mgr = event_mgr::new();
+
+        // basic security option with and without authority flags
+        let (ret, opts) = parse(&[ipv4_opt_types::SECURITY, 3, 0xAB, 0], &mut evt_mgr);
+        assert_eq!(ret, 0);
+        assert!(opts.has_security);
+        let (ret, _) = parse(&[ipv4_opt_types::SECURITY, 4, 0xAB, 0x01], &mut evt_mgr);
+        assert_eq!(ret, 0);
+        let (ret, _) = parse(&[ipv4_opt_types::SECURITY, 11, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+                             &mut evt_mgr);
+        assert_eq!(ret, 0);
+        assert!(evt_mgr.get_evt_desc_counts().is_empty());
+
+        let (ret, _) = parse(&[ipv4_opt_types::SECURITY, 2, 0, 0], &mut evt_mgr);
+        assert_eq!(ret, -1);
+        assert!(denied(&evt_mgr, event_desc::IPV4_OPT_INVAL_LEN));
+    }
+
+    #[test]
+    fn option_past_header() {
+        let mut evt_mgr = event_mgr::new();
+        let (ret, _) = parse(&[ipv4_opt_types::SECURITY, 8, 0xAB, 0], &mut evt_mgr);
+
+        assert_eq!(ret, -1);
+        assert!(denied(&evt_mgr, event_desc::IPV4_OPT_INVAL_LEN));
+    }
+
+    #[test]
+    fn record_route() {
+        let mut evt_mgr = event_mgr::new();
+        let (ret, opts) = parse(&[ipv4_opt_types::NOP, ipv4_opt_types::RECORD_ROUTE, 11, 8,
+                                  10, 0, 0, 1, 0, 0, 0, 0], &mut evt_mgr);
+
+        assert_eq!(ret, 0);
+        assert!(opts.has_record_route);
+        assert_eq!(opts.route, vec![0x0A000001, 0]);
+    }
+
+    #[test]
+    fn source_route_denied() {
+        let mut evt_mgr = event_mgr::new();
+        let (ret, opts) = parse(&[ipv4_opt_types::LOOSE_SRC_ROUTE, 7, 4, 10, 0, 0, 1, 0],
+                                &mut evt_mgr);
+
+        assert_eq!(ret, -1);
+        assert!(opts.has_loose_src_route);
+        assert!(denied(&evt_mgr, event_desc::IPV4_OPT_LOOSE_SRC_ROUTE));
+
+        let (ret, _) = parse(&[ipv4_opt_types::STRICT_SRC_ROUTE, 7, 4, 10, 0, 0, 1, 0],
+                             &mut evt_mgr);
+        assert_eq!(ret, -1);
+        assert!(denied(&evt_mgr, event_desc::IPV4_OPT_STRICT_SRC_ROUTE));
+    }
+
+    #[test]
+    fn inval_ptr() {
+        let mut evt_mgr = event_mgr::new();
+        let (ret, _) = parse(&[ipv4_opt_types::RECORD_ROUTE, 7, 5, 0, 0, 0, 0, 0], &mut evt_mgr);
+
+        assert_eq!(ret, -1);
+        assert!(denied(&evt_mgr, event_desc::IPV4_OPT_INVAL_PTR));
+    }
+
+    #[test]
+    fn stream_id_and_router_alert() {
+        let mut evt_mgr = event_mgr::new();
+        let (ret, opts) = parse(&[ipv4_opt_types::STREAM_ID, 4, 0x12, 0x34,
+                                  ipv4_opt_types::ROUTER_ALERT, 4, 0, 0], &mut evt_mgr);
+
+        assert_eq!(ret, 0);
+        assert_eq!(opts.stream_id, 0x1234);
+        assert!(opts.has_router_alert);
+
+        let (ret, _) = parse(&[ipv4_opt_types::STREAM_ID, 3, 0x12, 0], &mut evt_mgr);
+        assert_eq!(ret, -1);
+        assert!(denied(&evt_mgr, event_desc::IPV4_OPT_INVAL_LEN));
+    }
+}
@@ -0,0 +1,299 @@
+// @brief - implements ipv4 fragment reassembly
+// @copyright - 2024-present Devendra Naga All rights reserved
+#![allow(dead_code)]
+#![allow(non_camel_case_types)]
+
+use std::collections::{HashMap, VecDeque};
+
+use crate::{
+    events::{
+        event_desc::event_desc,
+        event_mgr::event_mgr,
+        event_type::event_type
+    },
+    lib::protocols::{
+        l3::{
+            frag_queue::{frag_insert_result, frag_limits, frag_queue},
+            ipv4::ipv4_hdr,
+            protocol_types::ProtocolTypes
+        },
+        packet::packet::packet
+    }
+};
+
+// @brief - identifies the fragments of a datagram (RFC 791)
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct ipv4_frag_key {
+    src_ipaddr          : u32,
+    dst_ipaddr          : u32,
+    id                  : u16,
+    protocol            : u8
+}
+
+// @brief - defines the ipv4 reassembly table
+pub struct ipv4_reasm {
+    queues              : HashMap<ipv4_frag_key, frag_queue>,
+    // keys in the order the datagrams were first seen
+    order               : VecDeque<ipv4_frag_key>,
+    n_bytes             : usize,
+    pub limits          : frag_limits
+}
+
+impl ipv4_reasm {
+    pub const MAX_DATAGRAM_LEN : u32 = 65535;
+    pub const FRAG_UNIT_LEN : u32 = 8;
+    pub const TCP_MIN_HDR_LEN : u32 = 20;
+
+    pub fn new() -> ipv4_reasm {
+        let reasm = ipv4_reasm {
+            queues              : HashMap::new(),
+            order               : VecDeque::new(),
+            n_bytes             : 0,
+            limits              : frag_limits::new()
+        };
+        reasm
+    }
+
+    // @brief - returns the number of datagrams waiting for fragments
+    pub fn n_datagrams(&self) -> usize { return self.queues.len(); }
+
+    // @brief - drop a datagram and its place in the order, a key that is
+    //          seen again (id reuse) starts over at the back
+    fn remove(&mut self, key : &ipv4_frag_key) {
+        if let Some(q) = self.queues.remove(key) {
+            self.n_bytes -= q.n_bytes;
+            if let Some(pos) = self.order.iter().position(|k| k == key) {
+                self.order.remove(pos);
+            }
+        }
+    }
+
+    // @brief - drop datagrams that did not complete in time
+    //
+    // @param [inout] self - reassembly table
+    // @param [in] now_sec - monotonic time
+    fn expire(&mut self, now_sec : u32) {
+        while let Some(key) = self.order.front().copied() {
+            match self.queues.get(&key) {
+                Some(q) => {
+                    if now_sec.wrapping_sub(q.created_sec) < self.limits.timeout_sec {
+                        break;
+                    }
+                    self.remove(&key);
+                }
+                None => { self.order.pop_front(); }
+            }
+        }
+    }
+
+    // @brief - drop the oldest datagram still waiting for fragments
+    fn evict_oldest(&mut self) {
+        if let Some(key) = self.order.front().copied() {
+            self.remove(&key);
+        }
+    }
+
+    fn deny(evt_mgr : &mut event_mgr, evt_desc : u32) -> i32 {
+        evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY, evt_desc);
+        return -1;
+    }
+
+    // @brief - check a fragment before it is queued
+    //
+    // @param [in] ip_h - ipv4 header of the fragment
+    // @param [in] data_len - length of the fragment data
+    // @param [out] evt_mgr - event mgr
+    //
+    // @return 0 on success -1 on failure
+    fn check_frag(ip_h : &ipv4_hdr, data_len : u32, evt_mgr : &mut event_mgr) -> i32 {
+        let frag_off = ip_h.frag_off_bytes();
+
+        // every fragment but the last carries a multiple of 8 bytes
+        if ip_h.more_frags() && ((data_len == 0) || (data_len % ipv4_reasm::FRAG_UNIT_LEN != 0)) {
+            return ipv4_reasm::deny(evt_mgr, event_desc::IPV4_FRAG_INVAL_LEN);
+        }
+
+        if ip_h.hdr_len() + frag_off + data_len > ipv4_reasm::MAX_DATAGRAM_LEN {
+            return ipv4_reasm::deny(evt_mgr, event_desc::IPV4_FRAG_OVERSIZE);
+        }
+
+        // RFC 1858 - a first fragment too short for the TCP header or a
+        // second fragment at offset 8 can rewrite the TCP flags past a filter
+        if ip_h.protocol == ProtocolTypes::TCP {
+            if (frag_off == 0) && (data_len < ipv4_reasm::TCP_MIN_HDR_LEN) {
+                return ipv4_reasm::deny(evt_mgr, event_desc::IPV4_FRAG_TINY);
+            }
+            if frag_off == ipv4_reasm::FRAG_UNIT_LEN {
+                return ipv4_reasm::deny(evt_mgr, event_desc::IPV4_FRAG_TINY);
+            }
+        }
+
+        return 0;
+    }
+
+    // @brief - add a fragment to the reassembly table
+    //
+    // @param [inout] self - reassembly table
+    // @param [in] ip_h - ipv4 header of the fragment
+    // @param [in] p - packet, offset at the fragment data
+    // @param [in] hdr_start - offset of the ipv4 header in the packet
+    // @param [in] now_sec - monotonic time
+    // @param [out] evt_mgr - event mgr
+    // @param [out] datagram - reassembled datagram, offset at the ipv4 payload
+    //
+    // @return 1 if the datagram is complete, 0 if more fragments are needed
+    //         -1 on failure
+    pub fn insert(&mut self, ip_h : &ipv4_hdr, p : &packet, hdr_start : usize,
+                  now_sec : u32, evt_mgr : &mut event_mgr, datagram : &mut packet) -> i32 {
+        let data_end = std::cmp::min(p.pkt_len, hdr_start + ip_h.total_len() as usize);
+        let data = &p.buf[p.off..std::cmp::max(p.off, data_end)];
+        let key = ipv4_frag_key {
+            src_ipaddr          : ip_h.src_ipaddr(),
+            dst_ipaddr          : ip_h.dst_ipaddr(),
+            id                  : ip_h.id(),
+            protocol            : ip_h.protocol
+        };
+
+        if ipv4_reasm::check_frag(ip_h, data.len() as u32, evt_mgr) < 0 {
+            self.remove(&key);
+            return -1;
+        }
+
+        self.expire(now_sec);
+
+        if !self.queues.contains_key(&key) {
+            if self.queues.len() >= self.limits.max_datagrams {
+                self.evict_oldest();
+                return ipv4_reasm::deny(evt_mgr, event_desc::IPV4_FRAG_FLOOD);
+            }
+            self.queues.insert(key, frag_queue::new(now_sec));
+            self.order.push_back(key);
+        }
+
+        if self.n_bytes + data.len() > self.limits.max_mem_bytes {
+            self.evict_oldest();
+            return ipv4_reasm::deny(evt_mgr, event_desc::IPV4_FRAG_FLOOD);
+        }
+
+        let q = self.queues.get_mut(&key).unwrap();
+        let ret = q.insert(ip_h.frag_off_bytes(), data, ip_h.more_frags());
+
+        match ret {
+            frag_insert_result::OK => {
+                self.n_bytes += data.len();
+                if ip_h.frag_off_bytes() == 0 {
+                    q.hdr = p.buf[hdr_start..p.off].to_vec();
+                }
+            }
+            frag_insert_result::DUPLICATE => return 0,
+            frag_insert_result::OVERLAP => {
+                // teardrop and friends rely on overlapping fragments
+                self.remove(&key);
+                return ipv4_reasm::deny(evt_mgr, event_desc::IPV4_FRAG_OVERLAP);
+            }
+            _ => {
+                self.remove(&key);
+                return ipv4_reasm::deny(evt_mgr, event_desc::IPV4_FRAG_INVAL_LEN);
+            }
+        }
+
+        if !q.is_complete() {
+            return 0;
+        }
+
+        let hdr_len = q.hdr.len();
+        let total_len = hdr_len + q.data_len() as usize;
+
+        *datagram = packet::with_buf_len(total_len);
+        datagram.buf[0..hdr_len].copy_from_slice(&q.hdr);
+        q.assemble(&mut datagram.buf[hdr_len..total_len]);
+        datagram.pkt_len = total_len;
+        datagram.off = hdr_len;
+
+        self.remove(&key);
+
+        return 1;
+    }
+
+    // @brief - drop every queued fragment
+    pub fn clear(&mut self) {
+        self.queues.clear();
+        self.order.clear();
+        self.n_bytes = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lib::protocols::l3::ipv4::ipv4_checks;
+
+    const DATA_LEN : usize = 16;
+
+    // @brief - build and queue a UDP fragment of DATA_LEN bytes
+    fn insert(reasm : &mut ipv4_reasm, id : u16, frag_units : u16, more_frags : bool,
+              now_sec : u32, evt_mgr : &mut event_mgr) -> i32 {
+        let mut p = packet::with_buf_len(ipv4_hdr::IPV4_MIN_HDR_LEN as usize + DATA_LEN);
+        let mut ip_h = ipv4_hdr::new();
+        let mut checks = ipv4_checks::new();
+        let mut datagram = packet::new();
+        let flags_off = ((more_frags as u16) << 13) | frag_units;
+        let total_len = p.buf.len() as u16;
+
+        checks.checksum = false;
+        p.buf[0] = 0x45;
+        p.buf[2..4].copy_from_slice(&total_len.to_be_bytes());
+        p.buf[4..6].copy_from_slice(&id.to_be_bytes());
+        p.buf[6..8].copy_from_slice(&flags_off.to_be_bytes());
+        p.buf[8] = 64;
+        p.buf[9] = ProtocolTypes::UDP;
+        p.buf[12..16].copy_from_slice(&[10, 0, 0, 1]);
+        p.buf[16..20].copy_from_slice(&[10, 0, 0, 2]);
+        p.pkt_len = p.buf.len();
+
+        assert_eq!(ip_h.deserialize(&mut p, evt_mgr, &checks, false), 0);
+        return reasm.insert(&ip_h, &p, 0, now_sec, evt_mgr, &mut datagram);
+    }
+
+    #[test]
+    fn reused_id_does_not_block_expiry() {
+        let mut reasm = ipv4_reasm::new();
+        let mut evt_mgr = event_mgr::new();
+
+        assert_eq!(insert(&mut reasm, 1, 0, true, 0, &mut evt_mgr), 0);
+        assert_eq!(insert(&mut reasm, 1, 2, false, 0, &mut evt_mgr), 1);
+        assert_eq!(insert(&mut reasm, 2, 0, true, 5, &mut evt_mgr), 0);
+        // id 1 comes back after its first datagram completed
+        assert_eq!(insert(&mut reasm, 1, 0, true, 20, &mut evt_mgr), 0);
+        assert_eq!(reasm.n_datagrams(), 2);
+
+        // id 2 times out, the newer id 1 stays
+        assert_eq!(insert(&mut reasm, 3, 0, true, 36, &mut evt_mgr), 0);
+        assert_eq!(reasm.n_datagrams(), 2);
+        assert!(reasm.queues.contains_key(&ipv4_frag_key {
+            src_ipaddr          : 0x0A000001,
+            dst_ipaddr          : 0x0A000002,
+            id                  : 1,
+            protocol            : ProtocolTypes::UDP
+        }));
+        assert_eq!(reasm.order.len(), 2);
+    }
+
+    #[test]
+    fn flood_evicts_oldest() {
+        let mut reasm = ipv4_reasm::new();
+        let mut evt_mgr = event_mgr::new();
+
+        reasm.limits.max_datagrams = 2;
+        assert_eq!(insert(&mut reasm, 1, 0, true, 0, &mut evt_mgr), 0);
+        assert_eq!(insert(&mut reasm, 1, 2, false, 0, &mut evt_mgr), 1);
+        assert_eq!(insert(&mut reasm, 2, 0, true, 1, &mut evt_mgr), 0);
+        assert_eq!(insert(&mut reasm, 1, 0, true, 2, &mut evt_mgr), 0);
+        assert_eq!(insert(&mut reasm, 3, 0, true, 3, &mut evt_mgr), -1);
+        assert_eq!(evt_mgr.get_evt_desc_counts().get(&event_desc::IPV4_FRAG_FLOOD), Some(&1));
+
+        // id 2 was the oldest
+        assert_eq!(reasm.n_datagrams(), 1);
+        assert_eq!(reasm.order.front().map(|k| k.id), Some(1));
+    }
+}
@@ -95,13 +95,13 @@ impl ipv6_hdr {
         p.deserialize_byte(&mut self.next_hdr);
         p.deserialize_byte(&mut self.hop_limit);
         p.deserialize_ip6addr(&mut self.src_ip6addr);
-        if ipv6_hdr::is_valid_address(&self.src_ip6addr) == false {
-            evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY,
-                                    event_desc::IPV6_SRC_ADDR_INVALID);
-            return -1;
-        }
+        evt_mgr.set_pkt_ipv6(&self.src_ip6addr, &self.dst_ip6addr, self.next_hdr);
+
+        // RFC 4291 - the unspecified source is left for the upper layer, a
+        // host sends from it while it has no address yet
 
         p.deserialize_ip6addr(&mut self.dst_ip6addr);
+        evt_mgr.set_pkt_ipv6(&self.src_ip6addr, &self.dst_ip6addr, self.next_hdr);
         if ipv6_hdr::is_valid_address(&self.dst_ip6addr) == false {
             evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY,
                                     event_desc::IPV6_DST_ADDR_INVALID);
@@ -113,6 +113,35 @@ impl ipv6_hdr {
         return 0;
     }
 
+    // @brief - returns the source address
+    pub fn src_addr(&self) -> &[u8; 16] { return &self.src_ip6addr; }
+
+    // @brief - returns true if the source is the unspecified address
+    pub fn src_unspecified(&self) -> bool { return !ipv6_hdr::is_valid_address(&self.src_ip6addr); }
+
+    // @brief - returns the destination address
+    pub fn dst_addr(&self) -> &[u8; 16] { return &self.dst_ip6addr; }
+
+    // @brief - returns the hop limit
+    pub fn hop_limit(&self) -> u8 { return self.hop_limit; }
+
+    // @brief - returns the length of the data following the ipv6 header
+    //
+    // @param [in] self - ipv6 header
+    //
+    // @return payload length given by the payload length field
+    pub fn payload_len(&self) -> u32 { return self.payload_len as u32; }
+
+    // @brief - make the header describe a reassembled packet
+    //
+    // @param [inout] self - ipv6 header
+    // @param [in] payload_len - length of the reassembled fragmentable part
+    // @param [in] next_hdr - next header of the fragmentable part
+    pub fn set_reassembled(&mut self, payload_len : u32, next_hdr : u8) {
+        self.payload_len = payload_len as u16;
+        self.next_hdr = next_hdr;
+    }
+
     // @brief - print ipv6 header
     //
     // @param [in] self - ipv6 packet
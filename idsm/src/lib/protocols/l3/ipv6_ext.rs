@@ -0,0 +1,561 @@
+// @brief - implements ipv6 extension header deserialization
+// @copyright - 2024-present Devendra Naga All rights reserved
+#![allow(dead_code)]
+#![allow(non_camel_case_types)]
+
+use crate::{
+    events::{
+        event_desc::event_desc,
+        event_mgr::event_mgr,
+        event_type::event_type
+    },
+    lib::protocols::packet::packet::packet
+};
+
+#[non_exhaustive]
+pub struct ipv6_ext_types;
+
+impl ipv6_ext_types {
+    pub const HOP_BY_HOP : u8 = 0;
+    pub const ROUTING : u8 = 43;
+    pub const FRAGMENT : u8 = 44;
+    pub const ESP : u8 = 50;
+    pub const AH : u8 = 51;
+    pub const NO_NEXT_HDR : u8 = 59;
+    pub const DEST_OPTS : u8 = 60;
+    pub const MOBILITY : u8 = 135;
+
+    // @brief - check if the next header value is an extension header
+    pub fn is_ext_hdr(next_hdr : u8) -> bool {
+        match next_hdr {
+            ipv6_ext_types::HOP_BY_HOP |
+            ipv6_ext_types::ROUTING |
+            ipv6_ext_types::FRAGMENT |
+            ipv6_ext_types::ESP |
+            ipv6_ext_types::AH |
+            ipv6_ext_types::DEST_OPTS |
+            ipv6_ext_types::MOBILITY => return true,
+            _ => return false,
+        }
+    }
+}
+
+#[non_exhaustive]
+pub struct ipv6_opt_types;
+
+impl ipv6_opt_types {
+    pub const PAD1 : u8 = 0;
+    pub const PADN : u8 = 1;
+    pub const ROUTER_ALERT : u8 = 5;
+    pub const JUMBO_PAYLOAD : u8 = 0xC2;
+
+    // @brief - action for an unrecognized option (RFC 8200 section 4.2)
+    pub const ACTION_MASK : u8 = 0xC0;
+    pub const ACTION_SKIP : u8 = 0x00;
+}
+
+// @brief - defines the fragment header
+pub struct ipv6_frag_hdr {
+    pub next_hdr        : u8,
+    pub frag_off        : u16, // 13 bits
+    pub more_frags      : u8, // 1 bit
+    pub id              : u32
+}
+
+impl ipv6_frag_hdr {
+    pub const FRAG_HDR_LEN : usize = 8;
+
+    #[inline(always)]
+    pub fn new() -> ipv6_frag_hdr {
+        let frag_h = ipv6_frag_hdr {
+            next_hdr        : 0,
+            frag_off        : 0,
+            more_frags      : 0,
+            id              : 0
+        };
+        frag_h
+    }
+
+    // @brief - returns the fragment offset in bytes
+    pub fn frag_off_bytes(&self) -> u32 { return self.frag_off as u32 * 8; }
+
+    // @brief - check if the fragment header is the only fragment
+    pub fn is_atomic(&self) -> bool { return (self.frag_off == 0) && (self.more_frags == 0); }
+}
+
+// @brief - defines the extension headers of a packet
+pub struct ipv6_ext_hdrs {
+    pub n_hdrs          : u32,
+    pub has_hop_by_hop  : bool,
+    pub has_router_alert : bool,
+    pub has_routing     : bool,
+    pub routing_type    : u8,
+    pub segments_left   : u8,
+    pub has_frag        : bool,
+    pub frag_h          : ipv6_frag_hdr,
+    // offset of the fragment header from the start of the extension headers
+    pub frag_hdr_off    : usize,
+    // number of headers up to and including the fragment header
+    pub frag_hdr_n      : u32,
+    pub has_dest_opts   : bool,
+    pub has_ah          : bool,
+    pub has_esp         : bool,
+    pub esp_spi         : u32,
+    // next header of the last extension header
+    pub upper_proto     : u8,
+    // length of the extension headers
+    pub ext_len         : u32,
+    pub max_hdrs        : u32
+}
+
+impl ipv6_ext_hdrs {
+    pub const DEFAULT_MAX_HDRS : u32 = 8;
+    pub const EXT_HDR_MIN_LEN : u32 = 8;
+    pub const RH0 : u8 = 0;
+    pub const ESP_SPI_LEN : u32 = 4;
+
+    #[inline(always)]
+    pub fn new() -> ipv6_ext_hdrs {
+        let ext_h = ipv6_ext_hdrs {
+            n_hdrs          : 0,
+            has_hop_by_hop  : false,
+            has_router_alert : false,
+            has_routing     : false,
+            routing_type    : 0,
+            segments_left   : 0,
+            has_frag        : false,
+            frag_h          : ipv6_frag_hdr::new(),
+            frag_hdr_off    : 0,
+            frag_hdr_n      : 0,
+            has_dest_opts   : false,
+            has_ah          : false,
+            has_esp         : false,
+            esp_spi         : 0,
+            upper_proto     : 0,
+            ext_len         : 0,
+            max_hdrs        : ipv6_ext_hdrs::DEFAULT_MAX_HDRS
+        };
+        ext_h
+    }
+
+    fn deny(evt_mgr : &mut event_mgr, evt_desc : u32) -> i32 {
+        evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY, evt_desc);
+        return -1;
+    }
+
+    // @brief - raise the event for a truncated extension header
+    //
+    // @details - RFC 7112 - the first fragment must hold the whole header
+    //            chain, so running out of data after a fragment header is
+    //            reported on its own.
+    fn short_hdr(&self, evt_mgr : &mut event_mgr) -> i32 {
+        if self.has_frag {
+            return ipv6_ext_hdrs::deny(evt_mgr, event_desc::IPV6_FRAG_UPPER_HDR_MISSING);
+        }
+
+        return ipv6_ext_hdrs::deny(evt_mgr, event_desc::IPV6_EXT_SHORT_HDR_LEN);
+    }
+
+    // @brief - returns the offset of the fragment data from the start of the extension headers
+    pub fn frag_data_off(&self) -> usize { return self.frag_hdr_off + ipv6_frag_hdr::FRAG_HDR_LEN; }
+
+    // @brief - deserialize the options of hop-by-hop and destination options
+    //
+    // @param [inout] self - extension headers
+    // @param [in] p - packet
+    // @param [out] evt_mgr - event mgr
+    // @param [in] opts_start - offset of the first option
+    // @param [in] opts_end - offset where the header ends
+    //
+    // @return 0 on success -1 on failure
+    fn deserialize_opts(&mut self, p : &packet, evt_mgr : &mut event_mgr,
+                        opts_start : usize, opts_end : usize) -> i32 {
+        let mut off = opts_start;
+
+        while off < opts_end {
+            let opt_type = p.buf[off];
+
+            if opt_type == ipv6_opt_types::PAD1 {
+                off += 1;
+                continue;
+            }
+
+            if off + 2 > opts_end {
+                return ipv6_ext_hdrs::deny(evt_mgr, event_desc::IPV6_EXT_INVAL_OPT_LEN);
+            }
+
+            let opt_len = p.buf[off + 1] as usize;
+            if off + 2 + opt_len > opts_end {
+                return ipv6_ext_hdrs::deny(evt_mgr, event_desc::IPV6_EXT_INVAL_OPT_LEN);
+            }
+
+            match opt_type {
+                ipv6_opt_types::PADN |
+                ipv6_opt_types::JUMBO_PAYLOAD => (),
+                ipv6_opt_types::ROUTER_ALERT => self.has_router_alert = true,
+                _ => {
+                    // the receiver would drop the packet, so a sender using
+                    // these is probing or trying to desync the filter
+                    if (opt_type & ipv6_opt_types::ACTION_MASK) != ipv6_opt_types::ACTION_SKIP {
+                        return ipv6_ext_hdrs::deny(evt_mgr, event_desc::IPV6_EXT_UNKNOWN_OPT_DISCARD);
+                    }
+                }
+            }
+
+            off += 2 + opt_len;
+        }
+
+        return 0;
+    }
+
+    // @brief - walk the extension header chain
+    //
+    // @param [inout] self - extension headers
+    // @param [inout] p - packet, offset after the ipv6 header
+    // @param [out] evt_mgr - event mgr
+    // @param [in] next_hdr - next header of the ipv6 header
+    // @param [in] debug - debug frame
+    //
+    // @return 0 on success -1 on failure
+    //
+    // @details - p.off is left at the upper layer header. walking stops at
+    //            ESP and at non-first fragments as what follows cannot be read.
+    pub fn deserialize(&mut self, p : &mut packet, evt_mgr : &mut event_mgr,
+                       next_hdr : u8, debug : bool) -> i32 {
+        let max_hdrs = self.max_hdrs;
+
+        *self = ipv6_ext_hdrs::new();
+        self.max_hdrs = max_hdrs;
+
+        return self.deserialize_chain(p, evt_mgr, next_hdr, debug);
+    }
+
+    // @brief - walk the extension headers that follow the fragment header
+    //            of a reassembled packet
+    //
+    // @param [inout] self - extension headers
+    // @param [inout] p - reassembled data
+    // @param [out] evt_mgr - event mgr
+    // @param [in] next_hdr - next header of the fragment header
+    // @param [in] debug - debug frame
+    //
+    // @return 0 on success -1 on failure
+    //
+    // @details - the headers before the fragment header, as walked by
+    //            deserialize(), are kept. they count towards the header
+    //            limit and the hop-by-hop position, and a routing header
+    //            among them still names the final destination.
+    pub fn deserialize_reassembled(&mut self, p : &mut packet, evt_mgr : &mut event_mgr,
+                                   next_hdr : u8, debug : bool) -> i32 {
+        self.n_hdrs = self.frag_hdr_n;
+        self.has_frag = false;
+        self.frag_h = ipv6_frag_hdr::new();
+        self.frag_hdr_off = 0;
+        self.frag_hdr_n = 0;
+        self.has_esp = false;
+        self.esp_spi = 0;
+
+        return self.deserialize_chain(p, evt_mgr, next_hdr, debug);
+    }
+
+    fn deserialize_chain(&mut self, p : &mut packet, evt_mgr : &mut event_mgr,
+                         next_hdr : u8, debug : bool) -> i32 {
+        let chain_start = p.off;
+        let mut nh = next_hdr;
+        let mut ret : i32;
+
+        while ipv6_ext_types::is_ext_hdr(nh) {
+            let hdr_start = p.off;
+            let hdr_len : usize;
+
+            self.n_hdrs += 1;
+            if self.n_hdrs > self.max_hdrs {
+                return ipv6_ext_hdrs::deny(evt_mgr, event_desc::IPV6_EXT_TOO_MANY_HDRS);
+            }
+
+            // RFC 8200 - hop-by-hop must immediately follow the ipv6 header
+            if (nh == ipv6_ext_types::HOP_BY_HOP) && (self.n_hdrs != 1) {
+                return ipv6_ext_hdrs::deny(evt_mgr, event_desc::IPV6_EXT_HBH_NOT_FIRST);
+            }
+
+            if nh == ipv6_ext_types::ESP {
+                if !p.remaining_len_in_bounds(ipv6_ext_hdrs::ESP_SPI_LEN) {
+                    return self.short_hdr(evt_mgr);
+                }
+                p.deserialize_4_bytes(&mut self.esp_spi);
+                p.off = hdr_start;
+                self.has_esp = true;
+                break;
+            }
+
+            if !p.remaining_len_in_bounds(ipv6_ext_hdrs::EXT_HDR_MIN_LEN) {
+                return self.short_hdr(evt_mgr);
+            }
+
+            let hdr_nh = p.buf[hdr_start];
+            match nh {
+                // AH length is in 4 byte units minus 2 (RFC 4302)
+                ipv6_ext_types::AH => hdr_len = (p.buf[hdr_start + 1] as usize + 2) * 4,
+                ipv6_ext_types::FRAGMENT => hdr_len = ipv6_frag_hdr::FRAG_HDR_LEN,
+                _ => hdr_len = (p.buf[hdr_start + 1] as usize + 1) * 8,
+            }
+
+            if !p.remaining_len_in_bounds(hdr_len as u32) {
+                return self.short_hdr(evt_mgr);
+            }
+
+            match nh {
+                ipv6_ext_types::HOP_BY_HOP |
+                ipv6_ext_types::DEST_OPTS => {
+                    ret = self.deserialize_opts(p, evt_mgr, hdr_start + 2, hdr_start + hdr_len);
+                    if ret < 0 {
+                        return -1;
+                    }
+                    if nh == ipv6_ext_types::HOP_BY_HOP {
+                        self.has_hop_by_hop = true;
+                    } else {
+                        self.has_dest_opts = true;
+                    }
+                }
+                ipv6_ext_types::ROUTING => {
+                    self.has_routing = true;
+                    self.routing_type = p.buf[hdr_start + 2];
+                    self.segments_left = p.buf[hdr_start + 3];
+
+                    // RFC 5095 - type 0 allows traffic amplification
+                    if self.routing_type == ipv6_ext_hdrs::RH0 {
+                        return ipv6_ext_hdrs::deny(evt_mgr, event_desc::IPV6_EXT_RH0);
+                    }
+                }
+                ipv6_ext_types::FRAGMENT => {
+                    self.frag_h.next_hdr = hdr_nh;
+                    p.off = hdr_start + 2;
+                    self.frag_h.frag_off = (((p.buf[p.off] as u16) << 8) | p.buf[p.off + 1] as u16) >> 3;
+                    self.frag_h.more_frags = p.buf[p.off + 1] & 0x01;
+                    p.off += 2;
+                    p.deserialize_4_bytes(&mut self.frag_h.id);
+                    self.frag_hdr_off = hdr_start - chain_start;
+                    self.frag_hdr_n = self.n_hdrs;
+                    self.has_frag = true;
+                }
+                ipv6_ext_types::AH => self.has_ah = true,
+                _ => (),
+            }
+
+            p.off = hdr_start + hdr_len;
+            nh = hdr_nh;
+
+            // only the first fragment carries the headers that follow
+            if self.has_frag && (self.frag_h.frag_off > 0) {
+                break;
+            }
+        }
+
+        self.upper_proto = nh;
+        self.ext_len = (p.off - chain_start) as u32;
+
+        if debug { self.print(); }
+
+        return 0;
+    }
+
+    pub fn print(&self) {
+        if self.n_hdrs == 0 {
+            return;
+        }
+
+        log::info!("ipv6_ext_hdrs: ");
+        log::info!("\t n_hdrs: {}", self.n_hdrs);
+        log::info!("\t hop_by_hop: {}", self.has_hop_by_hop);
+        log::info!("\t router_alert: {}", self.has_router_alert);
+        log::info!("\t routing: {}", self.has_routing);
+        if self.has_routing {
+            log::info!("\t\t routing_type: {}", self.routing_type);
+            log::info!("\t\t segments_left: {}", self.segments_left);
+        }
+        log::info!("\t fragment: {}", self.has_frag);
+        if self.has_frag {
+            log::info!("\t\t frag_off: {}", self.frag_h.frag_off);
+            log::info!("\t\t more_frags: {}", self.frag_h.more_frags);
+            log::info!("\t\t id: 0x{:08X}", self.frag_h.id);
+        }
+        log::info!("\t dest_opts: {}", self.has_dest_opts);
+        log::info!("\t ah: {}", self.has_ah);
+        log::info!("\t esp: {}", self.has_esp);
+        if self.has_esp {
+            log::info!("\t\t spi: 0x{:08X}", self.esp_spi);
+        }
+        log::info!("\t upper_proto: {}", self.upper_proto);
+        log::info!("\t ext_len: {}", self.ext_len);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UDP : u8 = 17;
+
+    // @brief - a header of 8 bytes, the rest is padding
+    fn hdr(next_hdr : u8) -> Vec<u8> {
+        return vec![next_hdr, 0, ipv6_opt_types::PADN, 4, 0, 0, 0, 0];
+    }
+
+    fn routing(next_hdr : u8, routing_type : u8, segments_left : u8) -> Vec<u8> {
+        return vec![next_hdr, 0, routing_type, segments_left, 0, 0, 0, 0];
+    }
+
+    fn frag(next_hdr : u8, frag_off : u16, more_frags : bool) -> Vec<u8> {
+        let off = (frag_off << 3) | more_frags as u16;
+
+        return vec![next_hdr, 0, (off >> 8) as u8, off as u8, 0, 0, 0, 1];
+    }
+
+    fn pkt(chain : &[Vec<u8>]) -> packet {
+        let data = chain.concat();
+        let mut p = packet::with_buf_len(data.len());
+
+        p.buf.copy_from_slice(&data);
+        p.pkt_len = data.len();
+        return p;
+    }
+
+    fn denied(evt_mgr : &event_mgr, desc : u32) -> bool {
+        return evt_mgr.get_evt_desc_counts().get(&desc) == Some(&1);
+    }
+
+    #[test]
+    fn routing_kept_after_reassembly() {
+        let mut evt_mgr = event_mgr::new();
+        let mut ext_h = ipv6_ext_hdrs::new();
+        let mut p = pkt(&[routing(ipv6_ext_types::FRAGMENT, 4, 1),
+                          frag(ipv6_ext_types::DEST_OPTS, 1, false)]);
+
+        assert_eq!(ext_h.deserialize(&mut p, &mut evt_mgr, ipv6_ext_types::ROUTING, false), 0);
+        assert!(ext_h.has_frag);
+        assert_eq!(ext_h.frag_hdr_n, 2);
+
+        let mut datagram = pkt(&[hdr(UDP)]);
+        assert_eq!(ext_h.deserialize_reassembled(&mut datagram, &mut evt_mgr,
+                                                 ipv6_ext_types::DEST_OPTS, false), 0);
+        assert!(ext_h.has_routing);
+        assert_eq!(ext_h.segments_left, 1);
+        assert!(!ext_h.has_frag);
+        assert!(ext_h.has_dest_opts);
+        assert_eq!(ext_h.n_hdrs, 3);
+        assert_eq!(ext_h.upper_proto, UDP);
+        assert_eq!(ext_h.ext_len, 8);
+    }
+
+    #[test]
+    fn non_first_fragment_stops_walk() {
+        let mut evt_mgr = event_mgr::new();
+        let mut ext_h = ipv6_ext_hdrs::new();
+        let mut p = pkt(&[frag(ipv6_ext_types::DEST_OPTS, 2, true), hdr(UDP)]);
+
+        assert_eq!(ext_h.deserialize(&mut p, &mut evt_mgr, ipv6_ext_types::FRAGMENT, false), 0);
+        assert!(!ext_h.has_dest_opts);
+        assert_eq!(ext_h.upper_proto, ipv6_ext_types::DEST_OPTS);
+        assert_eq!(p.off, ipv6_frag_hdr::FRAG_HDR_LEN);
+    }
+
+    #[test]
+    fn too_many_hdrs() {
+        let mut evt_mgr = event_mgr::new();
+        let mut ext_h = ipv6_ext_hdrs::new();
+        let n = ipv6_ext_hdrs::DEFAULT_MAX_HDRS as usize;
+        let mut chain = vec![hdr(ipv6_ext_types::DEST_OPTS); n - 1];
+
+        chain.push(hdr(UDP));
+        let mut p = pkt(&chain);
+        assert_eq!(ext_h.deserialize(&mut p, &mut evt_mgr, ipv6_ext_types::DEST_OPTS, false), 0);
+        assert!(evt_mgr.get_evt_desc_counts().is_empty());
+
+        chain.insert(0, hdr(ipv6_ext_types::DEST_OPTS));
+        let mut p = pkt(&chain);
+        assert_eq!(ext_h.deserialize(&mut p, &mut evt_mgr, ipv6_ext_types::DEST_OPTS, false), -1);
+        assert!(denied(&evt_mgr, event_desc::IPV6_EXT_TOO_MANY_HDRS));
+    }
+
+    #[test]
+    fn hop_by_hop_not_first() {
+        let mut evt_mgr = event_mgr::new();
+        let mut ext_h = ipv6_ext_hdrs::new();
+        let mut p = pkt(&[hdr(ipv6_ext_types::HOP_BY_HOP), hdr(UDP)]);
+
+        assert_eq!(ext_h.deserialize(&mut p, &mut evt_mgr, ipv6_ext_types::DEST_OPTS, false), -1);
+        assert!(denied(&evt_mgr, event_desc::IPV6_EXT_HBH_NOT_FIRST));
+    }
+
+    #[test]
+    fn routing_type_0() {
+        let mut evt_mgr = event_mgr::new();
+        let mut ext_h = ipv6_ext_hdrs::new();
+        let mut p = pkt(&[routing(UDP, ipv6_ext_hdrs::RH0, 1)]);
+
+        assert_eq!(ext_h.deserialize(&mut p, &mut evt_mgr, ipv6_ext_types::ROUTING, false), -1);
+        assert!(denied(&evt_mgr, event_desc::IPV6_EXT_RH0));
+    }
+
+    #[test]
+    fn walk_to_upper_layer() {
+        let mut evt_mgr = event_mgr::new();
+        let mut ext_h = ipv6_ext_hdrs::new();
+        // hop-by-hop with router alert, then AH of 16 bytes
+        let mut p = pkt(&[vec![ipv6_ext_types::AH, 0, ipv6_opt_types::ROUTER_ALERT, 2, 0, 0, ipv6_opt_types::PAD1, 0],
+                          vec![UDP, 2, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0]]);
+
+        assert_eq!(ext_h.deserialize(&mut p, &mut evt_mgr, ipv6_ext_types::HOP_BY_HOP, false), 0);
+        assert!(ext_h.has_hop_by_hop);
+        assert!(ext_h.has_router_alert);
+        assert!(ext_h.has_ah);
+        assert_eq!(ext_h.n_hdrs, 2);
+        assert_eq!(ext_h.upper_proto, UDP);
+        assert_eq!(ext_h.ext_len, 24);
+        assert_eq!(p.off, 24);
+    }
+
+    #[test]
+    fn esp_stops_walk() {
+        let mut evt_mgr = event_mgr::new();
+        let mut ext_h = ipv6_ext_hdrs::new();
+        let mut p = pkt(&[hdr(ipv6_ext_types::ESP), vec![0, 0, 0x10, 0x01, 0, 0, 0, 1]]);
+
+        assert_eq!(ext_h.deserialize(&mut p, &mut evt_mgr, ipv6_ext_types::DEST_OPTS, false), 0);
+        assert!(ext_h.has_esp);
+        assert_eq!(ext_h.esp_spi, 0x1001);
+        assert_eq!(ext_h.upper_proto, ipv6_ext_types::ESP);
+        assert_eq!(p.off, 8);
+    }
+
+    #[test]
+    fn short_hdr() {
+        let mut evt_mgr = event_mgr::new();
+        let mut ext_h = ipv6_ext_hdrs::new();
+        // header length says 16 bytes, only 8 are present
+        let mut p = pkt(&[vec![UDP, 1, ipv6_opt_types::PADN, 4, 0, 0, 0, 0]]);
+
+        assert_eq!(ext_h.deserialize(&mut p, &mut evt_mgr, ipv6_ext_types::DEST_OPTS, false), -1);
+        assert!(denied(&evt_mgr, event_desc::IPV6_EXT_SHORT_HDR_LEN));
+    }
+
+    #[test]
+    fn inval_opt_len() {
+        let mut evt_mgr = event_mgr::new();
+        let mut ext_h = ipv6_ext_hdrs::new();
+        let mut p = pkt(&[vec![UDP, 0, ipv6_opt_types::PADN, 5, 0, 0, 0, 0]]);
+
+        assert_eq!(ext_h.deserialize(&mut p, &mut evt_mgr, ipv6_ext_types::DEST_OPTS, false), -1);
+        assert!(denied(&evt_mgr, event_desc::IPV6_EXT_INVAL_OPT_LEN));
+    }
+
+    #[test]
+    fn unknown_opt_discard() {
+        let mut evt_mgr = event_mgr::new();
+        let mut ext_h = ipv6_ext_hdrs::new();
+        let mut p = pkt(&[vec![UDP, 0, 0x80, 4, 0, 0, 0, 0]]);
+
+        assert_eq!(ext_h.deserialize(&mut p, &mut evt_mgr, ipv6_ext_types::DEST_OPTS, false), -1);
+        assert!(denied(&evt_mgr, event_desc::IPV6_EXT_UNKNOWN_OPT_DISCARD));
+    }
+}
@@ -0,0 +1,237 @@
+// @brief - implements ipv6 fragment reassembly
+// @copyright - 2024-present Devendra Naga All rights reserved
+#![allow(dead_code)]
+#![allow(non_camel_case_types)]
+
+use std::collections::{HashMap, VecDeque};
+
+use crate::{
+    events::{
+        event_desc::event_desc,
+        event_mgr::event_mgr,
+        event_type::event_type
+    },
+    lib::protocols::{
+        l3::{
+            frag_queue::{frag_insert_result, frag_limits, frag_queue},
+            ipv6::ipv6_hdr,
+            ipv6_ext::ipv6_ext_hdrs,
+            protocol_types::ProtocolTypes
+        },
+        packet::packet::packet
+    }
+};
+
+// @brief - identifies the fragments of a packet (RFC 8200 section 4.5)
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct ipv6_frag_key {
+    src_ip6addr         : [u8; 16],
+    dst_ip6addr         : [u8; 16],
+    id                  : u32
+}
+
+// @brief - defines the ipv6 reassembly table
+pub struct ipv6_reasm {
+    queues              : HashMap<ipv6_frag_key, frag_queue>,
+    // keys in the order the packets were first seen
+    order               : VecDeque<ipv6_frag_key>,
+    n_bytes             : usize,
+    pub limits          : frag_limits
+}
+
+impl ipv6_reasm {
+    pub const MAX_PAYLOAD_LEN : u32 = 65535;
+    pub const FRAG_UNIT_LEN : u32 = 8;
+    pub const TCP_MIN_HDR_LEN : u32 = 20;
+    pub const UDP_HDR_LEN : u32 = 8;
+    pub const ICMP6_MIN_HDR_LEN : u32 = 4;
+
+    pub fn new() -> ipv6_reasm {
+        let reasm = ipv6_reasm {
+            queues              : HashMap::new(),
+            order               : VecDeque::new(),
+            n_bytes             : 0,
+            limits              : frag_limits::new()
+        };
+        reasm
+    }
+
+    // @brief - returns the number of packets waiting for fragments
+    pub fn n_datagrams(&self) -> usize { return self.queues.len(); }
+
+    // @brief - drop a datagram and its place in the order, a key that is
+    //          seen again (id reuse) starts over at the back
+    fn remove(&mut self, key : &ipv6_frag_key) {
+        if let Some(q) = self.queues.remove(key) {
+            self.n_bytes -= q.n_bytes;
+            if let Some(pos) = self.order.iter().position(|k| k == key) {
+                self.order.remove(pos);
+            }
+        }
+    }
+
+    // @brief - drop packets that did not complete in time
+    //
+    // @param [inout] self - reassembly table
+    // @param [in] now_sec - monotonic time
+    fn expire(&mut self, now_sec : u32) {
+        while let Some(key) = self.order.front().copied() {
+            match self.queues.get(&key) {
+                Some(q) => {
+                    if now_sec.wrapping_sub(q.created_sec) < self.limits.timeout_sec {
+                        break;
+                    }
+                    self.remove(&key);
+                }
+                None => { self.order.pop_front(); }
+            }
+        }
+    }
+
+    // @brief - drop the oldest packet still waiting for fragments
+    fn evict_oldest(&mut self) {
+        if let Some(key) = self.order.front().copied() {
+            self.remove(&key);
+        }
+    }
+
+    fn deny(evt_mgr : &mut event_mgr, evt_desc : u32) -> i32 {
+        evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY, evt_desc);
+        return -1;
+    }
+
+    // @brief - check that the first fragment holds the upper layer header
+    //
+    // @param [in] upper_proto - upper layer protocol
+    // @param [in] upper_len - bytes of the fragment after the extension headers
+    // @param [out] evt_mgr - event mgr
+    //
+    // @return 0 on success -1 on failure
+    //
+    // @details - RFC 7112 - a first fragment without the upper layer header
+    //            hides the ports and flags from anything that does not reassemble
+    pub fn check_first_frag(upper_proto : u8, upper_len : u32, evt_mgr : &mut event_mgr) -> i32 {
+        let min_len : u32;
+
+        match upper_proto {
+            ProtocolTypes::TCP => min_len = ipv6_reasm::TCP_MIN_HDR_LEN,
+            ProtocolTypes::UDP => min_len = ipv6_reasm::UDP_HDR_LEN,
+            ProtocolTypes::ICMP6 => min_len = ipv6_reasm::ICMP6_MIN_HDR_LEN,
+            _ => min_len = 0,
+        }
+
+        if upper_len < min_len {
+            return ipv6_reasm::deny(evt_mgr, event_desc::IPV6_FRAG_UPPER_HDR_MISSING);
+        }
+
+        return 0;
+    }
+
+    // @brief - add a fragment to the reassembly table
+    //
+    // @param [inout] self - reassembly table
+    // @param [in] ip6_h - ipv6 header of the fragment
+    // @param [in] ext_h - extension headers of the fragment
+    // @param [in] p - packet
+    // @param [in] hdr_start - offset of the ipv6 header in the packet
+    // @param [in] now_sec - monotonic time
+    // @param [out] evt_mgr - event mgr
+    // @param [out] datagram - reassembled packet, offset after the ipv6 header
+    // @param [out] next_hdr - next header of the fragmentable part
+    //
+    // @return 1 if the packet is complete, 0 if more fragments are needed
+    //         -1 on failure
+    //
+    // @details - the reassembled packet carries the ipv6 header of the first
+    //            fragment followed by the fragmentable part. extension headers
+    //            before the fragment header are left out.
+    pub fn insert(&mut self, ip6_h : &ipv6_hdr, ext_h : &ipv6_ext_hdrs, p : &packet,
+                  hdr_start : usize, now_sec : u32, evt_mgr : &mut event_mgr,
+                  datagram : &mut packet, next_hdr : &mut u8) -> i32 {
+        let ip6_hdr_len = ipv6_hdr::IPV6_MIN_HDR_LEN as usize;
+        let data_start = hdr_start + ip6_hdr_len + ext_h.frag_data_off();
+        let data_end = std::cmp::min(p.pkt_len, hdr_start + ip6_hdr_len + ip6_h.payload_len() as usize);
+        let data = &p.buf[data_start..std::cmp::max(data_start, data_end)];
+        let frag_off = ext_h.frag_h.frag_off_bytes();
+        let more_frags = ext_h.frag_h.more_frags != 0;
+        let key = ipv6_frag_key {
+            src_ip6addr         : *ip6_h.src_addr(),
+            dst_ip6addr         : *ip6_h.dst_addr(),
+            id                  : ext_h.frag_h.id
+        };
+
+        // every fragment but the last carries a multiple of 8 bytes
+        if more_frags && ((data.len() == 0) || (data.len() as u32 % ipv6_reasm::FRAG_UNIT_LEN != 0)) {
+            self.remove(&key);
+            return ipv6_reasm::deny(evt_mgr, event_desc::IPV6_FRAG_INVAL_LEN);
+        }
+
+        if frag_off + data.len() as u32 > ipv6_reasm::MAX_PAYLOAD_LEN {
+            self.remove(&key);
+            return ipv6_reasm::deny(evt_mgr, event_desc::IPV6_FRAG_OVERSIZE);
+        }
+
+        self.expire(now_sec);
+
+        if !self.queues.contains_key(&key) {
+            if self.queues.len() >= self.limits.max_datagrams {
+                self.evict_oldest();
+                return ipv6_reasm::deny(evt_mgr, event_desc::IPV6_FRAG_FLOOD);
+            }
+            self.queues.insert(key, frag_queue::new(now_sec));
+            self.order.push_back(key);
+        }
+
+        if self.n_bytes + data.len() > self.limits.max_mem_bytes {
+            self.evict_oldest();
+            return ipv6_reasm::deny(evt_mgr, event_desc::IPV6_FRAG_FLOOD);
+        }
+
+        let q = self.queues.get_mut(&key).unwrap();
+        let ret = q.insert(frag_off, data, more_frags);
+
+        match ret {
+            frag_insert_result::OK => {
+                self.n_bytes += data.len();
+                if frag_off == 0 {
+                    q.hdr = p.buf[hdr_start..hdr_start + ip6_hdr_len].to_vec();
+                    q.next_hdr = ext_h.frag_h.next_hdr;
+                }
+            }
+            frag_insert_result::DUPLICATE => return 0,
+            frag_insert_result::OVERLAP => {
+                // RFC 5722 - overlapping fragments discard the whole packet
+                self.remove(&key);
+                return ipv6_reasm::deny(evt_mgr, event_desc::IPV6_FRAG_OVERLAP);
+            }
+            _ => {
+                self.remove(&key);
+                return ipv6_reasm::deny(evt_mgr, event_desc::IPV6_FRAG_INVAL_LEN);
+            }
+        }
+
+        if !q.is_complete() {
+            return 0;
+        }
+
+        let total_len = ip6_hdr_len + q.data_len() as usize;
+
+        *datagram = packet::with_buf_len(total_len);
+        datagram.buf[0..ip6_hdr_len].copy_from_slice(&q.hdr);
+        q.assemble(&mut datagram.buf[ip6_hdr_len..total_len]);
+        datagram.pkt_len = total_len;
+        datagram.off = ip6_hdr_len;
+        *next_hdr = q.next_hdr;
+
+        self.remove(&key);
+
+        return 1;
+    }
+
+    // @brief - drop every queued fragment
+    pub fn clear(&mut self) {
+        self.queues.clear();
+        self.order.clear();
+        self.n_bytes = 0;
+    }
+}
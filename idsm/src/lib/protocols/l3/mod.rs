@@ -1,3 +1,8 @@
 pub(crate) mod ipv4;
+pub(crate) mod ipv4_opts;
+pub(crate) mod ipv4_reasm;
+pub(crate) mod frag_queue;
 pub(crate) mod protocol_types;
 pub(crate) mod ipv6;
+pub(crate) mod ipv6_ext;
+pub(crate) mod ipv6_reasm;
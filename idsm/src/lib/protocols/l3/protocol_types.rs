@@ -5,6 +5,8 @@
 pub struct ProtocolTypes;
 
 impl ProtocolTypes {
+    pub const ICMP          : u8 = 1;
     pub const TCP           : u8 = 6;
+    pub const UDP           : u8 = 17;
     pub const ICMP6         : u8 = 58;
 }
@@ -0,0 +1,487 @@
+// @brief - implements ICMP serialize and deserializer
+// @copyright - 2024-present Devendra Naga All rights reserved
+#![allow(dead_code)]
+#![allow(non_camel_case_types)]
+
+use crate::{
+    events::{
+        event_desc::event_desc,
+        event_mgr::event_mgr,
+        event_type::event_type
+    },
+    lib::protocols::packet::packet::packet
+};
+
+#[non_exhaustive]
+pub struct icmp_types;
+
+impl icmp_types {
+    pub const ECHO_REPLY : u8 = 0;
+    pub const DEST_UNREACHABLE : u8 = 3;
+    pub const SOURCE_QUENCH : u8 = 4;
+    pub const REDIRECT : u8 = 5;
+    pub const ECHO_REQ : u8 = 8;
+    pub const ROUTER_ADV : u8 = 9;
+    pub const ROUTER_SOL : u8 = 10;
+    pub const TIME_EXCEEDED : u8 = 11;
+    pub const PARAM_PROBLEM : u8 = 12;
+    pub const TIMESTAMP_REQ : u8 = 13;
+    pub const TIMESTAMP_REPLY : u8 = 14;
+    pub const INFO_REQ : u8 = 15;
+    pub const INFO_REPLY : u8 = 16;
+    pub const ADDR_MASK_REQ : u8 = 17;
+    pub const ADDR_MASK_REPLY : u8 = 18;
+
+    // @brief - returns the highest code defined for the type
+    //
+    // @param [in] icmp_type - ICMP type
+    //
+    // @return highest code, None if the type is not known
+    pub fn max_code(icmp_type : u8) -> Option<u8> {
+        match icmp_type {
+            icmp_types::DEST_UNREACHABLE => Some(15),
+            icmp_types::REDIRECT => Some(3),
+            icmp_types::TIME_EXCEEDED => Some(1),
+            icmp_types::PARAM_PROBLEM => Some(2),
+            // code 16 is "does not route common traffic" (RFC 2002)
+            icmp_types::ROUTER_ADV => Some(16),
+            icmp_types::ECHO_REPLY |
+            icmp_types::SOURCE_QUENCH |
+            icmp_types::ECHO_REQ |
+            icmp_types::ROUTER_SOL |
+            icmp_types::TIMESTAMP_REQ |
+            icmp_types::TIMESTAMP_REPLY |
+            icmp_types::INFO_REQ |
+            icmp_types::INFO_REPLY |
+            icmp_types::ADDR_MASK_REQ |
+            icmp_types::ADDR_MASK_REPLY => Some(0),
+            _ => None,
+        }
+    }
+
+    // @brief - check if the type reports an error about an earlier packet
+    pub fn is_error(icmp_type : u8) -> bool {
+        match icmp_type {
+            icmp_types::DEST_UNREACHABLE |
+            icmp_types::SOURCE_QUENCH |
+            icmp_types::REDIRECT |
+            icmp_types::TIME_EXCEEDED |
+            icmp_types::PARAM_PROBLEM => true,
+            _ => false,
+        }
+    }
+}
+
+// @brief - defines echo, timestamp, information and address mask
+//          identifier and sequence number
+pub struct icmp_echo {
+    pub id              : u16,
+    pub seq_no          : u16
+}
+
+impl icmp_echo {
+    #[inline(always)]
+    pub fn new() -> icmp_echo {
+        let e = icmp_echo {
+            id              : 0,
+            seq_no          : 0
+        };
+        e
+    }
+
+    pub fn deserialize(&mut self, p : &mut packet) {
+        p.deserialize_2_bytes(&mut self.id);
+        p.deserialize_2_bytes(&mut self.seq_no);
+    }
+
+    pub fn serialize(&mut self, p : &mut packet) {
+        p.serialize_2_bytes(&mut self.id);
+        p.serialize_2_bytes(&mut self.seq_no);
+    }
+
+    pub fn print(&self) {
+        log::info!("\t echo: ");
+        log::info!("\t\t id: {}", self.id);
+        log::info!("\t\t seq_no: {}", self.seq_no);
+    }
+}
+
+// @brief - defines the IP header and the first 8 bytes of the data
+//          of the packet that caused an ICMP error
+pub struct icmp_nested_ip {
+    pub version         : u8,
+    pub ihl             : u8,
+    pub total_len       : u16,
+    pub protocol        : u8,
+    pub src_ipaddr      : u32,
+    pub dst_ipaddr      : u32,
+    pub src_port        : u16,
+    pub dst_port        : u16
+}
+
+impl icmp_nested_ip {
+    pub const NESTED_IP_MIN_LEN : u32 = 20;
+    pub const NESTED_DATA_LEN : u32 = 8;
+
+    #[inline(always)]
+    pub fn new() -> icmp_nested_ip {
+        let n = icmp_nested_ip {
+            version         : 0,
+            ihl             : 0,
+            total_len       : 0,
+            protocol        : 0,
+            src_ipaddr      : 0,
+            dst_ipaddr      : 0,
+            src_port        : 0,
+            dst_port        : 0
+        };
+        n
+    }
+
+    // @brief - deserialize nested IP header
+    //
+    // @param [inout] self - nested IP header
+    // @param [inout] p - packet
+    // @param [out] evt_mgr - event mgr
+    //
+    // @return 0 on success -1 on failure
+    //
+    // @details - the nested header describes an earlier packet, so it does
+    //            not change the addresses reported with the events.
+    pub fn deserialize(&mut self, p : &mut packet, evt_mgr : &mut event_mgr) -> i32 {
+        let mut unused_u16 : u16 = 0;
+        let start = p.off;
+
+        if !p.remaining_len_in_bounds(icmp_nested_ip::NESTED_IP_MIN_LEN) {
+            evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY,
+                                    event_desc::ICMP_NESTED_IP_INVALID);
+            return -1;
+        }
+
+        self.version = (p.buf[p.off] & 0xF0) >> 4;
+        self.ihl = p.buf[p.off] & 0x0F;
+        if (self.version != 4) || ((self.ihl as u32 * 4) < icmp_nested_ip::NESTED_IP_MIN_LEN) {
+            evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY,
+                                    event_desc::ICMP_NESTED_IP_INVALID);
+            return -1;
+        }
+        p.off += 2;
+
+        p.deserialize_2_bytes(&mut self.total_len);
+        p.off += 5;
+        p.deserialize_byte(&mut self.protocol);
+        p.deserialize_2_bytes(&mut unused_u16);
+        p.deserialize_4_bytes(&mut self.src_ipaddr);
+        p.deserialize_4_bytes(&mut self.dst_ipaddr);
+
+        // skip the options of the nested header
+        if !p.remaining_len_in_bounds(self.ihl as u32 * 4 - icmp_nested_ip::NESTED_IP_MIN_LEN) {
+            evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY,
+                                    event_desc::ICMP_NESTED_IP_INVALID);
+            return -1;
+        }
+        p.off = start + (self.ihl as usize * 4);
+
+        // the first 8 bytes of the data carry the TCP and UDP ports
+        if p.remaining_len_in_bounds(icmp_nested_ip::NESTED_DATA_LEN) {
+            p.deserialize_2_bytes(&mut self.src_port);
+            p.deserialize_2_bytes(&mut self.dst_port);
+            p.off += 4;
+        }
+
+        return 0;
+    }
+
+    pub fn print(&self) {
+        log::info!("\t nested_ip: ");
+        log::info!("\t\t version: {}", self.version);
+        log::info!("\t\t ihl: {}", self.ihl);
+        log::info!("\t\t total_len: {}", self.total_len);
+        log::info!("\t\t protocol: {}", self.protocol);
+        packet::print_ipv4("\t\t src_ipaddr", self.src_ipaddr);
+        packet::print_ipv4("\t\t dst_ipaddr", self.dst_ipaddr);
+        log::info!("\t\t src_port: {}", self.src_port);
+        log::info!("\t\t dst_port: {}", self.dst_port);
+    }
+}
+
+pub struct icmp_hdr {
+    pub icmp_type       : u8,
+    pub code            : u8,
+    pub checksum        : u16,
+    // gateway of redirect, pointer of parameter problem,
+    // next hop MTU of destination unreachable
+    pub rest_of_hdr     : u32,
+    pub echo            : icmp_echo,
+    pub nested_ip       : icmp_nested_ip
+}
+
+impl icmp_hdr {
+    pub const ICMP_MIN_HDR_LEN : u32 = 4;
+    pub const ICMP_REST_OF_HDR_LEN : u32 = 4;
+    pub const IPV4_MAX_PKT_LEN : u32 = 65535;
+
+    #[inline(always)]
+    pub fn new() -> icmp_hdr {
+        let icmp_h = icmp_hdr {
+            icmp_type       : 0,
+            code            : 0,
+            checksum        : 0,
+            rest_of_hdr     : 0,
+            echo            : icmp_echo::new(),
+            nested_ip       : icmp_nested_ip::new()
+        };
+        icmp_h
+    }
+
+    // @brief - check the size of an ICMP fragment
+    //
+    // @param [in] frag_off - fragment offset in bytes
+    // @param [in] ip_total_len - total length of the fragment
+    // @param [out] evt_mgr - event mgr
+    //
+    // @return 0 on success -1 if the reassembled packet exceeds the maximum size
+    //
+    // @details - ping of death sends an echo request in fragments whose
+    //            reassembled length is larger than 65535 bytes. the ICMP
+    //            header is only in the first fragment so this is checked
+    //            on every fragment.
+    pub fn check_frag_len(frag_off : u32, ip_total_len : u32, evt_mgr : &mut event_mgr) -> i32 {
+        if frag_off + ip_total_len > icmp_hdr::IPV4_MAX_PKT_LEN {
+            evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY,
+                                    event_desc::ICMP_PING_OF_DEATH);
+            return -1;
+        }
+
+        return 0;
+    }
+
+    // @brief - deserialize ICMP header
+    //
+    // @param [inout] self - ICMP header
+    // @param [inout] p - packet
+    // @param [out] evt_mgr - event mgr
+    // @param [in] debug - debug frame
+    //
+    // @return 0 on success -1 on failure
+    pub fn deserialize(&mut self, p : &mut packet, evt_mgr : &mut event_mgr, debug : bool) -> i32 {
+        let mut ret : i32 = 0;
+
+        if !p.remaining_len_in_bounds(icmp_hdr::ICMP_MIN_HDR_LEN) {
+            evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY,
+                                    event_desc::ICMP_SHORT_HDR_LEN);
+            return -1;
+        }
+
+        p.deserialize_byte(&mut self.icmp_type);
+        p.deserialize_byte(&mut self.code);
+        p.deserialize_2_bytes(&mut self.checksum);
+
+        let max_code = match icmp_types::max_code(self.icmp_type) {
+            Some(c) => c,
+            None => {
+                evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY,
+                                        event_desc::ICMP_UNKNOWN_TYPE);
+                return -1;
+            }
+        };
+
+        // router advertisement only defines code 0 and 16
+        if (self.code > max_code) ||
+           ((self.icmp_type == icmp_types::ROUTER_ADV) && (self.code != 0) && (self.code != 16)) {
+            evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY,
+                                    event_desc::ICMP_INVAL_CODE);
+            return -1;
+        }
+
+        if !p.remaining_len_in_bounds(icmp_hdr::ICMP_REST_OF_HDR_LEN) {
+            evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY,
+                                    event_desc::ICMP_SHORT_HDR_LEN);
+            return -1;
+        }
+
+        match self.icmp_type {
+            icmp_types::ECHO_REQ |
+            icmp_types::ECHO_REPLY |
+            icmp_types::TIMESTAMP_REQ |
+            icmp_types::TIMESTAMP_REPLY |
+            icmp_types::INFO_REQ |
+            icmp_types::INFO_REPLY |
+            icmp_types::ADDR_MASK_REQ |
+            icmp_types::ADDR_MASK_REPLY => self.echo.deserialize(p),
+            _ => p.deserialize_4_bytes(&mut self.rest_of_hdr),
+        }
+
+        if icmp_types::is_error(self.icmp_type) {
+            ret = self.nested_ip.deserialize(p, evt_mgr);
+            if ret < 0 {
+                return -1;
+            }
+        }
+
+        match self.icmp_type {
+            icmp_types::REDIRECT => {
+                evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY,
+                                        event_desc::ICMP_REDIRECT);
+                ret = -1;
+            }
+            icmp_types::TIMESTAMP_REQ => {
+                evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY,
+                                        event_desc::ICMP_TIMESTAMP_REQ);
+                ret = -1;
+            }
+            icmp_types::ADDR_MASK_REQ => {
+                evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY,
+                                        event_desc::ICMP_ADDR_MASK_REQ);
+                ret = -1;
+            }
+            _ => (),
+        }
+
+        if debug { self.print(); }
+
+        return ret;
+    }
+
+    // @brief - serialize ICMP header
+    //
+    // @param [in] self - ICMP header
+    // @param [out] p - packet
+    //
+    // @return 0 on success -1 on failure
+    pub fn serialize(&mut self, p : &mut packet) -> i32 {
+        p.serialize_byte(&mut self.icmp_type);
+        p.serialize_byte(&mut self.code);
+        p.serialize_2_bytes(&mut self.checksum);
+
+        match self.icmp_type {
+            icmp_types::ECHO_REQ |
+            icmp_types::ECHO_REPLY |
+            icmp_types::TIMESTAMP_REQ |
+            icmp_types::TIMESTAMP_REPLY |
+            icmp_types::INFO_REQ |
+            icmp_types::INFO_REPLY |
+            icmp_types::ADDR_MASK_REQ |
+            icmp_types::ADDR_MASK_REPLY => self.echo.serialize(p),
+            _ => p.serialize_4_bytes(&mut self.rest_of_hdr),
+        }
+
+        return 0;
+    }
+
+    pub fn print(&self) {
+        log::info!("icmp_hdr: ");
+        log::info!("\t type: {}", self.icmp_type);
+        log::info!("\t code: {}", self.code);
+        log::info!("\t checksum: 0x{:02X}", self.checksum);
+        match self.icmp_type {
+            icmp_types::ECHO_REQ |
+            icmp_types::ECHO_REPLY |
+            icmp_types::TIMESTAMP_REQ |
+            icmp_types::TIMESTAMP_REPLY |
+            icmp_types::INFO_REQ |
+            icmp_types::INFO_REPLY |
+            icmp_types::ADDR_MASK_REQ |
+            icmp_types::ADDR_MASK_REPLY => self.echo.print(),
+            _ => log::info!("\t rest_of_hdr: 0x{:08X}", self.rest_of_hdr),
+        }
+        if icmp_types::is_error(self.icmp_type) {
+            self.nested_ip.print();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // @brief - build an ICMP message, error messages carry a UDP header in the nested IP
+    fn icmp(icmp_type : u8, code : u8) -> packet {
+        let mut p = packet::new();
+        let nested : [u8; 28] = [0x45, 0, 0, 28, 0, 0, 0, 0, 64, 17, 0, 0,
+                                 10, 0, 0, 1, 10, 0, 0, 2,
+                                 0x13, 0x88, 0, 53, 0, 8, 0, 0];
+
+        p.buf[0] = icmp_type;
+        p.buf[1] = code;
+        p.buf[4..8].copy_from_slice(&[0x12, 0x34, 0, 1]);
+        p.pkt_len = 8;
+        if icmp_types::is_error(icmp_type) {
+            p.buf[8..36].copy_from_slice(&nested);
+            p.pkt_len = 36;
+        }
+        return p;
+    }
+
+    fn check(mut p : packet, icmp_h : &mut icmp_hdr) -> u32 {
+        let mut evt_mgr = event_mgr::new();
+        let ret = icmp_h.deserialize(&mut p, &mut evt_mgr, false);
+        let counts = evt_mgr.get_evt_desc_counts();
+
+        assert!(counts.len() <= 1);
+        match counts.keys().next() {
+            Some(desc) => {
+                assert_eq!(ret, -1);
+                return *desc;
+            }
+            None => {
+                assert_eq!(ret, 0);
+                return 0;
+            }
+        }
+    }
+
+    #[test]
+    fn echo() {
+        let mut icmp_h = icmp_hdr::new();
+
+        assert_eq!(check(icmp(icmp_types::ECHO_REQ, 0), &mut icmp_h), 0);
+        assert_eq!(icmp_h.echo.id, 0x1234);
+        assert_eq!(icmp_h.echo.seq_no, 1);
+    }
+
+    #[test]
+    fn nested_ip() {
+        let mut icmp_h = icmp_hdr::new();
+
+        assert_eq!(check(icmp(icmp_types::DEST_UNREACHABLE, 3), &mut icmp_h), 0);
+        assert_eq!(icmp_h.nested_ip.protocol, 17);
+        assert_eq!(icmp_h.nested_ip.dst_ipaddr, 0x0A000002);
+        assert_eq!(icmp_h.nested_ip.src_port, 5000);
+        assert_eq!(icmp_h.nested_ip.dst_port, 53);
+
+        let mut p = icmp(icmp_types::TIME_EXCEEDED, 0);
+        p.buf[8] = 0x65;
+        assert_eq!(check(p, &mut icmp_h), event_desc::ICMP_NESTED_IP_INVALID);
+
+        let mut p = icmp(icmp_types::TIME_EXCEEDED, 0);
+        p.pkt_len = 20;
+        assert_eq!(check(p, &mut icmp_h), event_desc::ICMP_NESTED_IP_INVALID);
+    }
+
+    #[test]
+    fn anomalies() {
+        let mut icmp_h = icmp_hdr::new();
+        let mut p = icmp(icmp_types::ECHO_REQ, 0);
+
+        p.pkt_len = 3;
+        assert_eq!(check(p, &mut icmp_h), event_desc::ICMP_SHORT_HDR_LEN);
+        assert_eq!(check(icmp(42, 0), &mut icmp_h), event_desc::ICMP_UNKNOWN_TYPE);
+        assert_eq!(check(icmp(icmp_types::ECHO_REQ, 1), &mut icmp_h), event_desc::ICMP_INVAL_CODE);
+        assert_eq!(check(icmp(icmp_types::ROUTER_ADV, 16), &mut icmp_h), 0);
+        assert_eq!(check(icmp(icmp_types::ROUTER_ADV, 8), &mut icmp_h), event_desc::ICMP_INVAL_CODE);
+        assert_eq!(check(icmp(icmp_types::REDIRECT, 1), &mut icmp_h), event_desc::ICMP_REDIRECT);
+        assert_eq!(check(icmp(icmp_types::TIMESTAMP_REQ, 0), &mut icmp_h), event_desc::ICMP_TIMESTAMP_REQ);
+        assert_eq!(check(icmp(icmp_types::ADDR_MASK_REQ, 0), &mut icmp_h), event_desc::ICMP_ADDR_MASK_REQ);
+    }
+
+    #[test]
+    fn ping_of_death() {
+        let mut evt_mgr = event_mgr::new();
+
+        assert_eq!(icmp_hdr::check_frag_len(65512, 23, &mut evt_mgr), 0);
+        assert!(evt_mgr.get_evt_desc_counts().is_empty());
+        assert_eq!(icmp_hdr::check_frag_len(65512, 24, &mut evt_mgr), -1);
+        assert_eq!(evt_mgr.get_evt_desc_counts().get(&event_desc::ICMP_PING_OF_DEATH), Some(&1));
+    }
+}
@@ -7,7 +7,7 @@ use crate::{
         event_mgr::event_mgr,
         event_type::event_type
     },
-    lib::protocols::packet::packet::packet
+    lib::protocols::{l3::ipv6::ipv6_hdr, packet::packet::packet}
 };
 
 #[non_exhaustive]
@@ -30,6 +30,45 @@ impl icmp6_types {
     pub const REDIR_MSG : u8 = 137;
     pub const ROUTER_RENUMBERING : u8 = 138;
     pub const ICMP_NODE_INFO_QUERY : u8 = 139;
+    pub const ICMP_NODE_INFO_RESPONSE : u8 = 140;
+    pub const MLD2_LISTENER_REPORT : u8 = 143;
+
+    // @brief - returns the highest code defined for the type
+    //
+    // @param [in] icmp6_type - ICMPv6 type
+    //
+    // @return highest code, None if the type is not known
+    pub fn max_code(icmp6_type : u8) -> Option<u8> {
+        match icmp6_type {
+            icmp6_types::DEST_UNREACHABLE => Some(icmp6_dest_unreach_codes::ERR_IN_SRC_ROUTING_HDR),
+            icmp6_types::TIME_EXCEEDED => Some(icmp6_time_exceeded_codes::FRAG_REASSEMBLY_TIME_EXCEEDED),
+            icmp6_types::PARAM_PROBLEM => Some(10),
+            // 0 command, 1 result, 255 sequence number reset
+            icmp6_types::ROUTER_RENUMBERING => Some(255),
+            icmp6_types::ICMP_NODE_INFO_QUERY => Some(2),
+            // 0 successful, 1 refused, 2 unknown query type
+            icmp6_types::ICMP_NODE_INFO_RESPONSE => Some(2),
+            icmp6_types::PKT_TOO_BIG |
+            icmp6_types::ECHO_REQ |
+            icmp6_types::ECHO_REPLY |
+            icmp6_types::MCAST_LISTENER_DISC |
+            icmp6_types::MCAST_LISTENER_REPORT |
+            icmp6_types::MCAST_LISTENER_DONE |
+            icmp6_types::MLD2_LISTENER_REPORT |
+            icmp6_types::ROUTER_SOL |
+            icmp6_types::ROUTER_ADV |
+            icmp6_types::NEIGHBOR_SOL |
+            icmp6_types::NEIGHBOR_ADV |
+            icmp6_types::REDIR_MSG => Some(0),
+            _ => None,
+        }
+    }
+
+    // @brief - check if the type is a neighbor discovery message
+    pub fn is_ndp(icmp6_type : u8) -> bool {
+        return (icmp6_type >= icmp6_types::ROUTER_SOL) &&
+               (icmp6_type <= icmp6_types::REDIR_MSG);
+    }
 }
 
 #[non_exhaustive]
@@ -82,7 +121,7 @@ impl icmp6_dest_unreachable {
         d
     }
 
-    pub fn deserialize(&mut self, p : &mut packet, evt_mgr : &mut event_mgr, debug : bool) -> i32 {
+    pub fn deserialize(&mut self, p : &mut packet, evt_mgr : &mut event_mgr) -> i32 {
         if p.remaining_len_in_bounds(icmp6_dest_unreachable::DEST_UNREACHABLE_LEN) == false {
             evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY,
                                     event_desc::ICMP6_SHORT_DEST_UNREACH_HDR_LEN);
@@ -93,8 +132,6 @@ impl icmp6_dest_unreachable {
         p.deserialize_byte(&mut self.length);
         p.deserialize_2_bytes(&mut self.next_hop_mtu);
 
-        if debug { self.print(); }
-
         return 0;
     }
 
@@ -106,31 +143,575 @@ impl icmp6_dest_unreachable {
     }
 }
 
+// @brief - defines echo request and reply
+pub struct icmp6_echo {
+    pub id              : u16,
+    pub seq_no          : u16
+}
+
+impl icmp6_echo {
+    #[inline(always)]
+    pub fn new() -> icmp6_echo {
+        let e = icmp6_echo {
+            id              : 0,
+            seq_no          : 0
+        };
+        e
+    }
+
+    pub fn deserialize(&mut self, p : &mut packet) {
+        p.deserialize_2_bytes(&mut self.id);
+        p.deserialize_2_bytes(&mut self.seq_no);
+    }
+
+    pub fn print(&self) {
+        log::info!("\t echo: ");
+        log::info!("\t\t id: {}", self.id);
+        log::info!("\t\t seq_no: {}", self.seq_no);
+    }
+}
+
+// @brief - defines multicast listener query, report and done
+pub struct icmp6_mld {
+    pub max_resp_delay  : u16,
+    pub mcast_addr      : [u8; 16]
+}
+
+impl icmp6_mld {
+    pub const MLD_LEN : u32 = 20;
+
+    #[inline(always)]
+    pub fn new() -> icmp6_mld {
+        let m = icmp6_mld {
+            max_resp_delay  : 0,
+            mcast_addr      : [0; 16]
+        };
+        m
+    }
+
+    pub fn deserialize(&mut self, p : &mut packet) {
+        p.deserialize_2_bytes(&mut self.max_resp_delay);
+        p.off += 2;
+        p.deserialize_ip6addr(&mut self.mcast_addr);
+    }
+
+    pub fn print(&self) {
+        log::info!("\t mld: ");
+        log::info!("\t\t max_resp_delay: {}", self.max_resp_delay);
+        packet::print_ipv6("\t\t mcast_addr", &self.mcast_addr);
+    }
+}
+
+// @brief - defines MLDv2 listener report (RFC 3810 5.2)
+pub struct icmp6_mld2_report {
+    pub n_records       : u16,
+    pub mcast_addrs     : Vec<[u8; 16]>
+}
+
+impl icmp6_mld2_report {
+    pub const MLD2_REPORT_HDR_LEN : u32 = 4;
+    // record type, aux data len, number of sources and multicast address
+    pub const MLD2_RECORD_HDR_LEN : usize = 20;
+
+    #[inline(always)]
+    pub fn new() -> icmp6_mld2_report {
+        let m = icmp6_mld2_report {
+            n_records       : 0,
+            mcast_addrs     : Vec::new()
+        };
+        m
+    }
+
+    // @brief - deserialize the multicast address records
+    //
+    // @param [inout] self - MLDv2 report
+    // @param [inout] p - packet
+    // @param [out] evt_mgr - event mgr
+    // @param [in] msg_end - offset where the ICMPv6 message ends
+    //
+    // @return 0 on success -1 if a record runs past the message
+    pub fn deserialize(&mut self, p : &mut packet, evt_mgr : &mut event_mgr, msg_end : usize) -> i32 {
+        self.mcast_addrs.clear();
+
+        if p.off + icmp6_mld2_report::MLD2_REPORT_HDR_LEN as usize > msg_end {
+            evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY,
+                                    event_desc::ICMP6_SHORT_MSG_LEN);
+            return -1;
+        }
+
+        p.off += 2;
+        p.deserialize_2_bytes(&mut self.n_records);
+
+        for _ in 0..self.n_records {
+            let mut aux_len : u8 = 0;
+            let mut n_srcs : u16 = 0;
+            let mut mcast_addr : [u8; 16] = [0; 16];
+
+            if p.off + icmp6_mld2_report::MLD2_RECORD_HDR_LEN > msg_end {
+                evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY,
+                                        event_desc::ICMP6_SHORT_MSG_LEN);
+                return -1;
+            }
+
+            // record type is not checked, unknown types are ignored by the routers
+            p.off += 1;
+            p.deserialize_byte(&mut aux_len);
+            p.deserialize_2_bytes(&mut n_srcs);
+            p.deserialize_ip6addr(&mut mcast_addr);
+
+            // sources and the aux data in units of 32 bit words
+            let rec_len = n_srcs as usize * 16 + aux_len as usize * 4;
+            if p.off + rec_len > msg_end {
+                evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY,
+                                        event_desc::ICMP6_SHORT_MSG_LEN);
+                return -1;
+            }
+
+            p.off += rec_len;
+            self.mcast_addrs.push(mcast_addr);
+        }
+
+        return 0;
+    }
+
+    pub fn print(&self) {
+        log::info!("\t mld2_report: ");
+        log::info!("\t\t n_records: {}", self.n_records);
+        for addr in &self.mcast_addrs {
+            packet::print_ipv6("\t\t mcast_addr", addr);
+        }
+    }
+}
+
+// @brief - defines router advertisement
+pub struct icmp6_router_adv {
+    pub cur_hop_limit   : u8,
+    pub managed         : u8, // 1 bit
+    pub other           : u8, // 1 bit
+    pub router_lifetime : u16,
+    pub reachable_time  : u32,
+    pub retrans_timer   : u32
+}
+
+impl icmp6_router_adv {
+    pub const ROUTER_ADV_LEN : u32 = 12;
+
+    #[inline(always)]
+    pub fn new() -> icmp6_router_adv {
+        let r = icmp6_router_adv {
+            cur_hop_limit   : 0,
+            managed         : 0,
+            other           : 0,
+            router_lifetime : 0,
+            reachable_time  : 0,
+            retrans_timer   : 0
+        };
+        r
+    }
+
+    pub fn deserialize(&mut self, p : &mut packet) {
+        p.deserialize_byte(&mut self.cur_hop_limit);
+        self.managed = if (p.buf[p.off] & 0x80) == 0x80 { 1 } else { 0 };
+        self.other = if (p.buf[p.off] & 0x40) == 0x40 { 1 } else { 0 };
+        p.off += 1;
+        p.deserialize_2_bytes(&mut self.router_lifetime);
+        p.deserialize_4_bytes(&mut self.reachable_time);
+        p.deserialize_4_bytes(&mut self.retrans_timer);
+    }
+
+    pub fn print(&self) {
+        log::info!("\t router_adv: ");
+        log::info!("\t\t cur_hop_limit: {}", self.cur_hop_limit);
+        log::info!("\t\t managed: {}", self.managed);
+        log::info!("\t\t other: {}", self.other);
+        log::info!("\t\t router_lifetime: {}", self.router_lifetime);
+        log::info!("\t\t reachable_time: {}", self.reachable_time);
+        log::info!("\t\t retrans_timer: {}", self.retrans_timer);
+    }
+}
+
+// @brief - defines neighbor solicitation, neighbor advertisement and
+//          redirect target and destination
+pub struct icmp6_neighbor {
+    pub router          : u8, // 1 bit, advertisement only
+    pub solicited       : u8, // 1 bit, advertisement only
+    pub overide         : u8, // 1 bit, advertisement only
+    pub target_addr     : [u8; 16],
+    pub dest_addr       : [u8; 16] // redirect only
+}
+
+impl icmp6_neighbor {
+    pub const NEIGHBOR_LEN : u32 = 20;
+    pub const REDIRECT_LEN : u32 = 36;
+
+    #[inline(always)]
+    pub fn new() -> icmp6_neighbor {
+        let n = icmp6_neighbor {
+            router          : 0,
+            solicited       : 0,
+            overide         : 0,
+            target_addr     : [0; 16],
+            dest_addr       : [0; 16]
+        };
+        n
+    }
+
+    pub fn deserialize(&mut self, p : &mut packet, icmp6_type : u8) {
+        self.router = if (p.buf[p.off] & 0x80) == 0x80 { 1 } else { 0 };
+        self.solicited = if (p.buf[p.off] & 0x40) == 0x40 { 1 } else { 0 };
+        self.overide = if (p.buf[p.off] & 0x20) == 0x20 { 1 } else { 0 };
+        p.off += 4;
+        p.deserialize_ip6addr(&mut self.target_addr);
+        if icmp6_type == icmp6_types::REDIR_MSG {
+            p.deserialize_ip6addr(&mut self.dest_addr);
+        }
+    }
+
+    pub fn print(&self, icmp6_type : u8) {
+        log::info!("\t neighbor: ");
+        if icmp6_type == icmp6_types::NEIGHBOR_ADV {
+            log::info!("\t\t router: {}", self.router);
+            log::info!("\t\t solicited: {}", self.solicited);
+            log::info!("\t\t overide: {}", self.overide);
+        }
+        packet::print_ipv6("\t\t target_addr", &self.target_addr);
+        if icmp6_type == icmp6_types::REDIR_MSG {
+            packet::print_ipv6("\t\t dest_addr", &self.dest_addr);
+        }
+    }
+}
+
+#[non_exhaustive]
+pub struct icmp6_ndp_opt_types;
+
+impl icmp6_ndp_opt_types {
+    pub const SRC_LINK_LAYER_ADDR : u8 = 1;
+    pub const TARGET_LINK_LAYER_ADDR : u8 = 2;
+    pub const PREFIX_INFO : u8 = 3;
+    pub const REDIRECTED_HDR : u8 = 4;
+    pub const MTU : u8 = 5;
+    pub const RDNSS : u8 = 25;
+}
+
+// @brief - defines prefix information option
+#[derive(Clone)]
+pub struct icmp6_prefix_info {
+    pub prefix_len      : u8,
+    pub on_link         : u8, // 1 bit
+    pub autonomous      : u8, // 1 bit
+    pub valid_lifetime  : u32,
+    pub pref_lifetime   : u32,
+    pub prefix          : [u8; 16]
+}
+
+impl icmp6_prefix_info {
+    #[inline(always)]
+    pub fn new() -> icmp6_prefix_info {
+        let pi = icmp6_prefix_info {
+            prefix_len      : 0,
+            on_link         : 0,
+            autonomous      : 0,
+            valid_lifetime  : 0,
+            pref_lifetime   : 0,
+            prefix          : [0; 16]
+        };
+        pi
+    }
+}
+
+// @brief - defines the neighbor discovery options
+pub struct icmp6_ndp_opts {
+    pub has_src_lla     : bool,
+    pub src_lla         : [u8; 6],
+    pub has_target_lla  : bool,
+    pub target_lla      : [u8; 6],
+    pub prefixes        : Vec<icmp6_prefix_info>,
+    pub has_mtu         : bool,
+    pub mtu             : u32,
+    pub rdnss_lifetime  : u32,
+    pub rdnss           : Vec<[u8; 16]>
+}
+
+impl icmp6_ndp_opts {
+    pub const OPT_UNIT_LEN : u32 = 8;
+    pub const PREFIX_INFO_LEN : u8 = 4;
+    pub const MTU_LEN : u8 = 1;
+    pub const RDNSS_MIN_LEN : u8 = 3;
+
+    #[inline(always)]
+    pub fn new() -> icmp6_ndp_opts {
+        let o = icmp6_ndp_opts {
+            has_src_lla     : false,
+            src_lla         : [0; 6],
+            has_target_lla  : false,
+            target_lla      : [0; 6],
+            prefixes        : Vec::new(),
+            has_mtu         : false,
+            mtu             : 0,
+            rdnss_lifetime  : 0,
+            rdnss           : Vec::new()
+        };
+        o
+    }
+
+    // @brief - deserialize neighbor discovery options
+    //
+    // @param [inout] self - options
+    // @param [inout] p - packet
+    // @param [out] evt_mgr - event mgr
+    // @param [in] opts_end - offset where the ICMPv6 message ends
+    //
+    // @return 0 on success -1 on failure
+    //
+    // @details - unknown options are skipped as required by RFC 4861
+    pub fn deserialize(&mut self, p : &mut packet, evt_mgr : &mut event_mgr, opts_end : usize) -> i32 {
+        *self = icmp6_ndp_opts::new();
+
+        while p.off + 2 <= opts_end {
+            let opt_type = p.buf[p.off];
+            let opt_len = p.buf[p.off + 1];
+            let opt_start = p.off;
+            let opt_bytes = opt_len as usize * icmp6_ndp_opts::OPT_UNIT_LEN as usize;
+
+            if (opt_len == 0) || (opt_start + opt_bytes > opts_end) {
+                evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY,
+                                        event_desc::ICMP6_NDP_OPT_INVAL_LEN);
+                return -1;
+            }
+            p.off += 2;
+
+            match opt_type {
+                icmp6_ndp_opt_types::SRC_LINK_LAYER_ADDR => {
+                    p.deserialize_mac(&mut self.src_lla);
+                    self.has_src_lla = true;
+                }
+                icmp6_ndp_opt_types::TARGET_LINK_LAYER_ADDR => {
+                    p.deserialize_mac(&mut self.target_lla);
+                    self.has_target_lla = true;
+                }
+                icmp6_ndp_opt_types::PREFIX_INFO => {
+                    let mut pi = icmp6_prefix_info::new();
+
+                    if opt_len != icmp6_ndp_opts::PREFIX_INFO_LEN {
+                        evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY,
+                                                event_desc::ICMP6_NDP_OPT_INVAL_LEN);
+                        return -1;
+                    }
+
+                    p.deserialize_byte(&mut pi.prefix_len);
+                    pi.on_link = if (p.buf[p.off] & 0x80) == 0x80 { 1 } else { 0 };
+                    pi.autonomous = if (p.buf[p.off] & 0x40) == 0x40 { 1 } else { 0 };
+                    p.off += 1;
+                    p.deserialize_4_bytes(&mut pi.valid_lifetime);
+                    p.deserialize_4_bytes(&mut pi.pref_lifetime);
+                    p.off += 4;
+                    p.deserialize_ip6addr(&mut pi.prefix);
+
+                    if (pi.prefix_len > 128) || (pi.pref_lifetime > pi.valid_lifetime) {
+                        evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY,
+                                                event_desc::ICMP6_NDP_PREFIX_INVAL);
+                        return -1;
+                    }
+
+                    self.prefixes.push(pi);
+                }
+                icmp6_ndp_opt_types::MTU => {
+                    if opt_len != icmp6_ndp_opts::MTU_LEN {
+                        evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY,
+                                                event_desc::ICMP6_NDP_OPT_INVAL_LEN);
+                        return -1;
+                    }
+
+                    p.off += 2;
+                    p.deserialize_4_bytes(&mut self.mtu);
+                    self.has_mtu = true;
+                }
+                icmp6_ndp_opt_types::RDNSS => {
+                    // 8 byte header followed by 16 byte addresses
+                    if (opt_len < icmp6_ndp_opts::RDNSS_MIN_LEN) || (opt_len % 2 == 0) {
+                        evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY,
+                                                event_desc::ICMP6_NDP_OPT_INVAL_LEN);
+                        return -1;
+                    }
+
+                    p.off += 2;
+                    p.deserialize_4_bytes(&mut self.rdnss_lifetime);
+                    for _ in 0..((opt_len - 1) / 2) {
+                        let mut addr : [u8; 16] = [0; 16];
+
+                        p.deserialize_ip6addr(&mut addr);
+                        self.rdnss.push(addr);
+                    }
+                }
+                _ => (),
+            }
+
+            p.off = opt_start + opt_bytes;
+        }
+
+        return 0;
+    }
+
+    pub fn print(&self) {
+        log::info!("\t ndp_opts: ");
+        if self.has_src_lla {
+            packet::print_macaddr("\t\t src_lla", &self.src_lla);
+        }
+        if self.has_target_lla {
+            packet::print_macaddr("\t\t target_lla", &self.target_lla);
+        }
+        for pi in &self.prefixes {
+            log::info!("\t\t prefix_info: ");
+            log::info!("\t\t\t prefix_len: {}", pi.prefix_len);
+            log::info!("\t\t\t on_link: {}", pi.on_link);
+            log::info!("\t\t\t autonomous: {}", pi.autonomous);
+            log::info!("\t\t\t valid_lifetime: {}", pi.valid_lifetime);
+            log::info!("\t\t\t pref_lifetime: {}", pi.pref_lifetime);
+            packet::print_ipv6("\t\t\t prefix", &pi.prefix);
+        }
+        if self.has_mtu {
+            log::info!("\t\t mtu: {}", self.mtu);
+        }
+        if self.rdnss.len() > 0 {
+            log::info!("\t\t rdnss_lifetime: {}", self.rdnss_lifetime);
+            for addr in &self.rdnss {
+                packet::print_ipv6("\t\t rdnss", addr);
+            }
+        }
+    }
+}
+
 pub struct icmp6_hdr {
-    icmp6_type          : u8,
-    code                : u8,
-    checksum            : u16,
-    dest_unreach        : icmp6_dest_unreachable
+    pub icmp6_type      : u8,
+    pub code            : u8,
+    pub checksum        : u16,
+    // MTU of packet too big, pointer of parameter problem
+    pub rest_of_hdr     : u32,
+    pub dest_unreach    : icmp6_dest_unreachable,
+    pub echo            : icmp6_echo,
+    pub mld             : icmp6_mld,
+    pub mld2_report     : icmp6_mld2_report,
+    pub router_adv      : icmp6_router_adv,
+    pub neighbor        : icmp6_neighbor,
+    pub ndp_opts        : icmp6_ndp_opts
 }
 
 impl icmp6_hdr {
     pub const ICMP6_MIN_HDR_LEN : u32 = 4;
+    pub const ICMP6_REST_OF_HDR_LEN : u32 = 4;
+    pub const NDP_HOP_LIMIT : u8 = 255;
+    pub const ROUTER_RENUMBERING_LEN : u32 = 12;
+    pub const NODE_INFO_LEN : u32 = 12;
 
     #[inline(always)]
     pub fn new() -> icmp6_hdr {
         let icmp6_h = icmp6_hdr {
-            icmp6_type          : 0,
-            code                : 0,
-            checksum            : 0,
-            dest_unreach        : icmp6_dest_unreachable::new()
+            icmp6_type      : 0,
+            code            : 0,
+            checksum        : 0,
+            rest_of_hdr     : 0,
+            dest_unreach    : icmp6_dest_unreachable::new(),
+            echo            : icmp6_echo::new(),
+            mld             : icmp6_mld::new(),
+            mld2_report     : icmp6_mld2_report::new(),
+            router_adv      : icmp6_router_adv::new(),
+            neighbor        : icmp6_neighbor::new(),
+            ndp_opts        : icmp6_ndp_opts::new()
         };
         icmp6_h
     }
 
-    pub fn deserialize(&mut self, p : &mut packet, evt_mgr : &mut event_mgr, debug : bool) -> i32 {
-        let ret : i32;
+    // @brief - check that the message body fits in the packet
+    fn check_body_len(p : &packet, evt_mgr : &mut event_mgr, body_len : u32) -> i32 {
+        if !p.remaining_len_in_bounds(body_len) {
+            evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY,
+                                    event_desc::ICMP6_SHORT_MSG_LEN);
+            return -1;
+        }
 
-        if p.remaining_len_in_bounds(icmp6_hdr::ICMP6_MIN_HDR_LEN) {
+        return 0;
+    }
+
+    // @brief - check if an ipv6 address is link-local (fe80::/10)
+    fn is_link_local(addr : &[u8; 16]) -> bool {
+        return (addr[0] == 0xFE) && ((addr[1] & 0xC0) == 0x80);
+    }
+
+    // @brief - deserialize neighbor discovery message
+    //
+    // @param [inout] self - ICMPv6 header
+    // @param [inout] p - packet
+    // @param [out] evt_mgr - event mgr
+    // @param [in] ip6_h - ipv6 header carrying the message
+    // @param [in] msg_end - offset where the ICMPv6 message ends
+    //
+    // @return 0 on success -1 on failure
+    fn deserialize_ndp(&mut self, p : &mut packet, evt_mgr : &mut event_mgr,
+                       ip6_h : &ipv6_hdr, msg_end : usize) -> i32 {
+        let body_len : u32;
+
+        // RFC 4861 - a router or neighbor would never forward these, so a
+        // hop limit other than 255 means the message came from off-link
+        if ip6_h.hop_limit() != icmp6_hdr::NDP_HOP_LIMIT {
+            evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY,
+                                    event_desc::ICMP6_NDP_INVAL_HOP_LIMIT);
+            return -1;
+        }
+
+        match self.icmp6_type {
+            icmp6_types::ROUTER_SOL => body_len = icmp6_hdr::ICMP6_REST_OF_HDR_LEN,
+            icmp6_types::ROUTER_ADV => body_len = icmp6_router_adv::ROUTER_ADV_LEN,
+            icmp6_types::REDIR_MSG => body_len = icmp6_neighbor::REDIRECT_LEN,
+            _ => body_len = icmp6_neighbor::NEIGHBOR_LEN,
+        }
+
+        if icmp6_hdr::check_body_len(p, evt_mgr, body_len) < 0 {
+            return -1;
+        }
+
+        match self.icmp6_type {
+            icmp6_types::ROUTER_SOL => p.off += 4,
+            icmp6_types::ROUTER_ADV => {
+                // routers send advertisements from their link-local address
+                if !icmp6_hdr::is_link_local(ip6_h.src_addr()) {
+                    evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY,
+                                            event_desc::ICMP6_RA_SRC_NOT_LINK_LOCAL);
+                    return -1;
+                }
+                self.router_adv.deserialize(p);
+            }
+            _ => {
+                self.neighbor.deserialize(p, self.icmp6_type);
+
+                // target of solicitation and advertisement must be unicast
+                if (self.icmp6_type != icmp6_types::REDIR_MSG) &&
+                   (self.neighbor.target_addr[0] == 0xFF) {
+                    evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY,
+                                            event_desc::ICMP6_NDP_TARGET_MCAST);
+                    return -1;
+                }
+            }
+        }
+
+        return self.ndp_opts.deserialize(p, evt_mgr, msg_end);
+    }
+
+    // @brief - deserialize ICMPv6 message
+    //
+    // @param [inout] self - ICMPv6 header
+    // @param [inout] p - packet
+    // @param [out] evt_mgr - event mgr
+    // @param [in] ip6_h - ipv6 header carrying the message
+    // @param [in] msg_len - length of the ICMPv6 message
+    // @param [in] debug - debug frame
+    //
+    // @return 0 on success -1 on failure
+    pub fn deserialize(&mut self, p : &mut packet, evt_mgr : &mut event_mgr,
+                       ip6_h : &ipv6_hdr, msg_len : u32, debug : bool) -> i32 {
+        let mut ret : i32 = 0;
+        let msg_end = std::cmp::min(p.pkt_len, p.off + msg_len as usize);
+
+        if !p.remaining_len_in_bounds(icmp6_hdr::ICMP6_MIN_HDR_LEN) {
             evt_mgr.insert_evt_info(
                                     event_type::EVENT_TYPE_DENY,
                                     event_desc::ICMP6_SHORT_HDR_LEN);
@@ -141,9 +722,77 @@ impl icmp6_hdr {
         p.deserialize_byte(&mut self.code);
         p.deserialize_2_bytes(&mut self.checksum);
 
+        let max_code = match icmp6_types::max_code(self.icmp6_type) {
+            Some(c) => c,
+            None => {
+                evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY,
+                                        event_desc::ICMP6_UNKNOWN_TYPE);
+                return -1;
+            }
+        };
+
+        // router renumbering only defines code 0, 1 and 255
+        if (self.code > max_code) ||
+           ((self.icmp6_type == icmp6_types::ROUTER_RENUMBERING) &&
+            (self.code != 0) && (self.code != 1) && (self.code != 255)) {
+            evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY,
+                                    event_desc::ICMP6_INVAL_CODE);
+            return -1;
+        }
+
+        // RFC 4862 and RFC 3810 - duplicate address detection and the
+        // listener reports sent with it are the only messages of a host
+        // without an address
+        if ip6_h.src_unspecified() &&
+           (self.icmp6_type != icmp6_types::NEIGHBOR_SOL) &&
+           (self.icmp6_type != icmp6_types::MCAST_LISTENER_REPORT) &&
+           (self.icmp6_type != icmp6_types::MLD2_LISTENER_REPORT) {
+            evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY,
+                                    event_desc::IPV6_SRC_ADDR_INVALID);
+            return -1;
+        }
+
         match self.icmp6_type {
-            icmp6_types::DEST_UNREACHABLE => ret = self.dest_unreach.deserialize(p, evt_mgr, debug),
-            _ => ret = -1,
+            icmp6_types::DEST_UNREACHABLE => ret = self.dest_unreach.deserialize(p, evt_mgr),
+            icmp6_types::PKT_TOO_BIG |
+            icmp6_types::TIME_EXCEEDED |
+            icmp6_types::PARAM_PROBLEM => {
+                ret = icmp6_hdr::check_body_len(p, evt_mgr, icmp6_hdr::ICMP6_REST_OF_HDR_LEN);
+                if ret == 0 {
+                    p.deserialize_4_bytes(&mut self.rest_of_hdr);
+                }
+            }
+            icmp6_types::ECHO_REQ |
+            icmp6_types::ECHO_REPLY => {
+                ret = icmp6_hdr::check_body_len(p, evt_mgr, icmp6_hdr::ICMP6_REST_OF_HDR_LEN);
+                if ret == 0 {
+                    self.echo.deserialize(p);
+                }
+            }
+            icmp6_types::MCAST_LISTENER_DISC |
+            icmp6_types::MCAST_LISTENER_REPORT |
+            icmp6_types::MCAST_LISTENER_DONE => {
+                ret = icmp6_hdr::check_body_len(p, evt_mgr, icmp6_mld::MLD_LEN);
+                if ret == 0 {
+                    self.mld.deserialize(p);
+                }
+            }
+            icmp6_types::MLD2_LISTENER_REPORT => {
+                ret = self.mld2_report.deserialize(p, evt_mgr, msg_end);
+            }
+            icmp6_types::ROUTER_SOL |
+            icmp6_types::ROUTER_ADV |
+            icmp6_types::NEIGHBOR_SOL |
+            icmp6_types::NEIGHBOR_ADV |
+            icmp6_types::REDIR_MSG => ret = self.deserialize_ndp(p, evt_mgr, ip6_h, msg_end),
+            icmp6_types::ROUTER_RENUMBERING => {
+                ret = icmp6_hdr::check_body_len(p, evt_mgr, icmp6_hdr::ROUTER_RENUMBERING_LEN);
+            }
+            icmp6_types::ICMP_NODE_INFO_QUERY |
+            icmp6_types::ICMP_NODE_INFO_RESPONSE => {
+                ret = icmp6_hdr::check_body_len(p, evt_mgr, icmp6_hdr::NODE_INFO_LEN);
+            }
+            _ => (),
         }
 
         if ret < 0 {
@@ -157,9 +806,226 @@ impl icmp6_hdr {
 
     pub fn print(&self) {
         log::info!("icmp6_hdr: ");
+        log::info!("\t type: {}", self.icmp6_type);
+        log::info!("\t code: {}", self.code);
+        log::info!("\t checksum: 0x{:02X}", self.checksum);
         match self.icmp6_type {
             icmp6_types::DEST_UNREACHABLE => self.dest_unreach.print(),
+            icmp6_types::PKT_TOO_BIG |
+            icmp6_types::TIME_EXCEEDED |
+            icmp6_types::PARAM_PROBLEM => log::info!("\t rest_of_hdr: 0x{:08X}", self.rest_of_hdr),
+            icmp6_types::ECHO_REQ |
+            icmp6_types::ECHO_REPLY => self.echo.print(),
+            icmp6_types::MCAST_LISTENER_DISC |
+            icmp6_types::MCAST_LISTENER_REPORT |
+            icmp6_types::MCAST_LISTENER_DONE => self.mld.print(),
+            icmp6_types::MLD2_LISTENER_REPORT => self.mld2_report.print(),
+            icmp6_types::ROUTER_ADV => {
+                self.router_adv.print();
+                self.ndp_opts.print();
+            }
+            icmp6_types::ROUTER_SOL => self.ndp_opts.print(),
+            icmp6_types::NEIGHBOR_SOL |
+            icmp6_types::NEIGHBOR_ADV |
+            icmp6_types::REDIR_MSG => {
+                self.neighbor.print(self.icmp6_type);
+                self.ndp_opts.print();
+            }
             _ => (),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LINK_LOCAL : [u8; 16] = [0xFE, 0x80, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1];
+    const GLOBAL : [u8; 16] = [0x20, 0x01, 0x0D, 0xB8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1];
+    const UNSPECIFIED : [u8; 16] = [0; 16];
+    const ALL_NODES : [u8; 16] = [0xFF, 0x02, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1];
+
+    fn ip6(src : [u8; 16], hop_limit : u8) -> ipv6_hdr {
+        let mut p = packet::with_buf_len(ipv6_hdr::IPV6_MIN_HDR_LEN as usize);
+        let mut evt_mgr = event_mgr::new();
+        let mut ip6_h = ipv6_hdr::new();
+
+        p.buf[0] = 0x60;
+        p.buf[6] = 58;
+        p.buf[7] = hop_limit;
+        p.buf[8..24].copy_from_slice(&src);
+        p.buf[24..40].copy_from_slice(&ALL_NODES);
+        p.pkt_len = p.buf.len();
+        assert_eq!(ip6_h.deserialize(&mut p, &mut evt_mgr, false), 0);
+        return ip6_h;
+    }
+
+    // @brief - deserialize the message and return the single event raised, 0 if none
+    fn check(icmp6_h : &mut icmp6_hdr, ip6_h : &ipv6_hdr, msg : &[u8]) -> u32 {
+        let mut p = packet::with_buf_len(msg.len());
+        let mut evt_mgr = event_mgr::new();
+
+        p.buf.copy_from_slice(msg);
+        p.pkt_len = msg.len();
+        let ret = icmp6_h.deserialize(&mut p, &mut evt_mgr, ip6_h, msg.len() as u32, false);
+        let counts = evt_mgr.get_evt_desc_counts();
+
+        assert!(counts.len() <= 1);
+        match counts.keys().next() {
+            Some(desc) => {
+                assert_eq!(ret, -1);
+                return *desc;
+            }
+            None => {
+                assert_eq!(ret, 0);
+                return 0;
+            }
+        }
+    }
+
+    fn neighbor(icmp6_type : u8, flags : u8, target : [u8; 16], opts : &[u8]) -> Vec<u8> {
+        let mut msg = vec![icmp6_type, 0, 0, 0, flags, 0, 0, 0];
+
+        msg.extend_from_slice(&target);
+        msg.extend_from_slice(opts);
+        return msg;
+    }
+
+    fn router_adv(opts : &[u8]) -> Vec<u8> {
+        let mut msg = vec![icmp6_types::ROUTER_ADV, 0, 0, 0, 64, 0, 0x07, 0x08,
+                           0, 0, 0, 0, 0, 0, 0, 0];
+
+        msg.extend_from_slice(opts);
+        return msg;
+    }
+
+    fn prefix_info(valid : u32, pref : u32) -> Vec<u8> {
+        let mut opt = vec![icmp6_ndp_opt_types::PREFIX_INFO, 4, 64, 0xC0];
+
+        opt.extend_from_slice(&valid.to_be_bytes());
+        opt.extend_from_slice(&pref.to_be_bytes());
+        opt.extend_from_slice(&[0; 4]);
+        opt.extend_from_slice(&GLOBAL);
+        return opt;
+    }
+
+    #[test]
+    fn neighbor_solicitation() {
+        let mut icmp6_h = icmp6_hdr::new();
+        let slla = [icmp6_ndp_opt_types::SRC_LINK_LAYER_ADDR, 1, 0x02, 0, 0, 0, 0, 1];
+
+        assert_eq!(check(&mut icmp6_h, &ip6(LINK_LOCAL, 255),
+                         &neighbor(icmp6_types::NEIGHBOR_SOL, 0, GLOBAL, &slla)), 0);
+        assert_eq!(icmp6_h.neighbor.target_addr, GLOBAL);
+        assert!(icmp6_h.ndp_opts.has_src_lla);
+        assert_eq!(icmp6_h.ndp_opts.src_lla, [0x02, 0, 0, 0, 0, 1]);
+
+        // duplicate address detection is sent from the unspecified address
+        assert_eq!(check(&mut icmp6_h, &ip6(UNSPECIFIED, 255),
+                         &neighbor(icmp6_types::NEIGHBOR_SOL, 0, GLOBAL, &[])), 0);
+    }
+
+    #[test]
+    fn neighbor_advertisement_flags() {
+        let mut icmp6_h = icmp6_hdr::new();
+        let tlla = [icmp6_ndp_opt_types::TARGET_LINK_LAYER_ADDR, 1, 0x02, 0, 0, 0, 0, 2];
+
+        assert_eq!(check(&mut icmp6_h, &ip6(LINK_LOCAL, 255),
+                         &neighbor(icmp6_types::NEIGHBOR_ADV, 0xA0, GLOBAL, &tlla)), 0);
+        assert_eq!((icmp6_h.neighbor.router, icmp6_h.neighbor.solicited, icmp6_h.neighbor.overide), (1, 0, 1));
+        assert_eq!(icmp6_h.ndp_opts.target_lla, [0x02, 0, 0, 0, 0, 2]);
+    }
+
+    #[test]
+    fn ndp_anomalies() {
+        let mut icmp6_h = icmp6_hdr::new();
+        let ns = neighbor(icmp6_types::NEIGHBOR_SOL, 0, GLOBAL, &[]);
+
+        assert_eq!(check(&mut icmp6_h, &ip6(LINK_LOCAL, 64), &ns), event_desc::ICMP6_NDP_INVAL_HOP_LIMIT);
+        assert_eq!(check(&mut icmp6_h, &ip6(LINK_LOCAL, 255), &ns[..20]), event_desc::ICMP6_SHORT_MSG_LEN);
+        assert_eq!(check(&mut icmp6_h, &ip6(LINK_LOCAL, 255),
+                         &neighbor(icmp6_types::NEIGHBOR_SOL, 0, ALL_NODES, &[])),
+                   event_desc::ICMP6_NDP_TARGET_MCAST);
+        assert_eq!(check(&mut icmp6_h, &ip6(LINK_LOCAL, 255),
+                         &neighbor(icmp6_types::NEIGHBOR_SOL, 0, GLOBAL, &[1, 0, 0, 0, 0, 0, 0, 0])),
+                   event_desc::ICMP6_NDP_OPT_INVAL_LEN);
+        assert_eq!(check(&mut icmp6_h, &ip6(LINK_LOCAL, 255),
+                         &neighbor(icmp6_types::NEIGHBOR_SOL, 0, GLOBAL, &[1, 2, 0, 0, 0, 0, 0, 0])),
+                   event_desc::ICMP6_NDP_OPT_INVAL_LEN);
+    }
+
+    #[test]
+    fn router_advertisement() {
+        let mut icmp6_h = icmp6_hdr::new();
+        let mut opts = prefix_info(3600, 1800);
+
+        opts.extend_from_slice(&[icmp6_ndp_opt_types::MTU, 1, 0, 0, 0, 0, 0x05, 0xDC]);
+        opts.extend_from_slice(&[icmp6_ndp_opt_types::RDNSS, 3, 0, 0, 0, 0, 0x0E, 0x10]);
+        opts.extend_from_slice(&GLOBAL);
+        // unknown options are skipped
+        opts.extend_from_slice(&[200, 1, 0, 0, 0, 0, 0, 0]);
+
+        assert_eq!(check(&mut icmp6_h, &ip6(LINK_LOCAL, 255), &router_adv(&opts)), 0);
+        assert_eq!(icmp6_h.router_adv.cur_hop_limit, 64);
+        assert_eq!(icmp6_h.router_adv.router_lifetime, 0x0708);
+        assert_eq!(icmp6_h.ndp_opts.prefixes.len(), 1);
+        assert_eq!(icmp6_h.ndp_opts.prefixes[0].prefix_len, 64);
+        assert_eq!(icmp6_h.ndp_opts.prefixes[0].autonomous, 1);
+        assert_eq!(icmp6_h.ndp_opts.mtu, 1500);
+        assert_eq!(icmp6_h.ndp_opts.rdnss_lifetime, 3600);
+        assert_eq!(icmp6_h.ndp_opts.rdnss, vec![GLOBAL]);
+    }
+
+    #[test]
+    fn router_advertisement_anomalies() {
+        let mut icmp6_h = icmp6_hdr::new();
+
+        assert_eq!(check(&mut icmp6_h, &ip6(GLOBAL, 255), &router_adv(&[])),
+                   event_desc::ICMP6_RA_SRC_NOT_LINK_LOCAL);
+        assert_eq!(check(&mut icmp6_h, &ip6(LINK_LOCAL, 255), &router_adv(&prefix_info(1800, 3600))),
+                   event_desc::ICMP6_NDP_PREFIX_INVAL);
+        assert_eq!(check(&mut icmp6_h, &ip6(LINK_LOCAL, 255),
+                         &router_adv(&[icmp6_ndp_opt_types::MTU, 2, 0, 0, 0, 0, 0x05, 0xDC,
+                                       0, 0, 0, 0, 0, 0, 0, 0])),
+                   event_desc::ICMP6_NDP_OPT_INVAL_LEN);
+    }
+
+    #[test]
+    fn mld2_report() {
+        let mut icmp6_h = icmp6_hdr::new();
+        let mut msg = vec![icmp6_types::MLD2_LISTENER_REPORT, 0, 0, 0, 0, 0, 0, 2];
+
+        // a record with one source and one with aux data
+        msg.extend_from_slice(&[1, 0, 0, 1]);
+        msg.extend_from_slice(&ALL_NODES);
+        msg.extend_from_slice(&GLOBAL);
+        msg.extend_from_slice(&[2, 1, 0, 0]);
+        msg.extend_from_slice(&GLOBAL);
+        msg.extend_from_slice(&[0; 4]);
+
+        assert_eq!(check(&mut icmp6_h, &ip6(UNSPECIFIED, 1), &msg), 0);
+        assert_eq!(icmp6_h.mld2_report.mcast_addrs, vec![ALL_NODES, GLOBAL]);
+
+        // the second record runs past the message
+        assert_eq!(check(&mut icmp6_h, &ip6(LINK_LOCAL, 1), &msg[..msg.len() - 2]),
+                   event_desc::ICMP6_SHORT_MSG_LEN);
+    }
+
+    #[test]
+    fn header_anomalies() {
+        let mut icmp6_h = icmp6_hdr::new();
+        let ip6_h = ip6(LINK_LOCAL, 64);
+
+        assert_eq!(check(&mut icmp6_h, &ip6_h, &[icmp6_types::ECHO_REQ, 0, 0]), event_desc::ICMP6_SHORT_HDR_LEN);
+        assert_eq!(check(&mut icmp6_h, &ip6_h, &[100, 0, 0, 0, 0, 0, 0, 0]), event_desc::ICMP6_UNKNOWN_TYPE);
+        assert_eq!(check(&mut icmp6_h, &ip6_h, &[icmp6_types::ECHO_REQ, 1, 0, 0, 0, 0, 0, 0]),
+                   event_desc::ICMP6_INVAL_CODE);
+        assert_eq!(check(&mut icmp6_h, &ip6_h, &[icmp6_types::ROUTER_RENUMBERING, 255, 0, 0,
+                                                 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]), 0);
+        assert_eq!(check(&mut icmp6_h, &ip6_h, &[icmp6_types::ROUTER_RENUMBERING, 2, 0, 0,
+                                                 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]),
+                   event_desc::ICMP6_INVAL_CODE);
+        assert_eq!(check(&mut icmp6_h, &ip6(UNSPECIFIED, 64), &[icmp6_types::ECHO_REQ, 0, 0, 0, 0, 1, 0, 1]),
+                   event_desc::IPV6_SRC_ADDR_INVALID);
+    }
+}
@@ -1,4 +1,8 @@
 pub(crate) mod tcp;
+pub(crate) mod tcp_conn;
+pub(crate) mod tcp_syn_flood;
 pub(crate) mod icmp;
 pub(crate) mod icmp6;
+pub(crate) mod nd_guard;
+pub(crate) mod port_scan;
 pub(crate) mod udp;
@@ -0,0 +1,448 @@
+// @brief - implements IPv6 neighbor discovery spoofing and rogue router detection
+// @copyright - 2024-present Devendra Naga All rights reserved
+#![allow(dead_code)]
+#![allow(non_camel_case_types)]
+
+use std::collections::{HashMap, HashSet};
+use std::net::Ipv6Addr;
+
+use crate::{
+    events::{
+        event_desc::event_desc,
+        event_info::event_pkt_info,
+        event_mgr::event_mgr,
+        event_type::event_type
+    },
+    lib::protocols::l4::icmp6::{icmp6_hdr, icmp6_types}
+};
+
+// @brief - defines the limits of the ND guard
+pub struct nd_guard_limits {
+    pub max_bindings        : usize,
+    // learned bindings are forgotten when not seen for this long
+    pub binding_timeout_sec : u32,
+    // a host waits this long for an answer to its DAD probe
+    pub dad_timeout_sec     : u32
+}
+
+impl nd_guard_limits {
+    pub const DEFAULT_MAX_BINDINGS : usize = 4096;
+    pub const DEFAULT_BINDING_TIMEOUT_SEC : u32 = 14400;
+    pub const DEFAULT_DAD_TIMEOUT_SEC : u32 = 3;
+
+    pub fn new() -> nd_guard_limits {
+        let limits = nd_guard_limits {
+            max_bindings            : nd_guard_limits::DEFAULT_MAX_BINDINGS,
+            binding_timeout_sec     : nd_guard_limits::DEFAULT_BINDING_TIMEOUT_SEC,
+            dad_timeout_sec         : nd_guard_limits::DEFAULT_DAD_TIMEOUT_SEC
+        };
+        limits
+    }
+}
+
+// @brief - defines an IPv6 to MAC binding
+struct nd_binding {
+    mac                 : [u8; 6],
+    last_sec            : u32
+}
+
+// @brief - defines a duplicate address detection in progress
+struct nd_dad_probe {
+    // MAC of the host asking for the address
+    mac                 : [u8; 6],
+    start_sec           : u32
+}
+
+// @brief - defines the ND guard
+pub struct nd_guard {
+    bindings            : HashMap<[u8; 16], nd_binding>,
+    dad_probes          : HashMap<[u8; 16], nd_dad_probe>,
+    // routers allowed to advertise, an empty list allows any
+    router_macs         : HashSet<[u8; 6]>,
+    router_addrs        : HashSet<[u8; 16]>,
+    last_expire_sec     : u32,
+    pub ra_guard        : bool,
+    pub limits          : nd_guard_limits
+}
+
+impl nd_guard {
+    pub fn new() -> nd_guard {
+        let guard = nd_guard {
+            bindings            : HashMap::new(),
+            dad_probes          : HashMap::new(),
+            router_macs         : HashSet::new(),
+            router_addrs        : HashSet::new(),
+            last_expire_sec     : 0,
+            ra_guard            : false,
+            limits              : nd_guard_limits::new()
+        };
+        guard
+    }
+
+    // @brief - returns the number of bindings
+    pub fn n_bindings(&self) -> usize { return self.bindings.len(); }
+
+    // @brief - allow router advertisements from a MAC address
+    pub fn allow_router_mac(&mut self, mac : &[u8; 6]) {
+        self.router_macs.insert(*mac);
+    }
+
+    // @brief - allow router advertisements from a link-local address
+    pub fn allow_router_addr(&mut self, addr : &[u8; 16]) {
+        self.router_addrs.insert(*addr);
+    }
+
+    // @brief - forget the learned state and the allowed routers
+    pub fn clear(&mut self) {
+        self.bindings.clear();
+        self.dad_probes.clear();
+        self.router_macs.clear();
+        self.router_addrs.clear();
+    }
+
+    fn expire(&mut self, now_sec : u32) {
+        if now_sec == self.last_expire_sec {
+            return;
+        }
+        self.last_expire_sec = now_sec;
+
+        let limits = &self.limits;
+        self.bindings.retain(|_, b| now_sec.wrapping_sub(b.last_sec) < limits.binding_timeout_sec);
+        self.dad_probes.retain(|_, d| now_sec.wrapping_sub(d.start_sec) < limits.dad_timeout_sec);
+    }
+
+    fn deny(evt_mgr : &mut event_mgr, evt_desc : u32, details : Vec<(String, String)>) -> i32 {
+        evt_mgr.insert_evt_info_details(event_type::EVENT_TYPE_DENY, evt_desc, details);
+        return -1;
+    }
+
+    // @brief - check an answer to a DAD probe of the target
+    //
+    // @param [in] target - address under detection
+    // @param [in] mac - MAC address claiming the target
+    // @param [in] msg - message that claims it, ns or na
+    //
+    // @return 0 on success -1 if the address is denied to the prober
+    //
+    // @details - the owner of a known binding answering is a real duplicate,
+    //            anyone else answering keeps the prober from ever getting an
+    //            address (RFC 3756 4.1.3).
+    fn check_dad(&mut self, target : &[u8; 16], mac : &[u8; 6], msg : &str, evt_mgr : &mut event_mgr) -> i32 {
+        let probe = match self.dad_probes.get(target) {
+            Some(probe) => probe,
+            None => return 0,
+        };
+
+        if probe.mac == *mac {
+            return 0;
+        }
+
+        if let Some(b) = self.bindings.get(target) {
+            if b.mac == *mac {
+                return 0;
+            }
+        }
+
+        return nd_guard::deny(evt_mgr, event_desc::ICMP6_NDP_DAD_DENIAL, vec![
+            ("target".to_string(), Ipv6Addr::from(*target).to_string()),
+            ("prober_mac".to_string(), event_pkt_info::mac_str(&probe.mac)),
+            ("mac".to_string(), event_pkt_info::mac_str(mac)),
+            ("msg".to_string(), msg.to_string())
+        ]);
+    }
+
+    // @brief - learn a binding that is not known yet
+    fn learn(&mut self, addr : &[u8; 16], mac : &[u8; 6], now_sec : u32) {
+        if let Some(b) = self.bindings.get_mut(addr) {
+            if b.mac == *mac {
+                b.last_sec = now_sec;
+            }
+            return;
+        }
+
+        if self.bindings.len() < self.limits.max_bindings {
+            self.bindings.insert(*addr, nd_binding { mac : *mac, last_sec : now_sec });
+        }
+    }
+
+    // @brief - inspect a neighbor solicitation
+    fn inspect_ns(&mut self, icmp6_h : &icmp6_hdr, src_addr : &[u8; 16], src_mac : &[u8; 6],
+                  src_unspecified : bool, now_sec : u32, evt_mgr : &mut event_mgr) -> i32 {
+        let target = &icmp6_h.neighbor.target_addr;
+
+        if !src_unspecified {
+            // a solicitation does not override a binding, the owner of the
+            // address answers with an advertisement
+            if icmp6_h.ndp_opts.has_src_lla {
+                self.learn(src_addr, &icmp6_h.ndp_opts.src_lla, now_sec);
+            }
+            return 0;
+        }
+
+        // DAD probe, another host probing the same address at once takes it away
+        if self.check_dad(target, src_mac, "ns", evt_mgr) < 0 {
+            return -1;
+        }
+
+        if !self.dad_probes.contains_key(target) &&
+           (self.dad_probes.len() < self.limits.max_bindings) {
+            self.dad_probes.insert(*target, nd_dad_probe { mac : *src_mac, start_sec : now_sec });
+        }
+
+        return 0;
+    }
+
+    // @brief - inspect a neighbor advertisement
+    fn inspect_na(&mut self, icmp6_h : &icmp6_hdr, src_mac : &[u8; 6],
+                  now_sec : u32, evt_mgr : &mut event_mgr) -> i32 {
+        let target = &icmp6_h.neighbor.target_addr;
+        let mac : &[u8; 6];
+
+        // the target link-layer address may be left out when answering unicast
+        if icmp6_h.ndp_opts.has_target_lla {
+            mac = &icmp6_h.ndp_opts.target_lla;
+        } else {
+            mac = src_mac;
+        }
+
+        if self.check_dad(target, mac, "na", evt_mgr) < 0 {
+            return -1;
+        }
+
+        let b = match self.bindings.get_mut(target) {
+            Some(b) => b,
+            None => {
+                self.learn(target, mac, now_sec);
+                return 0;
+            }
+        };
+
+        if b.mac == *mac {
+            b.last_sec = now_sec;
+            return 0;
+        }
+
+        // RFC 4861 7.2.5 - without the override flag the cache keeps the
+        // known address, nothing changes
+        if icmp6_h.neighbor.overide == 0 {
+            return 0;
+        }
+
+        let details = vec![
+            ("target".to_string(), Ipv6Addr::from(*target).to_string()),
+            ("old_mac".to_string(), event_pkt_info::mac_str(&b.mac)),
+            ("new_mac".to_string(), event_pkt_info::mac_str(mac)),
+            ("solicited".to_string(), (icmp6_h.neighbor.solicited == 1).to_string())
+        ];
+
+        // follow the new MAC so a replaced NIC alerts once
+        b.mac = *mac;
+        b.last_sec = now_sec;
+
+        return nd_guard::deny(evt_mgr, event_desc::ICMP6_NDP_NA_OVERRIDE, details);
+    }
+
+    // @brief - check a router advertisement against the allowed routers
+    fn inspect_ra(&mut self, icmp6_h : &icmp6_hdr, src_addr : &[u8; 16], src_mac : &[u8; 6],
+                  evt_mgr : &mut event_mgr) -> i32 {
+        if !self.ra_guard {
+            return 0;
+        }
+
+        if (self.router_macs.is_empty() || self.router_macs.contains(src_mac)) &&
+           (self.router_addrs.is_empty() || self.router_addrs.contains(src_addr)) {
+            return 0;
+        }
+
+        return nd_guard::deny(evt_mgr, event_desc::ICMP6_RA_NOT_ALLOWED, vec![
+            ("src".to_string(), Ipv6Addr::from(*src_addr).to_string()),
+            ("src_mac".to_string(), event_pkt_info::mac_str(src_mac)),
+            ("router_lifetime".to_string(), icmp6_h.router_adv.router_lifetime.to_string())
+        ]);
+    }
+
+    // @brief - inspect a neighbor discovery message
+    //
+    // @param [inout] self - ND guard
+    // @param [in] icmp6_h - ICMPv6 header
+    // @param [in] src_addr - ipv6 source address
+    // @param [in] src_unspecified - source is the unspecified address
+    // @param [in] src_mac - source MAC of the ethernet header
+    // @param [in] now_sec - monotonic time
+    // @param [out] evt_mgr - event mgr
+    //
+    // @return 0 on success -1 if the message looks spoofed
+    pub fn inspect(&mut self, icmp6_h : &icmp6_hdr, src_addr : &[u8; 16], src_unspecified : bool,
+                   src_mac : &[u8; 6], now_sec : u32, evt_mgr : &mut event_mgr) -> i32 {
+        self.expire(now_sec);
+
+        match icmp6_h.icmp6_type {
+            icmp6_types::NEIGHBOR_SOL => return self.inspect_ns(icmp6_h, src_addr, src_mac,
+                                                                src_unspecified, now_sec, evt_mgr),
+            icmp6_types::NEIGHBOR_ADV => return self.inspect_na(icmp6_h, src_mac, now_sec, evt_mgr),
+            icmp6_types::ROUTER_ADV => return self.inspect_ra(icmp6_h, src_addr, src_mac, evt_mgr),
+            _ => return 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOST_MAC : [u8; 6] = [0x02, 0, 0, 0, 0, 1];
+    const ATTACKER_MAC : [u8; 6] = [0x02, 0, 0, 0, 0, 0x66];
+    const UNSPECIFIED : [u8; 16] = [0; 16];
+
+    fn addr(host : u8) -> [u8; 16] {
+        let mut addr = [0u8; 16];
+
+        addr[0] = 0xfe;
+        addr[1] = 0x80;
+        addr[15] = host;
+        return addr;
+    }
+
+    fn ns(target : u8, src_lla : Option<&[u8; 6]>) -> icmp6_hdr {
+        let mut icmp6_h = icmp6_hdr::new();
+
+        icmp6_h.icmp6_type = icmp6_types::NEIGHBOR_SOL;
+        icmp6_h.neighbor.target_addr = addr(target);
+        if let Some(mac) = src_lla {
+            icmp6_h.ndp_opts.has_src_lla = true;
+            icmp6_h.ndp_opts.src_lla = *mac;
+        }
+        return icmp6_h;
+    }
+
+    fn na(target : u8, target_lla : Option<&[u8; 6]>, overide : u8) -> icmp6_hdr {
+        let mut icmp6_h = icmp6_hdr::new();
+
+        icmp6_h.icmp6_type = icmp6_types::NEIGHBOR_ADV;
+        icmp6_h.neighbor.target_addr = addr(target);
+        icmp6_h.neighbor.overide = overide;
+        if let Some(mac) = target_lla {
+            icmp6_h.ndp_opts.has_target_lla = true;
+            icmp6_h.ndp_opts.target_lla = *mac;
+        }
+        return icmp6_h;
+    }
+
+    fn ra() -> icmp6_hdr {
+        let mut icmp6_h = icmp6_hdr::new();
+
+        icmp6_h.icmp6_type = icmp6_types::ROUTER_ADV;
+        icmp6_h.router_adv.router_lifetime = 1800;
+        return icmp6_h;
+    }
+
+    // @brief - inspect and return the single event raised, 0 if none
+    fn check(guard : &mut nd_guard, icmp6_h : &icmp6_hdr, src_addr : &[u8; 16],
+             src_mac : &[u8; 6], now_sec : u32) -> u32 {
+        let mut evt_mgr = event_mgr::new();
+        let ret = guard.inspect(icmp6_h, src_addr, *src_addr == UNSPECIFIED, src_mac, now_sec, &mut evt_mgr);
+        let counts = evt_mgr.get_evt_desc_counts();
+
+        assert!(counts.len() <= 1);
+        match counts.keys().next() {
+            Some(desc) => {
+                assert_eq!(ret, -1);
+                return *desc;
+            }
+            None => {
+                assert_eq!(ret, 0);
+                return 0;
+            }
+        }
+    }
+
+    #[test]
+    fn na_override() {
+        let mut guard = nd_guard::new();
+
+        assert_eq!(check(&mut guard, &na(1, Some(&HOST_MAC), 1), &addr(1), &HOST_MAC, 100), 0);
+        assert_eq!(guard.n_bindings(), 1);
+
+        // without the override flag the known binding is kept
+        assert_eq!(check(&mut guard, &na(1, Some(&ATTACKER_MAC), 0), &addr(1), &ATTACKER_MAC, 101), 0);
+        assert_eq!(check(&mut guard, &na(1, Some(&ATTACKER_MAC), 1), &addr(1), &ATTACKER_MAC, 102),
+                   event_desc::ICMP6_NDP_NA_OVERRIDE);
+
+        // the binding follows the new MAC, alerts once
+        assert_eq!(check(&mut guard, &na(1, Some(&ATTACKER_MAC), 1), &addr(1), &ATTACKER_MAC, 103), 0);
+
+        // the ethernet source stands in for a missing target link-layer address
+        assert_eq!(check(&mut guard, &na(1, None, 1), &addr(1), &HOST_MAC, 104),
+                   event_desc::ICMP6_NDP_NA_OVERRIDE);
+    }
+
+    #[test]
+    fn ns_does_not_override() {
+        let mut guard = nd_guard::new();
+
+        assert_eq!(check(&mut guard, &ns(2, Some(&HOST_MAC)), &addr(1), &HOST_MAC, 100), 0);
+        assert_eq!(check(&mut guard, &ns(2, Some(&ATTACKER_MAC)), &addr(1), &ATTACKER_MAC, 101), 0);
+        assert_eq!(guard.n_bindings(), 1);
+
+        // the first binding learned from the solicitation is kept
+        assert_eq!(check(&mut guard, &na(1, Some(&HOST_MAC), 1), &addr(1), &HOST_MAC, 102), 0);
+    }
+
+    #[test]
+    fn dad_denial() {
+        let mut guard = nd_guard::new();
+        let mut evt_mgr = event_mgr::new();
+
+        // a host probes the address, anyone else claims it
+        assert_eq!(check(&mut guard, &ns(5, None), &UNSPECIFIED, &HOST_MAC, 100), 0);
+        assert_eq!(guard.inspect(&na(5, Some(&ATTACKER_MAC), 1), &addr(5), false, &ATTACKER_MAC, 100, &mut evt_mgr), -1);
+
+        let evt = &evt_mgr.get_evt_list()[0];
+        assert_eq!(evt.event_desc, event_desc::ICMP6_NDP_DAD_DENIAL);
+        assert!(evt.details.contains(&("target".to_string(), "fe80::5".to_string())));
+        assert!(evt.details.contains(&("prober_mac".to_string(), "02:00:00:00:00:01".to_string())));
+        assert!(evt.details.contains(&("msg".to_string(), "na".to_string())));
+
+        // or probes the same address at once
+        assert_eq!(check(&mut guard, &ns(5, None), &UNSPECIFIED, &ATTACKER_MAC, 101),
+                   event_desc::ICMP6_NDP_DAD_DENIAL);
+
+        // the probe is over after the DAD timeout
+        assert_eq!(check(&mut guard, &na(5, Some(&ATTACKER_MAC), 1), &addr(5), &ATTACKER_MAC, 103), 0);
+    }
+
+    #[test]
+    fn dad_real_duplicate() {
+        let mut guard = nd_guard::new();
+
+        assert_eq!(check(&mut guard, &na(5, Some(&HOST_MAC), 1), &addr(5), &HOST_MAC, 100), 0);
+
+        // the known owner answering a probe is a real duplicate
+        assert_eq!(check(&mut guard, &ns(5, None), &UNSPECIFIED, &ATTACKER_MAC, 101), 0);
+        assert_eq!(check(&mut guard, &na(5, Some(&HOST_MAC), 1), &addr(5), &HOST_MAC, 101), 0);
+
+        // the prober answering itself is not a denial
+        assert_eq!(check(&mut guard, &ns(5, None), &UNSPECIFIED, &ATTACKER_MAC, 101), 0);
+    }
+
+    #[test]
+    fn ra_guard() {
+        let mut guard = nd_guard::new();
+        let router_mac = [0x02, 0, 0, 0, 0, 0xfe];
+
+        assert_eq!(check(&mut guard, &ra(), &addr(1), &ATTACKER_MAC, 100), 0);
+
+        // enabled with no allowed router, any router is allowed
+        guard.ra_guard = true;
+        assert_eq!(check(&mut guard, &ra(), &addr(1), &ATTACKER_MAC, 100), 0);
+
+        guard.allow_router_mac(&router_mac);
+        assert_eq!(check(&mut guard, &ra(), &addr(1), &router_mac, 100), 0);
+        assert_eq!(check(&mut guard, &ra(), &addr(1), &ATTACKER_MAC, 100), event_desc::ICMP6_RA_NOT_ALLOWED);
+
+        // both the MAC and the link-local address must be allowed
+        guard.allow_router_addr(&addr(1));
+        assert_eq!(check(&mut guard, &ra(), &addr(1), &router_mac, 100), 0);
+        assert_eq!(check(&mut guard, &ra(), &addr(2), &router_mac, 100), event_desc::ICMP6_RA_NOT_ALLOWED);
+    }
+}
@@ -0,0 +1,410 @@
+// @brief - implements port scan detection
+// @copyright - 2024-present Devendra Naga All rights reserved
+#![allow(dead_code)]
+#![allow(non_camel_case_types)]
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    events::{
+        event_desc::event_desc,
+        event_mgr::event_mgr,
+        event_type::event_type
+    },
+    lib::protocols::detect::detect_util::ip_addr_str
+};
+
+// @brief - defines the kinds of probes a scanner sends
+#[non_exhaustive]
+pub struct port_scan_types;
+
+impl port_scan_types {
+    pub const NONE      : u8 = 0;
+    // SYN whose handshake does not complete, any SYN without tracking
+    pub const SYN       : u8 = 1;
+    pub const FIN       : u8 = 2;
+    pub const NULL      : u8 = 3;
+    // FIN, PSH and URG
+    pub const XMAS      : u8 = 4;
+    // ACK without a session
+    pub const ACK       : u8 = 5;
+    pub const UDP       : u8 = 6;
+    pub const N_TYPES   : usize = 7;
+
+    pub fn name(scan_type : u8) -> &'static str {
+        match scan_type {
+            port_scan_types::SYN => return "syn",
+            port_scan_types::FIN => return "fin",
+            port_scan_types::NULL => return "null",
+            port_scan_types::XMAS => return "xmas",
+            port_scan_types::ACK => return "ack",
+            port_scan_types::UDP => return "udp",
+            _ => return "none",
+        }
+    }
+}
+
+// @brief - defines the thresholds of the port scan detector
+pub struct port_scan_limits {
+    pub window_sec      : u32,
+    // ports probed on one host, vertical scan
+    pub port_threshold  : u32,
+    // hosts probed on one port, horizontal scan
+    pub host_threshold  : u32,
+    pub max_srcs        : usize,
+    // distinct host and port pairs remembered per source
+    pub max_probes      : usize
+}
+
+impl port_scan_limits {
+    pub const DEFAULT_WINDOW_SEC : u32 = 60;
+    pub const DEFAULT_PORT_THRESHOLD : u32 = 25;
+    pub const DEFAULT_HOST_THRESHOLD : u32 = 25;
+    pub const DEFAULT_MAX_SRCS : usize = 4096;
+    pub const DEFAULT_MAX_PROBES : usize = 1024;
+
+    pub fn new() -> port_scan_limits {
+        let limits = port_scan_limits {
+            window_sec          : port_scan_limits::DEFAULT_WINDOW_SEC,
+            port_threshold      : port_scan_limits::DEFAULT_PORT_THRESHOLD,
+            host_threshold      : port_scan_limits::DEFAULT_HOST_THRESHOLD,
+            max_srcs            : port_scan_limits::DEFAULT_MAX_SRCS,
+            max_probes          : port_scan_limits::DEFAULT_MAX_PROBES
+        };
+        limits
+    }
+}
+
+// @brief - defines the probes sent by a source
+struct port_scan_src {
+    window_start        : u32,
+    last_sec            : u32,
+    probes              : HashSet<([u8; 16], u16)>,
+    ports_per_host      : HashMap<[u8; 16], u32>,
+    hosts_per_port      : HashMap<u16, u32>,
+    // host with the most ports probed
+    top_host            : [u8; 16],
+    top_host_ports      : u32,
+    // port probed on the most hosts
+    top_port            : u16,
+    top_port_hosts      : u32,
+    n_probes            : [u32; port_scan_types::N_TYPES],
+    // the scan is reported, the source is kept until it goes quiet
+    reported            : bool
+}
+
+impl port_scan_src {
+    fn new(now_sec : u32) -> port_scan_src {
+        let src = port_scan_src {
+            window_start        : now_sec,
+            last_sec            : now_sec,
+            probes              : HashSet::new(),
+            ports_per_host      : HashMap::new(),
+            hosts_per_port      : HashMap::new(),
+            top_host            : [0; 16],
+            top_host_ports      : 0,
+            top_port            : 0,
+            top_port_hosts      : 0,
+            n_probes            : [0; port_scan_types::N_TYPES],
+            reported            : false
+        };
+        src
+    }
+
+    // @brief - record a probe
+    fn add(&mut self, dst_addr : &[u8; 16], dst_port : u16, scan_type : u8, max_probes : usize) {
+        self.n_probes[scan_type as usize] = self.n_probes[scan_type as usize].saturating_add(1);
+
+        if (self.probes.len() >= max_probes) || !self.probes.insert((*dst_addr, dst_port)) {
+            return;
+        }
+
+        let host_ports = self.ports_per_host.entry(*dst_addr).or_insert(0);
+        *host_ports += 1;
+        if *host_ports > self.top_host_ports {
+            self.top_host_ports = *host_ports;
+            self.top_host = *dst_addr;
+        }
+
+        let port_hosts = self.hosts_per_port.entry(dst_port).or_insert(0);
+        *port_hosts += 1;
+        if *port_hosts > self.top_port_hosts {
+            self.top_port_hosts = *port_hosts;
+            self.top_port = dst_port;
+        }
+    }
+
+    // @brief - returns the probe type sent the most
+    fn scan_type(&self) -> u8 {
+        let mut scan_type : usize = 0;
+
+        for i in 1..port_scan_types::N_TYPES {
+            if self.n_probes[i] > self.n_probes[scan_type] {
+                scan_type = i;
+            }
+        }
+
+        return scan_type as u8;
+    }
+}
+
+// @brief - defines the port scan detector
+pub struct port_scan {
+    srcs                : HashMap<[u8; 16], port_scan_src>,
+    last_expire_sec     : u32,
+    pub limits          : port_scan_limits
+}
+
+impl port_scan {
+    pub fn new() -> port_scan {
+        let detector = port_scan {
+            srcs                : HashMap::new(),
+            last_expire_sec     : 0,
+            limits              : port_scan_limits::new()
+        };
+        detector
+    }
+
+    // @brief - drop sources whose window is over, reported scans are kept
+    //          until the source is quiet for a window
+    fn expire(&mut self, now_sec : u32) {
+        if now_sec == self.last_expire_sec {
+            return;
+        }
+        self.last_expire_sec = now_sec;
+
+        let window_sec = self.limits.window_sec;
+        self.srcs.retain(|_, src| {
+            if src.reported {
+                now_sec.wrapping_sub(src.last_sec) < window_sec
+            } else {
+                now_sec.wrapping_sub(src.window_start) < window_sec
+            }
+        });
+    }
+
+    // @brief - count a probe and report the scan once a threshold is crossed
+    //
+    // @param [inout] self - port scan detector
+    // @param [in] src_addr - source address
+    // @param [in] dst_addr - destination address
+    // @param [in] dst_port - destination port
+    // @param [in] scan_type - port_scan_types
+    // @param [in] now_sec - monotonic time
+    // @param [out] evt_mgr - event mgr
+    //
+    // @return 0 on success -1 if a scan is reported
+    //
+    // @details - one event is raised per scan, further probes of the same
+    //            source are counted silently until it stops for a window.
+    pub fn add_probe(&mut self, src_addr : &[u8; 16], dst_addr : &[u8; 16], dst_port : u16,
+                     scan_type : u8, now_sec : u32, evt_mgr : &mut event_mgr) -> i32 {
+        let window_sec = self.limits.window_sec;
+
+        self.expire(now_sec);
+
+        if !self.srcs.contains_key(src_addr) {
+            if self.srcs.len() >= self.limits.max_srcs {
+                return 0;
+            }
+            self.srcs.insert(*src_addr, port_scan_src::new(now_sec));
+        }
+
+        let src = self.srcs.get_mut(src_addr).unwrap();
+
+        if !src.reported && (now_sec.wrapping_sub(src.window_start) >= window_sec) {
+            *src = port_scan_src::new(now_sec);
+        }
+
+        src.last_sec = now_sec;
+        src.add(dst_addr, dst_port, scan_type, self.limits.max_probes);
+
+        if src.reported {
+            return 0;
+        }
+
+        let vertical = src.top_host_ports >= self.limits.port_threshold;
+        let horizontal = src.top_port_hosts >= self.limits.host_threshold;
+        let sweep : &str;
+
+        if vertical && horizontal {
+            sweep = "block";
+        } else if vertical {
+            sweep = "vertical";
+        } else if horizontal {
+            sweep = "horizontal";
+        } else {
+            return 0;
+        }
+
+        src.reported = true;
+
+        let details = vec![
+            ("scan_type".to_string(), port_scan_types::name(src.scan_type()).to_string()),
+            ("sweep".to_string(), sweep.to_string()),
+            ("src".to_string(), ip_addr_str(src_addr)),
+            ("hosts".to_string(), src.ports_per_host.len().to_string()),
+            ("ports".to_string(), src.hosts_per_port.len().to_string()),
+            ("probes".to_string(), src.n_probes.iter().sum::<u32>().to_string()),
+            ("top_host".to_string(), format!("{}({})", ip_addr_str(&src.top_host), src.top_host_ports)),
+            ("top_port".to_string(), format!("{}({})", src.top_port, src.top_port_hosts)),
+            ("window_sec".to_string(), window_sec.to_string())
+        ];
+        evt_mgr.insert_evt_info_details(event_type::EVENT_TYPE_DENY,
+                                        event_desc::PORT_SCAN, details);
+
+        return -1;
+    }
+
+    // @brief - forget every source
+    pub fn clear(&mut self) {
+        self.srcs.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::event_info::event_info;
+
+    fn addr(net : u8, host : u8) -> [u8; 16] {
+        let mut addr = [0u8; 16];
+
+        addr[10] = 0xff;
+        addr[11] = 0xff;
+        addr[12..16].copy_from_slice(&[10, 0, net, host]);
+        return addr;
+    }
+
+    fn detector() -> port_scan {
+        let mut detector = port_scan::new();
+
+        detector.limits.port_threshold = 10;
+        detector.limits.host_threshold = 10;
+        return detector;
+    }
+
+    fn detail(evt : &event_info, key : &str) -> String {
+        return evt.details.iter().find(|(k, _)| k == key).unwrap().1.clone();
+    }
+
+    #[test]
+    fn vertical() {
+        let mut evt_mgr = event_mgr::new();
+        let mut detector = detector();
+        let scanner = addr(0, 1);
+
+        for port in 1..10 {
+            assert_eq!(detector.add_probe(&scanner, &addr(1, 1), port, port_scan_types::SYN, 100, &mut evt_mgr), 0);
+        }
+        // probing a port again is not a new probe
+        assert_eq!(detector.add_probe(&scanner, &addr(1, 1), 1, port_scan_types::SYN, 100, &mut evt_mgr), 0);
+        assert_eq!(detector.add_probe(&scanner, &addr(1, 1), 10, port_scan_types::SYN, 100, &mut evt_mgr), -1);
+
+        let evts = evt_mgr.get_evt_list();
+        assert_eq!(evts.len(), 1);
+        assert_eq!(evts[0].event_desc, event_desc::PORT_SCAN);
+        assert_eq!(detail(&evts[0], "scan_type"), "syn");
+        assert_eq!(detail(&evts[0], "sweep"), "vertical");
+        assert_eq!(detail(&evts[0], "src"), "10.0.0.1");
+        assert_eq!(detail(&evts[0], "hosts"), "1");
+        assert_eq!(detail(&evts[0], "ports"), "10");
+        assert_eq!(detail(&evts[0], "probes"), "11");
+        assert_eq!(detail(&evts[0], "top_host"), "10.0.1.1(10)");
+    }
+
+    #[test]
+    fn horizontal() {
+        let mut evt_mgr = event_mgr::new();
+        let mut detector = detector();
+        let scanner = addr(0, 1);
+
+        for host in 1..=10 {
+            detector.add_probe(&scanner, &addr(1, host), 22, port_scan_types::UDP, 100, &mut evt_mgr);
+        }
+
+        let evts = evt_mgr.get_evt_list();
+        assert_eq!(evts.len(), 1);
+        assert_eq!(detail(&evts[0], "scan_type"), "udp");
+        assert_eq!(detail(&evts[0], "sweep"), "horizontal");
+        assert_eq!(detail(&evts[0], "top_port"), "22(10)");
+    }
+
+    #[test]
+    fn block() {
+        let mut evt_mgr = event_mgr::new();
+        let mut detector = detector();
+        let scanner = addr(0, 1);
+
+        detector.limits.port_threshold = 3;
+        detector.limits.host_threshold = 3;
+        for host in 1..=3 {
+            for port in 1..=3 {
+                detector.add_probe(&scanner, &addr(1, host), port, port_scan_types::FIN, 100, &mut evt_mgr);
+            }
+        }
+
+        let evts = evt_mgr.get_evt_list();
+        assert_eq!(evts.len(), 1);
+        assert_eq!(detail(&evts[0], "sweep"), "vertical");
+
+        evt_mgr.remove_evts(1);
+        detector.clear();
+        for (host, port) in [(1, 1), (1, 2), (2, 3), (3, 3), (1, 3)] {
+            detector.add_probe(&scanner, &addr(1, host), port, port_scan_types::FIN, 100, &mut evt_mgr);
+        }
+
+        // the last probe gives the first host its third port and port 3 its third host
+        let evts = evt_mgr.get_evt_list();
+        assert_eq!(evts.len(), 1);
+        assert_eq!(detail(&evts[0], "sweep"), "block");
+    }
+
+    #[test]
+    fn scan_type_is_most_sent() {
+        let mut evt_mgr = event_mgr::new();
+        let mut detector = detector();
+        let scanner = addr(0, 1);
+        let types = [port_scan_types::NULL, port_scan_types::XMAS, port_scan_types::XMAS];
+
+        for port in 0..10 {
+            detector.add_probe(&scanner, &addr(1, 1), port, types[port as usize % 3], 100, &mut evt_mgr);
+        }
+
+        let evts = evt_mgr.get_evt_list();
+        assert_eq!(evts.len(), 1);
+        assert_eq!(detail(&evts[0], "scan_type"), "xmas");
+    }
+
+    #[test]
+    fn one_event_per_scan() {
+        let mut evt_mgr = event_mgr::new();
+        let mut detector = detector();
+        let scanner = addr(0, 1);
+
+        for port in 0..100 {
+            detector.add_probe(&scanner, &addr(1, 1), port, port_scan_types::ACK, 100 + port as u32, &mut evt_mgr);
+        }
+        assert_eq!(evt_mgr.n_evts(), 1);
+
+        // reported again once the source is quiet for a window
+        for port in 0..10 {
+            detector.add_probe(&scanner, &addr(1, 1), port, port_scan_types::ACK, 300, &mut evt_mgr);
+        }
+        assert_eq!(evt_mgr.n_evts(), 2);
+    }
+
+    #[test]
+    fn slow_scan_below_window() {
+        let mut evt_mgr = event_mgr::new();
+        let mut detector = detector();
+        let scanner = addr(0, 1);
+
+        // one probe every 10 seconds, the window of 60 seconds never sees 10
+        for port in 0..100 {
+            assert_eq!(detector.add_probe(&scanner, &addr(1, 1), port, port_scan_types::SYN,
+                                          100 + port as u32 * 10, &mut evt_mgr), 0);
+        }
+        assert_eq!(evt_mgr.n_evts(), 0);
+    }
+}
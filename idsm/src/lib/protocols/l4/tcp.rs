@@ -132,8 +132,8 @@ impl tcp_flags {
 // @brief - TCP option timestamp structure
 pub struct tcp_opt_timestamp {
     len                 : u8, // 1 byte
-    ts_val              : u32, // 4 bytes
-    ts_echo_reply       : u32 // 4 bytes
+    pub ts_val          : u32, // 4 bytes
+    pub ts_echo_reply   : u32 // 4 bytes
 }
 
 impl tcp_opt_timestamp {
@@ -181,15 +181,77 @@ impl tcp_opt_timestamp {
     }
 }
 
+// @brief - defines the bits of the TCP options presence bitmap
+#[non_exhaustive]
+pub struct tcp_opt_bits;
+
+impl tcp_opt_bits {
+    pub const EOL           : u32 = 1 << 0;
+    pub const NO_OP         : u32 = 1 << 1;
+    pub const MSS           : u32 = 1 << 2;
+    pub const WSCALE        : u32 = 1 << 3;
+    pub const SACK_PERM     : u32 = 1 << 4;
+    pub const SACK          : u32 = 1 << 5;
+    pub const TIMESTAMP     : u32 = 1 << 6;
+    pub const MD5           : u32 = 1 << 7;
+    pub const USER_TIMEOUT  : u32 = 1 << 8;
+    pub const MPTCP         : u32 = 1 << 9;
+    pub const FAST_OPEN     : u32 = 1 << 10;
+    pub const EXPERIMENTAL  : u32 = 1 << 11;
+
+    // options that may only be sent on a SYN
+    pub const SYN_ONLY      : u32 = tcp_opt_bits::MSS |
+                                    tcp_opt_bits::WSCALE |
+                                    tcp_opt_bits::SACK_PERM |
+                                    tcp_opt_bits::FAST_OPEN;
+    // options that may appear more than once in a segment
+    pub const REPEATABLE    : u32 = tcp_opt_bits::EOL |
+                                    tcp_opt_bits::NO_OP |
+                                    tcp_opt_bits::MPTCP |
+                                    tcp_opt_bits::EXPERIMENTAL;
+}
+
 // @brief - defines TCP options
 pub struct tcp_opt {
-    pub available_options   : u32,
-    pub opt_timestamp       : tcp_opt_timestamp
+    pub available_options   : u32, // tcp_opt_bits
+    pub mss                 : u16,
+    pub wscale              : u8,
+    pub n_sack_blocks       : usize,
+    pub sack_blocks         : [(u32, u32); 4],
+    pub opt_timestamp       : tcp_opt_timestamp,
+    pub md5_digest          : [u8; 16],
+    pub user_timeout        : u16,
+    pub fast_open_cookie    : Vec<u8>
 }
 
 impl tcp_opt {
+    pub const OPT_EOL       : u8 = 0;
     pub const OPT_NO_OP     : u8 = 1;
+    pub const OPT_MSS       : u8 = 2;
+    pub const OPT_WSCALE    : u8 = 3;
+    pub const OPT_SACK_PERM : u8 = 4;
+    pub const OPT_SACK      : u8 = 5;
     pub const OPT_TIMESTAMP : u8 = 8;
+    pub const OPT_MD5       : u8 = 19;
+    pub const OPT_USER_TIMEOUT : u8 = 28;
+    pub const OPT_MPTCP     : u8 = 30;
+    pub const OPT_FAST_OPEN : u8 = 34;
+    pub const OPT_EXP1      : u8 = 253;
+    pub const OPT_EXP2      : u8 = 254;
+
+    pub const MSS_LEN       : u8 = 4;
+    pub const WSCALE_LEN    : u8 = 3;
+    pub const SACK_PERM_LEN : u8 = 2;
+    pub const MD5_LEN       : u8 = 18;
+    pub const USER_TIMEOUT_LEN : u8 = 4;
+    pub const MPTCP_MIN_LEN : u8 = 3;
+    // RFC 7413 - empty cookie on a request, 4 to 16 bytes otherwise
+    pub const FAST_OPEN_MIN_COOKIE_LEN : u8 = 4;
+    pub const FAST_OPEN_MAX_COOKIE_LEN : u8 = 16;
+    pub const SACK_BLOCK_LEN : u8 = 8;
+    pub const SACK_MAX_BLOCKS : u8 = 4;
+    // RFC 7323 - shift counts above 14 are treated as 14
+    pub const WSCALE_MAX    : u8 = 14;
 
     // @brief - clears TCP options
     //
@@ -198,60 +260,197 @@ impl tcp_opt {
     pub fn new() -> tcp_opt {
         let opts = tcp_opt {
             available_options : 0,
-            opt_timestamp : tcp_opt_timestamp::new()
+            mss : 0,
+            wscale : 0,
+            n_sack_blocks : 0,
+            sack_blocks : [(0, 0); 4],
+            opt_timestamp : tcp_opt_timestamp::new(),
+            md5_digest : [0; 16],
+            user_timeout : 0,
+            fast_open_cookie : Vec::new()
         };
         opts
     }
 
+    // @brief - check if an option is present
+    //
+    // @param [in] self - this struct
+    // @param [in] opt_bit - tcp_opt_bits
+    //
+    // @return true if present
+    pub fn has(&self, opt_bit : u32) -> bool {
+        return (self.available_options & opt_bit) != 0;
+    }
+
+    // @brief - returns the presence bit and the valid length check of an option
+    //
+    // @param [in] kind - option kind
+    // @param [in] len - option length including kind and length bytes
+    //
+    // @return (tcp_opt_bits, length valid), bit 0 for unknown options
+    fn kind_info(kind : u8, len : u8) -> (u32, bool) {
+        match kind {
+            tcp_opt::OPT_MSS => return (tcp_opt_bits::MSS, len == tcp_opt::MSS_LEN),
+            tcp_opt::OPT_WSCALE => return (tcp_opt_bits::WSCALE, len == tcp_opt::WSCALE_LEN),
+            tcp_opt::OPT_SACK_PERM => return (tcp_opt_bits::SACK_PERM, len == tcp_opt::SACK_PERM_LEN),
+            tcp_opt::OPT_SACK => {
+                let n_blocks = (len.wrapping_sub(2)) / tcp_opt::SACK_BLOCK_LEN;
+                return (tcp_opt_bits::SACK,
+                        (len > 2) && ((len - 2) % tcp_opt::SACK_BLOCK_LEN == 0) &&
+                        (n_blocks <= tcp_opt::SACK_MAX_BLOCKS));
+            }
+            tcp_opt::OPT_TIMESTAMP => return (tcp_opt_bits::TIMESTAMP, true),
+            tcp_opt::OPT_MD5 => return (tcp_opt_bits::MD5, len == tcp_opt::MD5_LEN),
+            tcp_opt::OPT_USER_TIMEOUT => return (tcp_opt_bits::USER_TIMEOUT, len == tcp_opt::USER_TIMEOUT_LEN),
+            tcp_opt::OPT_MPTCP => return (tcp_opt_bits::MPTCP, len >= tcp_opt::MPTCP_MIN_LEN),
+            tcp_opt::OPT_FAST_OPEN => {
+                let cookie_len = len.wrapping_sub(2);
+                return (tcp_opt_bits::FAST_OPEN,
+                        (cookie_len == 0) ||
+                        ((cookie_len >= tcp_opt::FAST_OPEN_MIN_COOKIE_LEN) &&
+                         (cookie_len <= tcp_opt::FAST_OPEN_MAX_COOKIE_LEN) &&
+                         (cookie_len % 2 == 0)));
+            }
+            tcp_opt::OPT_EXP1 |
+            tcp_opt::OPT_EXP2 => return (tcp_opt_bits::EXPERIMENTAL, true),
+            _ => return (0, true),
+        }
+    }
+
+    fn deny(evt_mgr : &mut event_mgr, evt_desc : u32) -> i32 {
+        evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY, evt_desc);
+        return -1;
+    }
+
     // @brief - deserialize TCP options
     //
     // @param [out] self - this struct
+    // @param [in] opt_len - length of the options
+    // @param [in] is_syn - the segment has the SYN flag
     // @param [inout] p - packet
     // @param [out] evt_mgr - event mgr
-    pub fn deserialize(&mut self, opt_len : u32, p : &mut packet, evt_mgr : &mut event_mgr) -> i32 {
-        let mut ret : i32 = 0;
-        let mut opt : u8;
-        let mut opt_off : u32 = 0;
+    //
+    // @return 0 on success -1 on failure
+    pub fn deserialize(&mut self, opt_len : u32, is_syn : bool, p : &mut packet, evt_mgr : &mut event_mgr) -> i32 {
+        let opts_end = p.off + opt_len as usize;
 
-        /* skip if no options present. */
-        while (opt_off < opt_len) && (p.off < p.pkt_len) {
-            opt = p.buf[p.off];
+        *self = tcp_opt::new();
 
-            if opt == tcp_opt::OPT_NO_OP {
-                p.off += 1;
+        while p.off < opts_end {
+            let kind = p.buf[p.off];
+            let opt_start = p.off;
 
-                opt_off += 1;
-                self.available_options |= tcp_opt::OPT_NO_OP as u32;
-            } else if opt == tcp_opt::OPT_TIMESTAMP {
+            if kind == tcp_opt::OPT_EOL {
+                // the rest of the option space is padding
+                self.available_options |= tcp_opt_bits::EOL;
+                break;
+            }
+
+            if kind == tcp_opt::OPT_NO_OP {
+                self.available_options |= tcp_opt_bits::NO_OP;
                 p.off += 1;
-                opt_off += 1;
+                continue;
+            }
+
+            if p.off + 2 > opts_end {
+                return tcp_opt::deny(evt_mgr, event_desc::TCP_OPT_INVAL_LEN);
+            }
+
+            let len = p.buf[p.off + 1];
+            if (len < 2) || (p.off + len as usize > opts_end) {
+                return tcp_opt::deny(evt_mgr, event_desc::TCP_OPT_INVAL_LEN);
+            }
 
-                ret = self.opt_timestamp.deserialize(p, evt_mgr);
-                if ret != 0 {
-                    return -1;
+            let (opt_bit, len_valid) = tcp_opt::kind_info(kind, len);
+            if opt_bit == 0 {
+                return tcp_opt::deny(evt_mgr, event_desc::TCP_UNKNOWN_OPT);
+            }
+
+            if !len_valid {
+                return tcp_opt::deny(evt_mgr, event_desc::TCP_OPT_INVAL_LEN);
+            }
+
+            if self.has(opt_bit) && ((opt_bit & tcp_opt_bits::REPEATABLE) == 0) {
+                return tcp_opt::deny(evt_mgr, event_desc::TCP_OPT_DUPLICATE);
+            }
+
+            // SYN only options negotiate the connection, SACK blocks need one
+            if (!is_syn && ((opt_bit & tcp_opt_bits::SYN_ONLY) != 0)) ||
+               (is_syn && (opt_bit == tcp_opt_bits::SACK)) {
+                return tcp_opt::deny(evt_mgr, event_desc::TCP_OPT_WRONG_SEGMENT);
+            }
+
+            self.available_options |= opt_bit;
+            p.off += 2;
+
+            match kind {
+                tcp_opt::OPT_MSS => p.deserialize_2_bytes(&mut self.mss),
+                tcp_opt::OPT_WSCALE => {
+                    p.deserialize_byte(&mut self.wscale);
+                    if self.wscale > tcp_opt::WSCALE_MAX {
+                        return tcp_opt::deny(evt_mgr, event_desc::TCP_OPT_WSCALE_TOO_LARGE);
+                    }
                 }
-                self.available_options |= tcp_opt::OPT_TIMESTAMP as u32;
-                opt_off += 9;
-            } else {
-                // unknown option
-                evt_mgr.insert_evt_info(
-                                        event_type::EVENT_TYPE_DENY,
-                                        event_desc::TCP_UNKNOWN_OPT);
-                ret = -1;
-                break;
+                tcp_opt::OPT_SACK => {
+                    self.n_sack_blocks = ((len - 2) / tcp_opt::SACK_BLOCK_LEN) as usize;
+                    for i in 0..self.n_sack_blocks {
+                        p.deserialize_4_bytes(&mut self.sack_blocks[i].0);
+                        p.deserialize_4_bytes(&mut self.sack_blocks[i].1);
+                    }
+                }
+                tcp_opt::OPT_TIMESTAMP => {
+                    p.off = opt_start + 1;
+                    if self.opt_timestamp.deserialize(p, evt_mgr) < 0 {
+                        return -1;
+                    }
+                }
+                tcp_opt::OPT_MD5 => {
+                    self.md5_digest.copy_from_slice(&p.buf[p.off..p.off + 16]);
+                }
+                tcp_opt::OPT_USER_TIMEOUT => p.deserialize_2_bytes(&mut self.user_timeout),
+                tcp_opt::OPT_FAST_OPEN => {
+                    self.fast_open_cookie = p.buf[p.off..opt_start + len as usize].to_vec();
+                }
+                _ => (),
             }
+
+            p.off = opt_start + len as usize;
         }
 
-        return ret;
+        p.off = opts_end;
+
+        return 0;
     }
 
     // @brief - print TCP options
     //
     // @param [in] self - this struct
     pub fn print(&self) {
-        if (self.available_options & tcp_opt::OPT_TIMESTAMP as u32) == 0 {
+        log::info!("\t options: 0x{:04X}", self.available_options);
+        if self.has(tcp_opt_bits::MSS) {
+            log::info!("\t\t mss: {}", self.mss);
+        }
+        if self.has(tcp_opt_bits::WSCALE) {
+            log::info!("\t\t wscale: {}", self.wscale);
+        }
+        if self.has(tcp_opt_bits::SACK_PERM) {
+            log::info!("\t\t sack_perm");
+        }
+        for i in 0..self.n_sack_blocks {
+            log::info!("\t\t sack: {} - {}", self.sack_blocks[i].0, self.sack_blocks[i].1);
+        }
+        if self.has(tcp_opt_bits::TIMESTAMP) {
             self.opt_timestamp.print();
         }
+        if self.has(tcp_opt_bits::MD5) {
+            log::info!("\t\t md5");
+        }
+        if self.has(tcp_opt_bits::USER_TIMEOUT) {
+            log::info!("\t\t user_timeout: {}", self.user_timeout);
+        }
+        if self.has(tcp_opt_bits::FAST_OPEN) {
+            log::info!("\t\t fast_open_cookie_len: {}", self.fast_open_cookie.len());
+        }
     }
 }
 
@@ -271,6 +470,15 @@ pub struct tcp_hdr {
 
 impl tcp_hdr {
     pub const TCP_MIN_HDR_LEN : u32 = 20;
+    pub const TCP_FLAGS_OFF : usize = 13;
+
+    // flags in the byte at TCP_FLAGS_OFF
+    pub const FLAG_FIN : u8 = 0x01;
+    pub const FLAG_SYN : u8 = 0x02;
+    pub const FLAG_RST : u8 = 0x04;
+    pub const FLAG_PSH : u8 = 0x08;
+    pub const FLAG_ACK : u8 = 0x10;
+    pub const FLAG_URG : u8 = 0x20;
 
     // @brier - zero initialize TCP header
     //
@@ -301,6 +509,8 @@ impl tcp_hdr {
     //
     // @return 0 on success -1 on failure
     pub fn deserialize(&mut self, p : &mut packet, evt_mgr : &mut event_mgr, debug : bool) -> i32 {
+        let hdr_start = p.off;
+
         // check if the packet within the TCP header length
         if ((p.pkt_len - p.off) as u32) < tcp_hdr::TCP_MIN_HDR_LEN {
             evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY,
@@ -309,13 +519,15 @@ impl tcp_hdr {
         }
 
         p.deserialize_2_bytes(&mut self.src_port);
+        p.deserialize_2_bytes(&mut self.dst_port);
+        evt_mgr.set_pkt_ports(self.src_port, self.dst_port);
+
         if self.src_port == 0 {
             evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY,
                          event_desc::TCP_SRC_PORT_ZERO);
             return -1;
         }
 
-        p.deserialize_2_bytes(&mut self.dst_port);
         if self.dst_port == 0 {
             evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY,
                          event_desc::TCP_DST_PORT_ZERO);
@@ -326,6 +538,17 @@ impl tcp_hdr {
         p.deserialize_4_bytes(&mut self.ack_no);
 
         self.hdr_len = (p.buf[p.off] & 0xF0) >> 4;
+        if (self.hdr_len as u32 * 4) < tcp_hdr::TCP_MIN_HDR_LEN {
+            evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY,
+                         event_desc::TCP_INVAL_HDR_LEN);
+            return -1;
+        }
+
+        if (p.pkt_len - hdr_start) < (self.hdr_len as usize * 4) {
+            evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY,
+                         event_desc::TCP_SHORT_HDR_LEN);
+            return -1;
+        }
 
         let mut ret = self.flags.deserialize(p, evt_mgr);
         if ret < 0 {
@@ -337,8 +560,9 @@ impl tcp_hdr {
         p.deserialize_2_bytes(&mut self.urg_ptr);
 
         let opt_len = (self.hdr_len * 4) as u32 - tcp_hdr::TCP_MIN_HDR_LEN;
+        self.options = tcp_opt::new();
         if opt_len > 0 {
-            ret = self.options.deserialize(opt_len, p, evt_mgr);
+            ret = self.options.deserialize(opt_len, self.flags.syn != 0, p, evt_mgr);
             if ret < 0 {
                 return -1;
             }
@@ -349,6 +573,50 @@ impl tcp_hdr {
         return 0;
     }
 
+    // @brief - read the ports and flags without validating the header
+    //
+    // @param [in] p - packet, offset at the TCP header
+    // @param [out] src_port - source port
+    // @param [out] dst_port - destination port
+    // @param [out] flags - CWR to FIN flags
+    //
+    // @return 0 on success -1 if the packet is too short
+    pub fn peek(p : &packet, src_port : &mut u16, dst_port : &mut u16, flags : &mut u8) -> i32 {
+        if p.pkt_len < p.off + tcp_hdr::TCP_FLAGS_OFF + 1 {
+            return -1;
+        }
+
+        *src_port = u16::from_be_bytes([p.buf[p.off], p.buf[p.off + 1]]);
+        *dst_port = u16::from_be_bytes([p.buf[p.off + 2], p.buf[p.off + 3]]);
+        *flags = p.buf[p.off + tcp_hdr::TCP_FLAGS_OFF];
+
+        return 0;
+    }
+
+    // @brief - returns the source port
+    pub fn src_port(&self) -> u16 { return self.src_port; }
+
+    // @brief - returns the destination port
+    pub fn dst_port(&self) -> u16 { return self.dst_port; }
+
+    // @brief - returns the sequence number
+    pub fn seq_no(&self) -> u32 { return self.seq_no; }
+
+    // @brief - returns the acknowledgement number
+    pub fn ack_no(&self) -> u32 { return self.ack_no; }
+
+    // @brief - returns the header length in bytes
+    pub fn hdr_len(&self) -> u32 { return self.hdr_len as u32 * 4; }
+
+    // @brief - returns the TCP flags
+    pub fn flags(&self) -> &tcp_flags { return &self.flags; }
+
+    // @brief - returns the window without the scale
+    pub fn window(&self) -> u16 { return self.window; }
+
+    // @brief - returns the TCP options
+    pub fn options(&self) -> &tcp_opt { return &self.options; }
+
     // @brief - print TCP header
     //
     // @param [in] self - this structure
@@ -367,3 +635,89 @@ impl tcp_hdr {
         self.options.print();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // @brief - deserialize the options and return the result and the raised event
+    fn parse_opts(opts : &[u8], is_syn : bool) -> (i32, Option<u32>, tcp_opt) {
+        let mut p = packet::with_buf_len(opts.len());
+        let mut evt_mgr = event_mgr::new();
+        let mut tcp_opts = tcp_opt::new();
+
+        p.buf.copy_from_slice(opts);
+        p.pkt_len = opts.len();
+
+        let ret = tcp_opts.deserialize(opts.len() as u32, is_syn, &mut p, &mut evt_mgr);
+        if ret == 0 {
+            assert_eq!(p.off, opts.len());
+        }
+
+        let evt_desc = evt_mgr.get_evt_desc_counts().keys().next().copied();
+
+        return (ret, evt_desc, tcp_opts);
+    }
+
+    #[test]
+    fn syn_options() {
+        // mss 1460, sack permitted, timestamp, nop, wscale 7
+        let opts = [2, 4, 0x05, 0xB4, 4, 2, 8, 10, 0, 0, 0, 1, 0, 0, 0, 0, 1, 3, 3, 7];
+        let (ret, evt_desc, o) = parse_opts(&opts, true);
+
+        assert_eq!((ret, evt_desc), (0, None));
+        assert!(o.has(tcp_opt_bits::MSS) && o.has(tcp_opt_bits::SACK_PERM) &&
+                o.has(tcp_opt_bits::TIMESTAMP) && o.has(tcp_opt_bits::WSCALE));
+        assert_eq!(o.mss, 1460);
+        assert_eq!(o.wscale, 7);
+    }
+
+    #[test]
+    fn eol_padding() {
+        let (ret, evt_desc, o) = parse_opts(&[1, 1, 0, 0xFF], false);
+
+        assert_eq!((ret, evt_desc), (0, None));
+        assert!(o.has(tcp_opt_bits::EOL));
+    }
+
+    #[test]
+    fn malformed_len() {
+        let cases : [&[u8]; 7] = [
+            &[2],                           // no length byte
+            &[2, 1, 1, 1],                  // shorter than kind and length
+            &[2, 0, 1, 1],                  // zero length
+            &[2, 8, 0x05, 0xB4],            // runs past the options
+            &[2, 3, 0x05, 1],               // mss of the wrong length
+            &[5, 6, 0, 0, 0, 0, 1, 1],      // sack not a whole block
+            &[4, 3, 0, 1]                   // sack permitted of the wrong length
+        ];
+
+        for opts in cases {
+            let (ret, evt_desc, _) = parse_opts(opts, opts[0] != 5);
+
+            assert_eq!((ret, evt_desc), (-1, Some(event_desc::TCP_OPT_INVAL_LEN)), "{:?}", opts);
+        }
+    }
+
+    #[test]
+    fn duplicate() {
+        let (ret, evt_desc, _) = parse_opts(&[2, 4, 0x05, 0xB4, 2, 4, 0x05, 0xB4], true);
+        assert_eq!((ret, evt_desc), (-1, Some(event_desc::TCP_OPT_DUPLICATE)));
+
+        let (ret, evt_desc, _) = parse_opts(&[3, 3, 7, 3, 3, 7, 1, 1], true);
+        assert_eq!((ret, evt_desc), (-1, Some(event_desc::TCP_OPT_DUPLICATE)));
+
+        // padding and experimental options repeat
+        let (ret, evt_desc, _) = parse_opts(&[1, 1, 254, 4, 0, 0, 254, 4, 0, 0, 1, 1], false);
+        assert_eq!((ret, evt_desc), (0, None));
+    }
+
+    #[test]
+    fn unknown_and_wrong_segment() {
+        let (ret, evt_desc, _) = parse_opts(&[99, 2, 1, 1], false);
+        assert_eq!((ret, evt_desc), (-1, Some(event_desc::TCP_UNKNOWN_OPT)));
+
+        let (ret, evt_desc, _) = parse_opts(&[2, 4, 0x05, 0xB4], false);
+        assert_eq!((ret, evt_desc), (-1, Some(event_desc::TCP_OPT_WRONG_SEGMENT)));
+    }
+}
@@ -0,0 +1,650 @@
+// @brief - implements TCP connection tracking
+// @copyright - 2024-present Devendra Naga All rights reserved
+#![allow(dead_code)]
+#![allow(non_camel_case_types)]
+
+use std::collections::{HashMap, VecDeque};
+
+use crate::{
+    events::{
+        event_desc::event_desc,
+        event_mgr::event_mgr,
+        event_type::event_type
+    },
+    lib::protocols::l4::tcp::{tcp_hdr, tcp_opt_bits}
+};
+
+// @brief - defines the states of a tracked connection
+#[non_exhaustive]
+pub struct tcp_conn_states;
+
+impl tcp_conn_states {
+    pub const SYN_SENT          : u8 = 1;
+    pub const SYN_RECEIVED      : u8 = 2;
+    pub const ESTABLISHED       : u8 = 3;
+    // one side sent a FIN
+    pub const FIN_WAIT          : u8 = 4;
+    // both sides sent a FIN
+    pub const CLOSING           : u8 = 5;
+    pub const TIME_WAIT         : u8 = 6;
+    pub const CLOSED            : u8 = 7;
+
+    pub fn name(state : u8) -> &'static str {
+        match state {
+            tcp_conn_states::SYN_SENT => return "SYN_SENT",
+            tcp_conn_states::SYN_RECEIVED => return "SYN_RECEIVED",
+            tcp_conn_states::ESTABLISHED => return "ESTABLISHED",
+            tcp_conn_states::FIN_WAIT => return "FIN_WAIT",
+            tcp_conn_states::CLOSING => return "CLOSING",
+            tcp_conn_states::TIME_WAIT => return "TIME_WAIT",
+            tcp_conn_states::CLOSED => return "CLOSED",
+            _ => return "UNKNOWN",
+        }
+    }
+}
+
+// @brief - defines the limits of the connection table
+pub struct tcp_conn_limits {
+    pub max_conns               : usize,
+    pub syn_timeout_sec         : u32,
+    pub established_timeout_sec : u32,
+    pub fin_timeout_sec         : u32,
+    pub close_timeout_sec       : u32,
+    // track connections that started before we saw the handshake silently,
+    // otherwise they raise one event when they are learned
+    pub midstream               : bool
+}
+
+impl tcp_conn_limits {
+    pub const DEFAULT_MAX_CONNS : usize = 65536;
+    pub const DEFAULT_SYN_TIMEOUT_SEC : u32 = 30;
+    pub const DEFAULT_ESTABLISHED_TIMEOUT_SEC : u32 = 3600;
+    pub const DEFAULT_FIN_TIMEOUT_SEC : u32 = 120;
+    pub const DEFAULT_CLOSE_TIMEOUT_SEC : u32 = 10;
+
+    pub fn new() -> tcp_conn_limits {
+        let limits = tcp_conn_limits {
+            max_conns               : tcp_conn_limits::DEFAULT_MAX_CONNS,
+            syn_timeout_sec         : tcp_conn_limits::DEFAULT_SYN_TIMEOUT_SEC,
+            established_timeout_sec : tcp_conn_limits::DEFAULT_ESTABLISHED_TIMEOUT_SEC,
+            fin_timeout_sec         : tcp_conn_limits::DEFAULT_FIN_TIMEOUT_SEC,
+            close_timeout_sec       : tcp_conn_limits::DEFAULT_CLOSE_TIMEOUT_SEC,
+            midstream               : false
+        };
+        limits
+    }
+
+    // @brief - returns the idle timeout of a state
+    pub fn timeout_sec(&self, state : u8) -> u32 {
+        match state {
+            tcp_conn_states::SYN_SENT |
+            tcp_conn_states::SYN_RECEIVED => return self.syn_timeout_sec,
+            tcp_conn_states::ESTABLISHED => return self.established_timeout_sec,
+            tcp_conn_states::FIN_WAIT |
+            tcp_conn_states::CLOSING => return self.fin_timeout_sec,
+            _ => return self.close_timeout_sec,
+        }
+    }
+}
+
+// @brief - identifies a connection, the source is the side that sent the SYN.
+//          ipv4 addresses are kept as ipv4 mapped ipv6 addresses.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct tcp_conn_key {
+    pub src_addr        : [u8; 16],
+    pub dst_addr        : [u8; 16],
+    pub src_port        : u16,
+    pub dst_port        : u16
+}
+
+impl tcp_conn_key {
+    // @brief - returns the key of the segments going the other way
+    pub fn reverse(&self) -> tcp_conn_key {
+        let key = tcp_conn_key {
+            src_addr        : self.dst_addr,
+            dst_addr        : self.src_addr,
+            src_port        : self.dst_port,
+            dst_port        : self.src_port
+        };
+        key
+    }
+}
+
+// @brief - defines the sequence space of one side of a connection
+#[derive(Clone, Copy)]
+struct tcp_peer {
+    // sequence number has been seen
+    seen                : bool,
+    next_seq            : u32,
+    // initial sequence number of the SYN
+    has_isn             : bool,
+    isn                 : u32,
+    // largest window advertised, scaled
+    max_win             : u32,
+    has_wscale          : bool,
+    wscale              : u8,
+    fin                 : bool
+}
+
+impl tcp_peer {
+    fn new() -> tcp_peer {
+        let peer = tcp_peer {
+            seen                : false,
+            next_seq            : 0,
+            has_isn             : false,
+            isn                 : 0,
+            max_win             : 0,
+            has_wscale          : false,
+            wscale              : 0,
+            fin                 : false
+        };
+        peer
+    }
+}
+
+// @brief - defines a tracked connection
+pub struct tcp_conn {
+    pub state           : u8,
+    pub last_sec        : u32,
+    // 0 - initiator, 1 - responder
+    peers               : [tcp_peer; 2]
+}
+
+impl tcp_conn {
+    // segments are allowed this far away from the expected sequence
+    // number when the receiver has not advertised a larger window
+    pub const MIN_SEQ_WINDOW : u32 = 65535;
+
+    fn new(state : u8, now_sec : u32) -> tcp_conn {
+        let conn = tcp_conn {
+            state               : state,
+            last_sec            : now_sec,
+            peers               : [tcp_peer::new(); 2]
+        };
+        conn
+    }
+
+    // @brief - check that the segment is near the expected sequence number
+    //
+    // @param [in] self - connection
+    // @param [in] dir - 0 from the initiator, 1 from the responder
+    // @param [in] tcp_h - TCP header
+    //
+    // @return true if in the window
+    fn seq_in_window(&self, dir : usize, tcp_h : &tcp_hdr) -> bool {
+        let snd = &self.peers[dir];
+        let rcv = &self.peers[1 - dir];
+
+        if !snd.seen {
+            return true;
+        }
+
+        let window = std::cmp::max(rcv.max_win, tcp_conn::MIN_SEQ_WINDOW) as i64;
+        let diff = tcp_h.seq_no().wrapping_sub(snd.next_seq) as i32 as i64;
+
+        return diff.abs() <= window;
+    }
+
+    // @brief - move the connection to the state the segment leads to
+    //
+    // @param [inout] self - connection
+    // @param [in] dir - 0 from the initiator, 1 from the responder
+    // @param [in] tcp_h - TCP header
+    //
+    // @return 0 on success -1 if the segment is not valid in the state
+    fn transition(&mut self, dir : usize, tcp_h : &tcp_hdr) -> i32 {
+        let flags = tcp_h.flags();
+        let syn = flags.syn != 0;
+        let ack = flags.ack != 0;
+
+        if flags.rst != 0 {
+            self.state = tcp_conn_states::CLOSED;
+            return 0;
+        }
+
+        match self.state {
+            tcp_conn_states::SYN_SENT => {
+                // only SYN retransmits until the responder answers
+                if dir == 0 {
+                    return if syn && !ack { 0 } else { -1 };
+                }
+                if !(syn && ack) {
+                    return -1;
+                }
+                self.state = tcp_conn_states::SYN_RECEIVED;
+                return 0;
+            }
+            tcp_conn_states::SYN_RECEIVED => {
+                if syn {
+                    // retransmits of either half of the handshake
+                    return if (dir == 0) != ack { 0 } else { -1 };
+                }
+                if (dir == 1) || !ack {
+                    return -1;
+                }
+                self.state = tcp_conn_states::ESTABLISHED;
+            }
+            _ => {
+                if syn {
+                    // the responder retransmits its SYN+ACK when the ACK
+                    // of the handshake is lost
+                    let responder = &self.peers[1];
+                    let retransmit = (self.state == tcp_conn_states::ESTABLISHED) &&
+                                     (dir == 1) && ack && responder.has_isn &&
+                                     (tcp_h.seq_no() == responder.isn);
+                    return if retransmit { 0 } else { -1 };
+                }
+            }
+        }
+
+        if flags.fin != 0 {
+            self.peers[dir].fin = true;
+        }
+
+        if self.peers[0].fin && self.peers[1].fin {
+            if self.state == tcp_conn_states::CLOSING {
+                // the ACK of the last FIN
+                if flags.fin == 0 {
+                    self.state = tcp_conn_states::TIME_WAIT;
+                }
+            } else if self.state < tcp_conn_states::CLOSING {
+                self.state = tcp_conn_states::CLOSING;
+            }
+        } else if (self.peers[0].fin || self.peers[1].fin) &&
+                  (self.state == tcp_conn_states::ESTABLISHED) {
+            self.state = tcp_conn_states::FIN_WAIT;
+        }
+
+        return 0;
+    }
+
+    // @brief - record the sequence space and window used by the segment
+    //
+    // @param [inout] self - connection
+    // @param [in] dir - 0 from the initiator, 1 from the responder
+    // @param [in] tcp_h - TCP header
+    // @param [in] data_len - length of the TCP data
+    fn advance(&mut self, dir : usize, tcp_h : &tcp_hdr, data_len : u32) {
+        let flags = tcp_h.flags();
+        let seq_len = data_len + flags.syn as u32 + flags.fin as u32;
+        let scaled = self.peers[0].has_wscale && self.peers[1].has_wscale;
+        let peer = &mut self.peers[dir];
+        let mut window = tcp_h.window() as u32;
+
+        if flags.syn != 0 {
+            if !peer.has_isn {
+                peer.has_isn = true;
+                peer.isn = tcp_h.seq_no();
+            }
+
+            // RFC 7323 - the window of a SYN is never scaled
+            if tcp_h.options().has(tcp_opt_bits::WSCALE) {
+                peer.has_wscale = true;
+                peer.wscale = tcp_h.options().wscale;
+            }
+        } else if scaled {
+            window <<= peer.wscale;
+        }
+
+        if window > peer.max_win {
+            peer.max_win = window;
+        }
+
+        if !peer.seen {
+            peer.seen = true;
+            peer.next_seq = tcp_h.seq_no();
+        }
+
+        let end = tcp_h.seq_no().wrapping_add(seq_len);
+        if (end.wrapping_sub(peer.next_seq) as i32) > 0 {
+            peer.next_seq = end;
+        }
+    }
+
+    // @brief - print the connection
+    pub fn print(&self) {
+        log::info!("tcp_conn: ");
+        log::info!("\t state: {}", tcp_conn_states::name(self.state));
+        log::info!("\t last_sec: {}", self.last_sec);
+        log::info!("\t next_seq: {} {}", self.peers[0].next_seq, self.peers[1].next_seq);
+    }
+}
+
+// @brief - defines the TCP connection table
+pub struct tcp_conn_table {
+    conns               : HashMap<tcp_conn_key, tcp_conn>,
+    // connections opened in SYN_SENT, oldest first. entries that have left
+    // SYN_SENT or are gone are skipped
+    syn_sent            : VecDeque<tcp_conn_key>,
+    // half open connections that were reset, timed out or evicted
+    failed              : Vec<tcp_conn_key>,
+    last_expire_sec     : u32,
+    pub limits          : tcp_conn_limits
+}
+
+impl tcp_conn_table {
+    pub fn new() -> tcp_conn_table {
+        let table = tcp_conn_table {
+            conns               : HashMap::new(),
+            syn_sent            : VecDeque::new(),
+            failed              : Vec::new(),
+            last_expire_sec     : 0,
+            limits              : tcp_conn_limits::new()
+        };
+        table
+    }
+
+    // @brief - returns the number of tracked connections
+    pub fn n_conns(&self) -> usize { return self.conns.len(); }
+
+    // @brief - take the connections whose handshake failed since the last call
+    //
+    // @return keys of the connections, the initiator is the source
+    //
+    // @details - a handshake fails when the connection is reset, times out
+    //            or is evicted before it is established. the caller takes
+    //            them after every track() so the list stays short.
+    pub fn take_failed(&mut self) -> Vec<tcp_conn_key> {
+        return std::mem::take(&mut self.failed);
+    }
+
+    fn is_half_open(state : u8) -> bool {
+        return (state == tcp_conn_states::SYN_SENT) || (state == tcp_conn_states::SYN_RECEIVED);
+    }
+
+    // @brief - returns the state of a connection
+    //
+    // @param [in] key - key as seen by either side
+    //
+    // @return state, 0 if not tracked
+    pub fn state(&self, key : &tcp_conn_key) -> u8 {
+        if let Some(conn) = self.conns.get(key) {
+            return conn.state;
+        }
+        if let Some(conn) = self.conns.get(&key.reverse()) {
+            return conn.state;
+        }
+        return 0;
+    }
+
+    // @brief - drop connections idle for longer than their state allows
+    //
+    // @param [inout] self - connection table
+    // @param [in] now_sec - monotonic time
+    fn expire(&mut self, now_sec : u32) {
+        // once a second is plenty for timeouts counted in seconds
+        if now_sec == self.last_expire_sec {
+            return;
+        }
+        self.last_expire_sec = now_sec;
+
+        let limits = &self.limits;
+        let failed = &mut self.failed;
+        self.conns.retain(|key, conn| {
+            let keep = now_sec.wrapping_sub(conn.last_sec) < limits.timeout_sec(conn.state);
+
+            if !keep && tcp_conn_table::is_half_open(conn.state) {
+                failed.push(*key);
+            }
+            keep
+        });
+
+        let conns = &self.conns;
+        self.syn_sent.retain(|key| {
+            conns.get(key).map_or(false, |conn| conn.state == tcp_conn_states::SYN_SENT)
+        });
+    }
+
+    // @brief - drop the oldest connection that is still in SYN_SENT
+    //
+    // @param [inout] self - connection table
+    //
+    // @return true if a connection is dropped
+    fn evict_syn_sent(&mut self) -> bool {
+        while let Some(key) = self.syn_sent.pop_front() {
+            let in_syn_sent = self.conns.get(&key)
+                                        .map_or(false, |conn| conn.state == tcp_conn_states::SYN_SENT);
+            if in_syn_sent {
+                self.conns.remove(&key);
+                self.failed.push(key);
+                return true;
+            }
+        }
+
+        return false;
+    }
+
+    fn deny(evt_mgr : &mut event_mgr, evt_desc : u32) -> i32 {
+        evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY, evt_desc);
+        return -1;
+    }
+
+    // @brief - start tracking a connection
+    //
+    // @param [inout] self - connection table
+    // @param [in] key - key of the connection
+    // @param [in] state - first state
+    // @param [in] tcp_h - TCP header of the first segment
+    // @param [in] data_len - length of the TCP data
+    // @param [in] now_sec - monotonic time
+    // @param [out] evt_mgr - event mgr
+    //
+    // @return 0 on success -1 on failure
+    //
+    // @details - a full table makes room by dropping the oldest half open
+    //            connection, so a SYN flood does not lock out the
+    //            connections that complete their handshake.
+    fn open(&mut self, key : tcp_conn_key, state : u8, tcp_h : &tcp_hdr, data_len : u32,
+            now_sec : u32, evt_mgr : &mut event_mgr) -> i32 {
+        if (self.conns.len() >= self.limits.max_conns) && !self.evict_syn_sent() {
+            return tcp_conn_table::deny(evt_mgr, event_desc::TCP_CONN_TABLE_FULL);
+        }
+
+        let mut conn = tcp_conn::new(state, now_sec);
+        conn.advance(0, tcp_h, data_len);
+        self.conns.insert(key, conn);
+
+        if state == tcp_conn_states::SYN_SENT {
+            self.syn_sent.push_back(key);
+        }
+
+        return 0;
+    }
+
+    // @brief - track a TCP segment
+    //
+    // @param [inout] self - connection table
+    // @param [in] key - key of the segment, the source is the sender
+    // @param [in] tcp_h - TCP header
+    // @param [in] data_len - length of the TCP data
+    // @param [in] now_sec - monotonic time
+    // @param [out] evt_mgr - event mgr
+    //
+    // @return 0 on success -1 on failure
+    pub fn track(&mut self, key : &tcp_conn_key, tcp_h : &tcp_hdr, data_len : u32,
+                 now_sec : u32, evt_mgr : &mut event_mgr) -> i32 {
+        let flags = tcp_h.flags();
+        let new_syn = (flags.syn != 0) && (flags.ack == 0);
+        let conn_key : tcp_conn_key;
+        let dir : usize;
+
+        self.expire(now_sec);
+
+        if self.conns.contains_key(key) {
+            conn_key = *key;
+            dir = 0;
+        } else if self.conns.contains_key(&key.reverse()) {
+            conn_key = key.reverse();
+            dir = 1;
+        } else {
+            if new_syn {
+                return self.open(*key, tcp_conn_states::SYN_SENT, tcp_h, data_len, now_sec, evt_mgr);
+            }
+            if flags.rst != 0 {
+                return tcp_conn_table::deny(evt_mgr, event_desc::TCP_CONN_NO_SESSION);
+            }
+
+            // open before we started or idle past its timeout, learn it so
+            // that its later segments do not raise an event each
+            let ret = self.open(*key, tcp_conn_states::ESTABLISHED, tcp_h, data_len, now_sec, evt_mgr);
+            if (ret < 0) || self.limits.midstream {
+                return ret;
+            }
+            return tcp_conn_table::deny(evt_mgr, event_desc::TCP_CONN_NO_SESSION);
+        }
+
+        let conn = self.conns.get_mut(&conn_key).unwrap();
+
+        // a closed connection may be reopened with the same ports
+        if new_syn && (conn.state >= tcp_conn_states::TIME_WAIT) {
+            self.conns.remove(&conn_key);
+            return self.open(*key, tcp_conn_states::SYN_SENT, tcp_h, data_len, now_sec, evt_mgr);
+        }
+
+        conn.last_sec = now_sec;
+        let half_open = tcp_conn_table::is_half_open(conn.state);
+
+        if !conn.seq_in_window(dir, tcp_h) {
+            return tcp_conn_table::deny(evt_mgr, event_desc::TCP_CONN_SEQ_OUT_OF_WINDOW);
+        }
+
+        if conn.transition(dir, tcp_h) < 0 {
+            return tcp_conn_table::deny(evt_mgr, event_desc::TCP_CONN_INVAL_STATE);
+        }
+
+        conn.advance(dir, tcp_h, data_len);
+
+        if half_open && (conn.state == tcp_conn_states::CLOSED) {
+            self.failed.push(conn_key);
+        }
+
+        return 0;
+    }
+
+    // @brief - drop every tracked connection
+    pub fn clear(&mut self) {
+        self.conns.clear();
+        self.syn_sent.clear();
+        self.failed.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lib::protocols::packet::packet::packet;
+
+    const SYN : u8 = 0x02;
+    const ACK : u8 = 0x10;
+
+    fn key(src_port : u16) -> tcp_conn_key {
+        let key = tcp_conn_key {
+            src_addr        : [1; 16],
+            dst_addr        : [2; 16],
+            src_port        : src_port,
+            dst_port        : 80
+        };
+        key
+    }
+
+    // @brief - deserialize a TCP header without options
+    fn seg(src_port : u16, dst_port : u16, seq : u32, ack : u32, flags : u8) -> tcp_hdr {
+        let mut p = packet::with_buf_len(tcp_hdr::TCP_MIN_HDR_LEN as usize);
+        let mut evt_mgr = event_mgr::new();
+        let mut tcp_h = tcp_hdr::new();
+
+        p.buf[0..2].copy_from_slice(&src_port.to_be_bytes());
+        p.buf[2..4].copy_from_slice(&dst_port.to_be_bytes());
+        p.buf[4..8].copy_from_slice(&seq.to_be_bytes());
+        p.buf[8..12].copy_from_slice(&ack.to_be_bytes());
+        p.buf[12] = 5 << 4;
+        p.buf[13] = flags;
+        p.buf[14..16].copy_from_slice(&1024u16.to_be_bytes());
+        p.pkt_len = p.buf.len();
+
+        assert_eq!(tcp_h.deserialize(&mut p, &mut evt_mgr, false), 0);
+
+        return tcp_h;
+    }
+
+    #[test]
+    fn synack_retransmit() {
+        let mut table = tcp_conn_table::new();
+        let mut evt_mgr = event_mgr::new();
+        let k = key(1000);
+        let r = k.reverse();
+
+        assert_eq!(table.track(&k, &seg(1000, 80, 100, 0, SYN), 0, 1, &mut evt_mgr), 0);
+        assert_eq!(table.track(&r, &seg(80, 1000, 500, 101, SYN | ACK), 0, 1, &mut evt_mgr), 0);
+        assert_eq!(table.track(&k, &seg(1000, 80, 101, 501, ACK), 0, 1, &mut evt_mgr), 0);
+        assert_eq!(table.state(&k), tcp_conn_states::ESTABLISHED);
+
+        // the ACK was lost, the responder sends its SYN+ACK again
+        assert_eq!(table.track(&r, &seg(80, 1000, 500, 101, SYN | ACK), 0, 2, &mut evt_mgr), 0);
+        assert_eq!(table.state(&k), tcp_conn_states::ESTABLISHED);
+        assert!(evt_mgr.get_evt_desc_counts().is_empty());
+
+        // a SYN+ACK with another sequence number is not a retransmit
+        assert_eq!(table.track(&r, &seg(80, 1000, 502, 101, SYN | ACK), 0, 2, &mut evt_mgr), -1);
+        assert!(evt_mgr.get_evt_desc_counts().contains_key(&event_desc::TCP_CONN_INVAL_STATE));
+
+        // nor is one from the initiator
+        assert_eq!(table.track(&k, &seg(1000, 80, 100, 501, SYN | ACK), 0, 2, &mut evt_mgr), -1);
+    }
+
+    #[test]
+    fn no_session_learned_once() {
+        let mut table = tcp_conn_table::new();
+        let mut evt_mgr = event_mgr::new();
+        let k = key(1000);
+
+        assert_eq!(table.track(&k, &seg(1000, 80, 100, 500, ACK), 10, 1, &mut evt_mgr), -1);
+        assert_eq!(table.track(&k.reverse(), &seg(80, 1000, 500, 110, ACK), 0, 1, &mut evt_mgr), 0);
+        assert_eq!(table.track(&k, &seg(1000, 80, 110, 500, ACK), 10, 2, &mut evt_mgr), 0);
+        assert_eq!(table.state(&k), tcp_conn_states::ESTABLISHED);
+        assert_eq!(evt_mgr.get_evt_desc_counts().get(&event_desc::TCP_CONN_NO_SESSION), Some(&1));
+
+        // a reset is not learned
+        assert_eq!(table.track(&key(1001), &seg(1001, 80, 1, 0, 0x04), 0, 2, &mut evt_mgr), -1);
+        assert_eq!(table.state(&key(1001)), 0);
+
+        // with midstream the connection is learned without an event
+        table.limits.midstream = true;
+        assert_eq!(table.track(&key(1002), &seg(1002, 80, 1, 1, ACK), 0, 2, &mut evt_mgr), 0);
+        assert_eq!(table.state(&key(1002)), tcp_conn_states::ESTABLISHED);
+        assert_eq!(evt_mgr.get_evt_desc_counts().get(&event_desc::TCP_CONN_NO_SESSION), Some(&2));
+    }
+
+    #[test]
+    fn full_table_evicts_syn_sent() {
+        let mut table = tcp_conn_table::new();
+        let mut evt_mgr = event_mgr::new();
+        let k = key(1000);
+
+        table.limits.max_conns = 4;
+
+        assert_eq!(table.track(&k, &seg(1000, 80, 100, 0, SYN), 0, 1, &mut evt_mgr), 0);
+        assert_eq!(table.track(&k.reverse(), &seg(80, 1000, 500, 101, SYN | ACK), 0, 1, &mut evt_mgr), 0);
+        assert_eq!(table.track(&k, &seg(1000, 80, 101, 501, ACK), 0, 1, &mut evt_mgr), 0);
+
+        // half open connections fill the table and keep replacing each other
+        for port in 2000..2010 {
+            assert_eq!(table.track(&key(port), &seg(port, 80, 1, 0, SYN), 0, 1, &mut evt_mgr), 0);
+        }
+
+        assert_eq!(table.n_conns(), 4);
+        assert_eq!(table.state(&k), tcp_conn_states::ESTABLISHED);
+        assert_eq!(table.state(&key(2000)), 0);
+        assert_eq!(table.state(&key(2009)), tcp_conn_states::SYN_SENT);
+        assert!(evt_mgr.get_evt_desc_counts().is_empty());
+
+        // with nothing half open the table is full
+        table.clear();
+        table.limits.midstream = true;
+        table.limits.max_conns = 1;
+
+        assert_eq!(table.track(&k, &seg(1000, 80, 101, 501, ACK), 0, 1, &mut evt_mgr), 0);
+        assert_eq!(table.track(&key(2000), &seg(2000, 80, 1, 0, SYN), 0, 1, &mut evt_mgr), -1);
+        assert!(evt_mgr.get_evt_desc_counts().contains_key(&event_desc::TCP_CONN_TABLE_FULL));
+    }
+}
@@ -0,0 +1,357 @@
+// @brief - implements TCP SYN flood detection
+// @copyright - 2024-present Devendra Naga All rights reserved
+#![allow(dead_code)]
+#![allow(non_camel_case_types)]
+
+use std::collections::HashMap;
+
+use crate::{
+    events::{
+        event_desc::event_desc,
+        event_mgr::event_mgr,
+        event_type::event_type
+    },
+    lib::protocols::detect::detect_util::{ip_addr_str, sliding_count}
+};
+
+// @brief - defines the thresholds of the SYN flood detector
+pub struct syn_flood_limits {
+    pub window_sec      : u32,
+    // half open connections to one destination
+    pub dst_threshold   : u32,
+    // half open connections from one source
+    pub src_threshold   : u32,
+    // number of sources listed in the event
+    pub top_srcs        : usize,
+    // hosts tracked in each of the source and destination tables
+    pub max_hosts       : usize
+}
+
+impl syn_flood_limits {
+    pub const DEFAULT_WINDOW_SEC : u32 = 10;
+    pub const DEFAULT_DST_THRESHOLD : u32 = 500;
+    pub const DEFAULT_SRC_THRESHOLD : u32 = 100;
+    pub const DEFAULT_TOP_SRCS : usize = 5;
+    pub const DEFAULT_MAX_HOSTS : usize = 16384;
+
+    pub fn new() -> syn_flood_limits {
+        let limits = syn_flood_limits {
+            window_sec          : syn_flood_limits::DEFAULT_WINDOW_SEC,
+            dst_threshold       : syn_flood_limits::DEFAULT_DST_THRESHOLD,
+            src_threshold       : syn_flood_limits::DEFAULT_SRC_THRESHOLD,
+            top_srcs            : syn_flood_limits::DEFAULT_TOP_SRCS,
+            max_hosts           : syn_flood_limits::DEFAULT_MAX_HOSTS
+        };
+        limits
+    }
+}
+
+// @brief - defines the SYNs seen for a host
+struct syn_flood_host {
+    syns                : sliding_count,
+    // handshakes completed
+    done                : sliding_count,
+    // half open per source in the current window, destinations only
+    srcs                : HashMap<[u8; 16], u32>,
+    srcs_window_start   : u32,
+    alerted             : bool,
+    last_sec            : u32
+}
+
+impl syn_flood_host {
+    // sources remembered per destination, spoofed floods use many more
+    pub const MAX_SRCS : usize = 256;
+
+    fn new(now_sec : u32) -> syn_flood_host {
+        let host = syn_flood_host {
+            syns                : sliding_count::new(now_sec),
+            done                : sliding_count::new(now_sec),
+            srcs                : HashMap::new(),
+            srcs_window_start   : now_sec,
+            alerted             : false,
+            last_sec            : now_sec
+        };
+        host
+    }
+
+    // @brief - returns the half open connections over the window
+    fn half_open(&mut self, now_sec : u32, window_sec : u32) -> u32 {
+        return self.syns.value(now_sec, window_sec).saturating_sub(self.done.value(now_sec, window_sec));
+    }
+}
+
+// @brief - defines the SYN flood detector
+pub struct syn_flood {
+    dsts                : HashMap<[u8; 16], syn_flood_host>,
+    srcs                : HashMap<[u8; 16], syn_flood_host>,
+    last_expire_sec     : u32,
+    pub limits          : syn_flood_limits
+}
+
+impl syn_flood {
+    pub fn new() -> syn_flood {
+        let detector = syn_flood {
+            dsts                : HashMap::new(),
+            srcs                : HashMap::new(),
+            last_expire_sec     : 0,
+            limits              : syn_flood_limits::new()
+        };
+        detector
+    }
+
+    // @brief - drop hosts not seen for two windows
+    fn expire(&mut self, now_sec : u32) {
+        if now_sec == self.last_expire_sec {
+            return;
+        }
+        self.last_expire_sec = now_sec;
+
+        let idle_sec = self.limits.window_sec * 2;
+        self.dsts.retain(|_, host| now_sec.wrapping_sub(host.last_sec) < idle_sec);
+        self.srcs.retain(|_, host| now_sec.wrapping_sub(host.last_sec) < idle_sec);
+    }
+
+    // @brief - returns the host entry, None if the table is full
+    fn get_host<'a>(hosts : &'a mut HashMap<[u8; 16], syn_flood_host>, addr : &[u8; 16],
+                    now_sec : u32, max_hosts : usize) -> Option<&'a mut syn_flood_host> {
+        if !hosts.contains_key(addr) {
+            if hosts.len() >= max_hosts {
+                return None;
+            }
+            hosts.insert(*addr, syn_flood_host::new(now_sec));
+        }
+
+        return hosts.get_mut(addr);
+    }
+
+    // @brief - returns the sources sending the most SYNs to a destination
+    //
+    // @param [in] host - destination
+    // @param [in] top_srcs - number of sources
+    //
+    // @return sources as addr(count) separated by ,
+    fn top_srcs_str(host : &syn_flood_host, top_srcs : usize) -> String {
+        let mut srcs : Vec<(&[u8; 16], &u32)> = host.srcs.iter().collect();
+        let mut top = String::new();
+
+        srcs.sort_by(|a, b| b.1.cmp(a.1));
+
+        for (addr, count) in srcs.iter().filter(|(_, count)| **count > 0).take(top_srcs) {
+            if top.len() > 0 {
+                top.push(',');
+            }
+            top.push_str(&format!("{}({})", ip_addr_str(addr), count));
+        }
+
+        return top;
+    }
+
+    // @brief - check the half open count of a host against the threshold
+    //
+    // @return true if the host crossed the threshold
+    fn check_host(host : &mut syn_flood_host, now_sec : u32, window_sec : u32,
+                  threshold : u32, half_open : &mut u32) -> bool {
+        *half_open = host.half_open(now_sec, window_sec);
+
+        // alert again once the flood has calmed down and comes back
+        if host.alerted {
+            if *half_open < threshold / 2 {
+                host.alerted = false;
+            }
+            return false;
+        }
+
+        if *half_open < threshold {
+            return false;
+        }
+
+        host.alerted = true;
+
+        return true;
+    }
+
+    // @brief - count a SYN that opens a connection
+    //
+    // @param [inout] self - SYN flood detector
+    // @param [in] src_addr - source address
+    // @param [in] dst_addr - destination address
+    // @param [in] now_sec - monotonic time
+    // @param [out] evt_mgr - event mgr
+    //
+    // @return 0 on success -1 if a flood is detected
+    pub fn add_syn(&mut self, src_addr : &[u8; 16], dst_addr : &[u8; 16],
+                   now_sec : u32, evt_mgr : &mut event_mgr) -> i32 {
+        let window_sec = self.limits.window_sec;
+        let mut half_open : u32 = 0;
+        let mut ret : i32 = 0;
+
+        self.expire(now_sec);
+
+        if let Some(host) = syn_flood::get_host(&mut self.dsts, dst_addr, now_sec, self.limits.max_hosts) {
+            host.last_sec = now_sec;
+            host.syns.add(now_sec, window_sec, 1);
+
+            if now_sec.wrapping_sub(host.srcs_window_start) >= window_sec {
+                host.srcs.clear();
+                host.srcs_window_start = now_sec;
+            }
+            if host.srcs.contains_key(src_addr) || (host.srcs.len() < syn_flood_host::MAX_SRCS) {
+                *host.srcs.entry(*src_addr).or_insert(0) += 1;
+            }
+
+            if syn_flood::check_host(host, now_sec, window_sec, self.limits.dst_threshold, &mut half_open) {
+                let details = vec![
+                    ("scope".to_string(), "dst".to_string()),
+                    ("target".to_string(), ip_addr_str(dst_addr)),
+                    ("half_open".to_string(), half_open.to_string()),
+                    ("window_sec".to_string(), window_sec.to_string()),
+                    ("top_srcs".to_string(), syn_flood::top_srcs_str(host, self.limits.top_srcs))
+                ];
+                evt_mgr.insert_evt_info_details(event_type::EVENT_TYPE_DENY,
+                                                event_desc::TCP_SYN_FLOOD, details);
+                ret = -1;
+            }
+        }
+
+        if let Some(host) = syn_flood::get_host(&mut self.srcs, src_addr, now_sec, self.limits.max_hosts) {
+            host.last_sec = now_sec;
+            host.syns.add(now_sec, window_sec, 1);
+
+            if syn_flood::check_host(host, now_sec, window_sec, self.limits.src_threshold, &mut half_open) {
+                let details = vec![
+                    ("scope".to_string(), "src".to_string()),
+                    ("top_srcs".to_string(), format!("{}({})", ip_addr_str(src_addr), half_open)),
+                    ("half_open".to_string(), half_open.to_string()),
+                    ("window_sec".to_string(), window_sec.to_string())
+                ];
+                evt_mgr.insert_evt_info_details(event_type::EVENT_TYPE_DENY,
+                                                event_desc::TCP_SYN_FLOOD, details);
+                ret = -1;
+            }
+        }
+
+        return ret;
+    }
+
+    // @brief - count a completed handshake
+    //
+    // @param [inout] self - SYN flood detector
+    // @param [in] src_addr - address of the side that sent the SYN
+    // @param [in] dst_addr - address of the side that answered
+    // @param [in] now_sec - monotonic time
+    pub fn add_done(&mut self, src_addr : &[u8; 16], dst_addr : &[u8; 16], now_sec : u32) {
+        let window_sec = self.limits.window_sec;
+
+        if let Some(host) = self.dsts.get_mut(dst_addr) {
+            host.done.add(now_sec, window_sec, 1);
+            if let Some(count) = host.srcs.get_mut(src_addr) {
+                *count = count.saturating_sub(1);
+            }
+        }
+
+        if let Some(host) = self.srcs.get_mut(src_addr) {
+            host.done.add(now_sec, window_sec, 1);
+        }
+    }
+
+    // @brief - forget every host
+    pub fn clear(&mut self) {
+        self.dsts.clear();
+        self.srcs.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::event_info::event_info;
+
+    fn addr(host : u8) -> [u8; 16] {
+        let mut addr = [0u8; 16];
+
+        addr[10] = 0xff;
+        addr[11] = 0xff;
+        addr[12..16].copy_from_slice(&[10, 0, 0, host]);
+        return addr;
+    }
+
+    fn detector(dst_threshold : u32, src_threshold : u32) -> syn_flood {
+        let mut detector = syn_flood::new();
+
+        detector.limits.dst_threshold = dst_threshold;
+        detector.limits.src_threshold = src_threshold;
+        return detector;
+    }
+
+    fn detail(evt : &event_info, key : &str) -> String {
+        return evt.details.iter().find(|(k, _)| k == key).unwrap().1.clone();
+    }
+
+    #[test]
+    fn dst_threshold() {
+        let mut evt_mgr = event_mgr::new();
+        let mut detector = detector(10, 100);
+
+        for host in 1..10 {
+            assert_eq!(detector.add_syn(&addr(host), &addr(200), 100, &mut evt_mgr), 0);
+        }
+        assert_eq!(detector.add_syn(&addr(1), &addr(200), 100, &mut evt_mgr), -1);
+
+        // one event until the flood calms down
+        assert_eq!(detector.add_syn(&addr(2), &addr(200), 100, &mut evt_mgr), 0);
+
+        let evts = evt_mgr.get_evt_list();
+        assert_eq!(evts.len(), 1);
+        assert_eq!(evts[0].event_desc, event_desc::TCP_SYN_FLOOD);
+        assert_eq!(detail(&evts[0], "scope"), "dst");
+        assert_eq!(detail(&evts[0], "target"), "10.0.0.200");
+        assert_eq!(detail(&evts[0], "half_open"), "10");
+        assert!(detail(&evts[0], "top_srcs").starts_with("10.0.0.1(2)"));
+    }
+
+    #[test]
+    fn src_threshold() {
+        let mut evt_mgr = event_mgr::new();
+        let mut detector = detector(100, 5);
+
+        for host in 1..5 {
+            assert_eq!(detector.add_syn(&addr(1), &addr(100 + host), 100, &mut evt_mgr), 0);
+        }
+        assert_eq!(detector.add_syn(&addr(1), &addr(200), 100, &mut evt_mgr), -1);
+
+        let evts = evt_mgr.get_evt_list();
+        assert_eq!(evts.len(), 1);
+        assert_eq!(detail(&evts[0], "scope"), "src");
+        assert_eq!(detail(&evts[0], "top_srcs"), "10.0.0.1(5)");
+    }
+
+    #[test]
+    fn completed_handshakes_not_counted() {
+        let mut evt_mgr = event_mgr::new();
+        let mut detector = detector(10, 10);
+
+        for _ in 0..50 {
+            assert_eq!(detector.add_syn(&addr(1), &addr(200), 100, &mut evt_mgr), 0);
+            detector.add_done(&addr(1), &addr(200), 100);
+        }
+        assert_eq!(evt_mgr.n_evts(), 0);
+    }
+
+    #[test]
+    fn alert_again_after_calm() {
+        let mut evt_mgr = event_mgr::new();
+        let mut detector = detector(10, 100);
+
+        for host in 1..=10 {
+            detector.add_syn(&addr(host), &addr(200), 100, &mut evt_mgr);
+        }
+        assert_eq!(evt_mgr.n_evts(), 1);
+
+        // the first window has almost slid out, the count drops below half
+        for host in 1..=8 {
+            assert_eq!(detector.add_syn(&addr(host), &addr(200), 119, &mut evt_mgr), 0);
+        }
+        assert_eq!(detector.add_syn(&addr(9), &addr(200), 119, &mut evt_mgr), -1);
+        assert_eq!(evt_mgr.n_evts(), 2);
+    }
+}
@@ -0,0 +1,186 @@
+// @brief - implements UDP serialize and deserializer
+// @copyright - 2024-present Devendra Naga All rights reserved
+#![allow(dead_code)]
+#![allow(non_camel_case_types)]
+
+use crate::{
+    events::{
+        event_desc::event_desc, event_mgr::event_mgr, event_type::event_type
+    },
+    lib::protocols::packet::packet::packet
+};
+
+// @brief - defines UDP header
+pub struct udp_hdr {
+    pub src_port        : u16, // 16 bits
+    pub dst_port        : u16, // 16 bits
+    pub length          : u16, // 16 bits
+    pub hdr_checksum    : u16  // 16 bits
+}
+
+impl udp_hdr {
+    pub const UDP_HDR_LEN : u32 = 8;
+    pub const REGISTERED_PORT_MIN : u16 = 1024;
+
+    // @brief - zero initialize UDP header
+    //
+    // @return returns zero initialized UDP header
+    #[inline(always)]
+    pub fn new() -> udp_hdr {
+        let udp_h = udp_hdr {
+            src_port        : 0,
+            dst_port        : 0,
+            length          : 0,
+            hdr_checksum    : 0
+        };
+        udp_h
+    }
+
+    // @brief - deserialize UDP header
+    //
+    // @param [inout] self - this struct
+    // @param [inout] p - packet
+    // @param [out] evt_mgr - event mgr
+    // @param [in] ip_payload_len - payload length given by the IP header
+    // @param [in] is_ipv6 - UDP is carried over IPv6
+    // @param [in] debug - debug frame
+    //
+    // @return 0 on success -1 on failure
+    pub fn deserialize(&mut self, p : &mut packet, evt_mgr : &mut event_mgr,
+                       ip_payload_len : u32, is_ipv6 : bool, debug : bool) -> i32 {
+        // check if the packet within the UDP header length
+        if !p.remaining_len_in_bounds(udp_hdr::UDP_HDR_LEN) {
+            evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY,
+                                    event_desc::UDP_SHORT_HDR_LEN);
+            return -1;
+        }
+
+        p.deserialize_2_bytes(&mut self.src_port);
+        p.deserialize_2_bytes(&mut self.dst_port);
+        evt_mgr.set_pkt_ports(self.src_port, self.dst_port);
+
+        if self.src_port == 0 {
+            evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY,
+                                    event_desc::UDP_SRC_PORT_ZERO);
+            return -1;
+        }
+
+        if self.dst_port == 0 {
+            evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY,
+                                    event_desc::UDP_DST_PORT_ZERO);
+            return -1;
+        }
+
+        p.deserialize_2_bytes(&mut self.length);
+        p.deserialize_2_bytes(&mut self.hdr_checksum);
+
+        // length covers the header and the data
+        if (self.length as u32) < udp_hdr::UDP_HDR_LEN {
+            evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY,
+                                    event_desc::UDP_LEN_TOO_SHORT);
+            return -1;
+        }
+
+        if (self.length as u32) > ip_payload_len {
+            evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY,
+                                    event_desc::UDP_LEN_EXCEEDS_IP_PAYLOAD);
+            return -1;
+        }
+
+        // checksum is mandatory over IPv6 (RFC 8200 section 8.1)
+        if is_ipv6 && (self.hdr_checksum == 0) {
+            evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY,
+                                    event_desc::UDP_IPV6_ZERO_CHECKSUM);
+            return -1;
+        }
+
+        if debug { self.print(); }
+
+        return 0;
+    }
+
+    // @brief - serialize UDP header
+    //
+    // @param [in] self - UDP header
+    // @param [out] p - packet
+    //
+    // @return 0 on success -1 on failure
+    pub fn serialize(&mut self, p : &mut packet) -> i32 {
+        p.serialize_2_bytes(&mut self.src_port);
+        p.serialize_2_bytes(&mut self.dst_port);
+        p.serialize_2_bytes(&mut self.length);
+        p.serialize_2_bytes(&mut self.hdr_checksum);
+
+        return 0;
+    }
+
+    // @brief - print UDP header
+    //
+    // @param [in] self - this structure
+    pub fn print(&self) {
+        log::info!("udp_hdr: ");
+        log::info!("\t src_port : {}", self.src_port);
+        log::info!("\t dst_port : {}", self.dst_port);
+        log::info!("\t length : {}", self.length);
+        log::info!("\t hdr_checksum : 0x{:02X}", self.hdr_checksum);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn udp(src_port : u16, dst_port : u16, length : u16, checksum : u16) -> packet {
+        let mut p = packet::with_buf_len(udp_hdr::UDP_HDR_LEN as usize);
+        let mut udp_h = udp_hdr {
+            src_port        : src_port,
+            dst_port        : dst_port,
+            length          : length,
+            hdr_checksum    : checksum
+        };
+
+        udp_h.serialize(&mut p);
+        p.off = 0;
+        p.pkt_len = udp_hdr::UDP_HDR_LEN as usize;
+        return p;
+    }
+
+    // @brief - deserialize and return the single event raised, 0 if none
+    fn check(mut p : packet, ip_payload_len : u32, is_ipv6 : bool) -> u32 {
+        let mut evt_mgr = event_mgr::new();
+        let mut udp_h = udp_hdr::new();
+        let ret = udp_h.deserialize(&mut p, &mut evt_mgr, ip_payload_len, is_ipv6, false);
+        let counts = evt_mgr.get_evt_desc_counts();
+
+        assert!(counts.len() <= 1);
+        match counts.keys().next() {
+            Some(desc) => {
+                assert_eq!(ret, -1);
+                return *desc;
+            }
+            None => {
+                assert_eq!(ret, 0);
+                return 0;
+            }
+        }
+    }
+
+    #[test]
+    fn valid() {
+        assert_eq!(check(udp(5353, 53, 20, 0), 20, false), 0);
+        assert_eq!(check(udp(5353, 53, 20, 0xABCD), 40, true), 0);
+    }
+
+    #[test]
+    fn anomalies() {
+        let mut p = udp(5353, 53, 8, 0);
+
+        p.pkt_len = 7;
+        assert_eq!(check(p, 8, false), event_desc::UDP_SHORT_HDR_LEN);
+        assert_eq!(check(udp(0, 53, 8, 0), 8, false), event_desc::UDP_SRC_PORT_ZERO);
+        assert_eq!(check(udp(5353, 0, 8, 0), 8, false), event_desc::UDP_DST_PORT_ZERO);
+        assert_eq!(check(udp(5353, 53, 7, 0), 8, false), event_desc::UDP_LEN_TOO_SHORT);
+        assert_eq!(check(udp(5353, 53, 21, 0), 20, false), event_desc::UDP_LEN_EXCEEDS_IP_PAYLOAD);
+        assert_eq!(check(udp(5353, 53, 8, 0), 8, true), event_desc::UDP_IPV6_ZERO_CHECKSUM);
+    }
+}
@@ -1,4 +1,6 @@
 pub(crate) mod packet;
+pub(crate) mod checksum;
 pub(crate) mod l2;
 pub(crate) mod l3;
-pub(crate) mod l4;
\ No newline at end of file
+pub(crate) mod l4;
+pub(crate) mod detect;
\ No newline at end of file
@@ -2,18 +2,29 @@
 #![allow(non_camel_case_types)]
 
 pub struct packet {
-    pub buf             : [u8; 2048],
+    pub buf             : Vec<u8>,
     buf_len             : usize,
     pub pkt_len         : usize,
     pub off             : usize
 }
 
 impl packet {
+    pub const DEFAULT_BUF_LEN : usize = 2048;
+
     #[inline(always)]
     pub fn new() -> packet {
+        return packet::with_buf_len(packet::DEFAULT_BUF_LEN);
+    }
+
+    // @brief - get a packet with a buffer of the given length
+    //
+    // @param [in] buf_len - buffer length
+    //
+    // @return packet
+    pub fn with_buf_len(buf_len : usize) -> packet {
         let p = packet {
-            buf             : [0; 2048],
-            buf_len         : 2048,
+            buf             : vec![0; buf_len],
+            buf_len         : buf_len,
             pkt_len         : 0,
             off             : 0
         };
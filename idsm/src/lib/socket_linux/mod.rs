@@ -1 +1,6 @@
 pub(crate) mod udp_client;
+pub(crate) mod tcp_client;
+pub(crate) mod unix_client;
+pub(crate) mod unix_server;
+pub(crate) mod stream_conn;
+pub(crate) mod tcp_server;
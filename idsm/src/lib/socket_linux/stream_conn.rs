@@ -0,0 +1,168 @@
+#![allow(dead_code)]
+#![allow(non_camel_case_types)]
+
+use crate::lib::time_linux::timestamp::{get_monotonic, timestamp};
+
+// @brief - defines an accepted stream connection
+pub struct stream_conn {
+    pub fd : i32
+}
+
+impl stream_conn {
+    pub fn new(fd : i32) -> stream_conn {
+        let conn = stream_conn {
+            fd : fd
+        };
+        conn
+    }
+
+    // @brief - set the receive and send timeout of the connection
+    //
+    // @param [in] self - connection
+    // @param [in] timeout_sec - timeout in seconds
+    pub fn set_timeout(&mut self, timeout_sec : u32) {
+        let tv = libc::timeval {
+            tv_sec : timeout_sec as libc::time_t,
+            tv_usec : 0
+        };
+
+        unsafe {
+            libc::setsockopt(self.fd,
+                             libc::SOL_SOCKET,
+                             libc::SO_RCVTIMEO,
+                             &tv as *const libc::timeval as *const libc::c_void,
+                             std::mem::size_of_val(&tv) as u32);
+            libc::setsockopt(self.fd,
+                             libc::SOL_SOCKET,
+                             libc::SO_SNDTIMEO,
+                             &tv as *const libc::timeval as *const libc::c_void,
+                             std::mem::size_of_val(&tv) as u32);
+        }
+    }
+
+    // @brief - receive a message
+    //
+    // @param [in] self - connection
+    // @param [out] buf - received message
+    // @param [in] buf_len - length of the buffer
+    //
+    // @return received length, 0 if the peer closed, -1 on failure
+    pub fn recv(&mut self, buf : &mut [u8], buf_len : usize) -> isize {
+        unsafe {
+            return libc::recv(self.fd,
+                              buf as *mut [u8] as *mut libc::c_void,
+                              buf_len,
+                              0);
+        }
+    }
+
+    // @brief - receive a request within a deadline
+    //
+    // @param [in] self - connection
+    // @param [out] buf - received request
+    // @param [in] timeout_ms - time allowed for the whole request
+    // @param [in] max_recvs - most receive calls made for the request
+    // @param [in] is_complete - true once the received bytes hold the request
+    //
+    // @return received length
+    //
+    // @details - the deadline and the number of calls bound the time a slow
+    //            client holds the caller, however it splits the request.
+    pub fn recv_request(&mut self, buf : &mut [u8], timeout_ms : u32, max_recvs : u32,
+                        is_complete : fn(&[u8]) -> bool) -> usize {
+        let mut len : usize = 0;
+        let mut now = timestamp::new();
+
+        if get_monotonic(&mut now) < 0 {
+            return 0;
+        }
+        let deadline_ms = stream_conn::to_ms(&now) + timeout_ms as u64;
+
+        for _ in 0..max_recvs {
+            if (len >= buf.len()) || (get_monotonic(&mut now) < 0) {
+                break;
+            }
+
+            let now_ms = stream_conn::to_ms(&now);
+            if now_ms >= deadline_ms {
+                break;
+            }
+
+            let rx_len : isize;
+            unsafe {
+                let mut pfd = libc::pollfd {
+                    fd : self.fd,
+                    events : libc::POLLIN,
+                    revents : 0
+                };
+
+                if libc::poll(&mut pfd, 1, (deadline_ms - now_ms) as i32) <= 0 {
+                    break;
+                }
+
+                rx_len = libc::recv(self.fd,
+                                    buf[len..].as_mut_ptr() as *mut libc::c_void,
+                                    buf.len() - len,
+                                    libc::MSG_DONTWAIT);
+            }
+            if rx_len <= 0 {
+                break;
+            }
+
+            len += rx_len as usize;
+            if is_complete(&buf[..len]) {
+                break;
+            }
+        }
+
+        return len;
+    }
+
+    fn to_ms(t : &timestamp) -> u64 {
+        return (t.sec as u64 * 1000) + (t.usec as u64 / 1000);
+    }
+
+    // @brief - send the whole message
+    //
+    // @param [in] self - connection
+    // @param [in] msg - message
+    // @param [in] msg_len - length of the message
+    //
+    // @return msg_len on success -1 on failure
+    pub fn send(&mut self, msg : &[u8], msg_len : usize) -> isize {
+        let mut off : usize = 0;
+
+        while off < msg_len {
+            let ret : isize;
+
+            unsafe {
+                ret = libc::send(self.fd,
+                                 msg[off..].as_ptr() as *const libc::c_void,
+                                 msg_len - off,
+                                 libc::MSG_NOSIGNAL);
+            }
+            if ret <= 0 {
+                return -1;
+            }
+
+            off += ret as usize;
+        }
+
+        return msg_len as isize;
+    }
+
+    pub fn close(&mut self) {
+        unsafe {
+            if self.fd >= 0 {
+                libc::close(self.fd);
+            }
+        }
+        self.fd = -1;
+    }
+}
+
+impl Drop for stream_conn {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
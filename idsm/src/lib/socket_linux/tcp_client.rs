@@ -0,0 +1,225 @@
+#![allow(dead_code)]
+#![allow(non_camel_case_types)]
+
+use std::ffi::CString;
+use std::mem::MaybeUninit;
+
+use crate::lib::time_linux::timestamp::{get_monotonic, timestamp};
+
+use super::udp_client::inet_aton;
+
+pub struct tcp_client {
+    pub fd : i32,
+    // connect is in progress on the non-blocking socket
+    connecting : bool,
+    connect_start_sec : u32,
+    timeout_sec : u32
+}
+
+impl tcp_client {
+    pub fn new() -> tcp_client {
+        let client = tcp_client {
+            fd : -1,
+            connecting : false,
+            connect_start_sec : 0,
+            timeout_sec : 0
+        };
+        client
+    }
+
+    // @brief - returns current monotonic time in seconds
+    fn now_sec() -> u32 {
+        let mut now = timestamp::new();
+
+        get_monotonic(&mut now);
+        return now.sec;
+    }
+
+    // @brief - switch the connected socket back to blocking sends
+    //
+    // @param [in] self - TCP client
+    fn set_connected(&mut self) {
+        self.connecting = false;
+
+        unsafe {
+            let flags = libc::fcntl(self.fd, libc::F_GETFL);
+            libc::fcntl(self.fd, libc::F_SETFL, flags & !libc::O_NONBLOCK);
+
+            if self.timeout_sec > 0 {
+                let tv = libc::timeval {
+                    tv_sec : self.timeout_sec as libc::time_t,
+                    tv_usec : 0
+                };
+
+                libc::setsockopt(self.fd,
+                                 libc::SOL_SOCKET,
+                                 libc::SO_SNDTIMEO,
+                                 &tv as *const libc::timeval as *const libc::c_void,
+                                 std::mem::size_of_val(&tv) as u32);
+            }
+        }
+    }
+
+    // @brief - create a TCP socket and start connecting to the server
+    //
+    // @param [in] self - TCP client
+    // @param [in] dest - server IPv4 address
+    // @param [in] dest_port - server port
+    // @param [in] timeout_sec - connect and send timeout, 0 to wait forever
+    //
+    // @return 0 if connected, 1 if the connect is in progress, -1 on failure
+    //
+    // @details - the connect does not block, finish_connect completes it.
+    pub fn start_connect(&mut self, dest : &String, dest_port : u32, timeout_sec : u32) -> i32 {
+        let dest_str = match CString::new(dest.as_str()) {
+            Ok(s) => s,
+            Err(_) => return -1,
+        };
+
+        self.close();
+
+        unsafe {
+            let mut ret : i32;
+            let mut addr : libc::sockaddr_in = MaybeUninit::zeroed().assume_init();
+
+            addr.sin_family = libc::AF_INET as u16;
+            addr.sin_port = (dest_port as u16).to_be();
+            ret = inet_aton(dest_str.as_ptr(), &mut addr.sin_addr);
+            if ret == 0 {
+                return -1;
+            }
+
+            self.fd = libc::socket(libc::AF_INET, libc::SOCK_STREAM | libc::SOCK_NONBLOCK, 0);
+            if self.fd < 0 {
+                return -1;
+            }
+
+            self.timeout_sec = timeout_sec;
+
+            ret = libc::connect(self.fd,
+                                &addr as *const libc::sockaddr_in as *const libc::sockaddr,
+                                std::mem::size_of_val(&addr) as u32);
+            if ret < 0 {
+                if *libc::__errno_location() != libc::EINPROGRESS {
+                    self.close();
+                    return -1;
+                }
+
+                self.connecting = true;
+                self.connect_start_sec = tcp_client::now_sec();
+                return 1;
+            }
+        }
+
+        self.set_connected();
+
+        return 0;
+    }
+
+    // @brief - check if the connect in progress has completed
+    //
+    // @param [in] self - TCP client
+    //
+    // @return 0 if connected, 1 if the connect is still in progress, -1 on failure
+    //
+    // @details - the socket is closed if the connect fails or times out.
+    pub fn finish_connect(&mut self) -> i32 {
+        if !self.connecting {
+            if self.fd >= 0 {
+                return 0;
+            }
+            return -1;
+        }
+
+        unsafe {
+            let mut pfd = libc::pollfd {
+                fd : self.fd,
+                events : libc::POLLOUT,
+                revents : 0
+            };
+
+            let ret = libc::poll(&mut pfd, 1, 0);
+            if ret < 0 {
+                self.close();
+                return -1;
+            }
+
+            if ret == 0 {
+                if (self.timeout_sec > 0) &&
+                   (tcp_client::now_sec().wrapping_sub(self.connect_start_sec) >= self.timeout_sec) {
+                    self.close();
+                    return -1;
+                }
+                return 1;
+            }
+
+            let mut err : i32 = 0;
+            let mut err_len = std::mem::size_of_val(&err) as libc::socklen_t;
+
+            let ret = libc::getsockopt(self.fd,
+                                       libc::SOL_SOCKET,
+                                       libc::SO_ERROR,
+                                       &mut err as *mut i32 as *mut libc::c_void,
+                                       &mut err_len);
+            if (ret < 0) || (err != 0) {
+                self.close();
+                return -1;
+            }
+        }
+
+        self.set_connected();
+
+        return 0;
+    }
+
+    // @brief - check if the client is connected
+    pub fn is_connected(&self) -> bool { return (self.fd >= 0) && !self.connecting; }
+
+    // @brief - check if the connect is in progress
+    pub fn is_connecting(&self) -> bool { return (self.fd >= 0) && self.connecting; }
+
+    // @brief - send the whole message
+    //
+    // @param [in] self - TCP client
+    // @param [in] msg - message
+    // @param [in] msg_len - length of the message
+    //
+    // @return msg_len on success -1 on failure
+    pub fn send(&mut self, msg : &[u8], msg_len : usize) -> isize {
+        let mut off : usize = 0;
+
+        while off < msg_len {
+            let ret : isize;
+
+            unsafe {
+                ret = libc::send(self.fd,
+                                 msg[off..].as_ptr() as *const libc::c_void,
+                                 msg_len - off,
+                                 libc::MSG_NOSIGNAL);
+            }
+            if ret <= 0 {
+                return -1;
+            }
+
+            off += ret as usize;
+        }
+
+        return msg_len as isize;
+    }
+
+    pub fn close(&mut self) {
+        unsafe {
+            if self.fd >= 0 {
+                libc::close(self.fd);
+            }
+        }
+        self.fd = -1;
+        self.connecting = false;
+    }
+}
+
+impl Drop for tcp_client {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
@@ -0,0 +1,119 @@
+#![allow(dead_code)]
+#![allow(non_camel_case_types)]
+
+use std::ffi::CString;
+use std::mem::MaybeUninit;
+
+use super::stream_conn::stream_conn;
+use super::udp_client::inet_aton;
+
+pub struct tcp_server {
+    pub fd : i32
+}
+
+impl tcp_server {
+    const BACKLOG : i32 = 8;
+
+    pub fn new() -> tcp_server {
+        let server = tcp_server {
+            fd : -1
+        };
+        server
+    }
+
+    // @brief - create a TCP socket listening on the address
+    //
+    // @param [in] self - TCP server
+    // @param [in] ipaddr - IPv4 address to listen on
+    // @param [in] port - port to listen on
+    //
+    // @return 0 on success -1 on failure
+    pub fn create(&mut self, ipaddr : &String, port : u32) -> i32 {
+        let ipaddr_str = match CString::new(ipaddr.as_str()) {
+            Ok(s) => s,
+            Err(_) => return -1,
+        };
+
+        self.close();
+
+        unsafe {
+            let mut ret : i32;
+            let mut addr : libc::sockaddr_in = MaybeUninit::zeroed().assume_init();
+            let reuse : i32 = 1;
+
+            addr.sin_family = libc::AF_INET as u16;
+            addr.sin_port = (port as u16).to_be();
+            ret = inet_aton(ipaddr_str.as_ptr(), &mut addr.sin_addr);
+            if ret == 0 {
+                return -1;
+            }
+
+            self.fd = libc::socket(libc::AF_INET, libc::SOCK_STREAM, 0);
+            if self.fd < 0 {
+                return -1;
+            }
+
+            // allow restarting while old connections are in TIME_WAIT
+            libc::setsockopt(self.fd,
+                             libc::SOL_SOCKET,
+                             libc::SO_REUSEADDR,
+                             &reuse as *const i32 as *const libc::c_void,
+                             std::mem::size_of_val(&reuse) as u32);
+
+            ret = libc::bind(self.fd,
+                             &addr as *const libc::sockaddr_in as *const libc::sockaddr,
+                             std::mem::size_of_val(&addr) as u32);
+            if ret < 0 {
+                self.close();
+                return -1;
+            }
+
+            ret = libc::listen(self.fd, tcp_server::BACKLOG);
+            if ret < 0 {
+                self.close();
+                return -1;
+            }
+        }
+
+        return 0;
+    }
+
+    pub fn get(&self) -> i32 { return self.fd; }
+
+    // @brief - accept a connection
+    //
+    // @param [in] self - TCP server
+    // @param [out] conn - accepted connection
+    //
+    // @return 0 on success -1 on failure
+    pub fn accept(&mut self, conn : &mut stream_conn) -> i32 {
+        let fd : i32;
+
+        unsafe {
+            fd = libc::accept(self.fd, std::ptr::null_mut(), std::ptr::null_mut());
+        }
+        if fd < 0 {
+            return -1;
+        }
+
+        conn.close();
+        conn.fd = fd;
+
+        return 0;
+    }
+
+    pub fn close(&mut self) {
+        unsafe {
+            if self.fd >= 0 {
+                libc::close(self.fd);
+            }
+        }
+        self.fd = -1;
+    }
+}
+
+impl Drop for tcp_server {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
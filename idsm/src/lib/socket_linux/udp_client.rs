@@ -1,6 +1,7 @@
 #![allow(dead_code)]
 #![allow(non_camel_case_types)]
 
+use std::ffi::CString;
 use std::mem::MaybeUninit;
 
 extern "C" {
@@ -33,15 +34,22 @@ impl udp_client {
     }
 
     pub fn sendto(&mut self, msg : &[u8], msg_len : usize, dest : &String, dest_port : u32) -> isize {
+        let dest_str = match CString::new(dest.as_str()) {
+            Ok(s) => s,
+            Err(_) => return -1,
+        };
+
         unsafe {
             let ret : isize;
             let res : i32;
             let mut addr : libc::sockaddr_in = MaybeUninit::zeroed().assume_init();
 
             addr.sin_family = libc::AF_INET as u16;
-            addr.sin_port = dest_port as u16;
-            res = inet_aton(dest.as_ptr() as *const libc::c_char, &mut addr.sin_addr);
-            if res < 0 {
+            addr.sin_port = (dest_port as u16).to_be();
+
+            // inet_aton returns 0 if the address is invalid
+            res = inet_aton(dest_str.as_ptr(), &mut addr.sin_addr);
+            if res == 0 {
                 return -1;
             }
 
@@ -54,4 +62,19 @@ impl udp_client {
             return ret;
         }
     }
+
+    pub fn close(&mut self) {
+        unsafe {
+            if self.fd > 0 {
+                libc::close(self.fd);
+            }
+        }
+        self.fd = -1;
+    }
+}
+
+impl Drop for udp_client {
+    fn drop(&mut self) {
+        self.close();
+    }
 }
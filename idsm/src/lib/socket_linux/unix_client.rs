@@ -0,0 +1,120 @@
+#![allow(dead_code)]
+#![allow(non_camel_case_types)]
+
+use std::mem::MaybeUninit;
+
+use crate::lib::c_lib::c_strcpy;
+
+pub struct unix_client {
+    pub fd : i32
+}
+
+impl unix_client {
+    pub fn new() -> unix_client {
+        let client = unix_client {
+            fd : -1
+        };
+        client
+    }
+
+    // @brief - create a unix datagram socket and connect to the path
+    //
+    // @param [in] self - unix client
+    // @param [in] path - path of the server socket
+    //
+    // @return 0 on success -1 on failure
+    pub fn connect(&mut self, path : &String) -> i32 {
+        return self.connect_type(path, libc::SOCK_DGRAM);
+    }
+
+    // @brief - create a unix stream socket and connect to the path
+    //
+    // @param [in] self - unix client
+    // @param [in] path - path of the server socket
+    //
+    // @return 0 on success -1 on failure
+    pub fn connect_stream(&mut self, path : &String) -> i32 {
+        return self.connect_type(path, libc::SOCK_STREAM);
+    }
+
+    fn connect_type(&mut self, path : &String, sock_type : i32) -> i32 {
+        self.close();
+
+        unsafe {
+            let ret : i32;
+            let mut addr : libc::sockaddr_un = MaybeUninit::zeroed().assume_init();
+
+            if path.len() >= addr.sun_path.len() {
+                return -1;
+            }
+
+            addr.sun_family = libc::AF_UNIX as u16;
+            c_strcpy::c_strcpy(&mut addr.sun_path, path.as_str());
+
+            self.fd = libc::socket(libc::AF_UNIX, sock_type, 0);
+            if self.fd < 0 {
+                return -1;
+            }
+
+            ret = libc::connect(self.fd,
+                                &addr as *const libc::sockaddr_un as *const libc::sockaddr,
+                                std::mem::size_of_val(&addr) as u32);
+            if ret < 0 {
+                self.close();
+                return -1;
+            }
+        }
+
+        return 0;
+    }
+
+    // @brief - send a message
+    //
+    // @param [in] self - unix client
+    // @param [in] msg - message
+    // @param [in] msg_len - length of the message
+    //
+    // @return sent length on success -1 on failure
+    pub fn send(&mut self, msg : &[u8], msg_len : usize) -> isize {
+        unsafe {
+            return libc::send(self.fd,
+                              msg as *const [u8] as *const libc::c_void,
+                              msg_len,
+                              libc::MSG_NOSIGNAL);
+        }
+    }
+
+    // @brief - receive a message
+    //
+    // @param [in] self - unix client
+    // @param [out] buf - received message
+    // @param [in] buf_len - length of the buffer
+    //
+    // @return received length, 0 if the server closed, -1 on failure
+    pub fn recv(&mut self, buf : &mut [u8], buf_len : usize) -> isize {
+        unsafe {
+            return libc::recv(self.fd,
+                              buf as *mut [u8] as *mut libc::c_void,
+                              buf_len,
+                              0);
+        }
+    }
+
+    // @brief - check if the client is connected
+    pub fn is_connected(&self) -> bool { return self.fd >= 0; }
+
+    pub fn close(&mut self) {
+        unsafe {
+            if self.fd >= 0 {
+                libc::close(self.fd);
+            }
+        }
+        self.fd = -1;
+    }
+}
+
+impl Drop for unix_client {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
@@ -0,0 +1,117 @@
+#![allow(dead_code)]
+#![allow(non_camel_case_types)]
+
+use std::mem::MaybeUninit;
+
+use crate::lib::c_lib::c_strcpy;
+use crate::lib::fileio::fileio::fileio;
+
+use super::stream_conn::stream_conn;
+
+pub struct unix_server {
+    pub fd : i32,
+    path : String
+}
+
+impl unix_server {
+    const BACKLOG : i32 = 8;
+
+    pub fn new() -> unix_server {
+        let server = unix_server {
+            fd : -1,
+            path : "".to_string()
+        };
+        server
+    }
+
+    // @brief - create a unix stream socket listening on the path
+    //
+    // @param [in] self - unix server
+    // @param [in] path - path of the socket, a stale socket is removed
+    //
+    // @return 0 on success -1 on failure
+    pub fn create(&mut self, path : &String) -> i32 {
+        self.close();
+
+        unsafe {
+            let mut ret : i32;
+            let mut addr : libc::sockaddr_un = MaybeUninit::zeroed().assume_init();
+
+            if path.len() >= addr.sun_path.len() {
+                return -1;
+            }
+
+            addr.sun_family = libc::AF_UNIX as u16;
+            c_strcpy::c_strcpy(&mut addr.sun_path, path.as_str());
+
+            self.fd = libc::socket(libc::AF_UNIX, libc::SOCK_STREAM, 0);
+            if self.fd < 0 {
+                return -1;
+            }
+
+            fileio::unlink(path);
+
+            ret = libc::bind(self.fd,
+                             &addr as *const libc::sockaddr_un as *const libc::sockaddr,
+                             std::mem::size_of_val(&addr) as u32);
+            if ret < 0 {
+                self.close();
+                return -1;
+            }
+
+            ret = libc::listen(self.fd, unix_server::BACKLOG);
+            if ret < 0 {
+                self.close();
+                return -1;
+            }
+        }
+
+        self.path = path.clone();
+
+        return 0;
+    }
+
+    pub fn get(&self) -> i32 { return self.fd; }
+
+    // @brief - accept a connection
+    //
+    // @param [in] self - unix server
+    // @param [out] conn - accepted connection
+    //
+    // @return 0 on success -1 on failure
+    pub fn accept(&mut self, conn : &mut stream_conn) -> i32 {
+        let fd : i32;
+
+        unsafe {
+            fd = libc::accept(self.fd, std::ptr::null_mut(), std::ptr::null_mut());
+        }
+        if fd < 0 {
+            return -1;
+        }
+
+        conn.close();
+        conn.fd = fd;
+
+        return 0;
+    }
+
+    pub fn close(&mut self) {
+        unsafe {
+            if self.fd >= 0 {
+                libc::close(self.fd);
+            }
+        }
+        self.fd = -1;
+
+        if self.path.len() > 0 {
+            fileio::unlink(&self.path);
+            self.path.clear();
+        }
+    }
+}
+
+impl Drop for unix_server {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
@@ -44,6 +44,31 @@ pub fn gmtime_linux(t : &mut tm_linux) -> i32 {
     return 0;
 }
 
+// @brief - convert seconds since epoch to UTC time
+//
+// @param [in] sec - seconds since epoch
+// @param [out] t - UTC time
+//
+// @return 0 on success -1 on failure
+pub fn gmtime_linux_sec(sec : u32, t : &mut tm_linux) -> i32 {
+    unsafe {
+        let now = sec as libc::time_t;
+        let mut tm : libc::tm = std::mem::MaybeUninit::zeroed().assume_init();
+
+        if libc::gmtime_r(&now, &mut tm).is_null() {
+            return -1;
+        }
+
+        t.year          = (tm.tm_year + 1900) as u32;
+        t.mon           = (tm.tm_mon + 1) as u32;
+        t.day           = tm.tm_mday as u32;
+        t.hour          = tm.tm_hour as u32;
+        t.min           = tm.tm_min as u32;
+        t.sec           = tm.tm_sec as u32;
+    }
+    return 0;
+}
+
 pub fn gmtime_filename(file_prefix : &String,
                        file_ext : &String,
                        filename : &mut String) -> i32 {
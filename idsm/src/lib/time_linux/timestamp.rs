@@ -1,3 +1,4 @@
+#![allow(dead_code)]
 #![allow(non_camel_case_types)]
 
 use std::mem::MaybeUninit;
@@ -32,3 +33,24 @@ pub fn get_wallclock(t : &mut timestamp) -> i32 {
 
     return 0;
 }
+
+// @brief - get the time elapsed since an unspecified point in the past
+//
+// @param [out] t - monotonic time, unaffected by wallclock changes
+//
+// @return 0 on success -1 on failure
+pub fn get_monotonic(t : &mut timestamp) -> i32 {
+    let ret : i32;
+
+    unsafe {
+        let mut tp : libc::timespec = MaybeUninit::zeroed().assume_init();
+        ret = libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut tp);
+        if ret < 0 {
+            return -1;
+        }
+        t.sec = tp.tv_sec as u32;
+        t.usec = (tp.tv_nsec / 1000) as u32;
+    }
+
+    return 0;
+}
@@ -8,6 +8,7 @@ mod events;
 mod config;
 mod core;
 mod stats;
+mod transport;
 
 fn main() {
     env_logger::init();
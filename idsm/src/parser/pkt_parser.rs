@@ -3,17 +3,23 @@
 #![allow(non_camel_case_types)]
 
 use crate::{
-    events::event_mgr::event_mgr,
+    config::config_parser::idsm_config,
+    events::{event_desc::event_desc, event_mgr::event_mgr, event_type::event_type},
     lib::protocols::{
         l2::{
-            arp, eth, ethertypes::Ethertypes, vlan
+            arp, arp_guard, eth, ethertypes::Ethertypes, vlan
         },
         l3::{
-            ipv4, ipv6, protocol_types::ProtocolTypes
+            ipv4, ipv4_reasm, ipv6, ipv6_ext, ipv6_reasm, protocol_types::ProtocolTypes
         },
-        l4::{icmp6, tcp},
+        checksum::inet_checksum::{
+            inet_checksum_add, inet_checksum_ok, inet_checksum_pseudo_ipv4, inet_checksum_pseudo_ipv6
+        },
+        l4::{icmp, icmp6, nd_guard, port_scan, tcp, tcp_conn, tcp_syn_flood, udp},
         packet::packet::packet
-    }, stats::stats_mgr
+    },
+    lib::time_linux::timestamp::{get_monotonic, timestamp},
+    stats::stats_mgr
 };
 
 // @brief - defines a group of protocol headers and some
@@ -25,14 +31,35 @@ pub struct pkt_parser {
     vh          : vlan::vlan_hdr,
     ipv4_h      : ipv4::ipv4_hdr,
     ipv6_h      : ipv6::ipv6_hdr,
+    ipv6_ext_h  : ipv6_ext::ipv6_ext_hdrs,
     tcp_h       : tcp::tcp_hdr,
+    udp_h       : udp::udp_hdr,
+    icmp_h      : icmp::icmp_hdr,
     icmp6_h     : icmp6::icmp6_hdr,
     has_vlan_h  : bool,
     has_ipv4_h  : bool,
     has_ipv6_h  : bool,
     has_tcp_h   : bool,
+    has_udp_h   : bool,
+    has_icmp_h  : bool,
     has_icmp6_h : bool,
     ethertype   : u16,
+    ipv4_checks : ipv4::ipv4_checks,
+    verify_l4_checksum : bool,
+    ipv4_reasm_enable : bool,
+    ipv4_reasm  : ipv4_reasm::ipv4_reasm,
+    ipv6_reasm_enable : bool,
+    ipv6_reasm  : ipv6_reasm::ipv6_reasm,
+    tcp_conn_track_enable : bool,
+    tcp_conns   : tcp_conn::tcp_conn_table,
+    syn_flood_enable : bool,
+    syn_flood   : tcp_syn_flood::syn_flood,
+    port_scan_enable : bool,
+    port_scan   : port_scan::port_scan,
+    arp_guard_enable : bool,
+    arp_guard   : arp_guard::arp_guard,
+    nd_guard_enable : bool,
+    nd_guard    : nd_guard::nd_guard,
 }
 
 impl pkt_parser {
@@ -46,18 +73,221 @@ impl pkt_parser {
             vh          : vlan::vlan_hdr::new(),
             ipv4_h      : ipv4::ipv4_hdr::new(),
             ipv6_h      : ipv6::ipv6_hdr::new(),
+            ipv6_ext_h  : ipv6_ext::ipv6_ext_hdrs::new(),
             tcp_h       : tcp::tcp_hdr::new(),
+            udp_h       : udp::udp_hdr::new(),
+            icmp_h      : icmp::icmp_hdr::new(),
             icmp6_h     : icmp6::icmp6_hdr::new(),
             has_vlan_h  : false,
             has_ipv4_h  : false,
             has_ipv6_h  : false,
             has_tcp_h   : false,
+            has_udp_h   : false,
+            has_icmp_h  : false,
             has_icmp6_h : false,
-            ethertype   : 0
+            ethertype   : 0,
+            ipv4_checks : ipv4::ipv4_checks::new(),
+            verify_l4_checksum : true,
+            ipv4_reasm_enable : true,
+            ipv4_reasm  : ipv4_reasm::ipv4_reasm::new(),
+            ipv6_reasm_enable : true,
+            ipv6_reasm  : ipv6_reasm::ipv6_reasm::new(),
+            tcp_conn_track_enable : true,
+            tcp_conns   : tcp_conn::tcp_conn_table::new(),
+            syn_flood_enable : true,
+            syn_flood   : tcp_syn_flood::syn_flood::new(),
+            port_scan_enable : true,
+            port_scan   : port_scan::port_scan::new(),
+            arp_guard_enable : true,
+            arp_guard   : arp_guard::arp_guard::new(),
+            nd_guard_enable : true,
+            nd_guard    : nd_guard::nd_guard::new()
         };
         parser
     }
 
+    // @brief - apply the protocol checks of the config
+    //
+    // @param [in] self - pkt_parser
+    // @param [in] config_data - idsm config
+    pub fn set_config(&mut self, config_data : &idsm_config) {
+        let ipv4_config = &config_data.protocols_config.ipv4_config;
+
+        self.verify_l4_checksum = config_data.protocols_config.verify_l4_checksum;
+
+        self.ipv4_checks.checksum = ipv4_config.check_checksum;
+        self.ipv4_checks.total_len = ipv4_config.check_total_len;
+        self.ipv4_checks.ttl = ipv4_config.check_ttl;
+        self.ipv4_checks.min_ttl = ipv4_config.min_ttl;
+        self.ipv4_checks.src_addr = ipv4_config.check_src_addr;
+        self.ipv4_checks.land = ipv4_config.check_land;
+
+        self.ipv4_reasm_enable = ipv4_config.reasm_config.enable;
+        self.ipv4_reasm.limits.timeout_sec = ipv4_config.reasm_config.timeout_sec;
+        self.ipv4_reasm.limits.max_datagrams = ipv4_config.reasm_config.max_datagrams as usize;
+        self.ipv4_reasm.limits.max_mem_bytes = ipv4_config.reasm_config.max_mem_bytes as usize;
+        if !self.ipv4_reasm_enable {
+            self.ipv4_reasm.clear();
+        }
+
+        let ipv6_config = &config_data.protocols_config.ipv6_config;
+
+        self.ipv6_ext_h.max_hdrs = ipv6_config.max_ext_hdrs;
+        self.ipv6_reasm_enable = ipv6_config.reasm_config.enable;
+        self.ipv6_reasm.limits.timeout_sec = ipv6_config.reasm_config.timeout_sec;
+        self.ipv6_reasm.limits.max_datagrams = ipv6_config.reasm_config.max_datagrams as usize;
+        self.ipv6_reasm.limits.max_mem_bytes = ipv6_config.reasm_config.max_mem_bytes as usize;
+        if !self.ipv6_reasm_enable {
+            self.ipv6_reasm.clear();
+        }
+
+        let conn_track_config = &config_data.protocols_config.tcp_config.conn_track_config;
+
+        self.tcp_conn_track_enable = conn_track_config.enable;
+        self.tcp_conns.limits.midstream = conn_track_config.midstream;
+        self.tcp_conns.limits.max_conns = conn_track_config.max_conns as usize;
+        self.tcp_conns.limits.syn_timeout_sec = conn_track_config.syn_timeout_sec;
+        self.tcp_conns.limits.established_timeout_sec = conn_track_config.established_timeout_sec;
+        self.tcp_conns.limits.fin_timeout_sec = conn_track_config.fin_timeout_sec;
+        self.tcp_conns.limits.close_timeout_sec = conn_track_config.close_timeout_sec;
+        if !self.tcp_conn_track_enable {
+            self.tcp_conns.clear();
+        }
+
+        let syn_flood_config = &config_data.protocols_config.tcp_config.syn_flood_config;
+
+        self.syn_flood_enable = syn_flood_config.enable;
+        self.syn_flood.limits.window_sec = syn_flood_config.window_sec;
+        self.syn_flood.limits.dst_threshold = syn_flood_config.dst_threshold;
+        self.syn_flood.limits.src_threshold = syn_flood_config.src_threshold;
+        self.syn_flood.limits.top_srcs = syn_flood_config.top_srcs as usize;
+        self.syn_flood.limits.max_hosts = syn_flood_config.max_hosts as usize;
+        self.syn_flood.clear();
+
+        let port_scan_config = &config_data.protocols_config.port_scan_config;
+
+        self.port_scan_enable = port_scan_config.enable;
+        self.port_scan.limits.window_sec = port_scan_config.window_sec;
+        self.port_scan.limits.port_threshold = port_scan_config.port_threshold;
+        self.port_scan.limits.host_threshold = port_scan_config.host_threshold;
+        self.port_scan.limits.max_srcs = port_scan_config.max_srcs as usize;
+        self.port_scan.limits.max_probes = port_scan_config.max_probes as usize;
+        self.port_scan.clear();
+
+        let arp_guard_config = &config_data.protocols_config.arp_guard_config;
+
+        self.arp_guard_enable = arp_guard_config.enable;
+        self.arp_guard.limits.max_bindings = arp_guard_config.max_bindings as usize;
+        self.arp_guard.limits.binding_timeout_sec = arp_guard_config.binding_timeout_sec;
+        self.arp_guard.limits.request_timeout_sec = arp_guard_config.request_timeout_sec;
+        self.arp_guard.limits.garp_window_sec = arp_guard_config.garp_window_sec;
+        self.arp_guard.limits.garp_threshold = arp_guard_config.garp_threshold;
+        self.arp_guard.clear();
+        for (ipaddr, mac) in &arp_guard_config.static_bindings {
+            self.arp_guard.add_static(*ipaddr, mac);
+        }
+
+        let nd_guard_config = &config_data.protocols_config.nd_guard_config;
+
+        self.nd_guard_enable = nd_guard_config.enable;
+        self.nd_guard.limits.max_bindings = nd_guard_config.max_bindings as usize;
+        self.nd_guard.limits.binding_timeout_sec = nd_guard_config.binding_timeout_sec;
+        self.nd_guard.limits.dad_timeout_sec = nd_guard_config.dad_timeout_sec;
+        self.nd_guard.ra_guard = nd_guard_config.ra_guard_config.enable;
+        self.nd_guard.clear();
+        for mac in &nd_guard_config.ra_guard_config.allowed_macs {
+            self.nd_guard.allow_router_mac(mac);
+        }
+        for addr in &nd_guard_config.ra_guard_config.allowed_link_locals {
+            self.nd_guard.allow_router_addr(addr);
+        }
+    }
+
+    // @brief - forget the headers of the previous packet
+    //
+    // @param [in] self - pkt_parser
+    fn reset(&mut self) {
+        self.has_vlan_h = false;
+        self.has_ipv4_h = false;
+        self.has_ipv6_h = false;
+        self.has_tcp_h = false;
+        self.has_udp_h = false;
+        self.has_icmp_h = false;
+        self.has_icmp6_h = false;
+        self.ethertype = 0;
+    }
+
+    // @brief - returns the length of the layer 4 data
+    fn l4_len(&self) -> u32 {
+        if self.has_ipv6_h {
+            return self.ipv6_l4_len();
+        }
+
+        return self.ipv4_h.payload_len();
+    }
+
+    // @brief - verify the layer 4 checksum
+    //
+    // @param [in] self - pkt_parser
+    // @param [in] p - packet
+    // @param [in] l4_start - offset of the layer 4 header
+    // @param [in] l4_len - length of the layer 4 header and data
+    // @param [in] protocol - layer 4 protocol
+    // @param [out] evt_mgr - event mgr
+    //
+    // @return 0 on success -1 on failure
+    fn verify_l4_checksum(&self, p : &packet, l4_start : usize, l4_len : u32,
+                          protocol : u8, evt_mgr : &mut event_mgr) -> i32 {
+        let mut sum : u32 = 0;
+        let l4_end = l4_start + l4_len as usize;
+
+        if !self.verify_l4_checksum {
+            return 0;
+        }
+
+        // the checksum covers the whole datagram, fragments that are not
+        // reassembled cannot be checked. the pseudo header of a packet that
+        // is still being source routed uses an address we do not know.
+        if (self.has_ipv4_h && !self.has_ipv6_h && self.ipv4_h.more_frags()) ||
+           (self.has_ipv6_h && self.ipv6_ext_h.has_frag) ||
+           (self.has_ipv6_h && self.ipv6_ext_h.has_routing && self.ipv6_ext_h.segments_left > 0) {
+            return 0;
+        }
+
+        // truncated capture, the length checks report it
+        if l4_end > p.pkt_len {
+            return 0;
+        }
+
+        if protocol != ProtocolTypes::ICMP {
+            if self.has_ipv6_h {
+                sum = inet_checksum_pseudo_ipv6(self.ipv6_h.src_addr(), self.ipv6_h.dst_addr(),
+                                                protocol, l4_len);
+            } else {
+                sum = inet_checksum_pseudo_ipv4(self.ipv4_h.src_ipaddr(), self.ipv4_h.dst_ipaddr(),
+                                                protocol, l4_len);
+            }
+        }
+
+        sum = inet_checksum_add(sum, &p.buf[l4_start..l4_end]);
+        if inet_checksum_ok(sum) {
+            return 0;
+        }
+
+        match protocol {
+            ProtocolTypes::TCP => evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY,
+                                                          event_desc::TCP_INVAL_CHECKSUM),
+            ProtocolTypes::UDP => evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY,
+                                                          event_desc::UDP_INVAL_CHECKSUM),
+            ProtocolTypes::ICMP => evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY,
+                                                           event_desc::ICMP_INVAL_CHECKSUM),
+            _ => evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY,
+                                         event_desc::ICMP6_INVAL_CHECKSUM),
+        }
+
+        return -1;
+    }
+
     // @brief - parse TCP frame
     //
     // @param [in] self - pkt_parser
@@ -66,16 +296,301 @@ impl pkt_parser {
     //
     // @return 0 on success -1 on failure
     fn parse_tcp(&mut self, p : &mut packet, evt_mgr : &mut event_mgr, stats_mgr : &mut stats_mgr::idsm_stats_mgr, debug : bool) -> i32 {
-        let ret : i32;
+        let mut ret : i32;
+        let l4_start = p.off;
+        let mut scan_type = port_scan::port_scan_types::NONE;
+        let mut dst_port : u16 = 0;
+
+        // NULL and Xmas probes fail the flag checks, classify before them
+        if self.port_scan_enable {
+            scan_type = self.tcp_scan_type(p, &mut dst_port);
+        }
+        // a tracked SYN is counted once its handshake fails, see track_tcp
+        let tracked_syn = self.tcp_conn_track_enable && (scan_type == port_scan::port_scan_types::SYN);
 
         ret = self.tcp_h.deserialize(p, evt_mgr, debug);
+        if ret == 0 {
+            ret = self.verify_l4_checksum(p, l4_start, self.l4_len(), ProtocolTypes::TCP, evt_mgr);
+        }
         if ret < 0 {
+            stats_mgr.inc_tcp_parse_err();
+            self.scan_probe(dst_port, scan_type, evt_mgr);
             return -1;
         }
 
         stats_mgr.inc_tcp_rx();
         self.has_tcp_h = true;
 
+        if self.tcp_conn_track_enable || self.syn_flood_enable {
+            ret = self.track_tcp(evt_mgr);
+        }
+
+        if !tracked_syn && (self.scan_probe(dst_port, scan_type, evt_mgr) < 0) {
+            ret = -1;
+        }
+
+        return ret;
+    }
+
+    // @brief - returns the source and destination address of the packet,
+    //          ipv4 addresses as ipv4 mapped ipv6 addresses
+    fn ip_addrs(&self, src_addr : &mut [u8; 16], dst_addr : &mut [u8; 16]) {
+        if self.has_ipv6_h {
+            *src_addr = *self.ipv6_h.src_addr();
+            *dst_addr = *self.ipv6_h.dst_addr();
+        } else {
+            *src_addr = [0; 16];
+            *dst_addr = [0; 16];
+            src_addr[10..12].copy_from_slice(&[0xFF, 0xFF]);
+            src_addr[12..16].copy_from_slice(&self.ipv4_h.src_ipaddr().to_be_bytes());
+            dst_addr[10..12].copy_from_slice(&[0xFF, 0xFF]);
+            dst_addr[12..16].copy_from_slice(&self.ipv4_h.dst_ipaddr().to_be_bytes());
+        }
+    }
+
+    // @brief - classify a TCP segment as a scan probe
+    //
+    // @param [in] self - pkt_parser
+    // @param [in] p - packet, offset at the TCP header
+    // @param [out] dst_port - destination port
+    //
+    // @return port_scan_types
+    fn tcp_scan_type(&self, p : &packet, dst_port : &mut u16) -> u8 {
+        let mut src_port : u16 = 0;
+        let mut flags : u8 = 0;
+        let ctl = tcp::tcp_hdr::FLAG_SYN | tcp::tcp_hdr::FLAG_ACK | tcp::tcp_hdr::FLAG_RST | tcp::tcp_hdr::FLAG_FIN;
+        let xmas = tcp::tcp_hdr::FLAG_FIN | tcp::tcp_hdr::FLAG_PSH | tcp::tcp_hdr::FLAG_URG;
+
+        if tcp::tcp_hdr::peek(p, &mut src_port, dst_port, &mut flags) < 0 {
+            return port_scan::port_scan_types::NONE;
+        }
+
+        if flags == 0 {
+            return port_scan::port_scan_types::NULL;
+        }
+
+        if (flags & xmas) == xmas && (flags & ctl) == tcp::tcp_hdr::FLAG_FIN {
+            return port_scan::port_scan_types::XMAS;
+        }
+
+        match flags & ctl {
+            tcp::tcp_hdr::FLAG_SYN => return port_scan::port_scan_types::SYN,
+            tcp::tcp_hdr::FLAG_FIN => return port_scan::port_scan_types::FIN,
+            tcp::tcp_hdr::FLAG_ACK => {
+                // an ACK is only a probe when it belongs to no connection
+                let mut key = tcp_conn::tcp_conn_key {
+                    src_addr    : [0; 16],
+                    dst_addr    : [0; 16],
+                    src_port    : src_port,
+                    dst_port    : *dst_port
+                };
+
+                self.ip_addrs(&mut key.src_addr, &mut key.dst_addr);
+                if self.tcp_conn_track_enable && (self.tcp_conns.state(&key) == 0) {
+                    return port_scan::port_scan_types::ACK;
+                }
+                return port_scan::port_scan_types::NONE;
+            }
+            _ => return port_scan::port_scan_types::NONE,
+        }
+    }
+
+    // @brief - check the ARP packet against the known bindings
+    //
+    // @param [in] self - pkt_parser
+    // @param [out] evt_mgr - event mgr
+    //
+    // @return 0 on success -1 if the packet looks spoofed
+    fn guard_arp(&mut self, evt_mgr : &mut event_mgr) -> i32 {
+        let mut now = timestamp::new();
+
+        if !self.arp_guard_enable {
+            return 0;
+        }
+
+        get_monotonic(&mut now);
+
+        return self.arp_guard.inspect(&self.ah, &self.eh.src_mac, now.sec, evt_mgr);
+    }
+
+    // @brief - check the neighbor discovery message against the known bindings
+    //            and the allowed routers
+    //
+    // @param [in] self - pkt_parser
+    // @param [out] evt_mgr - event mgr
+    //
+    // @return 0 on success -1 if the message looks spoofed
+    //
+    // @details - only a message carried in an ipv6 header of this packet is
+    //            inspected, the ipv6 header of an earlier packet is never used.
+    fn guard_nd(&mut self, evt_mgr : &mut event_mgr) -> i32 {
+        let mut now = timestamp::new();
+
+        if !self.nd_guard_enable || !self.has_ipv6_h || !self.has_icmp6_h ||
+           !icmp6::icmp6_types::is_ndp(self.icmp6_h.icmp6_type) {
+            return 0;
+        }
+
+        let src_addr = *self.ipv6_h.src_addr();
+        let src_unspecified = self.ipv6_h.src_unspecified();
+
+        get_monotonic(&mut now);
+
+        return self.nd_guard.inspect(&self.icmp6_h, &src_addr, src_unspecified,
+                                     &self.eh.src_mac, now.sec, evt_mgr);
+    }
+
+    // @brief - count a scan probe
+    //
+    // @param [in] self - pkt_parser
+    // @param [in] dst_port - destination port
+    // @param [in] scan_type - port_scan_types
+    // @param [out] evt_mgr - event mgr
+    //
+    // @return 0 on success -1 if a scan is detected
+    fn scan_probe(&mut self, dst_port : u16, scan_type : u8, evt_mgr : &mut event_mgr) -> i32 {
+        let mut now = timestamp::new();
+        let mut src_addr : [u8; 16] = [0; 16];
+        let mut dst_addr : [u8; 16] = [0; 16];
+
+        if scan_type == port_scan::port_scan_types::NONE {
+            return 0;
+        }
+
+        get_monotonic(&mut now);
+        self.ip_addrs(&mut src_addr, &mut dst_addr);
+
+        return self.port_scan.add_probe(&src_addr, &dst_addr, dst_port, scan_type, now.sec, evt_mgr);
+    }
+
+    // @brief - returns the connection key of the TCP segment
+    //
+    // @param [in] self - pkt_parser
+    //
+    // @return key with the sender as the source
+    fn tcp_conn_key(&self) -> tcp_conn::tcp_conn_key {
+        let mut key = tcp_conn::tcp_conn_key {
+            src_addr    : [0; 16],
+            dst_addr    : [0; 16],
+            src_port    : self.tcp_h.src_port(),
+            dst_port    : self.tcp_h.dst_port()
+        };
+
+        self.ip_addrs(&mut key.src_addr, &mut key.dst_addr);
+
+        return key;
+    }
+
+    // @brief - track the connection of the TCP segment and count the
+    //          handshakes for the SYN flood detector
+    //
+    // @param [in] self - pkt_parser
+    // @param [out] evt_mgr - event mgr
+    //
+    // @return 0 on success -1 on failure
+    //
+    // @details - completed handshakes are only known with the connection
+    //            tracking on, without it every SYN counts as half open and
+    //            as a scan probe. with it a SYN is a scan probe only if its
+    //            handshake fails, so a client opening many connections is
+    //            not a scanner.
+    fn track_tcp(&mut self, evt_mgr : &mut event_mgr) -> i32 {
+        let mut ret : i32 = 0;
+        let mut now = timestamp::new();
+        let key = self.tcp_conn_key();
+        let data_len = self.l4_len().saturating_sub(self.tcp_h.hdr_len());
+        let flags = self.tcp_h.flags();
+        let new_syn = (flags.syn != 0) && (flags.ack == 0);
+
+        get_monotonic(&mut now);
+
+        if self.tcp_conn_track_enable {
+            let prev_state = self.tcp_conns.state(&key);
+
+            ret = self.tcp_conns.track(&key, &self.tcp_h, data_len, now.sec, evt_mgr);
+
+            // the initiator sent the last ACK of the handshake
+            if self.syn_flood_enable &&
+               (prev_state == tcp_conn::tcp_conn_states::SYN_RECEIVED) &&
+               (self.tcp_conns.state(&key) == tcp_conn::tcp_conn_states::ESTABLISHED) {
+                self.syn_flood.add_done(&key.src_addr, &key.dst_addr, now.sec);
+            }
+
+            // the event of a scan carries the packet that failed the last
+            // handshake, the details name the scanner
+            for failed in self.tcp_conns.take_failed() {
+                if self.port_scan_enable &&
+                   (self.port_scan.add_probe(&failed.src_addr, &failed.dst_addr, failed.dst_port,
+                                             port_scan::port_scan_types::SYN, now.sec, evt_mgr) < 0) {
+                    ret = -1;
+                }
+            }
+        }
+
+        if self.syn_flood_enable && new_syn &&
+           (self.syn_flood.add_syn(&key.src_addr, &key.dst_addr, now.sec, evt_mgr) < 0) {
+            ret = -1;
+        }
+
+        return ret;
+    }
+
+    // @brief - parse UDP datagram
+    //
+    // @param [in] self - pkt_parser
+    // @param [in] p - packet
+    // @param [in] evt_info - event info
+    //
+    // @return 0 on success -1 on failure
+    fn parse_udp(&mut self, p : &mut packet, evt_mgr : &mut event_mgr, stats_mgr : &mut stats_mgr::idsm_stats_mgr, debug : bool) -> i32 {
+        let mut ret : i32;
+        let l4_start = p.off;
+
+        ret = self.udp_h.deserialize(p, evt_mgr, self.l4_len(), self.has_ipv6_h, debug);
+        // zero means no checksum over IPv4
+        if ret == 0 && self.udp_h.hdr_checksum != 0 {
+            ret = self.verify_l4_checksum(p, l4_start, self.udp_h.length as u32, ProtocolTypes::UDP, evt_mgr);
+        }
+        if ret < 0 {
+            stats_mgr.inc_udp_parse_err();
+            return -1;
+        }
+
+        stats_mgr.inc_udp_rx();
+        self.has_udp_h = true;
+
+        // replies of services come from well known ports to
+        // random ports, scanners send from ephemeral ports
+        if self.port_scan_enable && (self.udp_h.src_port >= udp::udp_hdr::REGISTERED_PORT_MIN) {
+            ret = self.scan_probe(self.udp_h.dst_port, port_scan::port_scan_types::UDP, evt_mgr);
+        }
+
+        return ret;
+    }
+
+    // @brief - parse ICMP message
+    //
+    // @param [in] self - pkt_parser
+    // @param [in] p - packet
+    // @param [in] evt_info - event info
+    //
+    // @return 0 on success -1 on failure
+    fn parse_icmp(&mut self, p : &mut packet, evt_mgr : &mut event_mgr, stats_mgr : &mut stats_mgr::idsm_stats_mgr, debug : bool) -> i32 {
+        let mut ret : i32;
+        let l4_start = p.off;
+
+        ret = self.icmp_h.deserialize(p, evt_mgr, debug);
+        if ret == 0 {
+            ret = self.verify_l4_checksum(p, l4_start, self.l4_len(), ProtocolTypes::ICMP, evt_mgr);
+        }
+        if ret < 0 {
+            stats_mgr.inc_icmp_parse_err();
+            return -1;
+        }
+
+        stats_mgr.inc_icmp_rx();
+        self.has_icmp_h = true;
+
         return ret;
     }
 
@@ -88,14 +603,28 @@ impl pkt_parser {
     //
     // @return 0 on success -1 on failure
     fn match_l4(&mut self, p : &mut packet, evt_mgr : &mut event_mgr, protocol : u8, stats_mgr : &mut stats_mgr::idsm_stats_mgr, debug : bool) -> i32 {
-        let ret : i32;
+        let mut ret : i32;
+        let l4_start = p.off;
 
         match protocol {
             ProtocolTypes::TCP => ret = self.parse_tcp(p, evt_mgr, stats_mgr, debug),
-            ProtocolTypes::ICMP6 => {
-                ret = self.icmp6_h.deserialize(p, evt_mgr, debug);
+            ProtocolTypes::UDP => ret = self.parse_udp(p, evt_mgr, stats_mgr, debug),
+            // ICMP and ICMPv6 are checked against the header they are carried in,
+            // the other one is an unknown protocol
+            ProtocolTypes::ICMP if self.has_ipv4_h => ret = self.parse_icmp(p, evt_mgr, stats_mgr, debug),
+            ProtocolTypes::ICMP6 if self.has_ipv6_h => {
+                ret = self.icmp6_h.deserialize(p, evt_mgr, &self.ipv6_h,
+                                               self.ipv6_l4_len(), debug);
+                if ret == 0 {
+                    ret = self.verify_l4_checksum(p, l4_start, self.ipv6_l4_len(),
+                                                  ProtocolTypes::ICMP6, evt_mgr);
+                }
                 if ret == 0 {
+                    stats_mgr.inc_icmp6_rx();
                     self.has_icmp6_h = true;
+                    ret = self.guard_nd(evt_mgr);
+                } else {
+                    stats_mgr.inc_icmp6_parse_err();
                 }
             }
             _ => ret = -1,
@@ -114,19 +643,75 @@ impl pkt_parser {
     fn parse_ipv4(&mut self, p : &mut packet, evt_mgr : &mut event_mgr, stats_mgr : &mut stats_mgr::idsm_stats_mgr, debug : bool) -> i32 {
         let mut ret : i32;
 
-        ret = self.ipv4_h.deserialize(p, evt_mgr, debug);
+        ret = self.ipv4_h.deserialize(p, evt_mgr, &self.ipv4_checks, debug);
         if ret < 0 {
+            stats_mgr.inc_ipv4_parse_err();
             return -1;
         }
 
         stats_mgr.inc_ipv4_rx();
         self.has_ipv4_h = true;
 
+        if self.ipv4_h.protocol == ProtocolTypes::ICMP &&
+           (self.ipv4_h.frag_off_bytes() > 0 || self.ipv4_h.more_frags()) {
+            ret = icmp::icmp_hdr::check_frag_len(self.ipv4_h.frag_off_bytes(),
+                                                 self.ipv4_h.total_len(), evt_mgr);
+            if ret < 0 {
+                return -1;
+            }
+        }
+
+        if self.ipv4_h.frag_off_bytes() > 0 || self.ipv4_h.more_frags() {
+            stats_mgr.inc_ipv4_frag_rx();
+
+            if self.ipv4_reasm_enable {
+                return self.reassemble_ipv4(p, evt_mgr, stats_mgr, debug);
+            }
+
+            // only the first fragment carries the layer 4 header
+            if self.ipv4_h.frag_off_bytes() > 0 {
+                return 0;
+            }
+        }
+
         ret = self.match_l4(p, evt_mgr, self.ipv4_h.protocol, stats_mgr, debug);
 
         return ret;
     }
 
+    // @brief - queue an IPv4 fragment and parse the datagram once complete
+    //
+    // @param [in] self - pkt_parser
+    // @param [in] p - packet, offset at the fragment data
+    // @param [in] evt_info - event info
+    //
+    // @return 0 on success -1 on failure
+    fn reassemble_ipv4(&mut self, p : &mut packet, evt_mgr : &mut event_mgr, stats_mgr : &mut stats_mgr::idsm_stats_mgr, debug : bool) -> i32 {
+        let mut ret : i32;
+        let mut now = timestamp::new();
+        let mut datagram = packet::with_buf_len(0);
+        let hdr_start = p.off - self.ipv4_h.hdr_len() as usize;
+
+        get_monotonic(&mut now);
+
+        ret = self.ipv4_reasm.insert(&self.ipv4_h, p, hdr_start, now.sec, evt_mgr, &mut datagram);
+        if ret < 0 {
+            stats_mgr.inc_ipv4_parse_err();
+            return -1;
+        }
+
+        if ret == 0 {
+            return 0;
+        }
+
+        stats_mgr.inc_ipv4_reasm();
+        self.ipv4_h.set_reassembled(datagram.pkt_len as u32);
+
+        ret = self.match_l4(&mut datagram, evt_mgr, self.ipv4_h.protocol, stats_mgr, debug);
+
+        return ret;
+    }
+
     // @brief - parse an IPv6 packet
     //
     // @param [in] self - pkt_parser
@@ -139,15 +724,121 @@ impl pkt_parser {
 
         ret = self.ipv6_h.deserialize(p, evt_mgr, debug);
         if ret < 0 {
+            stats_mgr.inc_ipv6_parse_err();
             return -1;
         }
 
         stats_mgr.inc_ipv6_rx();
         self.has_ipv6_h = true; 
 
-        ret = self.match_l4(p, evt_mgr, self.ipv6_h.next_hdr, stats_mgr, debug);
+        ret = self.ipv6_ext_h.deserialize(p, evt_mgr, self.ipv6_h.next_hdr, debug);
+        if ret < 0 {
+            stats_mgr.inc_ipv6_parse_err();
+            return -1;
+        }
 
-        return ret;
+        if self.ipv6_ext_h.has_frag {
+            stats_mgr.inc_ipv6_frag_rx();
+
+            // RFC 8021 - atomic fragments are only ever forced by a forged
+            // packet too big message
+            if self.ipv6_ext_h.frag_h.is_atomic() {
+                evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY,
+                                        event_desc::IPV6_FRAG_ATOMIC);
+                stats_mgr.inc_ipv6_parse_err();
+                return -1;
+            }
+
+            if self.ipv6_ext_h.frag_h.frag_off == 0 && !self.ipv6_ext_h.has_esp {
+                ret = ipv6_reasm::ipv6_reasm::check_first_frag(self.ipv6_ext_h.upper_proto,
+                                                               (p.pkt_len - p.off) as u32, evt_mgr);
+                if ret < 0 {
+                    stats_mgr.inc_ipv6_parse_err();
+                    return -1;
+                }
+            }
+
+            if self.ipv6_reasm_enable {
+                return self.reassemble_ipv6(p, evt_mgr, stats_mgr, debug);
+            }
+        }
+
+        return self.match_l4_ipv6(p, evt_mgr, stats_mgr, debug);
+    }
+
+    // @brief - parse the layer 4 header following the IPv6 extension headers
+    //
+    // @param [in] self - pkt_parser
+    // @param [in] p - packet, offset at the upper layer header
+    // @param [in] evt_info - event info
+    //
+    // @return 0 on success -1 on failure
+    fn match_l4_ipv6(&mut self, p : &mut packet, evt_mgr : &mut event_mgr, stats_mgr : &mut stats_mgr::idsm_stats_mgr, debug : bool) -> i32 {
+        // only ICMPv6 neighbor discovery and MLD use the unspecified source
+        if self.ipv6_h.src_unspecified() && (self.ipv6_ext_h.upper_proto != ProtocolTypes::ICMP6) {
+            evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY,
+                                    event_desc::IPV6_SRC_ADDR_INVALID);
+            return -1;
+        }
+
+        // encrypted or absent upper layer, or a non-first fragment
+        if self.ipv6_ext_h.has_esp ||
+           (self.ipv6_ext_h.upper_proto == ipv6_ext::ipv6_ext_types::NO_NEXT_HDR) ||
+           (self.ipv6_ext_h.has_frag && self.ipv6_ext_h.frag_h.frag_off > 0) {
+            return 0;
+        }
+
+        return self.match_l4(p, evt_mgr, self.ipv6_ext_h.upper_proto, stats_mgr, debug);
+    }
+
+    // @brief - queue an IPv6 fragment and parse the packet once complete
+    //
+    // @param [in] self - pkt_parser
+    // @param [in] p - packet, offset after the extension headers
+    // @param [in] evt_info - event info
+    //
+    // @return 0 on success -1 on failure
+    fn reassemble_ipv6(&mut self, p : &mut packet, evt_mgr : &mut event_mgr, stats_mgr : &mut stats_mgr::idsm_stats_mgr, debug : bool) -> i32 {
+        let mut ret : i32;
+        let mut now = timestamp::new();
+        let mut datagram = packet::with_buf_len(0);
+        let mut next_hdr : u8 = 0;
+        let hdr_start = p.off - self.ipv6_ext_h.ext_len as usize - ipv6::ipv6_hdr::IPV6_MIN_HDR_LEN as usize;
+
+        get_monotonic(&mut now);
+
+        ret = self.ipv6_reasm.insert(&self.ipv6_h, &self.ipv6_ext_h, p, hdr_start, now.sec,
+                                     evt_mgr, &mut datagram, &mut next_hdr);
+        if ret < 0 {
+            stats_mgr.inc_ipv6_parse_err();
+            return -1;
+        }
+
+        if ret == 0 {
+            return 0;
+        }
+
+        stats_mgr.inc_ipv6_reasm();
+        self.ipv6_h.set_reassembled((datagram.pkt_len - datagram.off) as u32, next_hdr);
+
+        // headers after the fragment header are part of the reassembled data,
+        // they continue the chain walked up to the fragment header
+        ret = self.ipv6_ext_h.deserialize_reassembled(&mut datagram, evt_mgr, next_hdr, debug);
+        if ret < 0 {
+            stats_mgr.inc_ipv6_parse_err();
+            return -1;
+        }
+
+        return self.match_l4_ipv6(&mut datagram, evt_mgr, stats_mgr, debug);
+    }
+
+    // @brief - returns the length of the layer 4 data of an IPv6 packet
+    //
+    // @param [in] self - pkt_parser
+    //
+    // @return payload length less the extension headers
+    fn ipv6_l4_len(&self) -> u32 {
+        return self.ipv6_h.payload_len().saturating_sub(self.ipv6_ext_h.ext_len);
     }
 
     // @brief - parse a VLAN packet
@@ -162,6 +853,7 @@ impl pkt_parser {
 
         ret = self.vh.deserialize(p, evt_mgr, debug);
         if ret < 0 {
+            stats_mgr.inc_vlan_parse_err();
             return -1;
         }
 
@@ -184,6 +876,7 @@ impl pkt_parser {
 
         ret = self.eh.deserialize(p, evt_mgr, debug);
         if ret < 0 {
+            stats_mgr.inc_eth_parse_err();
             return -1;
         }
         stats_mgr.inc_eth_rx();
@@ -193,6 +886,11 @@ impl pkt_parser {
             Ethertypes::ARP             => {
                 ret = self.ah.deserialize(p, evt_mgr, debug);
                 stats_mgr.inc_arp_rx();
+                if ret < 0 {
+                    stats_mgr.inc_arp_parse_err();
+                } else {
+                    ret = self.guard_arp(evt_mgr);
+                }
             }
             Ethertypes::IEEE_8021Q      => ret = self.parse_vlan(p, evt_mgr, stats_mgr, debug),
             _                           => ret = -1,
@@ -214,6 +912,11 @@ impl pkt_parser {
     pub fn parse(&mut self, p : &mut packet, evt_mgr : &mut event_mgr, stats_mgr : &mut stats_mgr::idsm_stats_mgr, debug : bool) -> i32 {
         let mut ret : i32;
 
+        self.reset();
+
+        // events raised while parsing this packet carry its header fields
+        evt_mgr.begin_pkt(p);
+
         ret = self.parse_l2(p, evt_mgr, stats_mgr, debug);
         if ret < 0 {
             return -1;
@@ -232,3 +935,120 @@ impl pkt_parser {
         return ret;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lib::protocols::checksum::inet_checksum::inet_checksum_fold;
+
+    const SYN : u8 = 0x02;
+    const RST : u8 = 0x04;
+    const ACK : u8 = 0x10;
+    const FIN : u8 = 0x01;
+    const PSH : u8 = 0x08;
+    const URG : u8 = 0x20;
+
+    // @brief - build an ethernet frame with an IPv4 TCP segment
+    fn tcp4(src : [u8; 4], dst : [u8; 4], src_port : u16, dst_port : u16,
+            seq : u32, ack : u32, flags : u8) -> packet {
+        let mut p = packet::new();
+        let mut ip : [u8; 20] = [0x45, 0, 0, 40, 0, 0, 0x40, 0, 64, 6, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        let mut tcp : [u8; 20] = [0; 20];
+
+        ip[12..16].copy_from_slice(&src);
+        ip[16..20].copy_from_slice(&dst);
+        let csum = inet_checksum_fold(inet_checksum_add(0, &ip));
+        ip[10..12].copy_from_slice(&csum.to_be_bytes());
+
+        tcp[0..2].copy_from_slice(&src_port.to_be_bytes());
+        tcp[2..4].copy_from_slice(&dst_port.to_be_bytes());
+        tcp[4..8].copy_from_slice(&seq.to_be_bytes());
+        tcp[8..12].copy_from_slice(&ack.to_be_bytes());
+        tcp[12] = 5 << 4;
+        tcp[13] = flags;
+        tcp[14..16].copy_from_slice(&1024u16.to_be_bytes());
+        let pseudo = inet_checksum_pseudo_ipv4(u32::from_be_bytes(src), u32::from_be_bytes(dst), 6, 20);
+        let csum = inet_checksum_fold(inet_checksum_add(pseudo, &tcp));
+        tcp[16..18].copy_from_slice(&csum.to_be_bytes());
+
+        p.buf[0..6].copy_from_slice(&[0x02, 0, 0, 0, 0, 2]);
+        p.buf[6..12].copy_from_slice(&[0x02, 0, 0, 0, 0, 1]);
+        p.buf[12..14].copy_from_slice(&0x0800u16.to_be_bytes());
+        p.buf[14..34].copy_from_slice(&ip);
+        p.buf[34..54].copy_from_slice(&tcp);
+        p.pkt_len = 54;
+
+        return p;
+    }
+
+    fn parse(parser : &mut pkt_parser, mut p : packet, evt_mgr : &mut event_mgr) -> i32 {
+        let mut stats_mgr = stats_mgr::idsm_stats_mgr::new();
+
+        return parser.parse(&mut p, evt_mgr, &mut stats_mgr, false);
+    }
+
+    #[test]
+    fn completed_handshakes_are_not_scans() {
+        let mut parser = pkt_parser::new();
+        let mut evt_mgr = event_mgr::new();
+        let client = [10, 0, 0, 1];
+
+        // a browser reaching many hosts on the same port
+        for i in 1..=40 {
+            let server = [10, 0, 1, i];
+            let port = 40000 + i as u16;
+
+            assert_eq!(parse(&mut parser, tcp4(client, server, port, 443, 100, 0, SYN), &mut evt_mgr), 0);
+            assert_eq!(parse(&mut parser, tcp4(server, client, 443, port, 500, 101, SYN | ACK), &mut evt_mgr), 0);
+            assert_eq!(parse(&mut parser, tcp4(client, server, port, 443, 101, 501, ACK), &mut evt_mgr), 0);
+        }
+
+        assert!(evt_mgr.get_evt_desc_counts().is_empty());
+    }
+
+    #[test]
+    fn reset_handshakes_are_scans() {
+        let mut parser = pkt_parser::new();
+        let mut evt_mgr = event_mgr::new();
+        let scanner = [10, 0, 0, 1];
+
+        for i in 1..=40 {
+            let host = [10, 0, 1, i];
+
+            _ = parse(&mut parser, tcp4(scanner, host, 40000, 443, 100, 0, SYN), &mut evt_mgr);
+            _ = parse(&mut parser, tcp4(host, scanner, 443, 40000, 0, 101, RST | ACK), &mut evt_mgr);
+        }
+
+        assert_eq!(evt_mgr.get_evt_desc_counts().get(&event_desc::PORT_SCAN), Some(&1));
+    }
+
+    // @brief - probe 30 ports of one host with the given flags
+    fn scan_type(flags : u8) -> Option<String> {
+        let mut parser = pkt_parser::new();
+        let mut evt_mgr = event_mgr::new();
+
+        for port in 1..=30 {
+            _ = parse(&mut parser, tcp4([10, 0, 0, 1], [10, 0, 1, 1], 40000, port, 100, 0, flags), &mut evt_mgr);
+        }
+
+        if evt_mgr.get_evt_desc_counts().get(&event_desc::PORT_SCAN) != Some(&1) {
+            return None;
+        }
+        // NULL and Xmas probes also raise their flag anomaly
+        let evt = evt_mgr.get_evt_list().iter().find(|evt| evt.event_desc == event_desc::PORT_SCAN).unwrap();
+        return evt.details.iter().find(|(k, _)| k == "scan_type").map(|(_, v)| v.clone());
+    }
+
+    #[test]
+    fn scan_classification() {
+        assert_eq!(scan_type(0).as_deref(), Some("null"));
+        assert_eq!(scan_type(FIN).as_deref(), Some("fin"));
+        assert_eq!(scan_type(FIN | PSH | URG).as_deref(), Some("xmas"));
+        assert_eq!(scan_type(ACK).as_deref(), Some("ack"));
+
+        // resets and handshake replies are not probes
+        assert_eq!(scan_type(RST), None);
+        assert_eq!(scan_type(RST | ACK), None);
+        assert_eq!(scan_type(SYN | ACK), None);
+    }
+}
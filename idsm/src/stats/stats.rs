@@ -6,8 +6,27 @@ pub struct idsm_stats {
     pub n_vlan_rx : u64,
     pub n_arp_rx : u64,
     pub n_ipv4_rx : u64,
+    pub n_ipv4_frag_rx : u64,
+    pub n_ipv4_reasm : u64,
     pub n_ipv6_rx : u64,
+    pub n_ipv6_frag_rx : u64,
+    pub n_ipv6_reasm : u64,
     pub n_tcp_rx : u64,
+    pub n_udp_rx : u64,
+    pub n_icmp_rx : u64,
+    pub n_icmp6_rx : u64,
+    pub n_evt_dropped : u64,
+    pub n_evt_coalesced : u64,
+    pub n_evt_aggregated : u64,
+    pub n_eth_parse_err : u64,
+    pub n_vlan_parse_err : u64,
+    pub n_arp_parse_err : u64,
+    pub n_ipv4_parse_err : u64,
+    pub n_ipv6_parse_err : u64,
+    pub n_tcp_parse_err : u64,
+    pub n_udp_parse_err : u64,
+    pub n_icmp_parse_err : u64,
+    pub n_icmp6_parse_err : u64,
 }
 
 impl idsm_stats {
@@ -18,9 +37,61 @@ impl idsm_stats {
             n_vlan_rx : 0,
             n_arp_rx : 0,
             n_ipv4_rx : 0,
+            n_ipv4_frag_rx : 0,
+            n_ipv4_reasm : 0,
             n_ipv6_rx : 0,
-            n_tcp_rx : 0
+            n_ipv6_frag_rx : 0,
+            n_ipv6_reasm : 0,
+            n_tcp_rx : 0,
+            n_udp_rx : 0,
+            n_icmp_rx : 0,
+            n_icmp6_rx : 0,
+            n_evt_dropped : 0,
+            n_evt_coalesced : 0,
+            n_evt_aggregated : 0,
+            n_eth_parse_err : 0,
+            n_vlan_parse_err : 0,
+            n_arp_parse_err : 0,
+            n_ipv4_parse_err : 0,
+            n_ipv6_parse_err : 0,
+            n_tcp_parse_err : 0,
+            n_udp_parse_err : 0,
+            n_icmp_parse_err : 0,
+            n_icmp6_parse_err : 0
         };
         stats
     }
+
+    // @brief - returns the counters as a JSON object
+    pub fn to_json(&self) -> serde_json::Value {
+        let obj = serde_json::json!({
+            "n_rx" : self.n_rx,
+            "n_eth_rx" : self.n_eth_rx,
+            "n_vlan_rx" : self.n_vlan_rx,
+            "n_arp_rx" : self.n_arp_rx,
+            "n_ipv4_rx" : self.n_ipv4_rx,
+            "n_ipv4_frag_rx" : self.n_ipv4_frag_rx,
+            "n_ipv4_reasm" : self.n_ipv4_reasm,
+            "n_ipv6_rx" : self.n_ipv6_rx,
+            "n_ipv6_frag_rx" : self.n_ipv6_frag_rx,
+            "n_ipv6_reasm" : self.n_ipv6_reasm,
+            "n_tcp_rx" : self.n_tcp_rx,
+            "n_udp_rx" : self.n_udp_rx,
+            "n_icmp_rx" : self.n_icmp_rx,
+            "n_icmp6_rx" : self.n_icmp6_rx,
+            "n_evt_dropped" : self.n_evt_dropped,
+            "n_evt_coalesced" : self.n_evt_coalesced,
+            "n_evt_aggregated" : self.n_evt_aggregated,
+            "n_eth_parse_err" : self.n_eth_parse_err,
+            "n_vlan_parse_err" : self.n_vlan_parse_err,
+            "n_arp_parse_err" : self.n_arp_parse_err,
+            "n_ipv4_parse_err" : self.n_ipv4_parse_err,
+            "n_ipv6_parse_err" : self.n_ipv6_parse_err,
+            "n_tcp_parse_err" : self.n_tcp_parse_err,
+            "n_udp_parse_err" : self.n_udp_parse_err,
+            "n_icmp_parse_err" : self.n_icmp_parse_err,
+            "n_icmp6_parse_err" : self.n_icmp6_parse_err
+        });
+        obj
+    }
 }
@@ -15,12 +15,32 @@ impl idsm_stats_mgr {
     }
 
     pub fn get(&mut self) -> &stats::idsm_stats { return &self.stats; }
+    pub fn clear(&mut self) { self.stats = stats::idsm_stats::new(); }
 
     pub fn inc_rx(&mut self) { self.stats.n_rx += 1; }
     pub fn inc_eth_rx(&mut self) { self.stats.n_eth_rx += 1; }
     pub fn inc_arp_rx(&mut self) { self.stats.n_arp_rx += 1; }
     pub fn inc_vlan_rx(&mut self) { self.stats.n_vlan_rx += 1; }
     pub fn inc_ipv4_rx(&mut self) { self.stats.n_ipv4_rx += 1; }
+    pub fn inc_ipv4_frag_rx(&mut self) { self.stats.n_ipv4_frag_rx += 1; }
+    pub fn inc_ipv4_reasm(&mut self) { self.stats.n_ipv4_reasm += 1; }
     pub fn inc_ipv6_rx(&mut self) { self.stats.n_ipv6_rx += 1; }
+    pub fn inc_ipv6_frag_rx(&mut self) { self.stats.n_ipv6_frag_rx += 1; }
+    pub fn inc_ipv6_reasm(&mut self) { self.stats.n_ipv6_reasm += 1; }
     pub fn inc_tcp_rx(&mut self) { self.stats.n_tcp_rx += 1; }
+    pub fn inc_udp_rx(&mut self) { self.stats.n_udp_rx += 1; }
+    pub fn inc_icmp_rx(&mut self) { self.stats.n_icmp_rx += 1; }
+    pub fn inc_icmp6_rx(&mut self) { self.stats.n_icmp6_rx += 1; }
+    pub fn set_evt_dropped(&mut self, n_dropped : u64) { self.stats.n_evt_dropped = n_dropped; }
+    pub fn set_evt_coalesced(&mut self, n_coalesced : u64) { self.stats.n_evt_coalesced = n_coalesced; }
+    pub fn set_evt_aggregated(&mut self, n_aggregated : u64) { self.stats.n_evt_aggregated = n_aggregated; }
+    pub fn inc_eth_parse_err(&mut self) { self.stats.n_eth_parse_err += 1; }
+    pub fn inc_vlan_parse_err(&mut self) { self.stats.n_vlan_parse_err += 1; }
+    pub fn inc_arp_parse_err(&mut self) { self.stats.n_arp_parse_err += 1; }
+    pub fn inc_ipv4_parse_err(&mut self) { self.stats.n_ipv4_parse_err += 1; }
+    pub fn inc_ipv6_parse_err(&mut self) { self.stats.n_ipv6_parse_err += 1; }
+    pub fn inc_tcp_parse_err(&mut self) { self.stats.n_tcp_parse_err += 1; }
+    pub fn inc_udp_parse_err(&mut self) { self.stats.n_udp_parse_err += 1; }
+    pub fn inc_icmp_parse_err(&mut self) { self.stats.n_icmp_parse_err += 1; }
+    pub fn inc_icmp6_parse_err(&mut self) { self.stats.n_icmp6_parse_err += 1; }
 }
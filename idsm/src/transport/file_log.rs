@@ -0,0 +1,432 @@
+// @brief - implements local append-only event log
+// @copyright - Devendra Naga 2024-present All rights reserved.
+#![allow(dead_code)]
+#![allow(non_camel_case_types)]
+
+use std::collections::VecDeque;
+
+use crate::{
+    config::config_parser::idsm_event_file_log_config,
+    events::{
+        event_encoder::event_encoder,
+        event_mgr::event_mgr
+    },
+    lib::{
+        fileio::fileio::fileio,
+        time_linux::{
+            gmtime::gmtime_filename,
+            timestamp::{get_monotonic, timestamp}
+        }
+    }
+};
+
+// @brief - defines local event log
+//
+// @details - events are written as JSON lines to files named
+//
+//            <file_prefix><year>_<mon>_<day>_<hour>_<min>_<sec>_<msec>.json
+//
+//            a new file is started once the current file would grow past
+//            max_size_bytes or is older than max_age_sec. only the last
+//            max_files files are kept, older files are removed.
+//
+//            the log is written independent of the network transport so
+//            that events are kept even when the collector is unreachable.
+//
+//            an event that is counted again after it was written, when the
+//            event list is full and coalescing, is written again with the
+//            same id and the new count. the last record of an id is current.
+pub struct file_log {
+    io                  : fileio,
+    filename            : String,
+    file_len            : u64,
+    file_created_sec    : u32,
+    filenames           : VecDeque<String>
+}
+
+impl file_log {
+    const FILE_EXT      : &'static str = ".json";
+
+    // @brief - zero initialize the event log
+    //
+    // @return event log
+    pub fn new() -> file_log {
+        let f = file_log {
+            io                  : fileio::new(),
+            filename            : "".to_string(),
+            file_len            : 0,
+            file_created_sec    : 0,
+            filenames           : VecDeque::new()
+        };
+        f
+    }
+
+    // @brief - returns current monotonic time in seconds
+    fn now_sec() -> u32 {
+        let mut now = timestamp::new();
+
+        get_monotonic(&mut now);
+        return now.sec;
+    }
+
+    // @brief - returns the directory and the file name prefix of the log files
+    //
+    // @param [in] file_log_config - event log config
+    // @param [out] name_prefix - file name prefix, empty if the prefix is a directory
+    //
+    // @return directory of the log files
+    fn log_dir(file_log_config : &idsm_event_file_log_config, name_prefix : &mut String) -> std::path::PathBuf {
+        let prefix = std::path::Path::new(&file_log_config.file_prefix);
+        let mut dir = match prefix.parent() {
+            Some(d) => d.to_path_buf(),
+            None => std::path::PathBuf::from("."),
+        };
+
+        name_prefix.clear();
+
+        // prefix ends with '/', the files are named only by the timestamp
+        if file_log_config.file_prefix.ends_with('/') {
+            dir = prefix.to_path_buf();
+        } else if let Some(n) = prefix.file_name() {
+            name_prefix.push_str(&n.to_string_lossy());
+        }
+        if dir.as_os_str().is_empty() {
+            dir = std::path::PathBuf::from(".");
+        }
+
+        return dir;
+    }
+
+    // @brief - check if the file is named as by gmtime_filename
+    //
+    // @param [in] name - file name
+    // @param [in] name_prefix - file name prefix
+    //
+    // @return true if the name is prefix, the 7 timestamp fields and the extension
+    fn is_log_filename(name : &str, name_prefix : &str) -> bool {
+        let ts = match name.strip_prefix(name_prefix)
+                           .and_then(|n| n.strip_suffix(file_log::FILE_EXT)) {
+            Some(ts) => ts,
+            None => return false,
+        };
+        let fields : Vec<&str> = ts.split('_').collect();
+
+        if fields.len() != 7 {
+            return false;
+        }
+
+        return fields.iter().all(|f| !f.is_empty() && f.bytes().all(|b| b.is_ascii_digit()));
+    }
+
+    // @brief - find the event log files left from a previous run
+    //
+    // @param [in] self - event log
+    // @param [in] file_log_config - event log config
+    //
+    // @details - the files are ordered from oldest to newest so that the
+    //            rotation removes the oldest ones first.
+    //
+    //            only the files named by the event log are picked up, other
+    //            files in the directory are never rotated away.
+    fn scan_files(&mut self, file_log_config : &idsm_event_file_log_config) {
+        let mut name_prefix = "".to_string();
+        let dir = file_log::log_dir(file_log_config, &mut name_prefix);
+        let mut files : Vec<(std::time::SystemTime, String)> = Vec::new();
+
+        self.filenames.clear();
+
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(e) => e,
+            Err(_) => return,
+        };
+
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+
+            if !file_log::is_log_filename(&name, &name_prefix) {
+                continue;
+            }
+
+            let mtime = match entry.metadata().and_then(|m| m.modified()) {
+                Ok(t) => t,
+                Err(_) => continue,
+            };
+
+            files.push((mtime, entry.path().to_string_lossy().to_string()));
+        }
+
+        files.sort();
+        for (_, name) in files {
+            self.filenames.push_back(name);
+        }
+    }
+
+    // @brief - remove the oldest files beyond max_files
+    //
+    // @param [in] self - event log
+    // @param [in] file_log_config - event log config
+    fn remove_old_files(&mut self, file_log_config : &idsm_event_file_log_config) {
+        if file_log_config.max_files == 0 {
+            return;
+        }
+
+        while self.filenames.len() > file_log_config.max_files as usize {
+            let name = self.filenames.pop_front().unwrap();
+
+            log::info!("file_log: removing {}", name);
+            fileio::unlink(&name);
+        }
+    }
+
+    // @brief - close the current file and start a new one
+    //
+    // @param [in] self - event log
+    // @param [in] file_log_config - event log config
+    //
+    // @return 0 on success -1 on failure
+    fn rotate(&mut self, file_log_config : &idsm_event_file_log_config) -> i32 {
+        let mut filename = String::new();
+        let ret : i32;
+
+        if self.io.is_open() {
+            self.io.sync();
+            self.io.close();
+        }
+
+        gmtime_filename(&file_log_config.file_prefix,
+                        &file_log::FILE_EXT.to_string(), &mut filename);
+
+        let filename = filename.trim_end_matches('\0').to_string();
+
+        ret = self.io.new_file(&filename);
+        if ret < 0 {
+            log::error!("file_log: failed to create {}", filename);
+            return -1;
+        }
+
+        // rotations within the same millisecond reuse the file name
+        if self.filenames.back() != Some(&filename) {
+            self.filenames.push_back(filename.clone());
+        }
+        self.filename = filename;
+        self.file_len = self.io.size().max(0) as u64;
+        self.file_created_sec = file_log::now_sec();
+
+        self.remove_old_files(file_log_config);
+
+        return 0;
+    }
+
+    // @brief - initialize the event log
+    //
+    // @param [in] self - event log
+    // @param [in] file_log_config - event log config
+    //
+    // @return 0 on success -1 on failure
+    //
+    // @details - the log directory is created if it does not exist.
+    pub fn init(&mut self, file_log_config : &idsm_event_file_log_config) -> i32 {
+        let mut name_prefix = "".to_string();
+        let dir = file_log::log_dir(file_log_config, &mut name_prefix);
+
+        if let Err(e) = std::fs::create_dir_all(&dir) {
+            log::error!("file_log: cannot create {}: {}", dir.display(), e);
+            return -1;
+        }
+
+        self.scan_files(file_log_config);
+
+        return self.rotate(file_log_config);
+    }
+
+    // @brief - write the ready events to the event log
+    //
+    // @param [in] self - event log
+    // @param [in] evt_mgr - event manager
+    // @param [in] file_log_config - event log config
+    //
+    // @return 0 on success -1 on failure
+    //
+    // @details - events stay in the event manager for the network
+    //            transport, the logged count is kept so that a failed
+    //            upload does not write them twice.
+    pub fn write(&mut self, evt_mgr : &mut event_mgr,
+                 file_log_config : &idsm_event_file_log_config) -> i32 {
+        let n_ready = evt_mgr.n_ready_evts();
+        let mut ret : i32 = 0;
+        let mut line = String::new();
+
+        if n_ready == 0 {
+            return 0;
+        }
+
+        for evt in evt_mgr.get_evt_list().iter_mut().take(n_ready) {
+            if evt.logged_count == evt.count {
+                continue;
+            }
+
+            line.clear();
+            event_encoder::encode_json(evt, &mut line);
+            line.push('\n');
+
+            let age = file_log::now_sec() - self.file_created_sec;
+            let grows_past = file_log_config.max_size_bytes > 0 &&
+                             self.file_len > 0 &&
+                             self.file_len + line.len() as u64 > file_log_config.max_size_bytes;
+            let too_old = file_log_config.max_age_sec > 0 &&
+                          age >= file_log_config.max_age_sec;
+
+            if !self.io.is_open() || grows_past || too_old {
+                if self.rotate(file_log_config) < 0 {
+                    ret = -1;
+                    break;
+                }
+            }
+
+            if self.io.write(line.as_bytes(), line.len()) != line.len() as isize {
+                log::error!("file_log: failed to write to {}", self.filename);
+                self.io.close();
+                ret = -1;
+                break;
+            }
+
+            self.file_len += line.len() as u64;
+            evt.logged_count = evt.count;
+        }
+
+        if self.io.is_open() {
+            self.io.sync();
+        }
+
+        return ret;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::{
+        event_desc::event_desc,
+        event_mgr::event_overflow_policy,
+        event_type::event_type
+    };
+
+    // @brief - a config logging into a directory of its own
+    fn test_config(name : &str) -> idsm_event_file_log_config {
+        let mut file_log_config = idsm_event_file_log_config::new();
+        let dir = std::env::temp_dir().join(format!("idsm_{}_{}", name, std::process::id()));
+
+        std::fs::create_dir_all(&dir).unwrap();
+        file_log_config.enable = true;
+        file_log_config.file_prefix = format!("{}/events_", dir.to_string_lossy());
+        file_log_config.max_files = 2;
+        file_log_config
+    }
+
+    fn read_lines(filename : &String) -> Vec<serde_json::Value> {
+        let data = std::fs::read_to_string(filename).unwrap();
+
+        return data.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+    }
+
+    fn n_log_files(f : &file_log) -> usize {
+        let dir = std::path::Path::new(&f.filename).parent().unwrap();
+
+        return std::fs::read_dir(dir).unwrap().flatten()
+                  .filter(|e| file_log::is_log_filename(&e.file_name().to_string_lossy(), "events_"))
+                  .count();
+    }
+
+    #[test]
+    fn log_filename() {
+        assert!(file_log::is_log_filename("events_2024_01_02_03_04_05_006.json", "events_"));
+        assert!(file_log::is_log_filename("2024_01_02_03_04_05_006.json", ""));
+        assert!(!file_log::is_log_filename("events_2024_01_02_03_04_05.json", "events_"));
+        assert!(!file_log::is_log_filename("events_2024_01_02_03_04_05_006.txt", "events_"));
+        assert!(!file_log::is_log_filename("other_2024_01_02_03_04_05_006.json", "events_"));
+        assert!(!file_log::is_log_filename("events_2024_01_02_03_04_05_0a6.json", "events_"));
+    }
+
+    #[test]
+    fn log_dir() {
+        let mut file_log_config = idsm_event_file_log_config::new();
+        let mut name_prefix = "".to_string();
+
+        file_log_config.file_prefix = "/var/log/idsm/events_".to_string();
+        assert_eq!(file_log::log_dir(&file_log_config, &mut name_prefix),
+                   std::path::PathBuf::from("/var/log/idsm"));
+        assert_eq!(name_prefix, "events_");
+
+        file_log_config.file_prefix = "/var/log/idsm/".to_string();
+        assert_eq!(file_log::log_dir(&file_log_config, &mut name_prefix),
+                   std::path::PathBuf::from("/var/log/idsm/"));
+        assert_eq!(name_prefix, "");
+
+        file_log_config.file_prefix = "events_".to_string();
+        assert_eq!(file_log::log_dir(&file_log_config, &mut name_prefix),
+                   std::path::PathBuf::from("."));
+        assert_eq!(name_prefix, "events_");
+    }
+
+    #[test]
+    fn rotate_by_size() {
+        let mut file_log_config = test_config("rotate");
+        let mut f = file_log::new();
+        let mut evt_mgr = event_mgr::new();
+
+        file_log_config.max_size_bytes = 1;
+        assert_eq!(f.init(&file_log_config), 0);
+
+        let dir = std::path::Path::new(&f.filename).parent().unwrap().to_path_buf();
+        let other = dir.join("events_notes.json");
+        std::fs::write(&other, "keep").unwrap();
+
+        for _ in 0..4 {
+            evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY, event_desc::IPV4_FRAG_TINY);
+            assert_eq!(f.write(&mut evt_mgr, &file_log_config), 0);
+            evt_mgr.remove_evts(1);
+            // file names have millisecond resolution
+            std::thread::sleep(std::time::Duration::from_millis(2));
+        }
+
+        // every file holds one event, only the last max_files are kept
+        assert_eq!(n_log_files(&f), 2);
+        assert_eq!(read_lines(&f.filename).len(), 1);
+        assert!(other.exists());
+
+        // a restart picks up the files and keeps rotating them
+        let mut f = file_log::new();
+        assert_eq!(f.init(&file_log_config), 0);
+        assert_eq!(f.filenames.len(), 2);
+        assert_eq!(n_log_files(&f), 2);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn coalesced_event_logged_again() {
+        let file_log_config = test_config("coalesce");
+        let mut f = file_log::new();
+        let mut evt_mgr = event_mgr::new();
+
+        evt_mgr.set_capacity(1, event_overflow_policy::COALESCE);
+        assert_eq!(f.init(&file_log_config), 0);
+
+        evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY, event_desc::IPV4_FRAG_TINY);
+        assert_eq!(f.write(&mut evt_mgr, &file_log_config), 0);
+        // nothing new, nothing written
+        assert_eq!(f.write(&mut evt_mgr, &file_log_config), 0);
+
+        evt_mgr.insert_evt_info(event_type::EVENT_TYPE_DENY, event_desc::IPV4_FRAG_TINY);
+        assert_eq!(evt_mgr.get_n_coalesced(), 1);
+        assert_eq!(f.write(&mut evt_mgr, &file_log_config), 0);
+
+        let lines = read_lines(&f.filename);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["id"], lines[1]["id"]);
+        assert_eq!(lines[0]["count"], 1);
+        assert_eq!(lines[1]["count"], 2);
+
+        std::fs::remove_dir_all(std::path::Path::new(&f.filename).parent().unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,4 @@
+pub(crate) mod udp_transport;
+pub(crate) mod syslog_transport;
+pub(crate) mod tcp_transport;
+pub(crate) mod file_log;
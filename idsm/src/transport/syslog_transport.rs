@@ -0,0 +1,380 @@
+// @brief - implements event upload as RFC 5424 syslog messages
+// @copyright - Devendra Naga 2024-present All rights reserved.
+#![allow(dead_code)]
+#![allow(non_camel_case_types)]
+
+use crate::{
+    config::config_parser::{idsm_event_transport_syslog, idsm_events_config, idsm_syslog_proto},
+    events::{
+        event_desc::event_desc,
+        event_encoder::{event_encoder, event_encoder_type},
+        event_info::event_info,
+        event_mgr::event_mgr
+    },
+    lib::{
+        socket_linux::{tcp_client::tcp_client, udp_client::udp_client, unix_client::unix_client},
+        time_linux::gmtime::{gmtime_linux_sec, tm_linux}
+    }
+};
+
+// @brief - defines syslog event transport
+pub struct syslog_transport {
+    udp             : udp_client,
+    tcp             : tcp_client,
+    unix            : unix_client,
+    hostname        : String,
+    procid          : u32
+}
+
+impl syslog_transport {
+    const SYSLOG_VERSION        : u32 = 1;
+    const MAX_HOSTNAME_LEN      : usize = 255;
+    const MAX_APP_NAME_LEN      : usize = 48;
+    const MAX_MSGID_LEN         : usize = 32;
+    const CONNECT_TIMEOUT_SEC   : u32 = 2;
+
+    // @brief - zero initialize the syslog transport
+    //
+    // @return syslog transport
+    pub fn new() -> syslog_transport {
+        let t = syslog_transport {
+            udp             : udp_client::new(),
+            tcp             : tcp_client::new(),
+            unix            : unix_client::new(),
+            hostname        : "-".to_string(),
+            procid          : 0
+        };
+        t
+    }
+
+    // @brief - get the hostname of this machine
+    //
+    // @return hostname, "-" if not available
+    fn get_hostname() -> String {
+        let mut buf : [u8; 256] = [0; 256];
+        let ret : i32;
+
+        unsafe {
+            ret = libc::gethostname(buf.as_mut_ptr() as *mut libc::c_char, buf.len());
+        }
+        if ret < 0 {
+            return "-".to_string();
+        }
+
+        let len = buf.iter().position(|&c| c == 0).unwrap_or(buf.len());
+        if len == 0 {
+            return "-".to_string();
+        }
+
+        return String::from_utf8_lossy(&buf[0..len]).to_string();
+    }
+
+    // @brief - connect to the syslog server
+    //
+    // @param [in] self - syslog transport
+    // @param [in] syslog_config - syslog transport config
+    //
+    // @return 0 on success, 1 if the TCP connect is in progress, -1 on failure
+    //
+    // @details - the TCP connect does not block, a connect in progress is
+    //            checked again on the next call.
+    fn connect(&mut self, syslog_config : &idsm_event_transport_syslog) -> i32 {
+        match syslog_config.proto {
+            idsm_syslog_proto::UDP => return self.udp.create(),
+            idsm_syslog_proto::TCP => {
+                if self.tcp.is_connected() {
+                    return 0;
+                }
+                if self.tcp.is_connecting() {
+                    return self.tcp.finish_connect();
+                }
+                return self.tcp.start_connect(&syslog_config.ipaddr,
+                                              syslog_config.port as u32,
+                                              syslog_transport::CONNECT_TIMEOUT_SEC);
+            }
+            idsm_syslog_proto::UNIX => return self.unix.connect(&syslog_config.path),
+            _ => return -1,
+        }
+    }
+
+    // @brief - initialize the syslog transport
+    //
+    // @param [in] self - syslog transport
+    // @param [in] syslog_config - syslog transport config
+    //
+    // @return 0 on success -1 on failure
+    //
+    // @details - a TCP server that is not yet reachable is not an error,
+    //            the connection is retried on every upload.
+    pub fn init(&mut self, syslog_config : &idsm_event_transport_syslog) -> i32 {
+        let ret : i32;
+
+        self.hostname = syslog_transport::get_hostname();
+        self.procid = std::process::id();
+
+        ret = self.connect(syslog_config);
+        if ret < 0 {
+            if syslog_config.proto == idsm_syslog_proto::TCP {
+                log::error!("syslog_transport: cannot connect to {}:{}, will retry",
+                                        syslog_config.ipaddr, syslog_config.port);
+                return 0;
+            }
+            return -1;
+        }
+
+        return 0;
+    }
+
+    // @brief - continue the TCP connect in progress
+    //
+    // @param [in] self - syslog transport
+    // @param [in] evt_config - events config
+    pub fn poll(&mut self, evt_config : &idsm_events_config) {
+        let syslog_config = &evt_config.syslog_config;
+
+        if self.tcp.is_connecting() && (self.connect(syslog_config) < 0) {
+            log::error!("syslog_transport: cannot connect to {}:{}",
+                                    syslog_config.ipaddr, syslog_config.port);
+        }
+    }
+
+    // @brief - map event severity to syslog severity
+    //
+    // @param [in] severity - event severity between 0 and 10
+    //
+    // @return syslog severity
+    fn syslog_severity(severity : u8) -> u8 {
+        match severity {
+            9..=10 => return 2, // critical
+            7..=8 => return 3, // error
+            5..=6 => return 4, // warning
+            3..=4 => return 5, // notice
+            _ => return 6, // informational
+        }
+    }
+
+    // @brief - keep the printable ascii characters of a header field
+    //
+    // @param [in] val - header field
+    // @param [in] max_len - maximum length of the field
+    //
+    // @return header field, "-" if empty
+    fn hdr_field(val : &str, max_len : usize) -> String {
+        let field : String = val.chars()
+                                .filter(|c| (*c as u32) >= 33 && (*c as u32) <= 126)
+                                .take(max_len)
+                                .collect();
+        if field.len() == 0 {
+            return "-".to_string();
+        }
+
+        return field;
+    }
+
+    // @brief - format an event as RFC 5424 syslog message
+    //
+    // @param [in] self - syslog transport
+    // @param [in] evt_info - event
+    // @param [in] evt_config - events config
+    // @param [out] msg - syslog message
+    //
+    // @details - <PRI>VERSION TIMESTAMP HOSTNAME APP-NAME PROCID MSGID SD MSG
+    //
+    //            MSG is encoded with the configured encoder, JSON is used
+    //            in place of the binary encoder.
+    fn format(&self, evt_info : &event_info, evt_config : &idsm_events_config, msg : &mut String) {
+        let syslog_config = &evt_config.syslog_config;
+        let severity = syslog_transport::syslog_severity(event_desc::severity(evt_info.event_desc));
+        let pri = (syslog_config.facility as u32 * 8) + severity as u32;
+        let mut t = tm_linux::new();
+        let mut encoder_type = evt_config.encoder_type;
+        let mut body = String::new();
+
+        if encoder_type == event_encoder_type::BINARY {
+            encoder_type = event_encoder_type::JSON;
+        }
+        event_encoder::encode(encoder_type, evt_info, &mut body);
+
+        msg.push_str(&format!("<{}>{} ", pri, syslog_transport::SYSLOG_VERSION));
+
+        if gmtime_linux_sec(evt_info.last_seen_ts.sec, &mut t) == 0 {
+            msg.push_str(&format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:06}Z ",
+                                  t.year, t.mon, t.day, t.hour, t.min, t.sec,
+                                  evt_info.last_seen_ts.usec));
+        } else {
+            msg.push_str("- ");
+        }
+
+        msg.push_str(&format!("{} {} {} {} - {}",
+                              syslog_transport::hdr_field(&self.hostname, syslog_transport::MAX_HOSTNAME_LEN),
+                              syslog_transport::hdr_field(&syslog_config.app_name, syslog_transport::MAX_APP_NAME_LEN),
+                              self.procid,
+                              syslog_transport::hdr_field(event_desc::name(evt_info.event_desc),
+                                                          syslog_transport::MAX_MSGID_LEN),
+                              body));
+    }
+
+    // @brief - send one syslog message
+    //
+    // @param [in] self - syslog transport
+    // @param [in] msg - syslog message
+    // @param [in] syslog_config - syslog transport config
+    //
+    // @return 0 on success -1 on failure
+    fn send(&mut self, msg : &String, syslog_config : &idsm_event_transport_syslog) -> i32 {
+        let ret : isize;
+
+        match syslog_config.proto {
+            idsm_syslog_proto::UDP => {
+                ret = self.udp.sendto(msg.as_bytes(), msg.len(),
+                                      &syslog_config.ipaddr, syslog_config.port as u32);
+            }
+            idsm_syslog_proto::TCP => {
+                // octet counting framing as in RFC 6587
+                let frame = format!("{} {}", msg.len(), msg);
+
+                ret = self.tcp.send(frame.as_bytes(), frame.len());
+                if ret < 0 {
+                    self.tcp.close();
+                }
+            }
+            idsm_syslog_proto::UNIX => {
+                ret = self.unix.send(msg.as_bytes(), msg.len());
+                if ret < 0 {
+                    self.unix.close();
+                }
+            }
+            _ => ret = -1,
+        }
+
+        if ret < 0 {
+            return -1;
+        }
+
+        return 0;
+    }
+
+    // @brief - upload the collected events to the syslog server
+    //
+    // @param [in] self - syslog transport
+    // @param [inout] evt_mgr - event manager, sent events are removed from it
+    // @param [in] evt_config - events config
+    //
+    // @return number of events sent on success -1 on failure
+    pub fn upload(&mut self, evt_mgr : &mut event_mgr, evt_config : &idsm_events_config) -> i32 {
+        let syslog_config = &evt_config.syslog_config;
+        let n_ready = evt_mgr.n_ready_evts();
+        let mut n_sent : usize = 0;
+        let mut ret : i32 = 0;
+
+        if n_ready == 0 {
+            return 0;
+        }
+
+        // reconnect if the connection is lost, the events wait for the
+        // TCP connect in progress. the unix socket is lost when the syslog
+        // daemon restarts
+        if ((syslog_config.proto == idsm_syslog_proto::TCP) && !self.tcp.is_connected()) ||
+           ((syslog_config.proto == idsm_syslog_proto::UNIX) && !self.unix.is_connected()) {
+            ret = self.connect(syslog_config);
+            if ret > 0 {
+                return 0;
+            }
+            if ret < 0 {
+                if syslog_config.proto == idsm_syslog_proto::UNIX {
+                    log::error!("syslog_transport: cannot connect to {}", syslog_config.path);
+                } else {
+                    log::error!("syslog_transport: cannot connect to {}:{}",
+                                            syslog_config.ipaddr, syslog_config.port);
+                }
+                return -1;
+            }
+        }
+
+        while n_sent < n_ready {
+            let mut msg = String::new();
+
+            self.format(&evt_mgr.get_evt_list()[n_sent], evt_config, &mut msg);
+
+            ret = self.send(&msg, syslog_config);
+            if ret < 0 {
+                log::error!("syslog_transport: failed to send events");
+                break;
+            }
+
+            n_sent += 1;
+        }
+
+        // sent events are no longer needed
+        evt_mgr.remove_evts(n_sent);
+
+        if ret < 0 {
+            return -1;
+        }
+
+        return n_sent as i32;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::event_type::event_type;
+
+    fn evt() -> event_info {
+        let mut evt_info = event_info::new();
+
+        evt_info.event_type = event_type::EVENT_TYPE_DENY;
+        evt_info.event_desc = event_desc::IPV4_FRAG_TINY;
+        evt_info.detection_ts.sec = 1700000000;
+        evt_info.last_seen_ts.sec = 1700000000;
+        evt_info.last_seen_ts.usec = 42;
+        evt_info
+    }
+
+    #[test]
+    fn rfc5424_format() {
+        let mut t = syslog_transport::new();
+        let mut evt_config = idsm_events_config::new();
+        let mut msg = String::new();
+        let mut body = String::new();
+
+        t.hostname = "sensor 1".to_string();
+        t.procid = 100;
+        evt_config.syslog_config.facility = 16;
+        evt_config.encoder_type = event_encoder_type::CEF;
+        t.format(&evt(), &evt_config, &mut msg);
+
+        // local0 and an event of severity 7 as syslog error
+        event_encoder::encode_cef(&evt(), &mut body);
+        assert_eq!(msg, format!("<131>1 2023-11-14T22:13:20.000042Z sensor1 idsd 100 IPV4_FRAG_TINY - {}",
+                                body));
+    }
+
+    #[test]
+    fn binary_sent_as_json() {
+        let t = syslog_transport::new();
+        let evt_config = idsm_events_config::new();
+        let mut msg = String::new();
+
+        t.format(&evt(), &evt_config, &mut msg);
+        assert!(msg.starts_with("<35>1 "));
+        assert!(msg.contains(" - idsd 0 IPV4_FRAG_TINY - {"));
+    }
+
+    #[test]
+    fn severity_map() {
+        assert_eq!(syslog_transport::syslog_severity(10), 2);
+        assert_eq!(syslog_transport::syslog_severity(7), 3);
+        assert_eq!(syslog_transport::syslog_severity(5), 4);
+        assert_eq!(syslog_transport::syslog_severity(3), 5);
+        assert_eq!(syslog_transport::syslog_severity(0), 6);
+    }
+
+    #[test]
+    fn hdr_field_limits() {
+        assert_eq!(syslog_transport::hdr_field("", 8), "-");
+        assert_eq!(syslog_transport::hdr_field("a b\tc", 8), "abc");
+        assert_eq!(syslog_transport::hdr_field("abcdefghij", 4), "abcd");
+    }
+}
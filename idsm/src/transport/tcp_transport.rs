@@ -0,0 +1,473 @@
+// @brief - implements reliable event upload over TCP
+// @copyright - Devendra Naga 2024-present All rights reserved.
+#![allow(dead_code)]
+#![allow(non_camel_case_types)]
+
+use crate::{
+    config::config_parser::{idsm_event_transport_tcp, idsm_events_config},
+    events::{
+        event_encoder::{event_encoder, event_encoder_type},
+        event_mgr::event_mgr,
+        event_msg::event_msg
+    },
+    lib::{
+        fileio::fileio::fileio,
+        protocols::packet::packet::packet,
+        socket_linux::tcp_client::tcp_client,
+        time_linux::timestamp::{get_monotonic, timestamp}
+    }
+};
+
+// @brief - defines TCP event transport
+//
+// @details - every event message or encoded event is sent as a frame of
+//
+//            length (4 bytes, network byte order) | payload (length bytes)
+//
+//            frames that cannot be delivered are appended to a spool file
+//            and replayed in order once the connection is established again.
+//            the replay sends a bounded chunk at a time, the frames sent so
+//            far are skipped by the spool offset until the whole spool is sent.
+pub struct tcp_transport {
+    client              : tcp_client,
+    spool_filename      : String,
+    spool_len           : u64,
+    spool_off           : u64,
+    backoff_sec         : u32,
+    next_connect_sec    : u32,
+    seq_no              : u32
+}
+
+impl tcp_transport {
+    const SPOOL_FILENAME        : &'static str = "idsm_events.spool";
+    const CONNECT_TIMEOUT_SEC   : u32 = 2;
+    const FRAME_HDR_LEN         : usize = 4;
+    // bytes of the spool replayed per call
+    const REPLAY_CHUNK_LEN      : usize = 128 * 1024;
+
+    // @brief - zero initialize the TCP transport
+    //
+    // @return TCP transport
+    pub fn new() -> tcp_transport {
+        let t = tcp_transport {
+            client              : tcp_client::new(),
+            spool_filename      : "".to_string(),
+            spool_len           : 0,
+            spool_off           : 0,
+            backoff_sec         : 0,
+            next_connect_sec    : 0,
+            seq_no              : 0
+        };
+        t
+    }
+
+    // @brief - returns current monotonic time in seconds
+    fn now_sec() -> u32 {
+        let mut now = timestamp::new();
+
+        get_monotonic(&mut now);
+        return now.sec;
+    }
+
+    // @brief - connect to the collector if the reconnect backoff has expired
+    //
+    // @param [in] self - TCP transport
+    // @param [in] tcp_config - TCP transport config
+    //
+    // @return 0 if connected -1 otherwise
+    //
+    // @details - the connect does not block, a connect in progress is
+    //            checked again on the next call.
+    fn connect(&mut self, tcp_config : &idsm_event_transport_tcp) -> i32 {
+        let now = tcp_transport::now_sec();
+        let ret : i32;
+
+        if self.client.is_connected() {
+            return 0;
+        }
+
+        if self.client.is_connecting() {
+            ret = self.client.finish_connect();
+        } else {
+            if now < self.next_connect_sec {
+                return -1;
+            }
+
+            ret = self.client.start_connect(&tcp_config.ipaddr, tcp_config.port as u32,
+                                            tcp_transport::CONNECT_TIMEOUT_SEC);
+        }
+
+        if ret > 0 {
+            return -1;
+        }
+
+        if ret < 0 {
+            // exponential backoff between the reconnects
+            self.next_connect_sec = now + self.backoff_sec;
+            log::error!("tcp_transport: cannot connect to {}:{}, retry in {} sec",
+                                    tcp_config.ipaddr, tcp_config.port, self.backoff_sec);
+
+            self.backoff_sec *= 2;
+            if self.backoff_sec > tcp_config.reconnect_max_sec {
+                self.backoff_sec = tcp_config.reconnect_max_sec;
+            }
+            return -1;
+        }
+
+        log::info!("tcp_transport: connected to {}:{}", tcp_config.ipaddr, tcp_config.port);
+        self.backoff_sec = tcp_config.reconnect_min_sec;
+
+        return 0;
+    }
+
+    // @brief - close the connection and schedule a reconnect
+    //
+    // @param [in] self - TCP transport
+    fn disconnect(&mut self) {
+        self.client.close();
+        self.next_connect_sec = tcp_transport::now_sec() + self.backoff_sec;
+    }
+
+    // @brief - initialize the TCP transport
+    //
+    // @param [in] self - TCP transport
+    // @param [in] tcp_config - TCP transport config
+    //
+    // @return 0 on success -1 on failure
+    //
+    // @details - a collector that is not reachable is not an error, the
+    //            events are spooled until the connection is established.
+    pub fn init(&mut self, tcp_config : &idsm_event_transport_tcp) -> i32 {
+        let mut io = fileio::new();
+
+        self.spool_filename = format!("{}/{}", tcp_config.spool_dir.trim_end_matches('/'),
+                                      tcp_transport::SPOOL_FILENAME);
+        self.backoff_sec = tcp_config.reconnect_min_sec;
+
+        // continue with the spool left by the previous run
+        if io.open_read(&self.spool_filename) == 0 {
+            let size = io.size();
+            if size > 0 {
+                self.spool_len = size as u64;
+                log::info!("tcp_transport: {} bytes of events spooled", self.spool_len);
+            }
+        } else {
+            // make sure the spool file can be created
+            if io.new_file(&self.spool_filename) < 0 {
+                log::error!("tcp_transport: cannot create spool file {}", self.spool_filename);
+                return -1;
+            }
+            fileio::unlink(&self.spool_filename);
+        }
+
+        self.connect(tcp_config);
+
+        return 0;
+    }
+
+    // @brief - add a frame header and payload to the list of frames
+    fn add_frame(frames : &mut Vec<Vec<u8>>, payload : &[u8]) {
+        let mut frame : Vec<u8> = Vec::with_capacity(tcp_transport::FRAME_HDR_LEN + payload.len());
+
+        frame.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+        frame.extend_from_slice(payload);
+        frames.push(frame);
+    }
+
+    // @brief - encode the events into frames
+    //
+    // @param [in] self - TCP transport
+    // @param [inout] evt_mgr - event manager
+    // @param [in] n_ready - number of events to encode
+    // @param [in] evt_config - events config
+    // @param [out] frames - list of frames
+    // @param [out] frame_evts - number of events in each frame
+    fn encode_frames(&mut self, evt_mgr : &mut event_mgr, n_ready : usize,
+                     evt_config : &idsm_events_config,
+                     frames : &mut Vec<Vec<u8>>, frame_evts : &mut Vec<usize>) {
+        let mut n_encoded : usize = 0;
+
+        while n_encoded < n_ready {
+            if evt_config.encoder_type == event_encoder_type::BINARY {
+                let mut p : packet = packet::new();
+                let evt_list = evt_mgr.get_evt_list().make_contiguous();
+
+                let n_events = event_msg::serialize(&mut evt_list[n_encoded..n_ready], self.seq_no, &mut p);
+                if n_events == 0 {
+                    break;
+                }
+
+                tcp_transport::add_frame(frames, &p.buf[0..p.pkt_len]);
+                frame_evts.push(n_events);
+                self.seq_no = self.seq_no.wrapping_add(1);
+                n_encoded += n_events;
+            } else {
+                let mut line = String::new();
+
+                event_encoder::encode(evt_config.encoder_type, &evt_mgr.get_evt_list()[n_encoded], &mut line);
+                tcp_transport::add_frame(frames, line.as_bytes());
+                frame_evts.push(1);
+                n_encoded += 1;
+            }
+        }
+    }
+
+    // @brief - append frames to the spool file
+    //
+    // @param [in] self - TCP transport
+    // @param [in] frames - list of frames
+    // @param [in] tcp_config - TCP transport config
+    //
+    // @return number of frames spooled
+    fn spool(&mut self, frames : &[Vec<u8>], tcp_config : &idsm_event_transport_tcp) -> usize {
+        let mut io = fileio::new();
+        let mut n_spooled : usize = 0;
+
+        if io.new_file(&self.spool_filename) < 0 {
+            log::error!("tcp_transport: cannot open spool file {}", self.spool_filename);
+            return 0;
+        }
+
+        for frame in frames {
+            // frames already replayed are not pending, they are dropped from
+            // the file once the replay reaches the end of the spool
+            if (self.spool_len - self.spool_off) + frame.len() as u64 > tcp_config.max_spool_bytes {
+                log::error!("tcp_transport: spool is full");
+                break;
+            }
+
+            if io.write(frame, frame.len()) != frame.len() as isize {
+                log::error!("tcp_transport: failed to write spool file {}", self.spool_filename);
+                break;
+            }
+
+            self.spool_len += frame.len() as u64;
+            n_spooled += 1;
+        }
+
+        io.sync();
+
+        return n_spooled;
+    }
+
+    // @brief - read until the buffer is full or the end of the file
+    //
+    // @param [in] io - opened file
+    // @param [out] buf - buffer to read into
+    //
+    // @return number of bytes read, less than the buffer at the end of the file, -1 on failure
+    fn read_full(io : &mut fileio, buf : &mut [u8]) -> isize {
+        let mut off : usize = 0;
+
+        while off < buf.len() {
+            let len = buf.len() - off;
+            let ret = io.read(&mut buf[off..], len);
+            if ret < 0 {
+                return -1;
+            }
+            if ret == 0 {
+                break;
+            }
+
+            off += ret as usize;
+        }
+
+        return off as isize;
+    }
+
+    // @brief - send the next spooled frames in order
+    //
+    // @param [in] self - TCP transport
+    //
+    // @return 0 if the spool is fully sent, 1 if frames are left, -1 on failure
+    //
+    // @details - at most REPLAY_CHUNK_LEN bytes are sent per call so the packet
+    //            capture is not held up by a large spool. the spool file is
+    //            removed once every frame is sent.
+    fn replay_spool(&mut self) -> i32 {
+        let mut io = fileio::new();
+        let mut n_sent : usize = 0;
+
+        if (io.open_read(&self.spool_filename) < 0) || (io.seek(self.spool_off) < 0) {
+            log::error!("tcp_transport: cannot read spool file {}", self.spool_filename);
+            return -1;
+        }
+
+        while n_sent < tcp_transport::REPLAY_CHUNK_LEN {
+            let mut hdr : [u8; 4] = [0; 4];
+
+            let ret = tcp_transport::read_full(&mut io, &mut hdr);
+            if ret < 0 {
+                log::error!("tcp_transport: cannot read spool file {}", self.spool_filename);
+                return -1;
+            }
+            if ret == 0 {
+                break;
+            }
+
+            let frame_len = tcp_transport::FRAME_HDR_LEN + u32::from_be_bytes(hdr) as usize;
+
+            // truncated frame at the end of the spool, drop it
+            if (ret as usize != hdr.len()) ||
+               (self.spool_off + frame_len as u64 > self.spool_len) {
+                log::error!("tcp_transport: dropping truncated frame in spool");
+                self.spool_off = self.spool_len;
+                break;
+            }
+
+            let mut frame : Vec<u8> = vec![0; frame_len];
+
+            frame[0..tcp_transport::FRAME_HDR_LEN].copy_from_slice(&hdr);
+            if tcp_transport::read_full(&mut io, &mut frame[tcp_transport::FRAME_HDR_LEN..]) !=
+               (frame_len - tcp_transport::FRAME_HDR_LEN) as isize {
+                log::error!("tcp_transport: cannot read spool file {}", self.spool_filename);
+                return -1;
+            }
+
+            if self.client.send(&frame, frame_len) < 0 {
+                return -1;
+            }
+
+            self.spool_off += frame_len as u64;
+            n_sent += frame_len;
+        }
+
+        if self.spool_off < self.spool_len {
+            return 1;
+        }
+
+        fileio::unlink(&self.spool_filename);
+        self.spool_len = 0;
+        self.spool_off = 0;
+        log::info!("tcp_transport: spool replayed");
+
+        return 0;
+    }
+
+    // @brief - continue the connect and the spool replay
+    //
+    // @param [in] self - TCP transport
+    // @param [in] evt_config - events config
+    //
+    // @details - called often from the main loop so a spool is replayed
+    //            a chunk at a time between the packets.
+    pub fn poll(&mut self, evt_config : &idsm_events_config) {
+        let tcp_config = &evt_config.tcp_config;
+
+        if (self.spool_len == 0) && !self.client.is_connecting() {
+            return;
+        }
+
+        if self.connect(tcp_config) < 0 {
+            return;
+        }
+
+        if (self.spool_len > 0) && (self.replay_spool() < 0) {
+            log::error!("tcp_transport: connection to {}:{} lost",
+                                    tcp_config.ipaddr, tcp_config.port);
+            self.disconnect();
+        }
+    }
+
+    // @brief - upload the collected events to the collector
+    //
+    // @param [in] self - TCP transport
+    // @param [inout] evt_mgr - event manager, sent or spooled events are removed from it
+    // @param [in] evt_config - events config
+    //
+    // @return number of events sent on success -1 on failure
+    pub fn upload(&mut self, evt_mgr : &mut event_mgr, evt_config : &idsm_events_config) -> i32 {
+        let tcp_config = &evt_config.tcp_config;
+        let n_ready = evt_mgr.n_ready_evts();
+        let mut frames : Vec<Vec<u8>> = Vec::new();
+        let mut frame_evts : Vec<usize> = Vec::new();
+        let mut n_frames_sent : usize = 0;
+        let mut n_sent : usize = 0;
+        let mut n_done : usize = 0;
+
+        if (n_ready == 0) && (self.spool_len == 0) {
+            return 0;
+        }
+
+        self.encode_frames(evt_mgr, n_ready, evt_config, &mut frames, &mut frame_evts);
+
+        if self.connect(tcp_config) == 0 {
+            // spooled frames go first to keep the events in order, the new
+            // frames are spooled behind them until the replay completes
+            if (self.spool_len > 0) && (self.replay_spool() < 0) {
+                self.disconnect();
+            }
+        }
+
+        if self.client.is_connected() && (self.spool_len == 0) {
+            for frame in &frames {
+                if self.client.send(frame, frame.len()) < 0 {
+                    log::error!("tcp_transport: connection to {}:{} lost",
+                                            tcp_config.ipaddr, tcp_config.port);
+                    self.disconnect();
+                    break;
+                }
+
+                n_sent += frame_evts[n_frames_sent];
+                n_frames_sent += 1;
+            }
+        }
+
+        n_done += n_sent;
+
+        // keep the frames that are not sent in the spool
+        if n_frames_sent < frames.len() {
+            let n_spooled = self.spool(&frames[n_frames_sent..], tcp_config);
+
+            for i in 0..n_spooled {
+                n_done += frame_evts[n_frames_sent + i];
+            }
+        }
+
+        // sent and spooled events are no longer needed
+        evt_mgr.remove_evts(n_done);
+
+        if n_done < n_ready {
+            return -1;
+        }
+
+        return n_sent as i32;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_format() {
+        let mut frames : Vec<Vec<u8>> = Vec::new();
+
+        tcp_transport::add_frame(&mut frames, b"{\"a\":1}");
+        assert_eq!(frames[0], b"\x00\x00\x00\x07{\"a\":1}".to_vec());
+    }
+
+    #[test]
+    fn spool_limit_counts_pending_bytes() {
+        let mut t = tcp_transport::new();
+        let mut tcp_config = idsm_event_transport_tcp::new();
+        let dir = std::env::temp_dir().join(format!("idsm_spool_test_{}", std::process::id()));
+        let frame = vec![0u8; 16];
+
+        std::fs::create_dir_all(&dir).unwrap();
+        tcp_config.spool_dir = dir.to_string_lossy().to_string();
+        tcp_config.max_spool_bytes = 48;
+        assert_eq!(t.init(&tcp_config), 0);
+
+        assert_eq!(t.spool(&[frame.clone(), frame.clone(), frame.clone(), frame.clone()], &tcp_config), 3);
+        assert_eq!(t.spool_len, 48);
+
+        // two frames went out by the replay, there is room for two more
+        t.spool_off = 32;
+        assert_eq!(t.spool(&[frame.clone(), frame.clone(), frame.clone()], &tcp_config), 2);
+        assert_eq!(t.spool_len, 80);
+
+        fileio::unlink(&t.spool_filename);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,170 @@
+// @brief - implements event upload over UDP
+// @copyright - Devendra Naga 2024-present All rights reserved.
+#![allow(dead_code)]
+#![allow(non_camel_case_types)]
+
+use crate::{
+    config::config_parser::{idsm_event_transport_udp, idsm_events_config},
+    events::{
+        event_encoder::{event_encoder, event_encoder_type},
+        event_mgr::event_mgr,
+        event_msg::event_msg
+    },
+    lib::{protocols::packet::packet::packet, socket_linux::udp_client::udp_client}
+};
+
+// @brief - defines UDP event transport
+pub struct udp_transport {
+    client          : udp_client,
+    seq_no          : u32
+}
+
+impl udp_transport {
+    // @brief - zero initialize the UDP transport
+    //
+    // @return UDP transport
+    pub fn new() -> udp_transport {
+        let t = udp_transport {
+            client          : udp_client::new(),
+            seq_no          : 0
+        };
+        t
+    }
+
+    // @brief - create the UDP socket used for the uploads
+    //
+    // @param [in] self - UDP transport
+    //
+    // @return 0 on success -1 on failure
+    pub fn init(&mut self) -> i32 {
+        return self.client.create();
+    }
+
+    // @brief - upload events as binary event messages
+    //
+    // @param [in] self - UDP transport
+    // @param [inout] evt_mgr - event manager
+    // @param [in] n_ready - number of events ready for upload
+    // @param [in] udp_config - UDP transport config
+    // @param [out] n_sent - number of events sent
+    //
+    // @return 0 on success -1 on failure
+    fn upload_binary(&mut self, evt_mgr : &mut event_mgr, n_ready : usize,
+                     udp_config : &idsm_event_transport_udp, n_sent : &mut usize) -> i32 {
+        while *n_sent < n_ready {
+            let mut p : packet = packet::new();
+            let evt_list = evt_mgr.get_evt_list().make_contiguous();
+
+            let n_events = event_msg::serialize(&mut evt_list[*n_sent..n_ready], self.seq_no, &mut p);
+            if n_events == 0 {
+                break;
+            }
+
+            let tx_len = self.client.sendto(&p.buf, p.pkt_len,
+                                            &udp_config.ipaddr, udp_config.port as u32);
+            if tx_len < 0 {
+                return -1;
+            }
+
+            self.seq_no = self.seq_no.wrapping_add(1);
+            *n_sent += n_events;
+        }
+
+        return 0;
+    }
+
+    // @brief - upload events as lines of text
+    //
+    // @param [in] self - UDP transport
+    // @param [inout] evt_mgr - event manager
+    // @param [in] n_ready - number of events ready for upload
+    // @param [in] evt_config - events config
+    // @param [out] n_sent - number of events sent
+    //
+    // @return 0 on success -1 on failure
+    //
+    // @details - lines are separated by a newline and packed into datagrams of at
+    //            most event_msg::EVENT_MSG_MAX_LEN bytes. a longer line is sent alone.
+    fn upload_text(&mut self, evt_mgr : &mut event_mgr, n_ready : usize,
+                   evt_config : &idsm_events_config, n_sent : &mut usize) -> i32 {
+        let udp_config = &evt_config.udp_config;
+        let mut msg = String::new();
+        let mut n_events : usize = 0;
+        let mut i : usize = *n_sent;
+
+        while i < n_ready {
+            let mut line = String::new();
+
+            event_encoder::encode(evt_config.encoder_type, &evt_mgr.get_evt_list()[i], &mut line);
+            line.push('\n');
+
+            // flush the pending lines if this line does not fit
+            if (n_events > 0) && (msg.len() + line.len() > event_msg::EVENT_MSG_MAX_LEN) {
+                let tx_len = self.client.sendto(msg.as_bytes(), msg.len(),
+                                                &udp_config.ipaddr, udp_config.port as u32);
+                if tx_len < 0 {
+                    return -1;
+                }
+
+                *n_sent += n_events;
+                n_events = 0;
+                msg.clear();
+            }
+
+            msg.push_str(&line);
+            n_events += 1;
+            i += 1;
+        }
+
+        if n_events > 0 {
+            let tx_len = self.client.sendto(msg.as_bytes(), msg.len(),
+                                            &udp_config.ipaddr, udp_config.port as u32);
+            if tx_len < 0 {
+                return -1;
+            }
+
+            *n_sent += n_events;
+        }
+
+        return 0;
+    }
+
+    // @brief - upload the collected events to the collector
+    //
+    // @param [in] self - UDP transport
+    // @param [inout] evt_mgr - event manager, sent events are removed from it
+    // @param [in] evt_config - events config
+    //
+    // @return number of events sent on success -1 on failure
+    //
+    // @details - events are encoded with the configured encoder and batched into
+    //            datagrams of at most event_msg::EVENT_MSG_MAX_LEN bytes each.
+    //            events that are still aggregating or could not be sent remain in
+    //            the event manager for the next upload.
+    pub fn upload(&mut self, evt_mgr : &mut event_mgr, evt_config : &idsm_events_config) -> i32 {
+        let mut n_sent : usize = 0;
+        let ret : i32;
+        let n_ready = evt_mgr.n_ready_evts();
+
+        if n_ready == 0 {
+            return 0;
+        }
+
+        if evt_config.encoder_type == event_encoder_type::BINARY {
+            ret = self.upload_binary(evt_mgr, n_ready, &evt_config.udp_config, &mut n_sent);
+        } else {
+            ret = self.upload_text(evt_mgr, n_ready, evt_config, &mut n_sent);
+        }
+
+        // sent events are no longer needed
+        evt_mgr.remove_evts(n_sent);
+
+        if ret < 0 {
+            log::error!("udp_transport: failed to send events to {}:{}",
+                                    evt_config.udp_config.ipaddr, evt_config.udp_config.port);
+            return -1;
+        }
+
+        return n_sent as i32;
+    }
+}